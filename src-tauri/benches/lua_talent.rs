@@ -0,0 +1,59 @@
+use criterion::{black_box, criterion_group, criterion_main, Criterion};
+use talent_heron_lib::lua_talent::{LuaTalentManager, TalentLoadout};
+
+/// Classes and spec counts used to generate the fixture
+const CLASSES: &[(&str, u8)] = &[
+    ("WARRIOR", 3),
+    ("PALADIN", 3),
+    ("HUNTER", 3),
+    ("ROGUE", 3),
+    ("PRIEST", 3),
+    ("DEATHKNIGHT", 3),
+    ("SHAMAN", 3),
+    ("MAGE", 3),
+    ("WARLOCK", 3),
+    ("MONK", 3),
+    ("DRUID", 4),
+    ("DEMONHUNTER", 2),
+    ("EVOKER", 3),
+];
+
+/// Generate a TalentLoadoutsEx.lua of roughly 800KB, the size of a file that
+/// collected loadouts for many characters over several years
+fn large_fixture() -> String {
+    let mut manager = LuaTalentManager::new();
+
+    for (class_name, specs) in CLASSES {
+        for spec_index in 1..=*specs {
+            for i in 0..110 {
+                let name = match i % 3 {
+                    0 => format!("R-heroic-boss-{}_ARCT", i),
+                    1 => format!("M+-dungeon-{}_ARCT", i),
+                    _ => format!("My \"{}\" build {}", class_name, i),
+                };
+                let text = format!(
+                    "{}/{}/CcEAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAA{:04}",
+                    class_name.to_lowercase(),
+                    spec_index,
+                    i
+                );
+                let mut talent = TalentLoadout::new(name, text);
+                talent.icon = 132355 + i64::from(i);
+                manager.add_talent(class_name.to_string(), spec_index, talent);
+            }
+        }
+    }
+
+    manager.to_lua_string()
+}
+
+fn bench_lua_talent(c: &mut Criterion) {
+    let content = large_fixture();
+    let manager = LuaTalentManager::parse_lua(&content, None).unwrap();
+
+    c.bench_function("parse_lua", |b| b.iter(|| LuaTalentManager::parse_lua(black_box(&content), None).unwrap()));
+    c.bench_function("to_lua_string", |b| b.iter(|| black_box(&manager).to_lua_string()));
+}
+
+criterion_group!(benches, bench_lua_talent);
+criterion_main!(benches);
@@ -0,0 +1,53 @@
+use criterion::{black_box, criterion_group, criterion_main, Criterion};
+use std::alloc::{GlobalAlloc, Layout, System};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use talent_heron_lib::wow::WowClass;
+
+/// System allocator that counts allocations, to check that spec lookups don't allocate
+struct CountingAllocator;
+
+static ALLOCATIONS: AtomicUsize = AtomicUsize::new(0);
+
+unsafe impl GlobalAlloc for CountingAllocator {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        ALLOCATIONS.fetch_add(1, Ordering::Relaxed);
+        System.alloc(layout)
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        System.dealloc(ptr, layout)
+    }
+}
+
+#[global_allocator]
+static GLOBAL: CountingAllocator = CountingAllocator;
+
+/// Every class/spec slug pair, collected up front so the lookups run without allocating
+fn all_specs() -> Vec<(WowClass, &'static str)> {
+    WowClass::all()
+        .iter()
+        .flat_map(|class| class.valid_specs().into_iter().map(move |slug| (*class, slug)))
+        .collect()
+}
+
+fn lookup_all(specs: &[(WowClass, &'static str)]) -> usize {
+    specs
+        .iter()
+        .filter_map(|(class, slug)| class.spec_index(black_box(slug)))
+        .count()
+}
+
+fn bench_spec_index(c: &mut Criterion) {
+    let specs = all_specs();
+
+    let before = ALLOCATIONS.load(Ordering::Relaxed);
+    assert_eq!(lookup_all(&specs), 39);
+    let allocations = ALLOCATIONS.load(Ordering::Relaxed) - before;
+    println!("spec_index allocations for all 39 specs: {}", allocations);
+    assert_eq!(allocations, 0);
+
+    c.bench_function("spec_index_all_specs", |b| b.iter(|| lookup_all(black_box(&specs))));
+}
+
+criterion_group!(benches, bench_spec_index);
+criterion_main!(benches);
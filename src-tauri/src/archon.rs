@@ -1,6 +1,9 @@
-use crate::wow::WowClass;
+use crate::wow::{GameFlavor, HeroTalent, WowSpec};
 use chrono::Datelike;
 
+/// Default name suffix marking auto-generated loadouts
+pub const DEFAULT_AUTO_SUFFIX: &str = "_ARCT";
+
 /// Content type for Archon.gg builds
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 #[allow(dead_code)]
@@ -73,34 +76,93 @@ impl MythicPlusTimespan {
 }
 
 /// Talent identifier for generated builds
+/// Builds of one hero talent tree carry its slug (e.g., "R-heroic-sikran/slayer").
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub enum TalentIdentifier {
     Raid {
         difficulty: RaidDifficulty,
         boss: String,
+        hero: Option<HeroTalent>,
     },
     MythicPlus {
         dungeon: String,
+        hero: Option<HeroTalent>,
     },
 }
 
 impl TalentIdentifier {
-    /// Generate the identifier string (e.g., "R-heroic-sikran" or "M+-ara-kara")
+    /// Generate the identifier string (e.g., "R-heroic-sikran" or "M+-ara-kara/slayer")
     pub fn as_identifier(&self) -> String {
+        let (content, hero) = match self {
+            Self::Raid { difficulty, boss, hero } => (format!("R-{}-{}", difficulty.as_str(), boss), hero),
+            Self::MythicPlus { dungeon, hero } => (format!("M+-{}", dungeon), hero),
+        };
+        match hero {
+            Some(hero) => format!("{}/{}", content, hero.slug()),
+            None => content,
+        }
+    }
+
+    /// Archon.gg page of this content for a spec; the timespan only applies to Mythic+
+    pub fn archon_url(&self, url_builder: &ArchonUrlBuilder, spec: WowSpec, timespan: MythicPlusTimespan) -> String {
         match self {
-            Self::Raid { difficulty, boss } => {
-                format!("R-{}-{}", difficulty.as_str(), boss)
-            }
-            Self::MythicPlus { dungeon } => {
-                format!("M+-{}", dungeon)
-            }
+            Self::Raid { difficulty, boss, hero } => url_builder.build_raid_url(spec, *difficulty, boss, *hero),
+            Self::MythicPlus { dungeon, hero } => url_builder.build_mythic_plus_url(spec, dungeon, timespan, *hero),
         }
     }
 
-    /// Generate the full name with _ARCT suffix for auto-generated talents
-    pub fn as_talent_name(&self) -> String {
-        format!("{}_ARCT", self.as_identifier())
+    /// Generate the full name with the auto-generated suffix (e.g., "_ARCT")
+    pub fn as_talent_name(&self, suffix: &str) -> String {
+        format!("{}{}", self.as_identifier(), suffix)
     }
+
+    /// Parse an auto-generated talent name back into an identifier
+    /// Returns None for manual loadouts or names that don't follow the identifier format
+    pub fn from_talent_name(name: &str, suffix: &str) -> Option<Self> {
+        let identifier = name.strip_suffix(suffix)?;
+        let (identifier, hero) = match identifier.split_once('/') {
+            Some((content, hero)) => (content, Some(HeroTalent::from_slug(hero)?)),
+            None => (identifier, None),
+        };
+
+        if let Some(dungeon) = identifier.strip_prefix("M+-") {
+            return Some(Self::MythicPlus {
+                dungeon: dungeon.to_string(),
+                hero,
+            });
+        }
+
+        let (difficulty, boss) = identifier.strip_prefix("R-")?.split_once('-')?;
+        Some(Self::Raid {
+            difficulty: RaidDifficulty::from_str(difficulty)?,
+            boss: boss.to_string(),
+            hero,
+        })
+    }
+}
+
+/// Boss and dungeon slugs that name the same content, e.g. Archon.gg's short and full names
+const CONTENT_SLUG_ALIASES: &[(&str, &str)] = &[
+    ("broodtwister-ovinax", "broodtwister"),
+    ("ansurek", "queen-ansurek"),
+    ("kyveza", "nexus-princess-kyveza"),
+    ("ara-kara-city-of-echoes", "ara-kara"),
+    ("dawnbreaker", "the-dawnbreaker"),
+    ("stonevault", "the-stonevault"),
+    ("necrotic-wake", "the-necrotic-wake"),
+];
+
+/// A boss or dungeon slug and its aliases (see `CONTENT_SLUG_ALIASES`)
+pub fn equivalent_content_slugs(slug: &str) -> Vec<&str> {
+    let mut slugs = vec![slug];
+    for (alias, name) in CONTENT_SLUG_ALIASES {
+        if *alias == slug {
+            slugs.push(name);
+        } else if *name == slug {
+            slugs.push(alias);
+        }
+    }
+    slugs
 }
 
 /// URL builder for Archon.gg talent builds
@@ -110,34 +172,42 @@ pub struct ArchonUrlBuilder {
 
 impl Default for ArchonUrlBuilder {
     fn default() -> Self {
-        Self {
-            base_url: "https://www.archon.gg/wow/builds".to_string(),
-        }
+        Self::for_flavor(GameFlavor::Retail)
     }
 }
 
 impl ArchonUrlBuilder {
+    #[allow(dead_code)]
     pub fn new() -> Self {
         Self::default()
     }
 
+    /// URL builder for the build pages of a game flavor
+    pub fn for_flavor(flavor: GameFlavor) -> Self {
+        Self {
+            base_url: flavor.archon_base_url().to_string(),
+        }
+    }
+
     /// Build URL for a raid boss talent build
     /// Format: https://www.archon.gg/wow/builds/{spec}/{class}/raid/overview/{difficulty}/{encounter}
+    /// with `?heroTree={hero}` to only consider builds of one hero talent tree
     pub fn build_raid_url(
         &self,
-        class: WowClass,
-        spec: &str,
+        spec: WowSpec,
         difficulty: RaidDifficulty,
         boss: &str,
+        hero: Option<HeroTalent>,
     ) -> String {
-        format!(
+        let url = format!(
             "{}/{}/{}/raid/overview/{}/{}",
             self.base_url,
-            spec.to_lowercase(),
-            class.to_url_format(),
+            spec.slug,
+            spec.class.to_url_format(),
             difficulty.as_str(),
             boss.to_lowercase()
-        )
+        );
+        with_hero_tree(url, hero)
     }
 
     /// Build URL for a Mythic+ dungeon talent build
@@ -145,25 +215,35 @@ impl ArchonUrlBuilder {
     /// Note the double slash (//) where difficulty would be for raids
     pub fn build_mythic_plus_url(
         &self,
-        class: WowClass,
-        spec: &str,
+        spec: WowSpec,
         dungeon: &str,
         timespan: MythicPlusTimespan,
+        hero: Option<HeroTalent>,
     ) -> String {
-        format!(
+        let url = format!(
             "{}/{}/{}/mythic-plus/overview/10//{}/{}",
             self.base_url,
-            spec.to_lowercase(),
-            class.to_url_format(),
+            spec.slug,
+            spec.class.to_url_format(),
             dungeon.to_lowercase(),
             timespan.as_str()
-        )
+        );
+        with_hero_tree(url, hero)
+    }
+}
+
+/// Limit a build page to one hero talent tree
+fn with_hero_tree(url: String, hero: Option<HeroTalent>) -> String {
+    match hero {
+        Some(hero) => format!("{}?heroTree={}", url, hero.slug()),
+        None => url,
     }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::wow::WowClass;
 
     #[test]
     fn test_raid_difficulty_parsing() {
@@ -191,28 +271,86 @@ mod tests {
         let id = TalentIdentifier::Raid {
             difficulty: RaidDifficulty::Heroic,
             boss: "sikran".to_string(),
+            hero: None,
         };
         assert_eq!(id.as_identifier(), "R-heroic-sikran");
-        assert_eq!(id.as_talent_name(), "R-heroic-sikran_ARCT");
+        assert_eq!(id.as_talent_name(DEFAULT_AUTO_SUFFIX), "R-heroic-sikran_ARCT");
     }
 
     #[test]
     fn test_talent_identifier_mythic_plus() {
         let id = TalentIdentifier::MythicPlus {
             dungeon: "ara-kara".to_string(),
+            hero: None,
         };
         assert_eq!(id.as_identifier(), "M+-ara-kara");
-        assert_eq!(id.as_talent_name(), "M+-ara-kara_ARCT");
+        assert_eq!(id.as_talent_name(DEFAULT_AUTO_SUFFIX), "M+-ara-kara_ARCT");
+    }
+
+    #[test]
+    fn test_talent_identifier_from_talent_name() {
+        assert_eq!(
+            TalentIdentifier::from_talent_name("R-heroic-queen-ansurek_ARCT", DEFAULT_AUTO_SUFFIX),
+            Some(TalentIdentifier::Raid {
+                difficulty: RaidDifficulty::Heroic,
+                boss: "queen-ansurek".to_string(),
+                hero: None,
+            })
+        );
+        assert_eq!(
+            TalentIdentifier::from_talent_name("M+-ara-kara_ARCT", DEFAULT_AUTO_SUFFIX),
+            Some(TalentIdentifier::MythicPlus {
+                dungeon: "ara-kara".to_string(),
+                hero: None,
+            })
+        );
+        assert_eq!(TalentIdentifier::from_talent_name("R-heroic-sikran", DEFAULT_AUTO_SUFFIX), None);
+        assert_eq!(TalentIdentifier::from_talent_name("R-lfr-sikran_ARCT", DEFAULT_AUTO_SUFFIX), None);
+        assert_eq!(TalentIdentifier::from_talent_name("My Build", DEFAULT_AUTO_SUFFIX), None);
+        assert_eq!(
+            TalentIdentifier::from_talent_name("M+-ara-kara_TH", "_TH"),
+            Some(TalentIdentifier::MythicPlus {
+                dungeon: "ara-kara".to_string(),
+                hero: None,
+            })
+        );
+    }
+
+    #[test]
+    fn test_talent_identifier_with_hero_talent() {
+        let id = TalentIdentifier::Raid {
+            difficulty: RaidDifficulty::Mythic,
+            boss: "queen-ansurek".to_string(),
+            hero: Some(HeroTalent::MountainThane),
+        };
+        assert_eq!(id.as_talent_name(DEFAULT_AUTO_SUFFIX), "R-mythic-queen-ansurek/mountain-thane_ARCT");
+        let name = "R-mythic-queen-ansurek/mountain-thane_ARCT";
+        assert_eq!(TalentIdentifier::from_talent_name(name, DEFAULT_AUTO_SUFFIX), Some(id));
+
+        let id = TalentIdentifier::MythicPlus {
+            dungeon: "ara-kara".to_string(),
+            hero: Some(HeroTalent::Slayer),
+        };
+        assert_eq!(id.as_identifier(), "M+-ara-kara/slayer");
+        let name = "M+-ara-kara/slayer_ARCT";
+        assert_eq!(TalentIdentifier::from_talent_name(name, DEFAULT_AUTO_SUFFIX), Some(id.clone()));
+        assert_eq!(TalentIdentifier::from_talent_name("M+-ara-kara/nonsense_ARCT", DEFAULT_AUTO_SUFFIX), None);
+
+        let builder = ArchonUrlBuilder::new();
+        assert_eq!(
+            id.archon_url(&builder, WowClass::Warrior.spec("fury").unwrap(), MythicPlusTimespan::ThisWeek),
+            "https://www.archon.gg/wow/builds/fury/warrior/mythic-plus/overview/10//ara-kara/this-week?heroTree=slayer"
+        );
     }
 
     #[test]
     fn test_build_raid_url() {
         let builder = ArchonUrlBuilder::new();
         let url = builder.build_raid_url(
-            WowClass::Mage,
-            "frost",
+            WowClass::Mage.spec("frost").unwrap(),
             RaidDifficulty::Heroic,
             "broodtwister",
+            None,
         );
         assert_eq!(
             url,
@@ -220,14 +358,22 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_build_raid_url_outlaw() {
+        let builder = ArchonUrlBuilder::new();
+        let spec = WowClass::Rogue.spec("combat").unwrap();
+        let url = builder.build_raid_url(spec, RaidDifficulty::Mythic, "sikran", None);
+        assert_eq!(url, "https://www.archon.gg/wow/builds/outlaw/rogue/raid/overview/mythic/sikran");
+    }
+
     #[test]
     fn test_build_raid_url_death_knight() {
         let builder = ArchonUrlBuilder::new();
         let url = builder.build_raid_url(
-            WowClass::DeathKnight,
-            "unholy",
+            WowClass::DeathKnight.spec("unholy").unwrap(),
             RaidDifficulty::Heroic,
             "sikran",
+            None,
         );
         assert_eq!(
             url,
@@ -239,10 +385,10 @@ mod tests {
     fn test_build_mythic_plus_url() {
         let builder = ArchonUrlBuilder::new();
         let url = builder.build_mythic_plus_url(
-            WowClass::Warrior,
-            "protection",
+            WowClass::Warrior.spec("protection").unwrap(),
             "ara-kara",
             MythicPlusTimespan::ThisWeek,
+            None,
         );
         assert_eq!(
             url,
@@ -254,10 +400,10 @@ mod tests {
     fn test_build_mythic_plus_url_with_last_week() {
         let builder = ArchonUrlBuilder::new();
         let url = builder.build_mythic_plus_url(
-            WowClass::DeathKnight,
-            "unholy",
+            WowClass::DeathKnight.spec("unholy").unwrap(),
             "mists-of-tirna-scithe",
             MythicPlusTimespan::LastWeek,
+            None,
         );
         assert_eq!(
             url,
@@ -270,11 +416,35 @@ mod tests {
         // Verify that M+ URLs have the double slash where difficulty would be
         let builder = ArchonUrlBuilder::new();
         let url = builder.build_mythic_plus_url(
-            WowClass::Mage,
-            "fire",
+            WowClass::Mage.spec("fire").unwrap(),
             "city-of-threads",
             MythicPlusTimespan::ThisWeek,
+            None,
         );
         assert!(url.contains("overview/10//city-of-threads"));
     }
+
+    #[test]
+    fn test_talent_identifier_archon_url() {
+        let builder = ArchonUrlBuilder::new();
+        let spec = WowClass::Hunter.spec("BM").unwrap();
+        let raid = TalentIdentifier::Raid {
+            difficulty: RaidDifficulty::Mythic,
+            boss: "sikran".to_string(),
+            hero: None,
+        };
+        assert_eq!(
+            raid.archon_url(&builder, spec, MythicPlusTimespan::LastWeek),
+            builder.build_raid_url(spec, RaidDifficulty::Mythic, "sikran", None)
+        );
+
+        let dungeon = TalentIdentifier::MythicPlus {
+            dungeon: "ara-kara".to_string(),
+            hero: None,
+        };
+        assert_eq!(
+            dungeon.archon_url(&builder, spec, MythicPlusTimespan::LastWeek),
+            "https://www.archon.gg/wow/builds/beast-mastery/hunter/mythic-plus/overview/10//ara-kara/last-week"
+        );
+    }
 }
@@ -0,0 +1,115 @@
+use anyhow::{Context, Result};
+use std::fs::{self, File};
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+/// How often to attempt the final rename before falling back to copying.
+/// On Windows the destination can be briefly locked (antivirus, the game client).
+#[cfg(target_os = "windows")]
+const RENAME_ATTEMPTS: u32 = 5;
+#[cfg(not(target_os = "windows"))]
+const RENAME_ATTEMPTS: u32 = 1;
+
+const RENAME_RETRY_DELAY: Duration = Duration::from_millis(100);
+
+/// Write `contents` to `path` without ever leaving a truncated file behind.
+///
+/// The data is written to a temporary file in the same directory, fsynced, and then
+/// renamed over the target. If the rename keeps failing, the temporary file is copied
+/// over the target instead. The temporary file is removed on any failure.
+pub fn write_atomic(path: impl AsRef<Path>, contents: impl AsRef<[u8]>) -> Result<()> {
+    let path = path.as_ref();
+    let temp_path = temp_path_for(path);
+
+    let result = write_temp(&temp_path, contents.as_ref()).and_then(|_| replace(&temp_path, path));
+
+    if result.is_err() && temp_path.exists() {
+        let _ = fs::remove_file(&temp_path);
+    }
+
+    result
+}
+
+/// Temporary file next to `path` (e.g., ".TalentLoadoutsEx.lua.tmp-1234")
+fn temp_path_for(path: &Path) -> PathBuf {
+    let file_name = path
+        .file_name()
+        .and_then(|n| n.to_str())
+        .unwrap_or("talent-heron");
+
+    path.with_file_name(format!(".{}.tmp-{}", file_name, std::process::id()))
+}
+
+/// Write and fsync the temporary file
+fn write_temp(temp_path: &Path, contents: &[u8]) -> Result<()> {
+    let mut file = File::create(temp_path)
+        .with_context(|| format!("Failed to create temporary file {:?}", temp_path))?;
+    file.write_all(contents)
+        .context("Failed to write temporary file")?;
+    file.sync_all().context("Failed to sync temporary file")?;
+    Ok(())
+}
+
+/// Move the temporary file over the target, retrying and falling back to copy + delete
+fn replace(temp_path: &Path, path: &Path) -> Result<()> {
+    let mut last_error = None;
+
+    for attempt in 0..RENAME_ATTEMPTS {
+        if attempt > 0 {
+            std::thread::sleep(RENAME_RETRY_DELAY);
+        }
+
+        match fs::rename(temp_path, path) {
+            Ok(()) => return Ok(()),
+            Err(e) => last_error = Some(e),
+        }
+    }
+
+    // Rename can fail across filesystem boundaries (e.g., network or FUSE mounts)
+    fs::copy(temp_path, path).with_context(|| {
+        format!(
+            "Failed to replace {:?} (rename error: {:?})",
+            path,
+            last_error.map(|e| e.to_string())
+        )
+    })?;
+    fs::remove_file(temp_path).context("Failed to remove temporary file")?;
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_util::temp_dir;
+
+    #[test]
+    fn test_write_atomic_replaces_file() {
+        let dir = temp_dir("atomic-replace");
+        let file = dir.join("TalentLoadoutsEx.lua");
+        fs::write(&file, "old").unwrap();
+
+        write_atomic(&file, "new").unwrap();
+
+        assert_eq!(fs::read_to_string(&file).unwrap(), "new");
+        assert_eq!(fs::read_dir(&dir).unwrap().count(), 1);
+    }
+
+    #[test]
+    fn test_write_atomic_cleans_up_temp_file_on_failure() {
+        let dir = temp_dir("atomic-failure");
+        // A directory in place of the target makes both rename and copy fail
+        let target = dir.join("TalentLoadoutsEx.lua");
+        fs::create_dir_all(target.join("occupied")).unwrap();
+
+        assert!(write_atomic(&target, "data").is_err());
+
+        let entries: Vec<_> = fs::read_dir(&dir)
+            .unwrap()
+            .flatten()
+            .map(|e| e.file_name().to_string_lossy().to_string())
+            .collect();
+        assert_eq!(entries, vec!["TalentLoadoutsEx.lua"]);
+    }
+}
@@ -0,0 +1,147 @@
+use anyhow::{Context, Result};
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// Name of the folder (next to the backed up file) that holds the backups
+pub const BACKUP_DIR_NAME: &str = "talent-heron-backups";
+
+/// Default number of backups kept per file
+pub const DEFAULT_BACKUP_COUNT: usize = 10;
+
+/// Get the backup folder for a given file
+pub fn backup_dir(path: &Path) -> PathBuf {
+    path.parent()
+        .unwrap_or_else(|| Path::new("."))
+        .join(BACKUP_DIR_NAME)
+}
+
+/// Copy `path` to `talent-heron-backups/<file name>.bak-<timestamp>` next to it,
+/// then prune all but the `keep` most recent backups of that file.
+/// Returns None if there is nothing to back up (file doesn't exist) or `keep` is 0.
+pub fn create_backup(path: impl AsRef<Path>, keep: usize) -> Result<Option<PathBuf>> {
+    let path = path.as_ref();
+
+    if keep == 0 || !path.exists() {
+        return Ok(None);
+    }
+
+    let dir = backup_dir(path);
+    fs::create_dir_all(&dir).context("Failed to create backup directory")?;
+
+    let timestamp = chrono::Local::now().format("%Y%m%d-%H%M%S-%3f");
+    let backup_path = dir.join(format!("{}{}", backup_prefix(path), timestamp));
+
+    fs::copy(path, &backup_path)
+        .with_context(|| format!("Failed to create backup at {:?}", backup_path))?;
+
+    prune_backups(path, keep)?;
+
+    Ok(Some(backup_path))
+}
+
+/// List existing backups of `path`, oldest first
+pub fn list_backups(path: impl AsRef<Path>) -> Result<Vec<PathBuf>> {
+    let path = path.as_ref();
+    let dir = backup_dir(path);
+
+    if !dir.exists() {
+        return Ok(Vec::new());
+    }
+
+    let prefix = backup_prefix(path);
+    let mut backups: Vec<PathBuf> = fs::read_dir(&dir)
+        .context("Failed to read backup directory")?
+        .flatten()
+        .map(|entry| entry.path())
+        .filter(|p| {
+            p.is_file()
+                && p.file_name()
+                    .and_then(|n| n.to_str())
+                    .is_some_and(|n| n.starts_with(&prefix))
+        })
+        .collect();
+
+    // Timestamps are zero-padded, so lexical order is chronological order
+    backups.sort();
+
+    Ok(backups)
+}
+
+/// Delete all but the `keep` most recent backups of `path`
+pub fn prune_backups(path: impl AsRef<Path>, keep: usize) -> Result<()> {
+    let backups = list_backups(path)?;
+    let excess = backups.len().saturating_sub(keep);
+
+    for old in &backups[..excess] {
+        fs::remove_file(old).with_context(|| format!("Failed to remove old backup {:?}", old))?;
+    }
+
+    Ok(())
+}
+
+/// File name prefix shared by all backups of `path` (e.g., "TalentLoadoutsEx.lua.bak-")
+fn backup_prefix(path: &Path) -> String {
+    let file_name = path
+        .file_name()
+        .and_then(|n| n.to_str())
+        .unwrap_or("TalentLoadoutsEx.lua");
+
+    format!("{}.bak-", file_name)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_util::temp_dir;
+
+    #[test]
+    fn test_create_backup_copies_file() {
+        let dir = temp_dir("backup-copy");
+        let file = dir.join("TalentLoadoutsEx.lua");
+        fs::write(&file, "TalentLoadoutEx = {}").unwrap();
+
+        let backup = create_backup(&file, 10).unwrap().unwrap();
+
+        assert!(backup.starts_with(dir.join(BACKUP_DIR_NAME)));
+        assert_eq!(fs::read_to_string(&backup).unwrap(), "TalentLoadoutEx = {}");
+    }
+
+    #[test]
+    fn test_create_backup_missing_file() {
+        let dir = temp_dir("backup-missing");
+        let file = dir.join("TalentLoadoutsEx.lua");
+
+        assert!(create_backup(&file, 10).unwrap().is_none());
+        assert!(!backup_dir(&file).exists());
+    }
+
+    #[test]
+    fn test_prune_keeps_most_recent() {
+        let dir = temp_dir("backup-prune");
+        let file = dir.join("TalentLoadoutsEx.lua");
+        let backups = backup_dir(&file);
+        fs::create_dir_all(&backups).unwrap();
+
+        for i in 1..=5 {
+            fs::write(backups.join(format!("TalentLoadoutsEx.lua.bak-2024010{}-000000-000", i)), "").unwrap();
+        }
+        // Unrelated files in the folder are left alone
+        fs::write(backups.join("Other.lua.bak-20240101-000000-000"), "").unwrap();
+
+        prune_backups(&file, 2).unwrap();
+
+        let remaining: Vec<_> = list_backups(&file)
+            .unwrap()
+            .iter()
+            .map(|p| p.file_name().unwrap().to_string_lossy().to_string())
+            .collect();
+        assert_eq!(
+            remaining,
+            vec![
+                "TalentLoadoutsEx.lua.bak-20240104-000000-000",
+                "TalentLoadoutsEx.lua.bak-20240105-000000-000",
+            ]
+        );
+        assert!(backups.join("Other.lua.bak-20240101-000000-000").exists());
+    }
+}
@@ -0,0 +1,228 @@
+use crate::atomic_write::write_atomic;
+use crate::backup::{backup_dir, create_backup, list_backups};
+use crate::btw_loadouts::SETS_VARIABLE_NAME;
+use crate::lua_talent::{AutoSuffixes, LuaTalentManager, TalentFileFormat};
+use crate::talent_loadouts_db::DB_VARIABLE_NAME;
+use anyhow::{Context, Result};
+use chrono::{DateTime, Local};
+use serde::{Deserialize, Serialize};
+use std::cmp::Reverse;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// File in the backup folder recording when and in which format each backup was taken
+pub const INDEX_FILE_NAME: &str = "index.json";
+
+/// Backups registered in a backup folder
+#[derive(Debug, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct BackupIndex {
+    #[serde(default)]
+    backups: Vec<IndexEntry>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct IndexEntry {
+    /// File name of the backup
+    id: String,
+    /// File name of the backed up file
+    source: String,
+    /// When the backup was taken (RFC 3339)
+    created_at: String,
+    format: TalentFileFormat,
+}
+
+impl BackupIndex {
+    /// Read the index of a backup folder; a missing file is an empty index
+    fn load(dir: &Path) -> Result<Self> {
+        let path = dir.join(INDEX_FILE_NAME);
+        if !path.exists() {
+            return Ok(Self::default());
+        }
+        let content = fs::read_to_string(&path).context("Failed to read backup index")?;
+        serde_json::from_str(&content).context("Failed to parse backup index")
+    }
+
+    fn save(&self, dir: &Path) -> Result<()> {
+        let json = serde_json::to_string_pretty(self).context("Failed to serialize backup index")?;
+        write_atomic(dir.join(INDEX_FILE_NAME), json).context("Failed to save backup index")
+    }
+}
+
+/// A backup of a talent file
+#[derive(Debug, Clone, Serialize)]
+pub struct BackupInfo {
+    /// File name of the backup, used to restore it
+    pub id: String,
+    pub path: PathBuf,
+    /// When the backup was taken (RFC 3339); the file's modification time if it isn't registered
+    pub created_at: String,
+    pub size: u64,
+    /// Saved-variable format, TalentLoadoutsEx for backups that aren't registered
+    pub format: TalentFileFormat,
+}
+
+impl BackupInfo {
+    /// Parse the backup; fails unless it parses cleanly
+    pub fn load(&self) -> Result<LuaTalentManager> {
+        let (manager, warnings) =
+            LuaTalentManager::load_from_file_recovering(&self.path, self.format, &AutoSuffixes::default())
+                .with_context(|| format!("Backup {} could not be read", self.id))?;
+        if !warnings.is_empty() {
+            anyhow::bail!(
+                "Backup {} doesn't parse cleanly ({})",
+                self.id,
+                warnings.parse_error.as_deref().unwrap_or("entries could not be read")
+            );
+        }
+        Ok(manager)
+    }
+}
+
+/// Record a backup of `path` taken in `format` in the index of its backup folder
+/// Entries of backups that were pruned in the meantime are dropped.
+pub fn register(backup_path: &Path, path: &Path, format: TalentFileFormat) -> Result<()> {
+    let dir = backup_dir(path);
+    let mut index = BackupIndex::load(&dir)?;
+    index.backups.retain(|entry| dir.join(&entry.id).is_file());
+    index.backups.push(IndexEntry {
+        id: file_name(backup_path),
+        source: file_name(path),
+        created_at: Local::now().to_rfc3339(),
+        format,
+    });
+    index.save(&dir)
+}
+
+/// Backups of `path`, newest first
+/// Registered backups and files named like backups are both listed; loadout counts aren't read.
+pub fn list(path: impl AsRef<Path>) -> Result<Vec<BackupInfo>> {
+    let path = path.as_ref();
+    let dir = backup_dir(path);
+    let index = BackupIndex::load(&dir)?;
+    let source = file_name(path);
+
+    let mut ids: Vec<String> = list_backups(path)?.iter().map(|backup| file_name(backup)).collect();
+    for entry in index.backups.iter().filter(|entry| entry.source == source) {
+        if !ids.contains(&entry.id) && dir.join(&entry.id).is_file() {
+            ids.push(entry.id.clone());
+        }
+    }
+
+    let mut backups = Vec::new();
+    for id in ids {
+        let backup_path = dir.join(&id);
+        let file = fs::metadata(&backup_path).with_context(|| format!("Failed to read backup {}", id))?;
+        let entry = index.backups.iter().find(|entry| entry.id == id && entry.source == source);
+        let created_at = match entry {
+            Some(entry) => entry.created_at.clone(),
+            None => DateTime::<Local>::from(file.modified().context("Failed to read backup time")?).to_rfc3339(),
+        };
+        backups.push(BackupInfo {
+            id,
+            path: backup_path,
+            created_at,
+            size: file.len(),
+            format: entry.map(|entry| entry.format).unwrap_or_default(),
+        });
+    }
+
+    backups.sort_by_cached_key(|backup| Reverse(DateTime::parse_from_rfc3339(&backup.created_at).ok()));
+    Ok(backups)
+}
+
+/// The backup `id` of `path`
+pub fn find(path: impl AsRef<Path>, id: &str) -> Result<BackupInfo> {
+    let path = path.as_ref();
+    list(path)?
+        .into_iter()
+        .find(|backup| backup.id == id)
+        .with_context(|| format!("No backup {} of {}", id, path.display()))
+}
+
+/// Replace `path` with its backup `id` once the backup is checked to parse cleanly
+/// The current file is backed up first (keeping `keep` backups). Returns the restored loadouts and
+/// the backup of the replaced file.
+pub fn restore(path: impl AsRef<Path>, id: &str, keep: usize) -> Result<(LuaTalentManager, Option<PathBuf>)> {
+    let path = path.as_ref();
+    let backup = find(path, id)?;
+    let manager = backup.load()?;
+
+    // Read before backing up the current file, which may prune this backup
+    let content = fs::read(&backup.path).with_context(|| format!("Failed to read backup {}", id))?;
+    let current_backup = create_backup(path, keep)?;
+    if let Some(current_backup) = &current_backup {
+        register(current_backup, path, file_format(current_backup)?)?;
+    }
+    write_atomic(path, content).with_context(|| format!("Failed to restore {}", path.display()))?;
+
+    Ok((manager, current_backup))
+}
+
+/// Saved-variable format of a talent file, from the global it assigns
+fn file_format(path: &Path) -> Result<TalentFileFormat> {
+    let content = fs::read_to_string(path).with_context(|| format!("Failed to read {}", path.display()))?;
+    let assigns = |name: &str| {
+        content
+            .lines()
+            .any(|line| line.split_once('=').is_some_and(|(global, _)| global.trim() == name))
+    };
+    Ok(if assigns(DB_VARIABLE_NAME) {
+        TalentFileFormat::TalentLoadouts
+    } else if assigns(SETS_VARIABLE_NAME) {
+        TalentFileFormat::BtwLoadouts
+    } else {
+        TalentFileFormat::TalentLoadoutsEx
+    })
+}
+
+fn file_name(path: &Path) -> String {
+    path.file_name().map(|name| name.to_string_lossy().into_owned()).unwrap_or_default()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_util::temp_dir;
+
+    #[test]
+    fn test_list_and_restore_backups() {
+        let dir = temp_dir("backup-restore");
+        let file = dir.join("TalentLoadoutsEx.lua");
+        let backups_dir = backup_dir(&file);
+        fs::create_dir_all(&backups_dir).unwrap();
+
+        // A backup from before the index existed, and a registered one that doesn't parse
+        let legacy = "TalentLoadoutEx = {\n}\n";
+        fs::write(backups_dir.join("TalentLoadoutsEx.lua.bak-20240101-000000-000"), legacy).unwrap();
+        fs::write(&file, "TalentLoadoutEx = {").unwrap();
+        let broken = create_backup(&file, 10).unwrap().unwrap();
+        register(&broken, &file, TalentFileFormat::TalentLoadoutsEx).unwrap();
+
+        let listed = list(&file).unwrap();
+        let ids: Vec<_> = listed.iter().map(|backup| backup.id.as_str()).collect();
+        assert_eq!(ids, [file_name(&broken).as_str(), "TalentLoadoutsEx.lua.bak-20240101-000000-000"]);
+        assert_eq!(listed[1].size, legacy.len() as u64);
+        assert!(find(&file, &listed[0].id).unwrap().load().is_err());
+        assert_eq!(find(&file, &listed[1].id).unwrap().load().unwrap().stats().total, 0);
+
+        assert!(restore(&file, &listed[0].id, 10).is_err());
+        assert!(restore(&file, "../TalentLoadoutsEx.lua", 10).is_err());
+        assert_eq!(fs::read_to_string(&file).unwrap(), "TalentLoadoutEx = {");
+
+        // Restoring backs up the replaced file first
+        let (_, replaced) = restore(&file, &listed[1].id, 10).unwrap();
+        let replaced = replaced.unwrap();
+        assert_eq!(fs::read_to_string(&file).unwrap(), legacy);
+        assert_eq!(fs::read_to_string(&replaced).unwrap(), "TalentLoadoutEx = {");
+        assert_eq!(list(&file).unwrap().len(), 3);
+
+        // The replaced file is registered in its own format, not the restored backup's
+        fs::write(&file, "TalentLoadoutsDB = {\n}\n").unwrap();
+        let (_, replaced) = restore(&file, &listed[1].id, 10).unwrap();
+        let id = file_name(&replaced.unwrap());
+        assert_eq!(find(&file, &id).unwrap().format, TalentFileFormat::TalentLoadouts);
+        assert_eq!(find(&file, &listed[1].id).unwrap().format, TalentFileFormat::TalentLoadoutsEx);
+    }
+}
@@ -0,0 +1,274 @@
+use anyhow::{Context, Result};
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// Folder Battle.net installs WoW into below the launcher's default install path
+const WOW_FOLDER_NAME: &str = "World of Warcraft";
+
+/// A game install registered with the Battle.net agent
+#[derive(Debug, Clone, PartialEq)]
+pub struct LauncherInstall {
+    /// Product code (e.g., "wow", "wow_classic", "wowt")
+    pub product_code: String,
+    pub install_path: PathBuf,
+}
+
+impl LauncherInstall {
+    /// Check if this is one of the WoW products (retail, PTR, beta, Classic)
+    pub fn is_wow(&self) -> bool {
+        self.product_code.starts_with("wow")
+    }
+}
+
+/// A decoded protobuf field value; only length-delimited values (strings and messages) are kept
+enum FieldValue<'a> {
+    Bytes(&'a [u8]),
+    Other,
+}
+
+/// Minimal protobuf reader, enough to walk product.db without its schema
+struct ProtoReader<'a> {
+    data: &'a [u8],
+    pos: usize,
+}
+
+impl<'a> ProtoReader<'a> {
+    fn new(data: &'a [u8]) -> Self {
+        Self { data, pos: 0 }
+    }
+
+    fn varint(&mut self) -> Result<u64> {
+        let mut value = 0u64;
+        for shift in (0..64).step_by(7) {
+            let byte = *self.data.get(self.pos).context("Truncated varint")?;
+            self.pos += 1;
+            value |= u64::from(byte & 0x7f) << shift;
+            if byte & 0x80 == 0 {
+                return Ok(value);
+            }
+        }
+        anyhow::bail!("Varint longer than 10 bytes")
+    }
+
+    fn take(&mut self, len: usize) -> Result<&'a [u8]> {
+        let end = self.pos.checked_add(len).filter(|end| *end <= self.data.len());
+        let end = end.context("Field runs past the end of the data")?;
+        let bytes = &self.data[self.pos..end];
+        self.pos = end;
+        Ok(bytes)
+    }
+
+    /// Read the next field number and value, `None` at the end of the data
+    fn next_field(&mut self) -> Result<Option<(u64, FieldValue<'a>)>> {
+        if self.pos >= self.data.len() {
+            return Ok(None);
+        }
+        let key = self.varint()?;
+        let value = match key & 0x7 {
+            0 => {
+                self.varint()?;
+                FieldValue::Other
+            }
+            1 => {
+                self.take(8)?;
+                FieldValue::Other
+            }
+            2 => {
+                let len = usize::try_from(self.varint()?)?;
+                FieldValue::Bytes(self.take(len)?)
+            }
+            5 => {
+                self.take(4)?;
+                FieldValue::Other
+            }
+            wire_type => anyhow::bail!("Unsupported wire type {} at byte {}", wire_type, self.pos),
+        };
+        Ok(Some((key >> 3, value)))
+    }
+}
+
+/// Parse the agent's product.db (a protobuf `Database` message)
+/// Only the product code and install path of each `ProductInstall` (field 1) are read:
+/// `ProductInstall { uid = 1; product_code = 2; settings = 3 { install_path = 1 } }`
+pub fn parse_product_db(data: &[u8]) -> Result<Vec<LauncherInstall>> {
+    let mut installs = Vec::new();
+    let mut database = ProtoReader::new(data);
+
+    while let Some((field, value)) = database.next_field()? {
+        let (1, FieldValue::Bytes(install)) = (field, value) else {
+            continue;
+        };
+
+        let mut product_code = None;
+        let mut install_path = None;
+        let mut reader = ProtoReader::new(install);
+        while let Some((field, value)) = reader.next_field()? {
+            match (field, value) {
+                (2, FieldValue::Bytes(code)) => product_code = Some(String::from_utf8_lossy(code).into_owned()),
+                (3, FieldValue::Bytes(settings)) => {
+                    let mut settings = ProtoReader::new(settings);
+                    while let Some((field, value)) = settings.next_field()? {
+                        if let (1, FieldValue::Bytes(path)) = (field, value) {
+                            install_path = Some(String::from_utf8_lossy(path).into_owned());
+                        }
+                    }
+                }
+                _ => {}
+            }
+        }
+
+        if let (Some(product_code), Some(install_path)) = (product_code, install_path) {
+            if !install_path.is_empty() {
+                installs.push(LauncherInstall {
+                    product_code,
+                    install_path: PathBuf::from(install_path),
+                });
+            }
+        }
+    }
+
+    Ok(installs)
+}
+
+/// Read the launcher's default install folder (`Client.Install.DefaultInstallPath`) from
+/// Battle.net.config
+pub fn parse_battlenet_config(contents: &str) -> Result<Option<PathBuf>> {
+    let config: serde_json::Value = serde_json::from_str(contents).context("Invalid Battle.net.config")?;
+    Ok(config
+        .pointer("/Client/Install/DefaultInstallPath")
+        .and_then(|path| path.as_str())
+        .filter(|path| !path.is_empty())
+        .map(PathBuf::from))
+}
+
+/// WoW install folders from product.db, followed by the WoW folder below the default
+/// install path from Battle.net.config
+/// Paths aren't checked for existence; launcher files that are missing or can't be
+/// parsed are skipped.
+pub fn wow_install_paths_from(product_db: &Path, battlenet_config: &Path) -> Vec<PathBuf> {
+    let mut paths = Vec::new();
+
+    if let Ok(data) = fs::read(product_db) {
+        match parse_product_db(&data) {
+            Ok(installs) => paths.extend(
+                installs
+                    .into_iter()
+                    .filter(LauncherInstall::is_wow)
+                    .map(|install| install.install_path),
+            ),
+            Err(e) => tracing::warn!(path = %product_db.display(), "Failed to read Battle.net product database: {}", e),
+        }
+    }
+
+    if let Ok(contents) = fs::read_to_string(battlenet_config) {
+        match parse_battlenet_config(&contents) {
+            Ok(Some(default_path)) => paths.push(default_path.join(WOW_FOLDER_NAME)),
+            Ok(None) => {}
+            Err(e) => tracing::warn!(path = %battlenet_config.display(), "Failed to read Battle.net config: {}", e),
+        }
+    }
+
+    paths.dedup();
+    paths
+}
+
+/// WoW install folders known to the Battle.net launcher on this platform
+pub fn wow_install_paths() -> Vec<PathBuf> {
+    #[allow(unused_mut)]
+    let mut locations: Vec<(PathBuf, PathBuf)> = Vec::new();
+
+    #[cfg(target_os = "windows")]
+    {
+        let program_data = std::env::var("ProgramData").unwrap_or_else(|_| "C:\\ProgramData".to_string());
+        let program_data = PathBuf::from(program_data).join("Battle.net");
+        let config_dir = std::env::var("APPDATA")
+            .map(|app_data| PathBuf::from(app_data).join("Battle.net"))
+            .unwrap_or_else(|_| program_data.clone());
+        locations.push((
+            program_data.join("Agent").join("product.db"),
+            config_dir.join("Battle.net.config"),
+        ));
+    }
+
+    #[cfg(target_os = "macos")]
+    {
+        let shared = PathBuf::from("/Users/Shared/Battle.net");
+        let config_dir = std::env::var("HOME")
+            .map(|home| PathBuf::from(home).join("Library/Application Support/Battle.net"))
+            .unwrap_or_else(|_| shared.clone());
+        locations.push((shared.join("Agent/product.db"), config_dir.join("Battle.net.config")));
+    }
+
+    locations
+        .iter()
+        .flat_map(|(product_db, config)| wow_install_paths_from(product_db, config))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_util::temp_dir;
+
+    const PRODUCT_DB: &[u8] = include_bytes!("../tests/fixtures/product.db");
+
+    #[test]
+    fn test_parse_product_db() {
+        let installs = parse_product_db(PRODUCT_DB).unwrap();
+        let found: Vec<_> = installs
+            .iter()
+            .map(|i| (i.product_code.as_str(), i.install_path.to_str().unwrap(), i.is_wow()))
+            .collect();
+        assert_eq!(
+            found,
+            [
+                ("agent", "C:/ProgramData/Battle.net/Agent", false),
+                ("wow", "D:/Games/World of Warcraft", true),
+                ("wow_classic", "D:/Games/World of Warcraft", true),
+                ("wowt", "E:/World of Warcraft/_ptr_", true),
+                ("pro", "C:/Program Files (x86)/Overwatch", false),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_parse_corrupt_product_db() {
+        // Cut off in the middle of an install
+        assert!(parse_product_db(&PRODUCT_DB[..PRODUCT_DB.len() / 2]).is_err());
+        // Unsupported wire type
+        assert!(parse_product_db(&[0x0f, 0x00]).is_err());
+        assert!(parse_product_db(b"").unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_parse_battlenet_config() {
+        let path = parse_battlenet_config(include_str!("../tests/fixtures/Battle.net.config")).unwrap();
+        assert_eq!(path, Some(PathBuf::from("F:/Blizzard")));
+
+        assert_eq!(parse_battlenet_config("{\"Client\": {}}").unwrap(), None);
+        assert!(parse_battlenet_config("{\"Client\": ").is_err());
+    }
+
+    #[test]
+    fn test_wow_install_paths_from_launcher_files() {
+        let dir = temp_dir("battlenet");
+        fs::write(dir.join("product.db"), PRODUCT_DB).unwrap();
+        fs::write(dir.join("Battle.net.config"), include_str!("../tests/fixtures/Battle.net.config")).unwrap();
+
+        let paths = wow_install_paths_from(&dir.join("product.db"), &dir.join("Battle.net.config"));
+        assert_eq!(
+            paths,
+            [
+                PathBuf::from("D:/Games/World of Warcraft"),
+                PathBuf::from("E:/World of Warcraft/_ptr_"),
+                PathBuf::from("F:/Blizzard/World of Warcraft"),
+            ]
+        );
+
+        // Missing and corrupt files are skipped
+        fs::write(dir.join("product.db"), [0x0a, 0xff]).unwrap();
+        fs::write(dir.join("Battle.net.config"), "not json").unwrap();
+        assert!(wow_install_paths_from(&dir.join("product.db"), &dir.join("Battle.net.config")).is_empty());
+        assert!(wow_install_paths_from(&dir.join("missing.db"), &dir.join("missing.config")).is_empty());
+    }
+}
@@ -0,0 +1,535 @@
+use crate::archon::{equivalent_content_slugs, RaidDifficulty, TalentIdentifier};
+use crate::lua_talent::{
+    lua_escape_string, normalize_source, string_value, AutoSuffixes, LuaTalentManager, TalentLoadout,
+};
+use crate::wow::WowClass;
+use anyhow::{Context, Result};
+use full_moon::ast::{Expression, Field, Stmt, TableConstructor, Var};
+use std::collections::HashMap;
+use std::hash::Hash;
+
+/// Saved variable written by the BtWLoadouts addon
+pub const SETS_VARIABLE_NAME: &str = "BtWLoadoutsSets";
+
+/// Blizzard difficulty ID used for Mythic+ conditions
+const MYTHIC_PLUS_DIFFICULTY_ID: u8 = 8;
+
+/// Instance ID of Nerub-ar Palace
+const NERUBAR_PALACE: u32 = 2657;
+
+/// Current-season raid bosses as (Archon slug, instance ID, Encounter Journal encounter ID)
+/// Instance IDs are the map IDs the client reports inside an instance (`GetInstanceInfo`), which
+/// BtWLoadouts compares conditions against.
+const RAID_ENCOUNTERS: &[(&str, u32, u32)] = &[
+    ("ulgrax-the-devourer", NERUBAR_PALACE, 2607),
+    ("the-bloodbound-horror", NERUBAR_PALACE, 2611),
+    ("sikran", NERUBAR_PALACE, 2599),
+    ("rashanan", NERUBAR_PALACE, 2609),
+    ("broodtwister", NERUBAR_PALACE, 2612),
+    ("nexus-princess-kyveza", NERUBAR_PALACE, 2601),
+    ("the-silken-court", NERUBAR_PALACE, 2608),
+    ("queen-ansurek", NERUBAR_PALACE, 2602),
+];
+
+/// Instance IDs of current-season Mythic+ dungeons, by Archon slug
+const DUNGEON_INSTANCES: &[(&str, u32)] = &[
+    ("ara-kara", 2660),
+    ("city-of-threads", 2669),
+    ("the-dawnbreaker", 2662),
+    ("the-stonevault", 2652),
+    ("mists-of-tirna-scithe", 2290),
+    ("the-necrotic-wake", 2286),
+    ("siege-of-boralus", 1822),
+    ("grim-batol", 670),
+];
+
+/// Parts of an existing BtWLoadouts file that talent-heron doesn't manage
+/// Everything is kept verbatim as Lua source and written back unchanged
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct BtwState {
+    /// Talent sets that aren't auto-generated (`[setID] = { ... }`)
+    pub manual_sets: Vec<String>,
+    /// Profiles that aren't auto-generated
+    pub manual_profiles: Vec<String>,
+    /// Conditions that aren't auto-generated
+    pub manual_conditions: Vec<String>,
+    /// Other top-level fields (equipment, action bars, ...)
+    pub extra_fields: Vec<String>,
+    /// Highest talent set ID in the file, auto-generated sets included
+    pub max_set_id: u32,
+    /// Highest profile ID in the file, auto-generated profiles included
+    pub max_profile_id: u32,
+    /// Highest condition ID in the file, auto-generated conditions included
+    pub max_condition_id: u32,
+    /// IDs of auto-generated talent sets by (spec ID, name), kept when they're written again so
+    /// manual profiles can refer to them
+    pub auto_set_ids: HashMap<(u16, String), u32>,
+    /// IDs of auto-generated profiles by (spec ID, name)
+    pub auto_profile_ids: HashMap<(u16, String), u32>,
+    /// IDs of auto-generated conditions by (profile ID, boss or instance ID)
+    pub auto_condition_ids: HashMap<(u32, u32), u32>,
+}
+
+/// What activates a condition: a raid boss or a Mythic+ dungeon
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum ConditionTarget {
+    Boss { instance_id: u32, boss_id: u32 },
+    Dungeon { instance_id: u32 },
+}
+
+impl ConditionTarget {
+    /// Boss ID, or instance ID for a dungeon, telling apart the conditions of a profile
+    fn key(&self) -> u32 {
+        match self {
+            Self::Boss { boss_id, .. } => *boss_id,
+            Self::Dungeon { instance_id } => *instance_id,
+        }
+    }
+}
+
+/// Hands out the IDs of one table: auto-generated entries keep the ID they were read with, new
+/// ones count up from the highest ID in the file
+struct IdAllocator<K> {
+    existing: HashMap<K, u32>,
+    last: u32,
+}
+
+impl<K: Hash + Eq> IdAllocator<K> {
+    fn new(existing: HashMap<K, u32>, last: u32) -> Self {
+        Self { existing, last }
+    }
+
+    fn id(&mut self, key: K) -> u32 {
+        self.existing.remove(&key).unwrap_or_else(|| {
+            self.last += 1;
+            self.last
+        })
+    }
+}
+
+/// Blizzard difficulty ID for a raid difficulty
+fn raid_difficulty_id(difficulty: RaidDifficulty) -> u8 {
+    match difficulty {
+        RaidDifficulty::Normal => 14,
+        RaidDifficulty::Heroic => 15,
+        RaidDifficulty::Mythic => 16,
+    }
+}
+
+/// Parse a BtWLoadouts saved variables file
+///
+/// Auto-generated talent sets are loaded into the manager (class and spec index are
+/// derived from the set's spec ID); manual sets, their conditions and all other
+/// tables are preserved verbatim in the manager's `BtwState`. Sets count as auto-generated
+/// if their name carries the current or a legacy suffix.
+pub fn parse_sets(content: &str, suffixes: &AutoSuffixes) -> Result<LuaTalentManager> {
+    let (content, crlf) = normalize_source(content);
+    let ast = full_moon::parse(&content).context("Failed to parse Lua file")?;
+    let mut manager = LuaTalentManager::new();
+    manager.set_crlf(crlf);
+    let mut state = BtwState::default();
+
+    for stmt in ast.nodes().stmts() {
+        let Stmt::Assignment(assignment) = stmt else {
+            continue;
+        };
+
+        let is_sets = assignment
+            .variables()
+            .iter()
+            .any(|v| matches!(v, Var::Name(name) if name.token().to_string() == SETS_VARIABLE_NAME));
+
+        if let (true, Some(Expression::TableConstructor(table))) =
+            (is_sets, assignment.expressions().iter().next())
+        {
+            parse_sets_table(table, suffixes, &mut manager, &mut state);
+        }
+    }
+
+    manager.set_btw_state(state);
+    Ok(manager)
+}
+
+/// Parse the top-level `BtWLoadoutsSets` table
+fn parse_sets_table(
+    table: &TableConstructor,
+    suffixes: &AutoSuffixes,
+    manager: &mut LuaTalentManager,
+    state: &mut BtwState,
+) {
+    for field in table.fields() {
+        if let Field::ExpressionKey {
+            key,
+            value: Expression::TableConstructor(inner),
+            ..
+        } = field
+        {
+            match string_value(key).as_deref() {
+                Some("talents") => {
+                    for set_field in inner.fields() {
+                        parse_talent_set(set_field, suffixes, manager, state);
+                    }
+                    continue;
+                }
+                Some("profiles") => {
+                    for profile_field in inner.fields() {
+                        parse_profile(profile_field, suffixes, state);
+                    }
+                    continue;
+                }
+                Some("conditions") => {
+                    for condition_field in inner.fields() {
+                        parse_condition(condition_field, suffixes, state);
+                    }
+                    continue;
+                }
+                _ => {}
+            }
+        }
+
+        state.extra_fields.push(field.to_string().trim().to_string());
+    }
+}
+
+/// Parse a single talent set; auto-generated ones go into the manager
+fn parse_talent_set(field: &Field, suffixes: &AutoSuffixes, manager: &mut LuaTalentManager, state: &mut BtwState) {
+    let fields = entry_fields(field);
+
+    if is_auto_name(&fields, suffixes) {
+        let spec = field_value(&fields, "specID")
+            .and_then(|id| id.parse::<u16>().ok())
+            .and_then(WowClass::spec_index_from_id);
+
+        if let (Some((class, spec_index)), Some(name), Some(text)) = (
+            spec,
+            field_value(&fields, "name"),
+            field_value(&fields, "exportString"),
+        ) {
+            manager.add_talent(
+                class.to_lua_format().to_string(),
+                spec_index,
+                TalentLoadout::new(name.to_string(), text.to_string()),
+            );
+            if let Some(spec_id) = class.spec_id_for_index(spec_index) {
+                state.auto_set_ids.insert((spec_id, name.to_string()), entry_id(&fields));
+            }
+            state.max_set_id = state.max_set_id.max(entry_id(&fields));
+            return;
+        }
+    }
+
+    state.max_set_id = state.max_set_id.max(entry_id(&fields));
+    state.manual_sets.push(field.to_string().trim().to_string());
+}
+
+/// Parse a single profile; auto-generated ones are dropped and written again from the loadouts
+fn parse_profile(field: &Field, suffixes: &AutoSuffixes, state: &mut BtwState) {
+    let fields = entry_fields(field);
+    state.max_profile_id = state.max_profile_id.max(entry_id(&fields));
+
+    let spec_id = field_value(&fields, "specID").and_then(|id| id.parse::<u16>().ok());
+    match (is_auto_name(&fields, suffixes), spec_id, field_value(&fields, "name")) {
+        (true, Some(spec_id), Some(name)) => {
+            state.auto_profile_ids.insert((spec_id, name.to_string()), entry_id(&fields));
+        }
+        _ => state.manual_profiles.push(field.to_string().trim().to_string()),
+    }
+}
+
+/// Parse a single condition; auto-generated ones are dropped and written again from the loadouts
+fn parse_condition(field: &Field, suffixes: &AutoSuffixes, state: &mut BtwState) {
+    let fields = entry_fields(field);
+    state.max_condition_id = state.max_condition_id.max(entry_id(&fields));
+
+    if !is_auto_name(&fields, suffixes) {
+        state.manual_conditions.push(field.to_string().trim().to_string());
+        return;
+    }
+    let number = |key| field_value(&fields, key).and_then(|value| value.parse::<u32>().ok());
+    if let (Some(profile_id), Some(target)) = (number("profileSet"), number("bossID").or(number("instanceID"))) {
+        state.auto_condition_ids.insert((profile_id, target), entry_id(&fields));
+    }
+}
+
+/// Collect the string and number fields of a set/condition entry as key/value pairs
+fn entry_fields(field: &Field) -> Vec<(String, String)> {
+    let table = match field {
+        Field::ExpressionKey {
+            value: Expression::TableConstructor(table),
+            ..
+        }
+        | Field::NoKey(Expression::TableConstructor(table)) => table,
+        _ => return Vec::new(),
+    };
+
+    table
+        .fields()
+        .iter()
+        .filter_map(|field| {
+            let (key, value) = match field {
+                Field::ExpressionKey { key, value, .. } => (string_value(key)?, value),
+                Field::NameKey { key, value, .. } => (key.token().to_string(), value),
+                _ => return None,
+            };
+
+            let value = match value {
+                Expression::Number(num) => num.token().to_string(),
+                other => string_value(other)?,
+            };
+
+            Some((key, value))
+        })
+        .collect()
+}
+
+fn field_value<'a>(fields: &'a [(String, String)], key: &str) -> Option<&'a str> {
+    fields.iter().find(|(k, _)| k == key).map(|(_, v)| v.as_str())
+}
+
+fn entry_id(fields: &[(String, String)]) -> u32 {
+    field_value(fields, "setID")
+        .and_then(|id| id.parse().ok())
+        .unwrap_or(0)
+}
+
+fn is_auto_name(fields: &[(String, String)], suffixes: &AutoSuffixes) -> bool {
+    field_value(fields, "name")
+        .is_some_and(|name| suffixes.matches_any(&TalentLoadout::new(name.to_string(), String::new())))
+}
+
+/// Conditions of an auto-generated build as (target, difficulty ID)
+/// Raid builds activate on their bosses (one per boss of a collapsed build), M+ builds in their
+/// dungeon. Content without a known instance gets no condition.
+fn conditions_for(name: &str, suffix: &str) -> Vec<(ConditionTarget, u8)> {
+    match TalentIdentifier::from_talent_name(name, suffix) {
+        Some(TalentIdentifier::Raid { difficulty, boss, .. }) => {
+            let difficulty_id = raid_difficulty_id(difficulty);
+            boss.split('+')
+                .filter_map(raid_encounter)
+                .map(|(instance_id, boss_id)| (ConditionTarget::Boss { instance_id, boss_id }, difficulty_id))
+                .collect()
+        }
+        Some(TalentIdentifier::MythicPlus { dungeon, .. }) => dungeon_instance(&dungeon)
+            .map(|instance_id| (ConditionTarget::Dungeon { instance_id }, MYTHIC_PLUS_DIFFICULTY_ID))
+            .into_iter()
+            .collect(),
+        None => Vec::new(),
+    }
+}
+
+/// Instance and encounter ID of a raid boss slug (aliases accepted)
+fn raid_encounter(slug: &str) -> Option<(u32, u32)> {
+    equivalent_content_slugs(slug).into_iter().find_map(|slug| {
+        RAID_ENCOUNTERS
+            .iter()
+            .find(|(s, _, _)| *s == slug)
+            .map(|(_, instance_id, boss_id)| (*instance_id, *boss_id))
+    })
+}
+
+/// Instance ID of a dungeon slug (aliases accepted)
+fn dungeon_instance(slug: &str) -> Option<u32> {
+    equivalent_content_slugs(slug)
+        .into_iter()
+        .find_map(|slug| DUNGEON_INSTANCES.iter().find(|(s, _)| *s == slug).map(|(_, id)| *id))
+}
+
+/// Lua source of a condition activating `profile_id`
+fn condition_entry(id: u32, name: &str, profile_id: u32, target: ConditionTarget, difficulty_id: u8) -> String {
+    let content = match target {
+        ConditionTarget::Boss { instance_id, boss_id } => format!(
+            "[\"type\"] = \"raid\", [\"instanceID\"] = {}, [\"difficultyID\"] = {}, [\"bossID\"] = {}",
+            instance_id, difficulty_id, boss_id
+        ),
+        ConditionTarget::Dungeon { instance_id } => format!(
+            "[\"type\"] = \"party\", [\"instanceID\"] = {}, [\"difficultyID\"] = {}",
+            instance_id, difficulty_id
+        ),
+    };
+    format!(
+        "[{id}] = {{ [\"setID\"] = {id}, [\"name\"] = \"{}\", [\"profileSet\"] = {}, {} }}",
+        lua_escape_string(name),
+        profile_id,
+        content,
+        id = id,
+    )
+}
+
+/// Convert the manager's loadouts to the BtWLoadouts saved variables format
+/// Preserved manual entries are written first, followed by one talent set per loadout in the
+/// manager, plus a profile with its conditions for recognized content. Sets, profiles and
+/// conditions that were read from the file keep their IDs; new ones get IDs above any in the file.
+pub fn to_sets_lua_string(manager: &LuaTalentManager) -> String {
+    let state = manager.btw_state().cloned().unwrap_or_default();
+
+    let mut sets = String::new();
+    let mut profiles = String::new();
+    let mut conditions = String::new();
+    let mut set_ids = IdAllocator::new(state.auto_set_ids, state.max_set_id);
+    let mut profile_ids = IdAllocator::new(state.auto_profile_ids, state.max_profile_id);
+    let mut condition_ids = IdAllocator::new(state.auto_condition_ids, state.max_condition_id);
+
+    for raw in &state.manual_sets {
+        sets.push_str(&format!("    {},\n", raw));
+    }
+    for raw in &state.manual_profiles {
+        profiles.push_str(&format!("    {},\n", raw));
+    }
+    for raw in &state.manual_conditions {
+        conditions.push_str(&format!("    {},\n", raw));
+    }
+
+    for class_name in manager.class_names() {
+        let (Some(class), Some(class_talents)) =
+            (WowClass::from_lua_format(class_name), manager.get_class_talents(class_name))
+        else {
+            continue;
+        };
+
+        let mut spec_indices: Vec<_> = class_talents.keys().copied().collect();
+        spec_indices.sort();
+
+        for spec_index in spec_indices {
+            let Some(spec_id) = class.spec_id_for_index(spec_index) else {
+                continue;
+            };
+
+            for talent in &class_talents[&spec_index] {
+                let name = lua_escape_string(&talent.name);
+                let set_id = set_ids.id((spec_id, talent.name.clone()));
+                sets.push_str(&format!(
+                    "    [{id}] = {{ [\"setID\"] = {id}, [\"specID\"] = {}, [\"name\"] = \"{}\", [\"exportString\"] = \"{}\" }},\n",
+                    spec_id,
+                    name,
+                    lua_escape_string(&talent.text),
+                    id = set_id,
+                ));
+
+                // Conditions activate profiles, so the set gets a profile of its own
+                let targets = conditions_for(&talent.name, &manager.auto_suffixes().current);
+                if targets.is_empty() {
+                    continue;
+                }
+                let profile_id = profile_ids.id((spec_id, talent.name.clone()));
+                profiles.push_str(&format!(
+                    "    [{id}] = {{ [\"setID\"] = {id}, [\"specID\"] = {}, [\"name\"] = \"{}\", [\"sets\"] = {{ [\"talents\"] = {{ {} }} }} }},\n",
+                    spec_id,
+                    name,
+                    set_id,
+                    id = profile_id,
+                ));
+                for (target, difficulty_id) in targets {
+                    let id = condition_ids.id((profile_id, target.key()));
+                    conditions.push_str(&format!(
+                        "    {},\n",
+                        condition_entry(id, &talent.name, profile_id, target, difficulty_id)
+                    ));
+                }
+            }
+        }
+    }
+
+    let mut result = format!("{} = {{\n", SETS_VARIABLE_NAME);
+    result.push_str(&format!("  [\"talents\"] = {{\n{}  }},\n", sets));
+    result.push_str(&format!("  [\"profiles\"] = {{\n{}  }},\n", profiles));
+    result.push_str(&format!("  [\"conditions\"] = {{\n{}  }},\n", conditions));
+    for field in &state.extra_fields {
+        result.push_str(&format!("  {},\n", field));
+    }
+    result.push_str("}\n");
+
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const FIXTURE: &str = include_str!("../tests/fixtures/BtWLoadouts.lua");
+
+    #[test]
+    fn test_parse_sets_fixture() {
+        let manager = parse_sets(FIXTURE, &AutoSuffixes::default()).unwrap();
+
+        // Only the auto-generated sets are managed
+        let arms = manager.get_spec_talents("WARRIOR", 1).unwrap();
+        assert_eq!(arms.len(), 2);
+        assert_eq!(arms[0].name, "R-heroic-sikran_ARCT");
+        assert_eq!(arms[0].text, "warrior/arms/XYZ789");
+
+        let state = manager.btw_state().unwrap();
+        assert_eq!(state.manual_sets.len(), 1);
+        assert!(state.manual_sets[0].contains("My Arms Build"));
+        assert_eq!(state.manual_profiles.len(), 2);
+        assert_eq!(state.manual_conditions.len(), 1);
+        assert_eq!(state.extra_fields.len(), 1);
+        assert_eq!((state.max_set_id, state.max_profile_id, state.max_condition_id), (9, 3, 4));
+        assert_eq!(state.auto_set_ids[&(71, "R-heroic-sikran_ARCT".to_string())], 7);
+        assert_eq!(state.auto_profile_ids[&(71, "R-heroic-sikran_ARCT".to_string())], 3);
+        assert_eq!(state.auto_condition_ids[&(3, 2599)], 4);
+    }
+
+    #[test]
+    fn test_manual_sets_survive_update() {
+        let mut manager = parse_sets(FIXTURE, &AutoSuffixes::default()).unwrap();
+        manager.remove_auto_generated("WARRIOR", 1);
+        for (name, text) in [
+            ("R-heroic-sikran_ARCT", "warrior/arms/NEW"),
+            ("R-mythic-sikran_ARCT", "warrior/arms/MYTHIC"),
+            ("R-heroic-rashanan+broodtwister_ARCT", "warrior/arms/COLLAPSED"),
+        ] {
+            manager.add_talent("WARRIOR".to_string(), 1, TalentLoadout::new(name.to_string(), text.to_string()));
+        }
+        manager.add_talent(
+            "MAGE".to_string(),
+            3,
+            TalentLoadout::new("M+-ara-kara_ARCT".to_string(), "mage/frost/GHI789".to_string()),
+        );
+
+        let output = to_sets_lua_string(&manager);
+
+        // Manual entries are written back verbatim
+        assert!(output.contains("[\"name\"] = \"My Arms Build\""));
+        assert!(output.contains("[\"name\"] = \"Arms raid\", [\"sets\"] = { [\"talents\"] = { 7 } }"));
+        assert!(output.contains("[\"name\"] = \"My Raid Condition\""));
+        assert!(output.contains("[\"equipment\"]"));
+
+        // A rewritten set keeps its ID, so the manual profile still points at it
+        assert!(output.contains(
+            "[7] = { [\"setID\"] = 7, [\"specID\"] = 71, [\"name\"] = \"R-heroic-sikran_ARCT\", \
+             [\"exportString\"] = \"warrior/arms/NEW\" }"
+        ));
+        assert!(output.contains("[\"sets\"] = { [\"talents\"] = { 7 } } },\n    [4]"));
+        assert!(output.contains(
+            "[4] = { [\"setID\"] = 4, [\"name\"] = \"R-heroic-sikran_ARCT\", [\"profileSet\"] = 3, \
+             [\"type\"] = \"raid\", [\"instanceID\"] = 2657, [\"difficultyID\"] = 15, [\"bossID\"] = 2599 }"
+        ));
+
+        // New entries get IDs above any in the file, the removed set's ID isn't reused
+        assert!(!output.contains("[9] ="));
+        assert!(output.contains(
+            "[\"name\"] = \"R-mythic-sikran_ARCT\", [\"profileSet\"] = 5, \
+             [\"type\"] = \"raid\", [\"instanceID\"] = 2657, [\"difficultyID\"] = 16, [\"bossID\"] = 2599"
+        ));
+        assert!(output.contains(
+            "[\"profileSet\"] = 6, [\"type\"] = \"raid\", [\"instanceID\"] = 2657, \
+             [\"difficultyID\"] = 15, [\"bossID\"] = 2609"
+        ));
+        assert!(output.contains(
+            "[\"profileSet\"] = 6, [\"type\"] = \"raid\", [\"instanceID\"] = 2657, \
+             [\"difficultyID\"] = 15, [\"bossID\"] = 2612"
+        ));
+        assert!(output.contains(
+            "[\"name\"] = \"M+-ara-kara_ARCT\", [\"profileSet\"] = 4, \
+             [\"type\"] = \"party\", [\"instanceID\"] = 2660, [\"difficultyID\"] = 8 }"
+        ));
+
+        // Round trip keeps the managed loadouts, preserved state and IDs
+        let reparsed = parse_sets(&output, &AutoSuffixes::default()).unwrap();
+        assert!(manager.diff(&reparsed).is_empty());
+        assert_eq!(reparsed.btw_state().unwrap().manual_sets.len(), 1);
+        assert_eq!(reparsed.btw_state().unwrap().manual_profiles.len(), 2);
+        assert_eq!(reparsed.btw_state().unwrap().manual_conditions.len(), 1);
+        assert_eq!(to_sets_lua_string(&reparsed), output);
+    }
+}
@@ -1,92 +1,730 @@
+use crate::archon::{equivalent_content_slugs, RaidDifficulty, DEFAULT_AUTO_SUFFIX};
+use crate::backup::DEFAULT_BACKUP_COUNT;
+use crate::icons::IconOverrides;
+use crate::logging::LogLevel;
+use crate::lua_talent::TalentFileFormat;
+use crate::scheduler::Schedule;
+use crate::warcraft_logs::DiscoveredContent;
+use crate::wow::{closest_match, did_you_mean, GameFlavor, Role, WowClass};
+use crate::wow_scanner::{DiscoveredCharacter, WowScanner};
+use crate::atomic_write::write_atomic;
+use anyhow::Context;
 use serde::{Deserialize, Serialize};
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
+
+/// Version of the config format written by this build; older configs are upgraded by `migrate`
+pub const CONFIG_VERSION: u32 = 1;
+
+/// Number of characters a starter config includes (see `starter_config`)
+const STARTER_CHARACTER_COUNT: usize = 5;
 
 /// Configuration structure for the Archon talent fetcher
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
 pub struct Config {
+    /// Config format version (see `CONFIG_VERSION`); 0 for configs written before it existed
+    #[serde(default)]
+    pub version: u32,
+
     /// List of characters to fetch talents for
     pub characters: Vec<Character>,
 
     /// Raid difficulties to fetch (e.g., ["heroic", "normal", "mythic"])
+    #[serde(default)]
     pub raid_difficulties: Vec<String>,
 
-    /// List of raid boss names (lowercase, hyphenated)
+    /// List of raid boss names (lowercase, hyphenated), in encounter order
+    #[serde(default)]
     pub raid_bosses: Vec<String>,
 
+    /// Bosses to fetch per difficulty, e.g. a full clear on heroic but only the last bosses on
+    /// mythic; when set, it replaces `raid_difficulties` and `raid_bosses`, which fetch every boss
+    /// on every difficulty
+    #[serde(default)]
+    pub raid_content: Vec<RaidContent>,
+
     /// List of dungeon names (lowercase, hyphenated)
     pub dungeons: Vec<String>,
 
-    /// Whether to clear all previous auto-generated builds before updating
-    /// When false: only removes builds for classes/specs being updated
-    /// When true: removes ALL auto-generated builds (with _ARCT suffix)
-    pub clear_previous_builds: bool,
+    /// Bosses of `raid_bosses` not to fetch, e.g. ones skipped on alts; kept separate so a
+    /// discovered boss list can be replaced without losing them
+    #[serde(default)]
+    pub skip_raid_bosses: Vec<String>,
+
+    /// Dungeons of `dungeons` not to fetch
+    #[serde(default)]
+    pub skip_dungeons: Vec<String>,
 
-    /// Path to TalentLoadoutsEx.lua file
+    /// Which previous auto-generated builds to clear before updating (see `ClearScope`)
+    /// Older configs use a bool: true for `All`, false for `ConfiguredSpecsOnly`.
+    #[serde(deserialize_with = "clear_scope_or_bool")]
+    pub clear_previous_builds: ClearScope,
+
+    /// Path to TalentLoadoutsEx.lua file, for characters without their own `output_path`
     /// Example: "/Applications/World of Warcraft/_retail_/WTF/Account/400793633#1/SavedVariables/TalentLoadoutsEx.lua"
-    pub output_path: PathBuf,
+    #[serde(default)]
+    pub output_path: Option<PathBuf>,
+
+    /// More talent files (e.g. of a PTR installation) that get the same changes as the output
+    /// files; their manual loadouts are kept
+    #[serde(default)]
+    pub additional_output_paths: Vec<PathBuf>,
+
+    /// Number of backups of the output file to keep (0 disables backups)
+    #[serde(default = "default_backup_count")]
+    pub backup_count: usize,
+
+    /// Whether to merge auto-generated builds with identical talent strings within a spec
+    /// into a single loadout (e.g., "R-heroic-sikran+broodtwister_ARCT")
+    #[serde(default)]
+    pub merge_identical_builds: bool,
+
+    /// Whether raid builds of a difficulty that share a talent string are written as one loadout:
+    /// the largest group as e.g. "R-heroic-general_ARCT", other groups named after their bosses
+    #[serde(default)]
+    pub collapse_identical: bool,
+
+    /// Number of previous versions of each generated build to keep, renamed with their date
+    /// (e.g., "R-heroic-sikran_ARCT~0614"), when a spec's builds are replaced; none if unset
+    #[serde(default)]
+    pub keep_previous_builds: Option<usize>,
+
+    /// Maximum number of loadouts per spec; excess auto-generated builds are evicted
+    #[serde(default)]
+    pub max_loadouts_per_spec: Option<usize>,
+
+    /// Saved-variable format of the output file ("talentloadoutsex", "talentloadouts" or "btwloadouts")
+    #[serde(default)]
+    pub output_format: TalentFileFormat,
+
+    /// Whether to also write a markdown list of the fetched builds and their import strings
+    #[serde(default)]
+    pub also_write_plaintext: bool,
+
+    /// Where to write the markdown build list (defaults to the app data directory)
+    #[serde(default)]
+    pub plaintext_path: Option<PathBuf>,
+
+    /// Icons to use instead of the built-in encounter, dungeon and spec icons
+    #[serde(default)]
+    pub icon_overrides: IconOverrides,
+
+    /// Name suffix that marks loadouts as auto-generated (default "_ARCT")
+    #[serde(default = "default_auto_suffix")]
+    pub auto_suffix: String,
+
+    /// Previously used suffixes; builds carrying them are cleaned up like auto-generated ones
+    #[serde(default)]
+    pub legacy_suffixes: Vec<String>,
+
+    /// Write the output file even if the existing one had to be recovered after a parse error
+    /// (loadouts that couldn't be recovered, the GUI table and addon options are lost), or if
+    /// the update didn't change it
+    #[serde(default)]
+    pub force_write: bool,
+
+    /// Clear previous builds even though entries of the existing file were skipped while reading it
+    #[serde(default)]
+    pub confirm_clear_with_issues: bool,
+
+    /// Write the talent file even though WoW is running (it overwrites the file on logout)
+    #[serde(default)]
+    pub ignore_running_client: bool,
+
+    /// Only fetch builds whose loadout was fetched more than this many hours ago; recent ones are
+    /// kept as they are (builds without provenance metadata are always fetched)
+    #[serde(default)]
+    pub update_if_older_than_hours: Option<u64>,
+
+    /// Stop the update at the first failed fetch instead of reporting it in the summary
+    #[serde(default)]
+    pub abort_on_error: bool,
+
+    /// Fetch builds and report what would change without writing any file
+    #[serde(default)]
+    pub dry_run: bool,
+
+    /// Most verbose messages written to the log file: error, warn, info, debug or trace
+    #[serde(default)]
+    pub log_level: LogLevel,
+
+    /// Check the configured bosses and dungeons against the current season before updating
+    #[serde(default)]
+    pub check_content: bool,
+
+    /// When to update talents automatically while the app is running; never if unset
+    #[serde(default)]
+    pub schedule: Option<Schedule>,
+
+    /// Game version to fetch builds for (default: retail)
+    #[serde(default)]
+    pub flavor: GameFlavor,
 }
 
-/// Character configuration
-#[derive(Debug, Clone, Serialize, Deserialize)]
+/// Auto-generated builds (with `auto_suffix` or a legacy suffix) removed before updating
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
+pub enum ClearScope {
+    /// Every auto-generated build in the file, including characters not in the config
+    All,
+    /// Only the builds of the specs being updated; builds of other specs are kept
+    #[default]
+    ConfiguredSpecsOnly,
+    /// Builds of the selected content types in the whole file, e.g. only Mythic+ builds after a
+    /// season rollover; builds of other types are replaced by name when fetched again
+    #[serde(rename_all = "camelCase")]
+    ContentTypes { raid: bool, mythic_plus: bool },
+}
+
+/// Raid bosses to fetch on one difficulty (see `Config::raid_content`)
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RaidContent {
+    /// Raid difficulty (e.g., "mythic")
+    pub difficulty: String,
+    /// Raid boss names (lowercase, hyphenated), in encounter order
+    pub bosses: Vec<String>,
+}
+
+/// Deserialize a `ClearScope`, also accepting the bool of older configs
+fn clear_scope_or_bool<'de, D: serde::Deserializer<'de>>(deserializer: D) -> Result<ClearScope, D::Error> {
+    #[derive(Deserialize)]
+    #[serde(untagged)]
+    enum Setting {
+        Bool(bool),
+        Scope(ClearScope),
+    }
+
+    Ok(match Setting::deserialize(deserializer)? {
+        Setting::Bool(true) => ClearScope::All,
+        Setting::Bool(false) => ClearScope::ConfiguredSpecsOnly,
+        Setting::Scope(scope) => scope,
+    })
+}
+
+fn default_backup_count() -> usize {
+    DEFAULT_BACKUP_COUNT
+}
+
+fn default_auto_suffix() -> String {
+    DEFAULT_AUTO_SUFFIX.to_string()
+}
+
+/// Character configuration
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", from = "CharacterSettings")]
 pub struct Character {
     /// Character name (for identification only)
     pub name: String,
 
+    /// Realm as the client shows it (e.g., "Twisting Nether"); TalentLoadouts profiles are keyed
+    /// by "Name - Realm"
+    #[serde(default)]
+    pub realm: Option<String>,
+
     /// Class name in PascalCase (e.g., "DeathKnight", "DemonHunter", "Warrior")
-    pub class: String,
+    pub class: WowClass,
+
+    /// Specializations to fetch, as names in lowercase (e.g., ["frost", "unholy"]) or with a hero
+    /// talent tree (e.g., { "spec": "arms", "heroTalent": "slayer" })
+    /// Abbreviations like "BM" or "Resto" are replaced by the slug when the config is read.
+    pub specializations: Vec<SpecSelection>,
+
+    /// Only fetch the specializations with one of these roles (all if unset)
+    #[serde(default)]
+    pub roles: Option<Vec<Role>>,
+
+    /// Talent file of this character, e.g. on another Battle.net account (the global
+    /// `output_path` if unset)
+    #[serde(default)]
+    pub output_path: Option<PathBuf>,
+}
+
+/// Character as written in the settings file, before spec names are normalized
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct CharacterSettings {
+    name: String,
+    #[serde(default)]
+    realm: Option<String>,
+    class: WowClass,
+    specializations: Vec<SpecSelection>,
+    #[serde(default)]
+    roles: Option<Vec<Role>>,
+    #[serde(default)]
+    output_path: Option<PathBuf>,
+}
+
+impl From<CharacterSettings> for Character {
+    fn from(settings: CharacterSettings) -> Self {
+        let class = settings.class;
+        Self {
+            name: settings.name,
+            realm: settings.realm,
+            class,
+            specializations: settings
+                .specializations
+                .into_iter()
+                .map(|selection| SpecSelection {
+                    spec: class.canonical_spec_name(&selection.spec),
+                    ..selection
+                })
+                .collect(),
+            roles: settings.roles,
+            output_path: settings.output_path,
+        }
+    }
+}
+
+/// A specialization to fetch, optionally limited to builds of one hero talent tree or one
+/// content type
+/// Written as the plain spec name with the default options, so older configs stay unchanged.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(from = "SpecSetting", into = "SpecSetting")]
+pub struct SpecSelection {
+    /// Spec name (e.g., "arms")
+    pub spec: String,
+    /// Hero talent tree slug or name (e.g., "slayer"), see `WowClass::hero_talents`
+    pub hero_talent: Option<String>,
+    /// Whether to fetch raid builds for this spec
+    pub fetch_raid: bool,
+    /// Whether to fetch Mythic+ builds for this spec
+    pub fetch_mythic_plus: bool,
+}
+
+/// A spec as written in the settings file: a plain name or an object with options
+#[derive(Serialize, Deserialize)]
+#[serde(untagged)]
+enum SpecSetting {
+    Name(String),
+    #[serde(rename_all = "camelCase")]
+    WithOptions {
+        spec: String,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        hero_talent: Option<String>,
+        #[serde(default = "default_true", skip_serializing_if = "is_true")]
+        fetch_raid: bool,
+        #[serde(default = "default_true", skip_serializing_if = "is_true")]
+        fetch_mythic_plus: bool,
+    },
+}
 
-    /// List of specialization names in lowercase (e.g., ["frost", "unholy"])
-    pub specializations: Vec<String>,
+fn default_true() -> bool {
+    true
+}
+
+fn is_true(value: &bool) -> bool {
+    *value
+}
+
+impl From<SpecSetting> for SpecSelection {
+    fn from(setting: SpecSetting) -> Self {
+        match setting {
+            SpecSetting::Name(spec) => spec.into(),
+            SpecSetting::WithOptions {
+                spec,
+                hero_talent,
+                fetch_raid,
+                fetch_mythic_plus,
+            } => Self {
+                spec,
+                hero_talent,
+                fetch_raid,
+                fetch_mythic_plus,
+            },
+        }
+    }
+}
+
+impl From<SpecSelection> for SpecSetting {
+    fn from(selection: SpecSelection) -> Self {
+        if selection.is_plain() {
+            return Self::Name(selection.spec);
+        }
+        Self::WithOptions {
+            spec: selection.spec,
+            hero_talent: selection.hero_talent,
+            fetch_raid: selection.fetch_raid,
+            fetch_mythic_plus: selection.fetch_mythic_plus,
+        }
+    }
+}
+
+impl SpecSelection {
+    /// Whether the selection is just the spec, without a hero talent or content filter
+    fn is_plain(&self) -> bool {
+        self.hero_talent.is_none() && self.fetch_raid && self.fetch_mythic_plus
+    }
+}
+
+impl From<String> for SpecSelection {
+    fn from(spec: String) -> Self {
+        Self {
+            spec,
+            hero_talent: None,
+            fetch_raid: true,
+            fetch_mythic_plus: true,
+        }
+    }
+}
+
+impl From<&str> for SpecSelection {
+    fn from(spec: &str) -> Self {
+        spec.to_string().into()
+    }
+}
+
+/// A selection equals a spec name if it has no hero talent or content filter
+impl PartialEq<str> for SpecSelection {
+    fn eq(&self, other: &str) -> bool {
+        self.is_plain() && self.spec == other
+    }
+}
+
+impl PartialEq<&str> for SpecSelection {
+    fn eq(&self, other: &&str) -> bool {
+        self == *other
+    }
+}
+
+impl std::fmt::Display for SpecSelection {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match &self.hero_talent {
+            Some(hero_talent) => write!(f, "{} ({})", self.spec, hero_talent),
+            None => f.write_str(&self.spec),
+        }
+    }
+}
+
+impl Character {
+    /// Specializations to fetch builds for, after applying the role filter
+    pub fn wanted_specializations(&self) -> impl Iterator<Item = &SpecSelection> + '_ {
+        self.specializations.iter().filter(move |selection| match &self.roles {
+            Some(roles) => self.class.spec_role(&selection.spec).is_some_and(|role| roles.contains(&role)),
+            None => true,
+        })
+    }
 }
 
 impl Config {
-    /// Load configuration from a JSON file
+    /// Load configuration from a JSON or TOML file (see `ConfigFormat::detect`), upgrading
+    /// older config versions
     pub fn from_file(path: impl AsRef<std::path::Path>) -> anyhow::Result<Self> {
+        let path = path.as_ref();
         let contents = std::fs::read_to_string(path)?;
-        let config: Config = serde_json::from_str(&contents)?;
+        let raw = match ConfigFormat::detect(path, &contents) {
+            ConfigFormat::Json => serde_json::from_str(&contents)?,
+            ConfigFormat::Toml => toml::from_str(&contents)?,
+        };
+        let config = migrate(raw)?;
         config.validate()?;
         Ok(config)
     }
 
+    /// Write the configuration atomically: as TOML for a `.toml` path, otherwise as pretty-printed
+    /// JSON with the fields in declaration order
+    pub fn to_file(&self, path: impl AsRef<std::path::Path>) -> anyhow::Result<()> {
+        let path = path.as_ref();
+        let contents = match ConfigFormat::from_extension(path) {
+            Some(ConfigFormat::Toml) => {
+                // TOML has no null; unset options are left out instead
+                let mut value = serde_json::to_value(self)?;
+                remove_nulls(&mut value);
+                toml::to_string_pretty(&value).context("Failed to serialize config as TOML")?
+            }
+            _ => serde_json::to_string_pretty(self)?,
+        };
+        write_atomic(path, contents).context("Failed to write config")
+    }
+
     /// Validate configuration settings
-    fn validate(&self) -> anyhow::Result<()> {
+    /// All problems are reported together, one per line, so a config can be fixed in one go.
+    pub fn validate(&self) -> anyhow::Result<()> {
+        let mut problems = Vec::new();
+
         if self.characters.is_empty() {
-            anyhow::bail!("Configuration must include at least one character");
+            problems.push("Configuration must include at least one character".to_string());
         }
 
-        if self.raid_difficulties.is_empty() && self.raid_bosses.is_empty() && self.dungeons.is_empty() {
-            anyhow::bail!("Configuration must include at least one of: raid difficulties/bosses or dungeons");
+        if self.raid_difficulties.is_empty()
+            && self.raid_bosses.is_empty()
+            && self.raid_content.is_empty()
+            && self.dungeons.is_empty()
+        {
+            problems.push("Configuration must include at least one of: raid difficulties/bosses or dungeons".to_string());
         }
 
-        for character in &self.characters {
-            if character.class.is_empty() {
-                anyhow::bail!("Character '{}' has no class specified", character.name);
+        let content_difficulties = self.raid_content.iter().map(|content| &content.difficulty);
+        for difficulty in self.raid_difficulties.iter().chain(content_difficulties) {
+            if RaidDifficulty::from_str(difficulty).is_none() {
+                problems.push(format!("Invalid raid difficulty '{}' (valid: normal, heroic, mythic)", difficulty));
             }
+        }
+
+        for (index, content) in self.raid_content.iter().enumerate() {
+            let difficulty = RaidDifficulty::from_str(&content.difficulty);
+            let earlier = &self.raid_content[..index];
+            if difficulty.is_some() && earlier.iter().any(|c| RaidDifficulty::from_str(&c.difficulty) == difficulty) {
+                problems.push(format!(
+                    "Raid difficulty '{}' is listed more than once in raidContent",
+                    content.difficulty
+                ));
+            }
+            if content.bosses.is_empty() {
+                problems.push(format!("Raid difficulty '{}' has no bosses in raidContent", content.difficulty));
+            }
+            for boss in content.bosses.iter().filter(|boss| !is_slug(boss)) {
+                problems.push(format!(
+                    "Invalid raid boss '{}' for {} (use lowercase, hyphenated names like 'queen-ansurek')",
+                    boss, content.difficulty
+                ));
+            }
+        }
 
+        for character in &self.characters {
             if character.specializations.is_empty() {
-                anyhow::bail!("Character '{}' has no specializations specified", character.name);
+                problems.push(format!("Character '{}' has no specializations specified", character.name));
+            }
+
+            if !self.flavor.classes().contains(&character.class) {
+                problems.push(format!(
+                    "{} '{}' is not available in {:?}",
+                    character.class, character.name, self.flavor
+                ));
+            } else {
+                for selection in &character.specializations {
+                    let hero_talent = character.class.spec_for(self.flavor, &selection.spec).and_then(|spec| {
+                        match &selection.hero_talent {
+                            Some(hero_talent) => character.class.parse_hero_talent(spec.index, hero_talent).map(Some),
+                            None => Ok(None),
+                        }
+                    });
+                    if let Err(e) = hero_talent {
+                        problems.push(format!("{} (character '{}')", e, character.name));
+                    }
+                }
+            }
+
+            if self.output_path_for(character).is_none() {
+                problems.push(format!(
+                    "Character '{}' has no output path; set outputPath for it or for all characters",
+                    character.name
+                ));
+            }
+
+            if !character.specializations.is_empty() && character.wanted_specializations().next().is_none() {
+                problems.push(format!(
+                    "Role filter of {} '{}' excludes all of its specializations",
+                    character.class, character.name
+                ));
             }
         }
 
-        Ok(())
+        let output_files = self.output_files();
+        for path in &self.additional_output_paths {
+            if output_files.iter().any(|(file, _)| file == path) {
+                problems.push(format!("Additional output path {} is already an output path", path.display()));
+            }
+        }
+
+        if self.auto_suffix.is_empty() || self.legacy_suffixes.iter().any(|s| s.is_empty()) {
+            problems.push("Auto-generated suffixes must not be empty".to_string());
+        }
+
+        if let Some(Err(e)) = self.schedule.as_ref().map(Schedule::validate) {
+            problems.push(e.to_string());
+        }
+
+        match problems.as_slice() {
+            [] => Ok(()),
+            [problem] => anyhow::bail!("{}", problem),
+            _ => anyhow::bail!("Configuration has {} problems:\n- {}", problems.len(), problems.join("\n- ")),
+        }
+    }
+
+    /// Raid difficulties to fetch; invalid names are left out (see `validate`)
+    pub fn difficulties(&self) -> Vec<RaidDifficulty> {
+        self.raid_difficulties.iter().filter_map(|d| RaidDifficulty::from_str(d)).collect()
+    }
+
+    /// Talent file a character's builds are written to: its own path, otherwise the global one
+    pub fn output_path_for<'a>(&'a self, character: &'a Character) -> Option<&'a Path> {
+        character.output_path.as_deref().or(self.output_path.as_deref())
+    }
+
+    /// Fill in a missing `output_path` with the talent file found on this machine (see
+    /// `WowScanner::discover_output_path`), if a character has nowhere else to be written
+    /// Returns the path filled in.
+    pub fn resolve_output_path(&mut self) -> anyhow::Result<Option<PathBuf>> {
+        if self.output_path.is_some() || self.characters.iter().all(|character| character.output_path.is_some()) {
+            return Ok(None);
+        }
+        let path = WowScanner::discover_output_path(self.flavor)?;
+        self.output_path = Some(path.clone());
+        Ok(Some(path))
+    }
+
+    /// Characters grouped by the talent file they're written to, in config order
+    /// Characters without an output path are left out (see `validate`).
+    pub fn output_files(&self) -> Vec<(PathBuf, Vec<&Character>)> {
+        let mut files: Vec<(PathBuf, Vec<&Character>)> = Vec::new();
+        for character in &self.characters {
+            let Some(path) = self.output_path_for(character) else {
+                continue;
+            };
+            match files.iter_mut().find(|(file, _)| file == path) {
+                Some((_, characters)) => characters.push(character),
+                None => files.push((path.to_path_buf(), vec![character])),
+            }
+        }
+        files
+    }
+
+    /// Every configured raid boss, in encounter order: those of `raid_content` if set, otherwise
+    /// `raid_bosses`
+    pub fn all_raid_bosses(&self) -> Vec<String> {
+        if self.raid_content.is_empty() {
+            return self.raid_bosses.clone();
+        }
+        let mut bosses: Vec<String> = Vec::new();
+        for boss in self.raid_content.iter().flat_map(|content| &content.bosses) {
+            if !bosses.contains(boss) {
+                bosses.push(boss.clone());
+            }
+        }
+        bosses
+    }
+
+    /// Bosses to fetch builds for on each difficulty, without `skip_raid_bosses`
+    /// Without `raid_content`, every boss of `raid_bosses` is fetched on every difficulty.
+    pub fn raid_content_to_fetch(&self) -> Vec<(RaidDifficulty, Vec<&String>)> {
+        if self.raid_content.is_empty() {
+            let bosses = self.without_skipped_bosses(&self.raid_bosses);
+            return self.difficulties().into_iter().map(|difficulty| (difficulty, bosses.clone())).collect();
+        }
+        self.raid_content
+            .iter()
+            .filter_map(|content| {
+                let difficulty = RaidDifficulty::from_str(&content.difficulty)?;
+                Some((difficulty, self.without_skipped_bosses(&content.bosses)))
+            })
+            .collect()
+    }
+
+    fn without_skipped_bosses<'a>(&self, bosses: &'a [String]) -> Vec<&'a String> {
+        bosses.iter().filter(|boss| !is_listed(boss, &self.skip_raid_bosses)).collect()
+    }
+
+    /// Bosses to fetch builds for on any difficulty, in encounter order
+    pub fn raid_bosses_to_fetch(&self) -> Vec<String> {
+        let content = self.raid_content_to_fetch();
+        self.all_raid_bosses()
+            .into_iter()
+            .filter(|boss| content.iter().any(|(_, bosses)| bosses.contains(&boss)))
+            .collect()
+    }
+
+    /// Number of raid builds fetched per spec
+    pub fn raid_builds_per_spec(&self) -> usize {
+        self.raid_content_to_fetch().iter().map(|(_, bosses)| bosses.len()).sum()
+    }
+
+    /// Dungeons to fetch builds for: `dungeons` without `skip_dungeons`
+    pub fn dungeons_to_fetch(&self) -> Vec<&String> {
+        self.dungeons.iter().filter(|dungeon| !is_listed(dungeon, &self.skip_dungeons)).collect()
+    }
+
+    /// Deprecated but still accepted settings, e.g. renamed specs, and skip entries matching nothing
+    pub fn warnings(&self) -> Vec<String> {
+        let raid_bosses = self.all_raid_bosses();
+        let unmatched_skips = self
+            .skip_raid_bosses
+            .iter()
+            .filter(|boss| !is_listed(boss, &raid_bosses))
+            .map(|boss| format!("Skipped raid boss '{}' is not in raidBosses", boss))
+            .chain(
+                self.skip_dungeons
+                    .iter()
+                    .filter(|dungeon| !is_listed(dungeon, &self.dungeons))
+                    .map(|dungeon| format!("Skipped dungeon '{}' is not in dungeons", dungeon)),
+            );
+
+        self.characters
+            .iter()
+            .flat_map(|character| {
+                character.specializations.iter().filter_map(|selection| {
+                    let spec = &selection.spec;
+                    let current = character.class.deprecated_spec_name(spec)?;
+                    // Classic flavors still use some of the old names
+                    character.class.spec_names_for(self.flavor).contains(&current).then(|| {
+                        format!(
+                            "Spec '{}' of {} '{}' is deprecated, use '{}' instead",
+                            spec, character.class, character.name, current
+                        )
+                    })
+                })
+            })
+            .chain(unmatched_skips)
+            .chain(
+                (!self.raid_content.is_empty() && (!self.raid_difficulties.is_empty() || !self.raid_bosses.is_empty()))
+                    .then(|| "raidDifficulties and raidBosses are ignored because raidContent is set".to_string()),
+            )
+            .collect()
+    }
+
+    /// Configured bosses and dungeons that aren't part of the discovered current-season content
+    /// Aliases of a slug (see `equivalent_content_slugs`) count as the same boss or dungeon.
+    pub fn validate_against_content(&self, content: &DiscoveredContent) -> Vec<ContentWarning> {
+        let unknown = |kind: ContentKind, slugs: &[String], discovered: &[String]| {
+            slugs
+                .iter()
+                .filter(|slug| {
+                    !equivalent_content_slugs(&slug.to_lowercase())
+                        .iter()
+                        .any(|slug| discovered.iter().any(|d| d == slug))
+                })
+                .map(|slug| {
+                    let suggestion = closest_match(slug, discovered.iter().map(|d| (d.as_str(), d.as_str())));
+                    let label = match kind {
+                        ContentKind::Boss => "Raid boss",
+                        ContentKind::Dungeon => "Dungeon",
+                    };
+                    ContentWarning {
+                        kind,
+                        slug: slug.clone(),
+                        suggestion: suggestion.map(str::to_string),
+                        message: format!("{} '{}' is not in the current season{}", label, slug, did_you_mean(suggestion)),
+                    }
+                })
+                .collect::<Vec<_>>()
+        };
+
+        let mut warnings = unknown(ContentKind::Boss, &self.all_raid_bosses(), &content.raid_bosses);
+        warnings.extend(unknown(ContentKind::Dungeon, &self.dungeons, &content.dungeons));
+        warnings
     }
 
     /// Create a default example configuration
     #[allow(dead_code)]
     pub fn example() -> Self {
         Config {
+            version: CONFIG_VERSION,
             characters: vec![
                 Character {
                     name: "MyWarrior".to_string(),
-                    class: "Warrior".to_string(),
-                    specializations: vec!["arms".to_string(), "fury".to_string()],
+                    realm: Some("Draenor".to_string()),
+                    class: WowClass::Warrior,
+                    specializations: vec!["arms".into(), "fury".into()],
+                    roles: None,
+                    output_path: None,
                 },
                 Character {
                     name: "MyMage".to_string(),
-                    class: "Mage".to_string(),
-                    specializations: vec!["frost".to_string(), "fire".to_string()],
+                    realm: Some("Draenor".to_string()),
+                    class: WowClass::Mage,
+                    specializations: vec!["frost".into(), "fire".into()],
+                    roles: None,
+                    output_path: None,
                 },
             ],
             raid_difficulties: vec!["heroic".to_string(), "normal".to_string()],
@@ -95,20 +733,250 @@ impl Config {
                 "sikran".to_string(),
                 "queen-ansurek".to_string(),
             ],
+            raid_content: Vec::new(),
             dungeons: vec![
                 "ara-kara".to_string(),
                 "city-of-threads".to_string(),
                 "mists-of-tirna-scithe".to_string(),
             ],
-            clear_previous_builds: false,
-            output_path: PathBuf::from("/Applications/World of Warcraft/_retail_/WTF/Account/YOUR_ACCOUNT_ID/SavedVariables/TalentLoadoutsEx.lua"),
+            skip_raid_bosses: Vec::new(),
+            skip_dungeons: Vec::new(),
+            clear_previous_builds: ClearScope::ConfiguredSpecsOnly,
+            output_path: Some(PathBuf::from("/Applications/World of Warcraft/_retail_/WTF/Account/YOUR_ACCOUNT_ID/SavedVariables/TalentLoadoutsEx.lua")),
+            additional_output_paths: Vec::new(),
+            backup_count: DEFAULT_BACKUP_COUNT,
+            merge_identical_builds: false,
+            collapse_identical: false,
+            keep_previous_builds: None,
+            max_loadouts_per_spec: None,
+            output_format: TalentFileFormat::TalentLoadoutsEx,
+            also_write_plaintext: false,
+            plaintext_path: None,
+            icon_overrides: IconOverrides::default(),
+            auto_suffix: DEFAULT_AUTO_SUFFIX.to_string(),
+            legacy_suffixes: Vec::new(),
+            force_write: false,
+            confirm_clear_with_issues: false,
+            ignore_running_client: false,
+            update_if_older_than_hours: None,
+            abort_on_error: false,
+            dry_run: false,
+            log_level: LogLevel::Info,
+            check_content: false,
+            schedule: None,
+            flavor: GameFlavor::Retail,
         }
     }
 }
 
+/// Whether a boss or dungeon slug is in `list`, also by an alias (see `equivalent_content_slugs`)
+/// Whether a boss or dungeon name is a slug like "queen-ansurek"
+fn is_slug(name: &str) -> bool {
+    !name.is_empty() && name.chars().all(|c| c.is_ascii_lowercase() || c.is_ascii_digit() || c == '-')
+}
+
+fn is_listed(slug: &str, list: &[String]) -> bool {
+    equivalent_content_slugs(&slug.to_lowercase())
+        .iter()
+        .any(|slug| list.iter().any(|listed| listed.eq_ignore_ascii_case(slug)))
+}
+
+/// Pre-filled config for onboarding: the most recently played characters, the current season's
+/// bosses and dungeons and the talent file of the last played account
+/// Characters with a detected class are validated as a `Config`; the others are included with an
+/// empty class for the UI to ask for, so the result is JSON rather than a `Config`.
+pub fn starter_config(
+    characters: &[DiscoveredCharacter],
+    content: DiscoveredContent,
+) -> anyhow::Result<serde_json::Value> {
+    let mut recent: Vec<&DiscoveredCharacter> = characters.iter().collect();
+    // RFC 3339 UTC timestamps sort chronologically; unknown ones go last
+    recent.sort_by(|a, b| b.last_played.cmp(&a.last_played));
+    recent.truncate(STARTER_CHARACTER_COUNT);
+
+    let output_path = recent
+        .iter()
+        .find(|character| character.is_last_played_account)
+        .or(recent.first())
+        .map(|character| character.talent_file_path.clone());
+    let flavor = recent.first().map(|character| character.flavor).unwrap_or_default();
+    // Characters on other accounts keep their own talent file
+    let character_output_path = |character: &DiscoveredCharacter| {
+        (Some(&character.talent_file_path) != output_path.as_ref()).then(|| character.talent_file_path.clone())
+    };
+
+    let (detected, undetected): (Vec<_>, Vec<_>) = recent.into_iter().partition(|character| {
+        character.class_detected && character.class.parse::<WowClass>().is_ok()
+    });
+
+    let mut config = Config::example();
+    config.characters = detected
+        .iter()
+        .map(|character| {
+            let class: WowClass = character.class.parse().expect("checked above");
+            let specializations = if character.specializations.is_empty() {
+                class.spec_names_for(flavor).iter().map(|spec| (*spec).into()).collect()
+            } else {
+                character.specializations.iter().map(|spec| spec.as_str().into()).collect()
+            };
+            Character {
+                name: character.name.clone(),
+                realm: Some(character.realm_display_name.clone()),
+                class,
+                specializations,
+                roles: None,
+                output_path: character_output_path(character),
+            }
+        })
+        .collect();
+    config.raid_difficulties = vec![RaidDifficulty::Heroic.as_str().to_string()];
+    config.raid_bosses = content.raid_bosses;
+    config.dungeons = content.dungeons;
+    config.output_path = output_path.clone();
+    config.flavor = flavor;
+
+    // Without any detected character there is nothing to validate yet
+    if !config.characters.is_empty() {
+        config.validate()?;
+    }
+
+    let mut value = serde_json::to_value(&config)?;
+    let characters = value["characters"].as_array_mut().expect("characters serialize as an array");
+    for character in undetected {
+        characters.push(serde_json::json!({
+            "name": character.name,
+            "class": "",
+            "specializations": character.specializations,
+            "outputPath": character_output_path(character),
+        }));
+    }
+    Ok(value)
+}
+
+/// Kind of content a `ContentWarning` is about
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub enum ContentKind {
+    Boss,
+    Dungeon,
+}
+
+/// A configured boss or dungeon that isn't in the current season (see `Config::validate_against_content`)
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ContentWarning {
+    pub kind: ContentKind,
+    pub slug: String,
+    /// Closest current-season slug, if one is similar
+    pub suggestion: Option<String>,
+    /// Human-readable description
+    pub message: String,
+}
+
+/// File format of a config file
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ConfigFormat {
+    Json,
+    Toml,
+}
+
+impl ConfigFormat {
+    /// Format by file extension (`.json` or `.toml`, any case)
+    fn from_extension(path: &std::path::Path) -> Option<Self> {
+        let extension = path.extension()?.to_str()?.to_ascii_lowercase();
+        match extension.as_str() {
+            "json" => Some(Self::Json),
+            "toml" => Some(Self::Toml),
+            _ => None,
+        }
+    }
+
+    /// Format by extension, otherwise by content: JSON configs start with `{`
+    pub fn detect(path: &std::path::Path, contents: &str) -> Self {
+        Self::from_extension(path).unwrap_or_else(|| {
+            if contents.trim_start_matches('\u{feff}').trim_start().starts_with('{') {
+                Self::Json
+            } else {
+                Self::Toml
+            }
+        })
+    }
+}
+
+/// Drop null values from objects, recursively
+fn remove_nulls(value: &mut serde_json::Value) {
+    match value {
+        serde_json::Value::Object(object) => {
+            object.retain(|_, value| !value.is_null());
+            object.values_mut().for_each(remove_nulls);
+        }
+        serde_json::Value::Array(values) => values.iter_mut().for_each(remove_nulls),
+        _ => {}
+    }
+}
+
+/// Upgrade a config in any known older shape to the current version and read it
+/// Version 0 covers every config without a `version`: settings of this app from before
+/// versioning, and the config.json of the original C# tool (PascalCase keys, talent file as
+/// `FilePath`).
+pub fn migrate(mut raw: serde_json::Value) -> anyhow::Result<Config> {
+    let version = match raw.get("version") {
+        None => 0,
+        Some(version) => version
+            .as_u64()
+            .and_then(|version| u32::try_from(version).ok())
+            .context("Config version must be a number")?,
+    };
+    if version > CONFIG_VERSION {
+        anyhow::bail!(
+            "Config version {} is newer than this app supports ({}); update the app",
+            version,
+            CONFIG_VERSION
+        );
+    }
+
+    if version < 1 {
+        migrate_v0(&mut raw)?;
+    }
+    serde_json::from_value(raw).context("Invalid config")
+}
+
+/// Version 0 to 1: camelCase keys and `outputPath` for the talent file
+fn migrate_v0(raw: &mut serde_json::Value) -> anyhow::Result<()> {
+    let config = raw.as_object_mut().context("Config must be a JSON object")?;
+    camel_case_keys(config);
+    if let Some(characters) = config.get_mut("characters").and_then(|c| c.as_array_mut()) {
+        for character in characters.iter_mut().filter_map(|c| c.as_object_mut()) {
+            camel_case_keys(character);
+        }
+    }
+
+    if let Some(path) = config.remove("filePath") {
+        config.entry("outputPath").or_insert(path);
+    }
+    config.insert("version".to_string(), 1.into());
+    Ok(())
+}
+
+/// Lowercase the first letter of every key ("RaidBosses" -> "raidBosses")
+fn camel_case_keys(object: &mut serde_json::Map<String, serde_json::Value>) {
+    *object = std::mem::take(object)
+        .into_iter()
+        .map(|(key, value)| {
+            let mut chars = key.chars();
+            let key = match chars.next() {
+                Some(first) => first.to_lowercase().chain(chars).collect(),
+                None => key,
+            };
+            (key, value)
+        })
+        .collect();
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::test_util::temp_dir;
 
     #[test]
     fn test_example_config_is_valid() {
@@ -116,6 +984,255 @@ mod tests {
         assert!(config.validate().is_ok());
     }
 
+    #[test]
+    fn test_migrate_fixtures_to_current_version() {
+        let wow1 = PathBuf::from(
+            "C:\\Program Files (x86)\\World of Warcraft\\_retail_\\WTF\\Account\\WOW1\\SavedVariables\\TalentLoadoutsEx.lua",
+        );
+        for fixture in [
+            include_str!("../tests/fixtures/config/v0-csharp.json"),
+            include_str!("../tests/fixtures/config/v0-settings.json"),
+            include_str!("../tests/fixtures/config/v1.json"),
+        ] {
+            let config = migrate(serde_json::from_str(fixture).unwrap()).unwrap();
+            config.validate().unwrap();
+            assert_eq!(config.version, CONFIG_VERSION);
+            assert_eq!(config.output_path.as_ref(), Some(&wow1));
+            assert_eq!(config.characters[0].name, "Grimbold");
+            assert_eq!(config.characters[0].specializations, ["arms", "protection"]);
+            // "combat" of older configs still resolves to its current name
+            let rogue = &config.characters[1];
+            assert_eq!(rogue.class.spec_for(config.flavor, &rogue.specializations[0].spec).unwrap().slug, "outlaw");
+            assert_eq!(config.raid_bosses, ["broodtwister", "sikran"]);
+            assert_eq!(config.clear_previous_builds, ClearScope::All);
+        }
+
+        let v1 = migrate(serde_json::from_str(include_str!("../tests/fixtures/config/v1.json")).unwrap()).unwrap();
+        assert!(v1.ignore_running_client);
+        assert!(v1.characters[1].output_path.is_some());
+
+        // Written configs carry the current version and read back unchanged
+        let written = serde_json::to_value(Config::example()).unwrap();
+        assert_eq!(written["version"], CONFIG_VERSION);
+        assert_eq!(migrate(written).unwrap().characters.len(), 2);
+    }
+
+    #[test]
+    fn test_config_file_round_trips_as_json_and_toml() {
+        let dir = temp_dir("config");
+        let mut example = Config::example();
+        example.characters[0].roles = Some(vec![Role::Damage]);
+        example.icon_overrides.specs.insert(71, 132355);
+        let expected = serde_json::to_value(&example).unwrap();
+
+        for name in ["config.json", "config.toml", "config.TOML"] {
+            let path = dir.join(name);
+            example.to_file(&path).unwrap();
+            let contents = std::fs::read_to_string(&path).unwrap();
+            assert_eq!(contents.trim_start().starts_with('{'), name.ends_with(".json"), "{}", contents);
+            let read = Config::from_file(&path).unwrap();
+            assert_eq!(read, example, "{}", name);
+            assert_eq!(serde_json::to_value(&read).unwrap(), expected, "{}", name);
+
+            // Saving again gives the same file
+            read.to_file(&path).unwrap();
+            assert_eq!(std::fs::read_to_string(&path).unwrap(), contents, "{}", name);
+        }
+
+        // camelCase keys in declaration order
+        let json = std::fs::read_to_string(dir.join("config.json")).unwrap();
+        let keys: Vec<_> = ["version", "characters", "raidDifficulties", "outputPath", "flavor"]
+            .iter()
+            .map(|key| json.find(&format!("\n  \"{}\"", key)).unwrap())
+            .collect();
+        assert!(keys.windows(2).all(|pair| pair[0] < pair[1]), "{}", json);
+        assert!(!json.contains("output_path"));
+
+        // Without a known extension the content decides
+        let toml = include_str!("../tests/fixtures/config/v1.toml");
+        std::fs::write(dir.join("config"), toml).unwrap();
+        let config = Config::from_file(dir.join("config")).unwrap();
+        assert_eq!(config.characters.len(), 2);
+        let alt_path = "D:/Games/WoW/_retail_/WTF/Account/WOW2/SavedVariables/TalentLoadoutsEx.lua";
+        assert_eq!(config.characters[1].output_path, Some(PathBuf::from(alt_path)));
+        assert!(config.ignore_running_client);
+        std::fs::write(dir.join("config"), include_str!("../tests/fixtures/config/v1.json")).unwrap();
+        assert_eq!(Config::from_file(dir.join("config")).unwrap().characters.len(), 2);
+    }
+
+    #[test]
+    fn test_migrate_rejects_unknown_versions() {
+        let mut config = serde_json::to_value(Config::example()).unwrap();
+        config["version"] = (CONFIG_VERSION + 1).into();
+        assert!(migrate(config.clone()).unwrap_err().to_string().contains("newer than this app"));
+        config["version"] = "one".into();
+        assert!(migrate(config).is_err());
+        assert!(migrate(serde_json::json!([])).is_err());
+    }
+
+    #[test]
+    fn test_validation_reports_all_problems() {
+        let mut config = Config::example();
+        config.raid_difficulties.push("legendary".to_string());
+        config.characters[0].specializations.push("fyre".into());
+        config.characters[1].specializations = vec!["frots".into(), "arcane".into(), "holy".into()];
+
+        let error = config.validate().unwrap_err().to_string();
+        assert!(error.starts_with("Configuration has 4 problems:\n- "), "{}", error);
+        assert!(error.contains("Invalid raid difficulty 'legendary' (valid: normal, heroic, mythic)"), "{}", error);
+        assert!(error.contains("'fyre' for Warrior"), "{}", error);
+        assert!(error.contains("valid: arms, fury, protection"), "{}", error);
+        assert!(error.contains("'frots' for Mage"), "{}", error);
+        assert!(error.contains("'holy' for Mage"), "{}", error);
+        assert!(error.contains("valid: arcane, fire, frost"), "{}", error);
+        assert_eq!(config.difficulties(), [RaidDifficulty::Heroic, RaidDifficulty::Normal]);
+    }
+
+    #[test]
+    fn test_validate_against_content() {
+        let mut config = Config::example();
+        config.raid_bosses = vec!["broodtwister-ovinax".to_string(), "sikren".to_string(), "kazzara".to_string()];
+        config.dungeons = vec!["ara-kara".to_string(), "stonevault".to_string(), "city-of-thread".to_string()];
+        let content = DiscoveredContent {
+            raid_bosses: vec!["broodtwister".to_string(), "sikran".to_string()],
+            dungeons: vec!["ara-kara".to_string(), "the-stonevault".to_string(), "city-of-threads".to_string()],
+        };
+
+        let warnings = config.validate_against_content(&content);
+        let found: Vec<_> = warnings
+            .iter()
+            .map(|w| (w.kind, w.slug.as_str(), w.suggestion.as_deref()))
+            .collect();
+        assert_eq!(
+            found,
+            vec![
+                (ContentKind::Boss, "sikren", Some("sikran")),
+                (ContentKind::Boss, "kazzara", None),
+                (ContentKind::Dungeon, "city-of-thread", Some("city-of-threads")),
+            ]
+        );
+        assert_eq!(warnings[0].message, "Raid boss 'sikren' is not in the current season, did you mean 'sikran'?");
+    }
+
+    #[test]
+    fn test_clear_scope_accepts_old_bool() {
+        let scope = |value: serde_json::Value| {
+            let mut config = serde_json::to_value(Config::example()).unwrap();
+            config["clearPreviousBuilds"] = value;
+            serde_json::from_value::<Config>(config).map(|config| config.clear_previous_builds)
+        };
+        assert_eq!(scope(true.into()).unwrap(), ClearScope::All);
+        assert_eq!(scope(false.into()).unwrap(), ClearScope::ConfiguredSpecsOnly);
+        assert_eq!(scope("all".into()).unwrap(), ClearScope::All);
+        assert_eq!(scope("configuredSpecsOnly".into()).unwrap(), ClearScope::ConfiguredSpecsOnly);
+        assert_eq!(
+            scope(serde_json::json!({"contentTypes": {"raid": false, "mythicPlus": true}})).unwrap(),
+            ClearScope::ContentTypes { raid: false, mythic_plus: true }
+        );
+        assert!(scope("everything".into()).is_err());
+        assert!(scope(serde_json::Value::Null).is_err());
+
+        // The new form survives saving as TOML
+        let dir = temp_dir("clear-scope");
+        let mut config = Config::example();
+        config.clear_previous_builds = ClearScope::ContentTypes { raid: true, mythic_plus: false };
+        config.to_file(dir.join("settings.toml")).unwrap();
+        assert_eq!(Config::from_file(dir.join("settings.toml")).unwrap(), config);
+    }
+
+    #[test]
+    fn test_starter_config() {
+        let discovered = |name: &str, class: &str, account: &str, last_played: Option<&str>| DiscoveredCharacter {
+            name: name.to_string(),
+            realm: "Blackrock".to_string(),
+            realm_display_name: "Blackrock".to_string(),
+            class: class.to_string(),
+            class_detected: !class.is_empty(),
+            account_id: account.to_string(),
+            flavor: GameFlavor::Retail,
+            talent_file_path: PathBuf::from(format!(
+                "/wow/_retail_/WTF/Account/{}/SavedVariables/TalentLoadoutsEx.lua",
+                account
+            )),
+            talent_file_exists: true,
+            is_last_played_account: account == "WOW1",
+            last_played: last_played.map(str::to_string),
+            also_found_in: Vec::new(),
+            specializations: Vec::new(),
+            path: PathBuf::new(),
+        };
+        let mut characters = vec![
+            discovered("Old", "Mage", "WOW1", Some("2024-01-01T00:00:00Z")),
+            discovered("Alt", "Priest", "WOW2", Some("2026-03-01T00:00:00Z")),
+            discovered("Main", "Warrior", "WOW1", Some("2026-03-02T00:00:00Z")),
+            discovered("Mystery", "", "WOW1", Some("2026-02-01T00:00:00Z")),
+        ];
+        characters[2].specializations = vec!["fury".into()];
+        characters.extend((0..5).map(|i| discovered(&format!("Never{}", i), "Rogue", "WOW1", None)));
+        let content = DiscoveredContent {
+            raid_bosses: vec!["sikran".to_string()],
+            dungeons: vec!["ara-kara".to_string()],
+        };
+
+        let value = starter_config(&characters, content).unwrap();
+        let names: Vec<_> = value["characters"].as_array().unwrap().iter().map(|c| c["name"].clone()).collect();
+        assert_eq!(names, ["Main", "Alt", "Old", "Never0", "Mystery"]);
+        assert_eq!(value["characters"][4]["class"], "");
+        assert_eq!(value["characters"][0]["specializations"], serde_json::json!(["fury"]));
+        assert_eq!(value["characters"][1]["specializations"], serde_json::json!(["discipline", "holy", "shadow"]));
+        assert_eq!(value["raidBosses"], serde_json::json!(["sikran"]));
+        assert_eq!(value["outputPath"], "/wow/_retail_/WTF/Account/WOW1/SavedVariables/TalentLoadoutsEx.lua");
+        // Characters on another account write to their own talent file
+        assert_eq!(
+            value["characters"][1]["outputPath"],
+            "/wow/_retail_/WTF/Account/WOW2/SavedVariables/TalentLoadoutsEx.lua"
+        );
+        assert!(value["characters"][0]["outputPath"].is_null());
+
+        // Once the UI fills in the class the config is valid
+        let mut value = value;
+        value["characters"][4]["class"] = "Hunter".into();
+        value["characters"][4]["specializations"] = serde_json::json!(["marksmanship"]);
+        migrate(value).unwrap().validate().unwrap();
+    }
+
+    #[test]
+    fn test_per_character_output_paths() {
+        let mut config = Config::example();
+        let global = config.output_path.clone().unwrap();
+        let other = PathBuf::from("/wow/_retail_/WTF/Account/OTHER/SavedVariables/TalentLoadoutsEx.lua");
+        config.characters[1].output_path = Some(other.clone());
+        config.characters.push(Character {
+            name: "MyDruid".to_string(),
+            realm: None,
+            class: WowClass::Druid,
+            specializations: vec!["balance".into()],
+            roles: None,
+            output_path: None,
+        });
+
+        let files: Vec<_> = config
+            .output_files()
+            .into_iter()
+            .map(|(path, characters)| (path, characters.iter().map(|c| c.name.as_str()).collect::<Vec<_>>()))
+            .collect();
+        assert_eq!(files, [(global, vec!["MyWarrior", "MyDruid"]), (other, vec!["MyMage"])]);
+        assert!(config.validate().is_ok());
+
+        // Every character needs a path, its own or the global one
+        config.output_path = None;
+        let error = config.validate().unwrap_err().to_string();
+        assert!(error.contains("'MyWarrior' has no output path"), "{}", error);
+        for character in &mut config.characters {
+            character.output_path.get_or_insert_with(|| PathBuf::from("/wow/own.lua"));
+        }
+        assert!(config.validate().is_ok());
+
+        let json = r#"{"name": "Alt", "class": "Mage", "specializations": ["frost"], "outputPath": "/wow/alt.lua"}"#;
+        let character: Character = serde_json::from_str(json).unwrap();
+        assert_eq!(character.output_path, Some(PathBuf::from("/wow/alt.lua")));
+    }
+
     #[test]
     fn test_empty_characters_fails_validation() {
         let mut config = Config::example();
@@ -125,9 +1242,130 @@ mod tests {
 
     #[test]
     fn test_character_without_class_fails_validation() {
+        let json = r#"{ "name": "MyWarrior", "class": "", "specializations": ["arms"] }"#;
+        assert!(serde_json::from_str::<Character>(json).is_err());
+    }
+
+    #[test]
+    fn test_invalid_class_or_spec_is_reported() {
+        let json = r#"{ "name": "MyWarrior", "class": "Warior", "specializations": ["arms"] }"#;
+        let error = serde_json::from_str::<Character>(json).unwrap_err().to_string();
+        assert!(error.contains("Invalid class 'Warior', did you mean 'Warrior'? (valid: Warrior,"), "{}", error);
+
+        let json = r#"{ "name": "MyWarrior", "class": "Warrior", "specializations": ["arms"] }"#;
+        assert_eq!(serde_json::from_str::<Character>(json).unwrap().class, WowClass::Warrior);
+
         let mut config = Config::example();
-        config.characters[0].class = String::new();
+        config.characters[0].specializations.push("frost".into());
+        let error = config.validate().unwrap_err().to_string();
+        assert!(error.contains("valid: arms, fury, protection"), "{}", error);
+
+        config.characters[0].specializations = vec!["Prot".into(), "furry".into()];
+        let error = config.validate().unwrap_err().to_string();
+        assert!(error.contains("Invalid spec 'furry' for Warrior, did you mean 'fury'?"), "{}", error);
+        assert!(error.ends_with("(character 'MyWarrior')"), "{}", error);
+
+        config.characters[0].class = WowClass::Shaman;
+        config.characters[0].specializations = vec!["restauration".into()];
+        let error = config.validate().unwrap_err().to_string();
+        assert!(error.contains("did you mean 'restoration'?"), "{}", error);
+
+        config.characters[0].class = WowClass::Warrior;
+        config.characters[0].specializations = vec!["frost".into()];
+        let error = config.validate().unwrap_err().to_string();
+        assert!(!error.contains("did you mean"), "{}", error);
+    }
+
+    #[test]
+    fn test_deprecated_spec_names_warn() {
+        let mut config = Config::example();
+        assert!(config.warnings().is_empty());
+
+        config.characters[0].class = WowClass::Rogue;
+        config.characters[0].specializations = vec!["combat".into(), "outlaw".into()];
+        assert!(config.validate().is_ok());
+        let warnings = config.warnings();
+        assert_eq!(warnings.len(), 1);
+        assert!(warnings[0].contains("use 'outlaw'"), "{}", warnings[0]);
+
+        // Combat is the current name in Classic
+        config.flavor = GameFlavor::Cataclysm;
+        config.characters[0].specializations = vec!["combat".into()];
+        assert!(config.validate().is_ok());
+        assert!(config.warnings().is_empty());
+    }
+
+    #[test]
+    fn test_skipped_content() {
+        let mut config = Config::example();
+        config.raid_bosses = vec!["broodtwister-ovinax".to_string(), "sikran".to_string(), "ansurek".to_string()];
+        config.skip_raid_bosses = vec!["broodtwister".to_string(), "Sikran".to_string()];
+        config.skip_dungeons = vec!["city-of-threads".to_string(), "grim-batol".to_string()];
+        assert_eq!(config.raid_bosses_to_fetch(), ["ansurek"]);
+        assert_eq!(config.dungeons_to_fetch(), ["ara-kara", "mists-of-tirna-scithe"]);
+
+        // Entries that match nothing only warn
+        assert!(config.validate().is_ok());
+        assert_eq!(config.warnings(), ["Skipped dungeon 'grim-batol' is not in dungeons"]);
+    }
+
+    #[test]
+    fn test_raid_content_per_difficulty() {
+        // The flat lists fetch every boss on every difficulty
+        let mut config = Config::example();
+        config.skip_raid_bosses = vec!["sikran".to_string()];
+        let flat = config.raid_content_to_fetch();
+        assert_eq!(flat.len(), 2);
+        assert!(flat.iter().all(|(_, bosses)| *bosses == ["broodtwister", "queen-ansurek"]));
+        assert_eq!(config.raid_builds_per_spec(), 4);
+
+        let json = r#"[
+            { "difficulty": "heroic", "bosses": ["broodtwister", "sikran", "queen-ansurek"] },
+            { "difficulty": "mythic", "bosses": ["queen-ansurek"] }
+        ]"#;
+        config.raid_content = serde_json::from_str(json).unwrap();
+        config.raid_difficulties.clear();
+        config.raid_bosses.clear();
+        let content: Vec<_> = config
+            .raid_content_to_fetch()
+            .into_iter()
+            .map(|(difficulty, bosses)| (difficulty, bosses.into_iter().cloned().collect::<Vec<_>>()))
+            .collect();
+        assert_eq!(content[0], (RaidDifficulty::Heroic, vec!["broodtwister".to_string(), "queen-ansurek".to_string()]));
+        assert_eq!(content[1], (RaidDifficulty::Mythic, vec!["queen-ansurek".to_string()]));
+        assert_eq!(config.raid_bosses_to_fetch(), ["broodtwister", "queen-ansurek"]);
+        assert_eq!(config.all_raid_bosses(), ["broodtwister", "sikran", "queen-ansurek"]);
+        assert_eq!(config.raid_builds_per_spec(), 3);
+        assert!(config.validate().is_ok());
+        assert!(config.warnings().is_empty());
+
+        config.raid_bosses = vec!["sikran".to_string()];
+        assert_eq!(config.warnings(), ["raidDifficulties and raidBosses are ignored because raidContent is set"]);
+
+        config.raid_content[1].difficulty = "legendary".to_string();
+        config.raid_content[0].bosses.push("Queen Ansurek".to_string());
+        config.raid_content.push(RaidContent { difficulty: "Heroic".to_string(), bosses: Vec::new() });
+        let error = config.validate().unwrap_err().to_string();
+        assert!(error.contains("Invalid raid difficulty 'legendary'"), "{}", error);
+        assert!(error.contains("Invalid raid boss 'Queen Ansurek' for heroic"), "{}", error);
+        assert!(error.contains("Raid difficulty 'Heroic' is listed more than once"), "{}", error);
+        assert!(error.contains("Raid difficulty 'Heroic' has no bosses"), "{}", error);
+    }
+
+    #[test]
+    fn test_flavor_restricts_classes_and_specs() {
+        let mut config = Config::example();
+        config.flavor = GameFlavor::Cataclysm;
+        assert!(config.validate().is_ok());
+
+        config.characters[0].class = WowClass::Monk;
+        config.characters[0].specializations = vec!["windwalker".into()];
         assert!(config.validate().is_err());
+
+        config.characters[0].class = WowClass::Druid;
+        config.characters[0].specializations = vec!["guardian".into()];
+        let error = config.validate().unwrap_err().to_string();
+        assert!(error.contains("valid: balance, feral, restoration"), "{}", error);
     }
 
     #[test]
@@ -136,4 +1374,95 @@ mod tests {
         config.characters[0].specializations.clear();
         assert!(config.validate().is_err());
     }
+
+    #[test]
+    fn test_spec_codes_are_normalized() {
+        let json = r#"{ "name": "MyHunter", "class": "Hunter", "specializations": ["BM", "Marksmanship", "Surv"] }"#;
+        let character: Character = serde_json::from_str(json).unwrap();
+        assert_eq!(character.specializations, ["beast-mastery", "marksmanship", "survival"]);
+
+        let saved = serde_json::to_string(&character).unwrap();
+        assert!(saved.contains(r#""specializations":["beast-mastery","marksmanship","survival"]"#), "{}", saved);
+    }
+
+    #[test]
+    fn test_class_and_spec_casing_is_canonicalized() {
+        let json = r#"[
+            { "name": "A", "class": "death_knight", "specializations": ["Frost", "Unholy"] },
+            { "name": "B", "class": "HUNTER", "specializations": ["BeastMastery", "beast_mastery", "Marksmanship"] },
+            { "name": "C", "class": "demon hunter", "specializations": ["VENGEANCE"] }
+        ]"#;
+        let characters: Vec<Character> = serde_json::from_str(json).unwrap();
+        assert_eq!(characters[0].class, WowClass::DeathKnight);
+        assert_eq!(characters[0].specializations, ["frost", "unholy"]);
+        assert_eq!(characters[1].specializations, ["beast-mastery", "beast-mastery", "marksmanship"]);
+        assert_eq!(characters[2].class, WowClass::DemonHunter);
+
+        let saved = serde_json::to_string(&characters).unwrap();
+        assert!(saved.contains(r#""class":"DeathKnight","specializations":["frost","unholy"]"#), "{}", saved);
+        assert!(saved.contains(r#""class":"DemonHunter","specializations":["vengeance"]"#), "{}", saved);
+    }
+
+    #[test]
+    fn test_hero_talent_selection() {
+        let json = r#"{
+            "name": "MyWarrior",
+            "class": "Warrior",
+            "specializations": ["prot", { "spec": "arms", "heroTalent": "slayer" }, { "spec": "Fury" }]
+        }"#;
+        let character: Character = serde_json::from_str(json).unwrap();
+        assert_eq!(character.specializations[0], "protection");
+        assert_eq!(
+            character.specializations[1],
+            SpecSelection { hero_talent: Some("slayer".to_string()), .."arms".into() }
+        );
+        assert_eq!(character.specializations[2], "fury");
+
+        // Specs without a hero talent are saved as plain names
+        let saved = serde_json::to_string(&character).unwrap();
+        assert!(
+            saved.contains(r#""specializations":["protection",{"spec":"arms","heroTalent":"slayer"},"fury"]"#),
+            "{}",
+            saved
+        );
+
+        let mut config = Config::example();
+        config.characters[0] = character;
+        config.validate().unwrap();
+        config.characters[0].specializations[2].hero_talent = Some("colossus".to_string());
+        let error = config.validate().unwrap_err().to_string();
+        assert!(error.contains("Invalid hero talent colossus for WARRIOR spec 2"), "{}", error);
+    }
+
+    #[test]
+    fn test_content_type_toggles() {
+        let json = r#"{
+            "name": "MyPriest",
+            "class": "Priest",
+            "specializations": [{ "spec": "holy", "fetchRaid": false }, { "spec": "shadow", "fetchMythicPlus": true }]
+        }"#;
+        let character: Character = serde_json::from_str(json).unwrap();
+        assert!(!character.specializations[0].fetch_raid);
+        assert!(character.specializations[0].fetch_mythic_plus);
+        assert_eq!(character.specializations[1], "shadow");
+
+        let saved = serde_json::to_string(&character).unwrap();
+        assert!(saved.contains(r#""specializations":[{"spec":"holy","fetchRaid":false},"shadow"]"#), "{}", saved);
+    }
+
+    #[test]
+    fn test_role_filter() {
+        let json = r#"{ "name": "MyWarrior", "class": "Warrior", "specializations": ["arms", "prot"], "roles": ["dps"] }"#;
+        let character: Character = serde_json::from_str(json).unwrap();
+        assert_eq!(character.roles, Some(vec![Role::Damage]));
+        assert_eq!(character.wanted_specializations().collect::<Vec<_>>(), ["arms"]);
+
+        let mut config = Config::example();
+        config.characters[0] = character;
+        assert!(config.validate().is_ok());
+
+        config.characters[0].roles = Some(vec![Role::Healer]);
+        let error = config.validate().unwrap_err().to_string();
+        assert!(error.contains("Role filter of Warrior 'MyWarrior' excludes all"), "{}", error);
+    }
 }
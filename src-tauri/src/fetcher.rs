@@ -1,4 +1,5 @@
 use anyhow::{Context, Result};
+use futures_util::future::BoxFuture;
 use reqwest::{Client, StatusCode};
 use scraper::{Html, Selector};
 use std::sync::Arc;
@@ -6,9 +7,37 @@ use std::time::Duration;
 use tokio::sync::Semaphore;
 
 const MAX_CONCURRENT_REQUESTS: usize = 5;
-const REQUEST_TIMEOUT: Duration = Duration::from_secs(180); // 3 minutes
+pub const REQUEST_TIMEOUT: Duration = Duration::from_secs(180); // 3 minutes
+/// Timeout of the final attempt at builds that failed for transient reasons
+pub const RETRY_TIMEOUT: Duration = Duration::from_secs(360);
 const WOWHEAD_PREFIX: &str = "https://www.wowhead.com/talent-calc/blizzard/";
 
+/// A build page that couldn't be fetched
+#[derive(Debug, thiserror::Error)]
+#[error("{message}")]
+pub struct FetchError {
+    /// Timeouts, connection problems and server overload, which may pass on a retry
+    pub transient: bool,
+    message: String,
+}
+
+impl FetchError {
+    pub fn transient(message: impl Into<String>) -> Self {
+        Self { transient: true, message: message.into() }
+    }
+
+    pub fn permanent(message: impl Into<String>) -> Self {
+        Self { transient: false, message: message.into() }
+    }
+}
+
+/// Source of talent builds, implemented by `ArchonFetcher` (and by fakes in tests)
+pub trait TalentFetcher: Send + Sync {
+    /// Fetch the talent string of a build page, None if the page has no build
+    /// Failures are `FetchError`s.
+    fn fetch<'a>(&'a self, url: &'a str, timeout: Duration) -> BoxFuture<'a, Result<Option<String>>>;
+}
+
 /// HTTP client for fetching talent builds from Archon.gg
 pub struct ArchonFetcher {
     client: Client,
@@ -41,8 +70,10 @@ impl ArchonFetcher {
     /// Returns None if:
     /// - HTTP 500 (insufficient data)
     /// - No talent link found in response
-    /// - Request fails
-    pub async fn fetch_talent_build(&self, url: &str) -> Result<Option<String>> {
+    ///
+    /// Failed requests and other error statuses are `FetchError`s; timeouts, connection errors,
+    /// 408, 429 and 502-504 are transient.
+    pub async fn fetch_talent_build(&self, url: &str, timeout: Duration) -> Result<Option<String>> {
         // Acquire semaphore permit to limit concurrent requests
         let _permit = self
             .semaphore
@@ -51,28 +82,41 @@ impl ArchonFetcher {
             .context("Failed to acquire semaphore permit")?;
 
         // Make HTTP request
-        let response = match self.client.get(url).send().await {
+        let response = match self.client.get(url).timeout(timeout).send().await {
             Ok(resp) => resp,
             Err(e) => {
-                // Log error but don't fail - some builds may not exist
-                eprintln!("Failed to fetch {}: {}", url, e);
-                return Ok(None);
+                tracing::warn!(url, error = %e, "Request failed");
+                return Err(FetchError::transient(format!("Request failed: {}", e)).into());
             }
         };
 
         // Handle HTTP 500 as "no data available" (expected for new/unpopular builds)
-        if response.status() == StatusCode::INTERNAL_SERVER_ERROR {
+        let status = response.status();
+        if status == StatusCode::INTERNAL_SERVER_ERROR {
+            tracing::debug!(url, "No build data (HTTP 500)");
             return Ok(None);
         }
 
         // Check for other error status codes
-        if !response.status().is_success() {
-            eprintln!("HTTP {} for {}", response.status(), url);
-            return Ok(None);
+        if !status.is_success() {
+            tracing::warn!(url, %status, "Unexpected response");
+            let transient = matches!(
+                status,
+                StatusCode::REQUEST_TIMEOUT
+                    | StatusCode::TOO_MANY_REQUESTS
+                    | StatusCode::BAD_GATEWAY
+                    | StatusCode::SERVICE_UNAVAILABLE
+                    | StatusCode::GATEWAY_TIMEOUT
+            );
+            let message = format!("HTTP {}", status);
+            return Err(if transient { FetchError::transient(message) } else { FetchError::permanent(message) }.into());
         }
 
         // Parse HTML response
-        let html = response.text().await.context("Failed to read response body")?;
+        let html = match response.text().await {
+            Ok(html) => html,
+            Err(e) => return Err(FetchError::transient(format!("Failed to read response body: {}", e)).into()),
+        };
         let talent_string = self.extract_talent_string(&html)?;
 
         Ok(talent_string)
@@ -110,6 +154,12 @@ impl ArchonFetcher {
     }
 }
 
+impl TalentFetcher for ArchonFetcher {
+    fn fetch<'a>(&'a self, url: &'a str, timeout: Duration) -> BoxFuture<'a, Result<Option<String>>> {
+        Box::pin(self.fetch_talent_build(url, timeout))
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
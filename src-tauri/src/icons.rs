@@ -0,0 +1,128 @@
+use crate::archon::{RaidDifficulty, TalentIdentifier};
+use crate::wow::WowClass;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+
+/// Encounter journal icons (fileDataIDs) of current-season raid bosses, by Archon slug
+const ENCOUNTER_ICONS: &[(&str, i64)] = &[
+    ("ulgrax-the-devourer", 5779391),
+    ("the-bloodbound-horror", 5779390),
+    ("sikran", 5779394),
+    ("rashanan", 5779392),
+    ("broodtwister", 5779387),
+    ("broodtwister-ovinax", 5779387),
+    ("nexus-princess-kyveza", 5779393),
+    ("the-silken-court", 5779395),
+    ("queen-ansurek", 5779389),
+];
+
+/// Dungeon icons (fileDataIDs) of current-season Mythic+ dungeons, by Archon slug
+const DUNGEON_ICONS: &[(&str, i64)] = &[
+    ("ara-kara", 5912542),
+    ("city-of-threads", 5912543),
+    ("the-dawnbreaker", 5912544),
+    ("the-stonevault", 5912547),
+    ("mists-of-tirna-scithe", 3759919),
+    ("the-necrotic-wake", 3759920),
+    ("siege-of-boralus", 2011144),
+    ("grim-batol", 409596),
+];
+
+/// Icon overrides from the config, taking precedence over the built-in tables
+/// Sorted maps keep saved configs stable.
+#[derive(Debug, Clone, Default, Serialize, Deserialize, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct IconOverrides {
+    /// Raid boss or dungeon slug -> fileDataID
+    #[serde(default)]
+    pub content: BTreeMap<String, i64>,
+    /// Raid difficulty ("normal", "heroic", "mythic") -> fileDataID for bosses without an icon
+    #[serde(default)]
+    pub difficulties: BTreeMap<String, i64>,
+    /// Blizzard spec ID -> fileDataID
+    #[serde(default)]
+    pub specs: BTreeMap<u16, i64>,
+}
+
+/// Fallback icon for raid bosses without an encounter icon
+fn difficulty_icon(difficulty: RaidDifficulty) -> i64 {
+    match difficulty {
+        RaidDifficulty::Normal => 1322720,
+        RaidDifficulty::Heroic => 1322721,
+        RaidDifficulty::Mythic => 1322722,
+    }
+}
+
+fn lookup(table: &[(&str, i64)], slug: &str) -> Option<i64> {
+    table.iter().find(|(s, _)| *s == slug).map(|(_, icon)| *icon)
+}
+
+/// Icon for a spec, honoring overrides, or `FALLBACK_SPEC_ICON` if unknown
+pub fn spec_icon(wow_class: WowClass, spec_index: u8, overrides: &IconOverrides) -> i64 {
+    wow_class
+        .spec_id_for_index(spec_index)
+        .and_then(|spec_id| overrides.specs.get(&spec_id).copied())
+        .unwrap_or_else(|| wow_class.spec_icon(spec_index))
+}
+
+/// Pick the icon for an auto-generated loadout
+///
+/// Raid builds use the encounter icon (falling back to the difficulty icon), M+ builds
+/// the dungeon icon; anything else, including unknown dungeons, uses the spec icon.
+pub fn icon_for(
+    talent_name: &str,
+    suffix: &str,
+    wow_class: WowClass,
+    spec_index: u8,
+    overrides: &IconOverrides,
+) -> i64 {
+    let content_icon = match TalentIdentifier::from_talent_name(talent_name, suffix) {
+        Some(TalentIdentifier::Raid { difficulty, boss, .. }) => overrides
+            .content
+            .get(&boss)
+            .copied()
+            .or_else(|| lookup(ENCOUNTER_ICONS, boss.as_str()))
+            .or_else(|| overrides.difficulties.get(difficulty.as_str()).copied())
+            .or(Some(difficulty_icon(difficulty))),
+        Some(TalentIdentifier::MythicPlus { dungeon, .. }) => overrides
+            .content
+            .get(&dungeon)
+            .copied()
+            .or_else(|| lookup(DUNGEON_ICONS, dungeon.as_str())),
+        None => None,
+    };
+
+    content_icon.unwrap_or_else(|| spec_icon(wow_class, spec_index, overrides))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::archon::DEFAULT_AUTO_SUFFIX;
+
+    #[test]
+    fn test_icon_for_content() {
+        let overrides = IconOverrides::default();
+
+        assert_eq!(icon_for("R-heroic-sikran_ARCT", DEFAULT_AUTO_SUFFIX, WowClass::Warrior, 1, &overrides), 5779394);
+        assert_eq!(icon_for("M+-ara-kara_ARCT", DEFAULT_AUTO_SUFFIX, WowClass::Mage, 3, &overrides), 5912542);
+
+        // Unknown boss falls back to the difficulty, unknown dungeon to the spec
+        assert_eq!(icon_for("R-mythic-new-boss_ARCT", DEFAULT_AUTO_SUFFIX, WowClass::Warrior, 1, &overrides), 1322722);
+        assert_eq!(icon_for("M+-new-dungeon_ARCT", DEFAULT_AUTO_SUFFIX, WowClass::Warrior, 1, &overrides), 132355);
+        assert_eq!(icon_for("Something_ARCT", DEFAULT_AUTO_SUFFIX, WowClass::Monk, 2, &overrides), 608952);
+    }
+
+    #[test]
+    fn test_icon_overrides() {
+        let overrides = IconOverrides {
+            content: BTreeMap::from([("sikran".to_string(), 1)]),
+            difficulties: BTreeMap::from([("mythic".to_string(), 2)]),
+            specs: BTreeMap::from([(71, 3)]),
+        };
+
+        assert_eq!(icon_for("R-heroic-sikran_ARCT", DEFAULT_AUTO_SUFFIX, WowClass::Warrior, 1, &overrides), 1);
+        assert_eq!(icon_for("R-mythic-new-boss_ARCT", DEFAULT_AUTO_SUFFIX, WowClass::Warrior, 1, &overrides), 2);
+        assert_eq!(icon_for("M+-new-dungeon_ARCT", DEFAULT_AUTO_SUFFIX, WowClass::Warrior, 1, &overrides), 3);
+    }
+}
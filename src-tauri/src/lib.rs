@@ -1,19 +1,56 @@
 // Modules
 mod archon;
+mod atomic_write;
+mod backup;
+mod backups;
+mod battlenet;
+mod btw_loadouts;
 mod config;
 mod fetcher;
-mod lua_talent;
+mod icons;
+mod logging;
+pub mod lua_talent;
+mod metadata;
 mod orchestrator;
+mod plaintext;
+mod profiles;
+mod progress;
+mod recovery;
+mod run_history;
+mod saved_variables;
+mod scan_cache;
+mod scheduler;
+mod talent_loadouts_db;
+mod talent_string;
+#[cfg(test)]
+mod test_util;
 mod updater;
 mod warcraft_logs;
-mod wow;
+pub mod wow;
 mod wow_scanner;
 
-use config::Config;
-use orchestrator::{TalentOrchestrator, UpdateSummary};
+use backups::BackupInfo;
+use config::{Config, ContentWarning};
+use lua_talent::{
+    matches_pattern, LoadoutRef, LuaTalentManager, MergeStrategy, MergeSummary, ParseIssue, RoundTripReport,
+    SuffixOnCopy, TalentFileFormat, TalentStats,
+};
+use metadata::{LoadoutMetadataEntry, MetadataStore};
+use orchestrator::{TalentOrchestrator, UpdateSummary, WowRunningError};
+use profiles::ProfileStore;
+use progress::ProgressUpdate;
+use run_history::{RunHistory, RunRecord};
+use scheduler::{Schedule, ScheduleState, UpdateLock};
+use serde::Serialize;
+use tauri::{Emitter, Manager};
 use updater::UpdateInfo;
 use warcraft_logs::{DiscoveredContent, WarcraftLogsService};
-use wow_scanner::{DiscoveredCharacter, WowScanner};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use wow_scanner::{
+    suggested_output_format, AccountInfo, AddonStatus, InstalledAddon, PathDiagnosis, ScanResult, WowInstallation,
+    WowScanner,
+};
 
 // Learn more about Tauri commands at https://tauri.app/develop/calling-rust/
 #[tauri::command]
@@ -36,42 +73,512 @@ fn find_wow_path() -> Result<String, String> {
         .ok_or_else(|| "Could not find WoW installation".to_string())
 }
 
+/// Installed addons and the output format proposed for them
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct AddonList {
+    addons: Vec<InstalledAddon>,
+    suggested_output_format: Option<TalentFileFormat>,
+}
+
+/// Tauri command to list the installed addons, flagging the known loadout addons
+#[tauri::command]
+fn list_addons(wow_path: String) -> AddonList {
+    let addons = WowScanner::new(wow_path).scan_addons();
+    AddonList {
+        suggested_output_format: suggested_output_format(&addons),
+        addons,
+    }
+}
+
+/// Tauri command to get the TalentLoadoutsEx.lua path of an account, without scanning characters
+#[tauri::command]
+fn suggest_output_path(wow_path: String, account_id: String) -> Result<String, String> {
+    WowScanner::new(wow_path)
+        .get_talent_loadouts_path(&account_id)
+        .to_str()
+        .map(|s| s.to_string())
+        .ok_or_else(|| "Output path is not valid UTF-8".to_string())
+}
+
+/// Tauri command to list every WoW installation path found, retail first, for a picker
+#[tauri::command]
+fn find_wow_path_candidates() -> Vec<String> {
+    WowScanner::find_installation_candidates()
+        .iter()
+        .filter_map(|p| p.to_str().map(|s| s.to_string()))
+        .collect()
+}
+
+/// Tauri command to list the installed game products (retail, PTR, beta, Classic)
+#[tauri::command]
+fn list_installations() -> Vec<WowInstallation> {
+    WowScanner::find_installations()
+}
+
+/// Tauri command to get an installation with its accounts and addon state in one call
+#[tauri::command]
+fn inspect_installation(path: String) -> WowInstallation {
+    WowScanner::new(path).inspect()
+}
+
+/// Scanner for an installation from `list_installations` or `inspect_installation`; a bare
+/// path is treated by its folder name
+fn scanner_for(wow_path: String, installation: Option<WowInstallation>) -> WowScanner {
+    match installation {
+        Some(installation) => WowScanner::for_installation(installation),
+        None => WowScanner::new(wow_path),
+    }
+}
+
+/// Cancellation flag of the running character scan, shared with `cancel_scan`
+#[derive(Default)]
+struct ScanCancellation(Arc<AtomicBool>);
+
 /// Tauri command to scan for characters in WoW installation
+/// Pass an installation from `list_installations`; a bare path is treated by its folder name.
+/// With `min_last_played_days`, characters not played in that many days are left out.
+/// Runs on the blocking pool and emits `scan-progress` events while scanning.
 #[tauri::command]
-fn scan_characters(wow_path: String) -> Result<Vec<DiscoveredCharacter>, String> {
-    let scanner = WowScanner::new(wow_path);
-    scanner
-        .scan_characters()
+async fn scan_characters(
+    app: tauri::AppHandle,
+    cancellation: tauri::State<'_, ScanCancellation>,
+    wow_path: String,
+    installation: Option<WowInstallation>,
+    min_last_played_days: Option<u32>,
+) -> Result<ScanResult, String> {
+    let scanner = scanner_for(wow_path, installation);
+    let cancel = Arc::clone(&cancellation.0);
+    cancel.store(false, Ordering::Relaxed);
+
+    let mut scan = run_blocking(move || {
+        scanner
+            .scan_characters(&cancel, |progress| {
+                app.emit("scan-progress", progress.clone()).ok();
+            })
+            .map_err(|e| format!("Failed to scan characters: {}", e))
+    })
+    .await?;
+
+    if let Some(days) = min_last_played_days {
+        let now = chrono::Utc::now();
+        scan.characters.retain(|character| character.played_within(days, now));
+    }
+    Ok(scan)
+}
+
+/// Tauri command to scan for characters, reusing the cached scan of accounts whose folders
+/// haven't changed if it is younger than `max_age_secs`
+#[tauri::command]
+async fn scan_characters_cached(
+    app: tauri::AppHandle,
+    cancellation: tauri::State<'_, ScanCancellation>,
+    wow_path: String,
+    installation: Option<WowInstallation>,
+    max_age_secs: u64,
+    min_last_played_days: Option<u32>,
+) -> Result<ScanResult, String> {
+    let scanner = scanner_for(wow_path, installation);
+    let cache_path = app_data_file(&app, scan_cache::SCAN_CACHE_FILE_NAME)?;
+    let cancel = Arc::clone(&cancellation.0);
+    cancel.store(false, Ordering::Relaxed);
+
+    let mut scan = run_blocking(move || {
+        scan_cache::scan_characters_cached(&scanner, &cache_path, max_age_secs, &cancel, |progress| {
+            app.emit("scan-progress", progress.clone()).ok();
+        })
         .map_err(|e| format!("Failed to scan characters: {}", e))
+    })
+    .await?;
+
+    if let Some(days) = min_last_played_days {
+        let now = chrono::Utc::now();
+        scan.characters.retain(|character| character.played_within(days, now));
+    }
+    Ok(scan)
+}
+
+/// Tauri command to stop the running character scan
+#[tauri::command]
+fn cancel_scan(cancellation: tauri::State<'_, ScanCancellation>) {
+    cancellation.0.store(true, Ordering::Relaxed);
 }
 
 /// Tauri command to update talents from Archon.gg
 #[tauri::command]
-async fn update_talents_from_config(config: Config) -> Result<UpdateSummary, String> {
-    // Create orchestrator and run
-    let orchestrator = TalentOrchestrator::new(config);
-    orchestrator
-        .run()
-        .await
-        .map_err(|e| format!("Failed to update talents: {}", e))
+async fn update_talents_from_config(app: tauri::AppHandle, config: serde_json::Value) -> Result<UpdateSummary, String> {
+    // Settings saved by older versions are upgraded first
+    let mut config = config::migrate(config).map_err(|e| format!("Failed to load config: {}", e))?;
+    let resolved_output_path = config
+        .resolve_output_path()
+        .map_err(|e| format!("Failed to find the talent file: {}", e))?;
+    config.validate().map_err(|e| format!("Failed to load config: {}", e))?;
+    if config.plaintext_path.is_none() {
+        config.plaintext_path = Some(default_plaintext_path(&app)?);
+    }
+
+    let mut summary = run_manual_update(&app, config).await?;
+    summary.resolved_output_path = resolved_output_path;
+    Ok(summary)
+}
+
+/// Tauri command to get the talent file an update would use for a config without `outputPath`,
+/// so the UI can confirm it first; None if the config doesn't need one
+#[tauri::command]
+fn resolve_output_path(config: serde_json::Value) -> Result<Option<std::path::PathBuf>, String> {
+    let mut config = config::migrate(config).map_err(|e| format!("Invalid config: {}", e))?;
+    config
+        .resolve_output_path()
+        .map_err(|e| format!("Failed to find the talent file: {}", e))
 }
 
 /// Tauri command to update talents from a config file (kept for backwards compatibility)
 #[tauri::command]
-async fn update_talents(config_path: String) -> Result<String, String> {
+async fn update_talents(app: tauri::AppHandle, config_path: String) -> Result<String, String> {
     // Load configuration
-    let config = Config::from_file(&config_path).map_err(|e| format!("Failed to load config: {}", e))?;
+    let mut config = Config::from_file(&config_path).map_err(|e| format!("Failed to load config: {}", e))?;
+    if config.plaintext_path.is_none() {
+        config.plaintext_path = Some(default_plaintext_path(&app)?);
+    }
 
-    // Create orchestrator and run
-    let orchestrator = TalentOrchestrator::new(config);
-    orchestrator
-        .run()
-        .await
-        .map_err(|e| format!("Failed to update talents: {}", e))?;
+    run_manual_update(&app, config).await?;
 
     Ok("Talents updated successfully!".to_string())
 }
 
+/// Orchestrator forwarding its progress to the UI as `talent-update-progress` events
+fn orchestrator_for(app: &tauri::AppHandle, config: Config) -> TalentOrchestrator {
+    logging::set_level(config.log_level);
+    let app = app.clone();
+    TalentOrchestrator::new(config).with_progress_reporter(move |update: &ProgressUpdate<'_>| {
+        app.emit("talent-update-progress", update).ok();
+    })
+}
+
+/// Run an update started from the UI, after a scheduled update in progress has finished
+/// A successful run (other than a dry run) restarts the schedule and makes its config the one
+/// scheduled updates use.
+async fn run_manual_update(app: &tauri::AppHandle, config: Config) -> Result<UpdateSummary, String> {
+    let lock = app.state::<UpdateLock>().inner().clone();
+    let _guard = lock.0.lock().await;
+
+    let summary = run_recorded(app, &config, false).await.map_err(update_error)?;
+    if config.dry_run {
+        return Ok(summary);
+    }
+    if let Err(e) = record_run(app, &config) {
+        tracing::warn!("Failed to record update for the schedule: {}", e);
+    }
+    Ok(summary)
+}
+
+/// Run an update and add it to the run history, unless it is a dry run
+/// Failing to write the history doesn't fail the update.
+async fn run_recorded(app: &tauri::AppHandle, config: &Config, scheduled: bool) -> anyhow::Result<UpdateSummary> {
+    let started = std::time::Instant::now();
+    let result = orchestrator_for(app, config.clone()).run().await;
+    if !config.dry_run {
+        let record = RunRecord::new(config, &result, started.elapsed(), scheduled);
+        let recorded = run_history_path(app)
+            .and_then(|path| run_history::append(&path, record).map_err(|e| format!("{:#}", e)));
+        if let Err(e) = recorded {
+            tracing::warn!("Failed to record update in the run history: {}", e);
+        }
+    }
+    result
+}
+
+/// Error message of a failed update; a running client is prefixed with its code so the UI can
+/// ask to close the game
+fn update_error(e: anyhow::Error) -> String {
+    if e.is::<WowRunningError>() {
+        format!("{}: {}", WowRunningError::CODE, e)
+    } else {
+        format!("Failed to update talents: {}", e)
+    }
+}
+
+/// Tauri command to validate and save a config file, as TOML for a `.toml` path and pretty
+/// JSON otherwise
+#[tauri::command]
+fn save_config(config: serde_json::Value, path: String) -> Result<(), String> {
+    let config = config::migrate(config).map_err(|e| format!("Invalid config: {}", e))?;
+    config.validate().map_err(|e| format!("Invalid config: {}", e))?;
+    config.to_file(&path).map_err(|e| format!("Failed to save config: {}", e))
+}
+
+/// Path of a file or folder in the app data directory
+fn app_data_file(app: &tauri::AppHandle, name: &str) -> Result<std::path::PathBuf, String> {
+    app.path()
+        .app_data_dir()
+        .map(|dir| dir.join(name))
+        .map_err(|e| format!("Failed to resolve app data directory: {}", e))
+}
+
+/// Named configs in the app data directory
+fn profile_store(app: &tauri::AppHandle) -> Result<ProfileStore, String> {
+    app_data_file(app, profiles::PROFILES_DIR_NAME).map(ProfileStore::new)
+}
+
+/// Tauri command to list the names of all config profiles
+#[tauri::command]
+fn list_profiles(app: tauri::AppHandle) -> Result<Vec<String>, String> {
+    profile_store(&app)?
+        .list()
+        .map_err(|e| format!("Failed to list profiles: {}", e))
+}
+
+/// Tauri command to load the config of a profile
+#[tauri::command]
+fn get_profile(app: tauri::AppHandle, name: String) -> Result<Config, String> {
+    profile_store(&app)?
+        .get(&name)
+        .map_err(|e| format!("Failed to load profile: {}", e))
+}
+
+/// Tauri command to validate and save a config as a profile, replacing a profile of the same name
+#[tauri::command]
+fn save_profile(app: tauri::AppHandle, name: String, config: serde_json::Value) -> Result<(), String> {
+    let config = config::migrate(config).map_err(|e| format!("Invalid config: {}", e))?;
+    config.validate().map_err(|e| format!("Invalid config: {}", e))?;
+    profile_store(&app)?
+        .save(&name, &config)
+        .map_err(|e| format!("Failed to save profile: {}", e))
+}
+
+/// Tauri command to delete a profile
+#[tauri::command]
+fn delete_profile(app: tauri::AppHandle, name: String) -> Result<(), String> {
+    profile_store(&app)?
+        .delete(&name)
+        .map_err(|e| format!("Failed to delete profile: {}", e))
+}
+
+/// Tauri command to copy a profile to a new name
+#[tauri::command]
+fn duplicate_profile(app: tauri::AppHandle, from: String, to: String) -> Result<(), String> {
+    profile_store(&app)?
+        .duplicate(&from, &to)
+        .map_err(|e| format!("Failed to duplicate profile: {}", e))
+}
+
+/// Tauri command to update talents with the config of a profile
+#[tauri::command]
+async fn update_talents_from_profile(app: tauri::AppHandle, name: String) -> Result<UpdateSummary, String> {
+    let mut config = profile_store(&app)?
+        .get(&name)
+        .map_err(|e| format!("Failed to load profile: {}", e))?;
+    let resolved_output_path = config
+        .resolve_output_path()
+        .map_err(|e| format!("Failed to find the talent file: {}", e))?;
+    config.validate().map_err(|e| format!("Failed to load profile: {}", e))?;
+    if config.plaintext_path.is_none() {
+        config.plaintext_path = Some(default_plaintext_path(&app)?);
+    }
+
+    let mut summary = run_manual_update(&app, config).await?;
+    summary.resolved_output_path = resolved_output_path;
+    Ok(summary)
+}
+
+/// State file of scheduled updates in the app data directory
+fn schedule_state_path(app: &tauri::AppHandle) -> Result<std::path::PathBuf, String> {
+    app_data_file(app, scheduler::SCHEDULE_STATE_FILE_NAME)
+}
+
+/// Log file in the app data directory
+fn log_file_path(app: &tauri::AppHandle) -> Result<std::path::PathBuf, String> {
+    app_data_file(app, logging::LOG_FILE_NAME)
+}
+
+/// Tauri command to get the path of the log file, e.g. to attach it to a bug report
+#[tauri::command]
+fn get_log_file_path(app: tauri::AppHandle) -> Result<String, String> {
+    log_file_path(&app).map(|path| path.to_string_lossy().into_owned())
+}
+
+/// Tauri command to read the last `lines` lines of the log
+#[tauri::command]
+fn get_recent_logs(app: tauri::AppHandle, lines: usize) -> Result<Vec<String>, String> {
+    let path = log_file_path(&app)?;
+    logging::recent_lines(&path, lines).map_err(|e| format!("Failed to read log: {}", e))
+}
+
+/// History of previous updates in the app data directory
+fn run_history_path(app: &tauri::AppHandle) -> Result<std::path::PathBuf, String> {
+    app_data_file(app, run_history::RUN_HISTORY_FILE_NAME)
+}
+
+/// Tauri command to list previous updates (at most 50), newest first
+#[tauri::command]
+fn get_run_history(app: tauri::AppHandle) -> Result<Vec<RunRecord>, String> {
+    let history = RunHistory::load(&run_history_path(&app)?)
+        .map_err(|e| format!("Failed to load run history: {}", e))?;
+    Ok(history.runs().cloned().collect())
+}
+
+/// Tauri command to get the last update, None before the first one
+#[tauri::command]
+fn get_last_run(app: tauri::AppHandle) -> Result<Option<RunRecord>, String> {
+    let history = RunHistory::load(&run_history_path(&app)?)
+        .map_err(|e| format!("Failed to load run history: {}", e))?;
+    Ok(history.last().cloned())
+}
+
+/// Record a finished update in the schedule state
+fn record_run(app: &tauri::AppHandle, config: &Config) -> Result<(), String> {
+    let path = schedule_state_path(app)?;
+    let mut state = ScheduleState::load(&path).map_err(|e| format!("{:#}", e))?;
+    state
+        .record_run(config, chrono::Local::now())
+        .and_then(|_| state.save(&path))
+        .map_err(|e| format!("{:#}", e))
+}
+
+/// Tauri command to get the time of the next scheduled update (RFC 3339), if a schedule is set
+#[tauri::command]
+fn get_next_scheduled_run(app: tauri::AppHandle) -> Result<Option<String>, String> {
+    let state = ScheduleState::load(&schedule_state_path(&app)?)
+        .map_err(|e| format!("Failed to load schedule: {}", e))?;
+    state
+        .next_run(chrono::Local::now())
+        .map(|next| next.map(|next| next.to_rfc3339()))
+        .map_err(|e| format!("Failed to load schedule: {}", e))
+}
+
+/// Tauri command to set or clear (`None`) the schedule of automatic updates
+/// Scheduled updates use the config of the last update, so one has to have run first.
+/// Returns the time of the next scheduled update.
+#[tauri::command]
+fn set_schedule(app: tauri::AppHandle, schedule: Option<Schedule>) -> Result<Option<String>, String> {
+    if let Some(schedule) = &schedule {
+        schedule.validate().map_err(|e| format!("Invalid schedule: {}", e))?;
+    }
+
+    let path = schedule_state_path(&app)?;
+    let mut state = ScheduleState::load(&path).map_err(|e| format!("Failed to load schedule: {}", e))?;
+    let mut config = state
+        .config()
+        .map_err(|e| format!("Failed to load schedule: {}", e))?
+        .ok_or_else(|| "Update talents once before scheduling updates".to_string())?;
+    config.schedule = schedule;
+    let now = chrono::Local::now();
+    let last_run = state.last_run().ok().flatten().unwrap_or(now);
+    state
+        .record_run(&config, last_run)
+        .and_then(|_| state.save(&path))
+        .map_err(|e| format!("Failed to save schedule: {}", e))?;
+
+    state
+        .next_run(now)
+        .map(|next| next.map(|next| next.to_rfc3339()))
+        .map_err(|e| format!("Failed to save schedule: {}", e))
+}
+
+/// Result of a scheduled update, sent to the UI as a `scheduled-update` event
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct ScheduledUpdate {
+    summary: Option<UpdateSummary>,
+    error: Option<String>,
+}
+
+/// Background task running scheduled updates
+/// The state is read again on every tick, so schedule and config changes apply right away; due
+/// times are compared with the wall clock, so updates missed during sleep run once on wake.
+async fn run_scheduler(app: tauri::AppHandle) {
+    let mut ticks = tokio::time::interval(scheduler::TICK_INTERVAL);
+    ticks.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Skip);
+    loop {
+        ticks.tick().await;
+        if let Err(e) = run_scheduled_update_if_due(&app).await {
+            tracing::warn!("Scheduled update check failed: {}", e);
+        }
+    }
+}
+
+async fn run_scheduled_update_if_due(app: &tauri::AppHandle) -> Result<(), String> {
+    let path = schedule_state_path(app)?;
+    let now = chrono::Local::now();
+    let mut state = ScheduleState::load(&path).map_err(|e| format!("{:#}", e))?;
+    let Some(next) = state.next_run(now).map_err(|e| format!("{:#}", e))? else {
+        return Ok(());
+    };
+    if next > now {
+        return Ok(());
+    }
+    let Some(config) = state.config().map_err(|e| format!("{:#}", e))? else {
+        return Ok(());
+    };
+
+    // A manual update is running; it restarts the schedule when it's done
+    let lock = app.state::<UpdateLock>().inner().clone();
+    let Ok(_guard) = lock.0.try_lock() else {
+        return Ok(());
+    };
+
+    // Checked here rather than failing the run, so the update happens once the game is closed
+    let wait_for_idle = config.schedule.as_ref().is_some_and(|s| s.only_when_idle) || !config.ignore_running_client;
+    if wait_for_idle && wow_running(&config) {
+        return Ok(());
+    }
+
+    let result = run_recorded(app, &config, true).await;
+    // Recorded even on failure so a persistent error isn't retried every tick
+    state
+        .record_run(&config, now)
+        .and_then(|_| state.save(&path))
+        .map_err(|e| format!("{:#}", e))?;
+
+    let payload = match result {
+        Ok(summary) => ScheduledUpdate { summary: Some(summary), error: None },
+        Err(e) => ScheduledUpdate { summary: None, error: Some(update_error(e)) },
+    };
+    app.emit("scheduled-update", &payload).ok();
+    Ok(())
+}
+
+/// Whether WoW runs for any of the config's talent files
+fn wow_running(config: &Config) -> bool {
+    config.output_files().iter().any(|(path, _)| wow_running_for(path))
+}
+
+/// Whether the installation a talent file belongs to is running
+fn wow_running_for(path: &std::path::Path) -> bool {
+    wow_scanner::wow_path_for_talent_file(path).is_some_and(|wow_path| WowScanner::new(wow_path).is_wow_running())
+}
+
+/// Refuse to rewrite a talent file the running client would overwrite on logout, with the same
+/// error code as an update, unless the config's `ignore_running_client` is set
+fn ensure_wow_closed(path: &str, ignore_running_client: bool) -> Result<(), String> {
+    if !ignore_running_client && wow_running_for(std::path::Path::new(path)) {
+        return Err(format!("{}: {}", WowRunningError::CODE, WowRunningError));
+    }
+    Ok(())
+}
+
+/// Default location of the plaintext build list in the app data directory
+fn default_plaintext_path(app: &tauri::AppHandle) -> Result<std::path::PathBuf, String> {
+    app_data_file(app, plaintext::PLAINTEXT_FILE_NAME)
+}
+
+/// Tauri command to read the plaintext build list (defaults to the app data directory)
+#[tauri::command]
+fn read_plaintext_builds(app: tauri::AppHandle, path: Option<String>) -> Result<String, String> {
+    let path = match path {
+        Some(path) => std::path::PathBuf::from(path),
+        None => default_plaintext_path(&app)?,
+    };
+
+    std::fs::read_to_string(&path).map_err(|e| format!("Failed to read build list: {}", e))
+}
+
+/// Tauri command to check a WoW path piece by piece, with a corrected path if one is found
+#[tauri::command]
+fn validate_wow_path(path: String) -> PathDiagnosis {
+    WowScanner::diagnose_path(std::path::Path::new(&path))
+}
+
 /// Tauri command to check if TalentLoadoutsEx addon is installed
 #[tauri::command]
 fn check_addon_installed(wow_path: String) -> bool {
@@ -80,6 +587,365 @@ fn check_addon_installed(wow_path: String) -> bool {
         .exists()
 }
 
+/// Tauri command to list the accounts of an installation and the state of their talent files
+#[tauri::command]
+fn list_accounts(wow_path: String, installation: Option<WowInstallation>) -> Vec<AccountInfo> {
+    scanner_for(wow_path, installation).list_accounts()
+}
+
+/// Tauri command to get the addon's installed version and which accounts have its data
+#[tauri::command]
+fn check_addon(wow_path: String, installation: Option<WowInstallation>) -> AddonStatus {
+    scanner_for(wow_path, installation).addon_status()
+}
+
+/// Run file work on tokio's blocking pool so large files don't stall the command thread
+async fn run_blocking<T, F>(f: F) -> Result<T, String>
+where
+    T: Send + 'static,
+    F: FnOnce() -> Result<T, String> + Send + 'static,
+{
+    tokio::task::spawn_blocking(f)
+        .await
+        .map_err(|e| format!("Background task failed: {}", e))?
+}
+
+/// Entries skipped while reading a talent file, sent to the UI as a warning
+#[derive(Clone, Serialize)]
+struct ParseIssuesEvent {
+    path: String,
+    issues: Vec<ParseIssue>,
+}
+
+/// Emit a `parse-issues` event if entries of the file at `path` were skipped
+fn report_parse_issues(app: &tauri::AppHandle, path: &str, issues: Vec<ParseIssue>) {
+    if issues.is_empty() {
+        return;
+    }
+
+    tracing::warn!(path, ?issues, "Skipped {} entries while reading the talent file", issues.len());
+    app.emit(
+        "parse-issues",
+        ParseIssuesEvent {
+            path: path.to_string(),
+            issues,
+        },
+    )
+    .ok();
+}
+
+/// Load a TalentLoadoutsEx.lua file, or start empty if it doesn't exist yet
+fn load_talents_or_new(app: &tauri::AppHandle, path: &str) -> Result<LuaTalentManager, String> {
+    if std::path::Path::new(path).exists() {
+        let (manager, issues) =
+            LuaTalentManager::load_from_file(path, None).map_err(|e| format!("Failed to load {}: {}", path, e))?;
+        report_parse_issues(app, path, issues);
+        Ok(manager)
+    } else {
+        Ok(LuaTalentManager::new())
+    }
+}
+
+/// Back up the existing file, then write the manager's loadouts atomically
+/// Fails with the WOW_RUNNING code while the game is running, see `ensure_wow_closed`.
+fn backup_and_write(manager: &LuaTalentManager, path: &str, ignore_running_client: bool) -> Result<(), String> {
+    backup_and_write_with(manager, path, ignore_running_client, |_| {})
+}
+
+/// Like `backup_and_write`, applying `edit` to the metadata sidecar once the file is written, so
+/// the sidecar never describes a change that failed to reach the file
+fn backup_and_write_with(
+    manager: &LuaTalentManager,
+    path: &str,
+    ignore_running_client: bool,
+    edit: impl FnOnce(&mut MetadataStore),
+) -> Result<(), String> {
+    ensure_wow_closed(path, ignore_running_client)?;
+    let backup_path = backup::create_backup(path, backup::DEFAULT_BACKUP_COUNT)
+        .map_err(|e| format!("Failed to back up {}: {}", path, e))?;
+    if let Some(backup_path) = backup_path {
+        backups::register(&backup_path, std::path::Path::new(path), TalentFileFormat::TalentLoadoutsEx)
+            .map_err(|e| format!("Failed to register backup of {}: {}", path, e))?;
+    }
+    manager
+        .write_to_file(path)
+        .map_err(|e| format!("Failed to write {}: {}", path, e))?;
+
+    update_metadata(manager, path, edit)
+}
+
+/// Apply `edit` to the metadata sidecar of a talent file, if it has one, and drop the provenance
+/// of loadouts that are no longer in `manager`
+fn update_metadata(
+    manager: &LuaTalentManager,
+    path: &str,
+    edit: impl FnOnce(&mut MetadataStore),
+) -> Result<(), String> {
+    let metadata_path = metadata::sidecar_path(std::path::Path::new(path));
+    if !metadata_path.exists() {
+        return Ok(());
+    }
+    let mut store =
+        MetadataStore::load(&metadata_path).map_err(|e| format!("Failed to update loadout metadata: {}", e))?;
+    let original = store.clone();
+    edit(&mut store);
+    store.reconcile(manager);
+    if store != original {
+        store.save(&metadata_path).map_err(|e| format!("Failed to update loadout metadata: {}", e))?;
+    }
+    Ok(())
+}
+
+/// Tauri command to merge the loadouts of one TalentLoadoutsEx.lua file into another
+#[tauri::command]
+async fn merge_talent_files(
+    app: tauri::AppHandle,
+    source_path: String,
+    dest_path: String,
+    strategy: MergeStrategy,
+    ignore_running_client: bool,
+) -> Result<MergeSummary, String> {
+    run_blocking(move || {
+        let (source, issues) = LuaTalentManager::load_from_file(&source_path, None)
+            .map_err(|e| format!("Failed to load source file: {}", e))?;
+        report_parse_issues(&app, &source_path, issues);
+
+        let mut dest = load_talents_or_new(&app, &dest_path)?;
+        let summary = dest.merge_from(source, strategy);
+        backup_and_write(&dest, &dest_path, ignore_running_client)?;
+
+        Ok(summary)
+    })
+    .await
+}
+
+/// Tauri command to export all loadouts of a TalentLoadoutsEx.lua file as JSON
+#[tauri::command]
+async fn export_loadouts_json(app: tauri::AppHandle, lua_path: String) -> Result<String, String> {
+    run_blocking(move || {
+        let (manager, issues) = LuaTalentManager::load_from_file(&lua_path, None)
+            .map_err(|e| format!("Failed to export loadouts: {}", e))?;
+        report_parse_issues(&app, &lua_path, issues);
+
+        manager.to_json().map_err(|e| format!("Failed to export loadouts: {}", e))
+    })
+    .await
+}
+
+/// Tauri command to import loadouts from JSON into a TalentLoadoutsEx.lua file
+/// With `replace`, all existing loadouts are dropped first; otherwise imported
+/// loadouts replace existing ones with the same name
+#[tauri::command]
+async fn import_loadouts_json(
+    app: tauri::AppHandle,
+    lua_path: String,
+    json: String,
+    replace: bool,
+    ignore_running_client: bool,
+) -> Result<MergeSummary, String> {
+    let imported = LuaTalentManager::from_json(&json)
+        .map_err(|e| format!("Failed to import loadouts: {}", e))?;
+
+    run_blocking(move || {
+        let mut manager = load_talents_or_new(&app, &lua_path)?;
+        if replace {
+            manager.clear();
+        }
+        let summary = manager.merge_from(imported, MergeStrategy::PreferOther);
+        backup_and_write(&manager, &lua_path, ignore_running_client)?;
+
+        Ok(summary)
+    })
+    .await
+}
+
+/// Tauri command to remove auto-generated loadouts whose name matches a pattern
+/// (`*` is a wildcard, e.g. "R-*-sikran_ARCT"). With `dry_run`, nothing is written.
+/// Returns the names of the removed (or to-be-removed) loadouts.
+#[tauri::command]
+async fn remove_loadouts(
+    app: tauri::AppHandle,
+    lua_path: String,
+    pattern: String,
+    dry_run: bool,
+    ignore_running_client: bool,
+) -> Result<Vec<String>, String> {
+    run_blocking(move || {
+        let (mut manager, issues) = LuaTalentManager::load_from_file(&lua_path, None)
+            .map_err(|e| format!("Failed to load {}: {}", lua_path, e))?;
+        report_parse_issues(&app, &lua_path, issues);
+
+        let removed: Vec<String> = manager
+            .remove_matching(None, None, |t| matches_pattern(&pattern, &t.name))
+            .into_iter()
+            .map(|(_, _, talent)| talent.name)
+            .collect();
+
+        if !dry_run && !removed.is_empty() {
+            backup_and_write(&manager, &lua_path, ignore_running_client)?;
+        }
+
+        Ok(removed)
+    })
+    .await
+}
+
+/// Tauri command to rename a loadout in a TalentLoadoutsEx.lua file, keeping its position
+#[tauri::command]
+async fn rename_loadout(
+    app: tauri::AppHandle,
+    lua_path: String,
+    class_name: String,
+    spec_index: u8,
+    old_name: String,
+    new_name: String,
+    ignore_running_client: bool,
+) -> Result<(), String> {
+    run_blocking(move || {
+        let (mut manager, issues) = LuaTalentManager::load_from_file(&lua_path, None)
+            .map_err(|e| format!("Failed to load {}: {}", lua_path, e))?;
+        report_parse_issues(&app, &lua_path, issues);
+
+        manager
+            .rename_talent(&class_name, spec_index, &old_name, &new_name)
+            .map_err(|e| format!("Failed to rename loadout: {}", e))?;
+
+        // Keep the provenance of the loadout under its new name
+        backup_and_write_with(&manager, &lua_path, ignore_running_client, |store| {
+            store.rename(&class_name, spec_index, &old_name, &new_name)
+        })
+    })
+    .await
+}
+
+/// Tauri command to move a loadout to another class, spec or name
+#[tauri::command]
+async fn move_loadout(
+    app: tauri::AppHandle,
+    lua_path: String,
+    from: LoadoutRef,
+    to: LoadoutRef,
+    ignore_running_client: bool,
+) -> Result<(), String> {
+    run_blocking(move || {
+        let (mut manager, issues) = LuaTalentManager::load_from_file(&lua_path, None)
+            .map_err(|e| format!("Failed to load {}: {}", lua_path, e))?;
+        report_parse_issues(&app, &lua_path, issues);
+
+        manager.move_talent(&from, &to).map_err(|e| format!("Failed to move loadout: {}", e))?;
+        backup_and_write(&manager, &lua_path, ignore_running_client)
+    })
+    .await
+}
+
+/// Tauri command to copy a loadout to another class or spec; returns the name of the copy
+#[tauri::command]
+async fn copy_loadout(
+    app: tauri::AppHandle,
+    lua_path: String,
+    from: LoadoutRef,
+    to: LoadoutRef,
+    suffix: SuffixOnCopy,
+    ignore_running_client: bool,
+) -> Result<String, String> {
+    run_blocking(move || {
+        let (mut manager, issues) = LuaTalentManager::load_from_file(&lua_path, None)
+            .map_err(|e| format!("Failed to load {}: {}", lua_path, e))?;
+        report_parse_issues(&app, &lua_path, issues);
+
+        let name = manager
+            .copy_talent(&from, &to, suffix)
+            .map_err(|e| format!("Failed to copy loadout: {}", e))?;
+        backup_and_write(&manager, &lua_path, ignore_running_client)?;
+
+        Ok(name)
+    })
+    .await
+}
+
+/// Tauri command to count the manual and auto-generated loadouts of a TalentLoadoutsEx.lua file
+#[tauri::command]
+async fn get_talent_stats(app: tauri::AppHandle, path: String) -> Result<TalentStats, String> {
+    run_blocking(move || {
+        let (manager, issues) =
+            LuaTalentManager::load_from_file(&path, None).map_err(|e| format!("Failed to load {}: {}", path, e))?;
+        report_parse_issues(&app, &path, issues);
+
+        Ok(manager.stats())
+    })
+    .await
+}
+
+/// Tauri command to list the backups of a talent file, newest first
+/// Loadout counts aren't read here; `get_backup_stats` parses one backup on demand.
+#[tauri::command]
+async fn list_backups(output_path: String) -> Result<Vec<BackupInfo>, String> {
+    run_blocking(move || {
+        backups::list(&output_path).map_err(|e| format!("Failed to list backups of {}: {}", output_path, e))
+    })
+    .await
+}
+
+/// Tauri command to get the loadout counts of one backup of a talent file
+#[tauri::command]
+async fn get_backup_stats(output_path: String, backup_id: String) -> Result<TalentStats, String> {
+    run_blocking(move || {
+        let manager = backups::find(&output_path, &backup_id)
+            .and_then(|backup| backup.load())
+            .map_err(|e| format!("Failed to read backup of {}: {:#}", output_path, e))?;
+        Ok(manager.stats())
+    })
+    .await
+}
+
+/// Tauri command to replace a talent file with one of its backups, after backing up the current
+/// file (keeping the config's `backup_count` backups); returns the path of that backup
+#[tauri::command]
+async fn restore_backup(
+    output_path: String,
+    backup_id: String,
+    backup_count: usize,
+    ignore_running_client: bool,
+) -> Result<Option<String>, String> {
+    run_blocking(move || {
+        ensure_wow_closed(&output_path, ignore_running_client)?;
+        let (manager, current_backup) = backups::restore(&output_path, &backup_id, backup_count)
+            .map_err(|e| format!("Failed to restore {}: {:#}", output_path, e))?;
+        // Drop provenance of loadouts the restored file doesn't have
+        update_metadata(&manager, &output_path, |_| {})?;
+        Ok(current_backup.map(|path| path.to_string_lossy().into_owned()))
+    })
+    .await
+}
+
+/// Tauri command to check that a TalentLoadoutsEx.lua file survives a write unchanged
+/// (loads, serializes and re-parses it without writing anything)
+#[tauri::command]
+async fn verify_talent_file(path: String) -> Result<RoundTripReport, String> {
+    run_blocking(move || {
+        LuaTalentManager::verify_roundtrip(&path).map_err(|e| format!("Failed to verify {}: {}", path, e))
+    })
+    .await
+}
+
+/// Tauri command to look up where loadouts with the given name were fetched from
+/// (one entry per class/spec that has such a loadout)
+#[tauri::command]
+async fn get_loadout_metadata(lua_path: String, name: String) -> Result<Vec<LoadoutMetadataEntry>, String> {
+    run_blocking(move || {
+        let store = MetadataStore::load(metadata::sidecar_path(std::path::Path::new(&lua_path)))
+            .map_err(|e| format!("Failed to load loadout metadata: {}", e))?;
+        Ok(store.find(&name))
+    })
+    .await
+}
+
+/// Tauri command to list all classes with their color, icon and specs
+#[tauri::command]
+fn class_metadata() -> Vec<wow::ClassMetadata> {
+    wow::class_metadata()
+}
+
 /// Tauri command to check for updates on GitHub
 #[tauri::command]
 async fn check_for_updates() -> Result<UpdateInfo, String> {
@@ -104,23 +970,111 @@ async fn discover_content() -> Result<DiscoveredContent, String> {
         .map_err(|e| format!("Failed to discover content: {}", e))
 }
 
+/// Tauri command to pre-fill a config for onboarding from a character scan and content discovery
+/// Nothing is written; characters whose class couldn't be detected have an empty class.
+#[tauri::command]
+async fn generate_default_config(wow_path: String) -> Result<serde_json::Value, String> {
+    let scanner = WowScanner::new(wow_path);
+    let scan = run_blocking(move || {
+        scanner
+            .scan_characters(&AtomicBool::new(false), |_| {})
+            .map_err(|e| format!("Failed to scan characters: {}", e))
+    })
+    .await?;
+    if scan.characters.is_empty() {
+        return Err("No characters found in this installation".to_string());
+    }
+
+    let content = WarcraftLogsService::discover_current_content()
+        .await
+        .map_err(|e| format!("Failed to discover content: {}", e))?;
+    config::starter_config(&scan.characters, content).map_err(|e| format!("Failed to generate config: {}", e))
+}
+
+/// Tauri command to check the configured bosses and dungeons against the current season
+#[tauri::command]
+async fn validate_config_content(config: serde_json::Value) -> Result<Vec<ContentWarning>, String> {
+    let config = config::migrate(config).map_err(|e| format!("Invalid config: {}", e))?;
+    let content = WarcraftLogsService::discover_current_content()
+        .await
+        .map_err(|e| format!("Failed to discover content: {}", e))?;
+    Ok(config.validate_against_content(&content))
+}
+
 #[cfg_attr(mobile, tauri::mobile_entry_point)]
 pub fn run() {
     tauri::Builder::default()
         .plugin(tauri_plugin_opener::init())
         .plugin(tauri_plugin_dialog::init())
         .plugin(tauri_plugin_store::Builder::new().build())
+        .manage(ScanCancellation::default())
+        .manage(UpdateLock::default())
+        .setup(|app| {
+            // Without a log file the app still works; messages only go to stdout
+            match log_file_path(&app.handle().clone()) {
+                Ok(path) => {
+                    let dir = path.parent().unwrap_or(std::path::Path::new("."));
+                    if let Err(e) = logging::init(dir) {
+                        eprintln!("Failed to set up logging: {:#}", e);
+                    }
+                }
+                Err(e) => eprintln!("{}", e),
+            }
+            tauri::async_runtime::spawn(run_scheduler(app.handle().clone()));
+            Ok(())
+        })
         .invoke_handler(tauri::generate_handler![
             greet,
             read_file,
             find_wow_path,
+            find_wow_path_candidates,
             scan_characters,
+            scan_characters_cached,
             update_talents_from_config,
             update_talents,
+            resolve_output_path,
+            save_config,
+            list_profiles,
+            get_profile,
+            save_profile,
+            delete_profile,
+            duplicate_profile,
+            update_talents_from_profile,
+            get_next_scheduled_run,
+            set_schedule,
+            get_log_file_path,
+            get_recent_logs,
+            get_run_history,
+            get_last_run,
             discover_content,
+            validate_config_content,
+            generate_default_config,
             check_for_updates,
             download_and_install_update,
-            check_addon_installed
+            validate_wow_path,
+            check_addon_installed,
+            check_addon,
+            list_accounts,
+            list_addons,
+            suggest_output_path,
+            merge_talent_files,
+            export_loadouts_json,
+            import_loadouts_json,
+            read_plaintext_builds,
+            remove_loadouts,
+            get_talent_stats,
+            verify_talent_file,
+            list_backups,
+            get_backup_stats,
+            restore_backup,
+            get_loadout_metadata,
+            rename_loadout,
+            move_loadout,
+            copy_loadout,
+            class_metadata,
+            list_installations,
+            inspect_installation,
+            cancel_scan
         ])
         .run(tauri::generate_context!())
         .expect("error while running tauri application");
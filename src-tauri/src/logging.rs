@@ -0,0 +1,170 @@
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+use std::fs::{self, File, OpenOptions};
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+use std::sync::{Mutex, OnceLock};
+use tracing_subscriber::filter::LevelFilter;
+use tracing_subscriber::layer::SubscriberExt;
+use tracing_subscriber::util::SubscriberInitExt;
+use tracing_subscriber::{fmt, reload, Registry};
+
+/// Log file in the app data directory
+pub const LOG_FILE_NAME: &str = "talent-heron.log";
+
+/// Size at which the log file is rotated
+const MAX_LOG_SIZE: u64 = 5 * 1024 * 1024;
+
+/// Rotated log files kept besides the current one (`talent-heron.log.1`, ...)
+const ROTATED_LOG_FILES: usize = 2;
+
+/// Handle to change the level of the installed logger
+static LEVEL: OnceLock<reload::Handle<LevelFilter, Registry>> = OnceLock::new();
+
+/// Most verbose messages written to the log
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum LogLevel {
+    Error,
+    Warn,
+    #[default]
+    Info,
+    Debug,
+    Trace,
+}
+
+impl From<LogLevel> for LevelFilter {
+    fn from(level: LogLevel) -> Self {
+        match level {
+            LogLevel::Error => LevelFilter::ERROR,
+            LogLevel::Warn => LevelFilter::WARN,
+            LogLevel::Info => LevelFilter::INFO,
+            LogLevel::Debug => LevelFilter::DEBUG,
+            LogLevel::Trace => LevelFilter::TRACE,
+        }
+    }
+}
+
+/// Log file that starts over once it reaches `max_size`, shifting the previous ones
+/// to `<name>.1`, `<name>.2`, ... and dropping the oldest
+pub struct RotatingFile {
+    path: PathBuf,
+    max_size: u64,
+    file: File,
+    size: u64,
+}
+
+impl RotatingFile {
+    pub fn open(path: PathBuf, max_size: u64) -> io::Result<Self> {
+        let file = OpenOptions::new().create(true).append(true).open(&path)?;
+        let size = file.metadata()?.len();
+        Ok(Self { path, max_size, file, size })
+    }
+
+    fn rotate(&mut self) -> io::Result<()> {
+        for index in (1..ROTATED_LOG_FILES).rev() {
+            let from = rotated_path(&self.path, index);
+            if from.exists() {
+                fs::rename(&from, rotated_path(&self.path, index + 1))?;
+            }
+        }
+        fs::rename(&self.path, rotated_path(&self.path, 1))?;
+        self.file = File::create(&self.path)?;
+        self.size = 0;
+        Ok(())
+    }
+}
+
+impl Write for RotatingFile {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        if self.size > 0 && self.size + buf.len() as u64 > self.max_size {
+            self.rotate()?;
+        }
+        let written = self.file.write(buf)?;
+        self.size += written as u64;
+        Ok(written)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.file.flush()
+    }
+}
+
+/// Path of the `index`th rotated log file
+fn rotated_path(path: &Path, index: usize) -> PathBuf {
+    let mut name = path.file_name().unwrap_or_default().to_os_string();
+    name.push(format!(".{}", index));
+    path.with_file_name(name)
+}
+
+/// Log to stdout and to `LOG_FILE_NAME` in `dir`; returns the log file path
+/// Logging is set up once; later calls only return the path.
+pub fn init(dir: &Path) -> Result<PathBuf> {
+    let path = dir.join(LOG_FILE_NAME);
+    if LEVEL.get().is_some() {
+        return Ok(path);
+    }
+
+    fs::create_dir_all(dir).context("Failed to create log directory")?;
+    let file = RotatingFile::open(path.clone(), MAX_LOG_SIZE).context("Failed to open log file")?;
+    let (filter, handle) = reload::Layer::new(LevelFilter::from(LogLevel::default()));
+    tracing_subscriber::registry()
+        .with(filter)
+        .with(fmt::layer().with_ansi(false).with_writer(Mutex::new(file)))
+        .with(fmt::layer())
+        .try_init()
+        .context("Failed to set up logging")?;
+    let _ = LEVEL.set(handle);
+
+    Ok(path)
+}
+
+/// Change the level of the installed logger (no-op before `init`)
+pub fn set_level(level: LogLevel) {
+    if let Some(handle) = LEVEL.get() {
+        let _ = handle.reload(LevelFilter::from(level));
+    }
+}
+
+/// Last `lines` lines of the log, continuing into the previous file after a rotation
+pub fn recent_lines(path: &Path, lines: usize) -> Result<Vec<String>> {
+    let mut recent = Vec::new();
+    for file in [path.to_path_buf(), rotated_path(path, 1)] {
+        if recent.len() >= lines || !file.exists() {
+            break;
+        }
+        let content = fs::read_to_string(&file).with_context(|| format!("Failed to read {}", file.display()))?;
+        let mut older: Vec<String> =
+            content.lines().rev().take(lines - recent.len()).map(str::to_string).collect();
+        older.reverse();
+        older.append(&mut recent);
+        recent = older;
+    }
+    Ok(recent)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_util::temp_dir;
+
+    #[test]
+    fn test_rotation_and_recent_lines() {
+        let dir = temp_dir("logs");
+        let path = dir.join(LOG_FILE_NAME);
+
+        // Each line is 7 bytes (written at once, like a log event), so every file holds two lines
+        let mut file = RotatingFile::open(path.clone(), 16).unwrap();
+        for line in 1..=7 {
+            file.write_all(format!("line {}\n", line).as_bytes()).unwrap();
+        }
+
+        assert_eq!(fs::read_to_string(&path).unwrap(), "line 7\n");
+        assert_eq!(fs::read_to_string(rotated_path(&path, 1)).unwrap(), "line 5\nline 6\n");
+        assert_eq!(fs::read_to_string(rotated_path(&path, 2)).unwrap(), "line 3\nline 4\n");
+        assert!(!rotated_path(&path, 3).exists());
+
+        assert_eq!(recent_lines(&path, 2).unwrap(), ["line 6", "line 7"]);
+        assert_eq!(recent_lines(&path, 10).unwrap(), ["line 5", "line 6", "line 7"]);
+    }
+}
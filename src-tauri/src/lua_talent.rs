@@ -1,8 +1,300 @@
+use crate::archon::{RaidDifficulty, TalentIdentifier, DEFAULT_AUTO_SUFFIX};
+use crate::atomic_write::write_atomic;
+use crate::btw_loadouts::{self, BtwState};
+use crate::talent_loadouts_db::Profile;
+use crate::recovery::{self, ParseWarnings};
+use crate::talent_loadouts_db;
+use crate::wow::{HeroTalent, WowClass};
 use anyhow::{Context, Result};
-use full_moon::ast::{Expression, Field, Stmt, TableConstructor, Var};
-use std::collections::HashMap;
+use chrono::{Datelike, NaiveDate};
+use crate::saved_variables::{self, Assignment, Key, TableField, Value};
+use full_moon::ast::{Expression, TableConstructor};
+use full_moon::tokenizer::{StringLiteralQuoteType, TokenType};
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, BTreeSet, HashMap, HashSet};
+use std::fmt::Write;
 use std::path::Path;
 
+/// Escape a string for use inside a double-quoted Lua string literal
+///
+/// Quotes, backslashes and common whitespace escapes are written symbolically,
+/// other control characters as decimal `\ddd` escapes. Non-ASCII characters are
+/// kept as raw UTF-8, matching how WoW writes SavedVariables.
+pub fn lua_escape_string(s: &str) -> String {
+    let mut result = String::with_capacity(s.len());
+    push_lua_escaped(&mut result, s);
+    result
+}
+
+/// Append `s` escaped like `lua_escape_string` without allocating
+fn push_lua_escaped(out: &mut String, s: &str) {
+    for c in s.chars() {
+        match c {
+            '"' => out.push_str("\\\""),
+            '\\' => out.push_str("\\\\"),
+            '\n' => out.push_str("\\n"),
+            '\r' => out.push_str("\\r"),
+            '\t' => out.push_str("\\t"),
+            c if c.is_ascii_control() => {
+                let _ = write!(out, "\\{:03}", c as u32);
+            }
+            c => out.push(c),
+        }
+    }
+}
+
+/// Resolve the escape sequences of a quoted Lua string literal body
+///
+/// Supports the Lua 5.1-5.4 escapes (`\n`, `\ddd`, `\xXX`, `\z`, `\u{XXX}`, ...).
+/// Decimal and hex escapes produce raw bytes, so the result is decoded as UTF-8
+/// (lossily) once all escapes have been resolved.
+pub fn lua_unescape_string(s: &str) -> String {
+    let mut bytes = Vec::with_capacity(s.len());
+    let mut chars = s.chars().peekable();
+
+    while let Some(c) = chars.next() {
+        if c != '\\' {
+            let mut buf = [0u8; 4];
+            bytes.extend_from_slice(c.encode_utf8(&mut buf).as_bytes());
+            continue;
+        }
+
+        match chars.next() {
+            Some('a') => bytes.push(0x07),
+            Some('b') => bytes.push(0x08),
+            Some('f') => bytes.push(0x0c),
+            Some('n') | Some('\n') => bytes.push(b'\n'),
+            Some('r') => bytes.push(b'\r'),
+            Some('t') => bytes.push(b'\t'),
+            Some('v') => bytes.push(0x0b),
+            Some('z') => {
+                while chars.peek().is_some_and(|c| c.is_whitespace()) {
+                    chars.next();
+                }
+            }
+            Some('x') => {
+                let mut value = 0u32;
+                for _ in 0..2 {
+                    match chars.peek().and_then(|c| c.to_digit(16)) {
+                        Some(digit) => {
+                            value = value * 16 + digit;
+                            chars.next();
+                        }
+                        None => break,
+                    }
+                }
+                bytes.push(value as u8);
+            }
+            Some('u') if chars.peek() == Some(&'{') => {
+                chars.next();
+                let mut value = 0u32;
+                while let Some(digit) = chars.peek().and_then(|c| c.to_digit(16)) {
+                    value = value.saturating_mul(16).saturating_add(digit);
+                    chars.next();
+                }
+                if chars.peek() == Some(&'}') {
+                    chars.next();
+                }
+                let c = char::from_u32(value).unwrap_or(char::REPLACEMENT_CHARACTER);
+                let mut buf = [0u8; 4];
+                bytes.extend_from_slice(c.encode_utf8(&mut buf).as_bytes());
+            }
+            Some(d) if d.is_ascii_digit() => {
+                let mut value = d.to_digit(10).unwrap_or(0);
+                for _ in 0..2 {
+                    match chars.peek().and_then(|c| c.to_digit(10)) {
+                        Some(digit) => {
+                            value = value * 10 + digit;
+                            chars.next();
+                        }
+                        None => break,
+                    }
+                }
+                bytes.push(value.min(255) as u8);
+            }
+            // \\, \", \' and any unknown escape map to the character itself
+            Some(other) => {
+                let mut buf = [0u8; 4];
+                bytes.extend_from_slice(other.encode_utf8(&mut buf).as_bytes());
+            }
+            None => bytes.push(b'\\'),
+        }
+    }
+
+    String::from_utf8_lossy(&bytes).into_owned()
+}
+
+/// Boss part of the loadout shared by most bosses of a difficulty (e.g., "R-heroic-general_ARCT")
+pub const GENERAL_BOSS: &str = "general";
+
+/// Longest name given to a collapsed loadout (see `LuaTalentManager::collapse_raid_builds`)
+pub const MAX_COLLAPSED_NAME_LENGTH: usize = 48;
+
+/// Name of a loadout shared by the raid builds of `bosses`, within `MAX_COLLAPSED_NAME_LENGTH`
+/// e.g. "R-heroic-sikran+ulgrax_ARCT", shortened to "R-heroic-sikran+2_ARCT" or "R-heroic-3-bosses_ARCT"
+fn collapsed_name(difficulty: RaidDifficulty, hero: Option<HeroTalent>, bosses: &[String], suffix: &str) -> String {
+    let candidates = [
+        bosses.join("+"),
+        format!("{}+{}", bosses[0], bosses.len() - 1),
+        format!("{}-bosses", bosses.len()),
+    ];
+    let names = candidates
+        .into_iter()
+        .map(|boss| TalentIdentifier::Raid { difficulty, boss, hero }.as_talent_name(suffix));
+    let mut shortest = String::new();
+    for name in names {
+        if name.chars().count() <= MAX_COLLAPSED_NAME_LENGTH {
+            return name;
+        }
+        shortest = name;
+    }
+    shortest
+}
+
+/// Merge two auto-generated loadout names, keeping their common identifier prefix once
+/// e.g., "R-heroic-sikran_ARCT" + "R-heroic-broodtwister_ARCT" -> "R-heroic-sikran+broodtwister_ARCT"
+fn merge_talent_names(first: &str, second: &str, suffix: &str) -> String {
+    let first_id = first.strip_suffix(suffix).unwrap_or(first);
+    let second_id = second.strip_suffix(suffix).unwrap_or(second);
+
+    // Common prefix, cut back to the last '-' so only whole identifier parts are shared
+    let common = first_id
+        .char_indices()
+        .zip(second_id.chars())
+        .take_while(|((_, a), b)| a == b)
+        .filter(|((_, c), _)| *c == '-')
+        .map(|((i, _), _)| i + 1)
+        .last()
+        .unwrap_or(0);
+
+    format!("{}+{}{}", first_id, &second_id[common..], suffix)
+}
+
+/// Eviction priority of an auto-generated loadout (lower is evicted first)
+fn eviction_rank(name: &str, suffix: &str) -> u8 {
+    match TalentIdentifier::from_talent_name(name, suffix) {
+        None => 0,
+        Some(TalentIdentifier::Raid { difficulty, .. }) => match difficulty {
+            RaidDifficulty::Normal => 1,
+            RaidDifficulty::Heroic => 2,
+            RaidDifficulty::Mythic => 3,
+        },
+        Some(TalentIdentifier::MythicPlus { .. }) => 4,
+    }
+}
+
+/// Prepare saved-variable source for parsing
+/// Strips a UTF-8 byte order mark and normalizes CRLF to LF; returns whether the
+/// source used CRLF line endings so they can be restored on write.
+pub(crate) fn normalize_source(content: &str) -> (String, bool) {
+    let content = content.strip_prefix('\u{feff}').unwrap_or(content);
+    let crlf = content.contains("\r\n");
+
+    if crlf {
+        (content.replace("\r\n", "\n"), true)
+    } else {
+        (content.to_string(), false)
+    }
+}
+
+/// Match a loadout name against a pattern where `*` matches any run of characters
+/// e.g., "R-*-sikran_ARCT" or "M+-*"
+pub fn matches_pattern(pattern: &str, name: &str) -> bool {
+    let mut parts = pattern.split('*');
+    let first = parts.next().unwrap_or("");
+    let Some(mut rest) = name.strip_prefix(first) else {
+        return false;
+    };
+
+    let parts: Vec<_> = parts.collect();
+    let Some((last, middle)) = parts.split_last() else {
+        // No wildcard: the whole name must match
+        return rest.is_empty();
+    };
+
+    for part in middle {
+        match rest.find(part) {
+            Some(i) => rest = &rest[i + part.len()..],
+            None => return false,
+        }
+    }
+
+    rest.ends_with(last)
+}
+
+/// Spec index of a spec table key; older addon versions wrote `["1"]` instead of `[1]`
+fn spec_key(key: &Key) -> Option<u8> {
+    match key {
+        Key::Value(Value::Number(num)) => num.parse().ok(),
+        Key::Value(Value::String(s)) => s.trim().parse().ok(),
+        _ => None,
+    }
+}
+
+/// String of a `["key"]` table key
+fn string_key<'a>(key: &'a Key) -> Option<&'a str> {
+    match key {
+        Key::Value(value) => value.as_str(),
+        _ => None,
+    }
+}
+
+/// Display position of a loadout: manual loadouts, then raid builds by difficulty and
+/// encounter order, then M+ builds alphabetically, then unrecognized auto-generated builds
+fn content_sort_key(talent: &TalentLoadout, encounter_order: &[String], suffix: &str) -> (u8, u8, usize, String) {
+    if !talent.is_auto_generated(suffix) {
+        return (0, 0, 0, String::new());
+    }
+
+    match TalentIdentifier::from_talent_name(&talent.name, suffix) {
+        // A loadout shared by most bosses comes first, merged ones with their first boss
+        Some(TalentIdentifier::Raid { difficulty, boss, .. }) if boss == GENERAL_BOSS => (1, difficulty as u8, 0, boss),
+        Some(TalentIdentifier::Raid { difficulty, boss, .. }) => {
+            let first_boss = boss.split('+').next().unwrap_or_default();
+            let position = encounter_order
+                .iter()
+                .position(|b| b == first_boss)
+                .unwrap_or(encounter_order.len());
+            (1, difficulty as u8, position + 1, boss)
+        }
+        Some(TalentIdentifier::MythicPlus { dungeon, .. }) => (2, 0, 0, dungeon),
+        None => (3, 0, 0, talent.name.clone()),
+    }
+}
+
+/// Serialize a single loadout as a Lua table constructor
+pub(crate) fn talent_to_lua(talent: &TalentLoadout) -> String {
+    let mut result = String::with_capacity(talent.name.len() + talent.text.len() + 48);
+    push_talent_lua(&mut result, talent);
+    result
+}
+
+/// Append a loadout serialized like `talent_to_lua` without intermediate allocations
+fn push_talent_lua(out: &mut String, talent: &TalentLoadout) {
+    let _ = write!(out, "{{ [\"icon\"] = {}, [\"name\"] = \"", talent.icon);
+    push_lua_escaped(out, &talent.name);
+    out.push_str("\", [\"text\"] = \"");
+    push_lua_escaped(out, &talent.text);
+    out.push_str("\" }");
+}
+
+/// Extract the unescaped value of a string expression, if it is one
+pub(crate) fn string_value(expression: &Expression) -> Option<String> {
+    let Expression::String(token) = expression else {
+        return None;
+    };
+
+    match token.token().token_type() {
+        TokenType::StringLiteral {
+            literal,
+            quote_type: StringLiteralQuoteType::Brackets,
+            ..
+        } => Some(literal.to_string()),
+        TokenType::StringLiteral { literal, .. } => Some(lua_unescape_string(literal)),
+        _ => None,
+    }
+}
+
 /// Represents a single talent loadout entry
 #[derive(Debug, Clone, PartialEq)]
 pub struct TalentLoadout {
@@ -20,9 +312,335 @@ impl TalentLoadout {
         }
     }
 
-    /// Check if this is an auto-generated talent (has _ARCT suffix)
-    pub fn is_auto_generated(&self) -> bool {
-        self.name.ends_with("_ARCT")
+    /// Create a loadout showing the icon of its spec
+    pub fn with_spec_icon(name: String, text: String, wow_class: WowClass, spec_index: u8) -> Self {
+        Self {
+            icon: wow_class.spec_icon(spec_index),
+            name,
+            text,
+        }
+    }
+
+    /// Check if this is an auto-generated talent (name ends with `suffix`, e.g. "_ARCT"), or a
+    /// kept previous version of one (e.g. "R-heroic-sikran_ARCT~0614")
+    pub fn is_auto_generated(&self, suffix: &str) -> bool {
+        !suffix.is_empty() && split_version_date(&self.name).0.ends_with(suffix)
+    }
+
+    /// Whether this is a previous version of a generated build kept by `archive_auto_generated`
+    pub fn is_previous_version(&self) -> bool {
+        split_version_date(&self.name).1.is_some()
+    }
+}
+
+/// Separator between a generated build's name and the date ("MMDD") of a kept previous version
+pub const VERSION_DATE_SEPARATOR: char = '~';
+
+/// Name of a kept previous version without its date, and the date ("0614") if it is one
+pub fn split_version_date(name: &str) -> (&str, Option<&str>) {
+    match name.rsplit_once(VERSION_DATE_SEPARATOR) {
+        Some((base, date)) if date.len() == 4 && date.bytes().all(|b| b.is_ascii_digit()) => (base, Some(date)),
+        _ => (name, None),
+    }
+}
+
+/// Days between a "MMDD" version date and `today`; dates after today are from the previous year
+fn version_age_days(date: &str, today: NaiveDate) -> i64 {
+    let (Ok(month), Ok(day)) = (date[..2].parse(), date[2..].parse()) else {
+        return i64::MAX;
+    };
+    let dated = |year| NaiveDate::from_ymd_opt(year, month, day);
+    match dated(today.year()).filter(|date| *date <= today).or_else(|| dated(today.year() - 1)) {
+        Some(date) => (today - date).num_days(),
+        None => i64::MAX,
+    }
+}
+
+/// Name suffixes that mark loadouts as auto-generated
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct AutoSuffixes {
+    /// Suffix appended to newly generated loadouts
+    pub current: String,
+    /// Suffixes of earlier versions or other tools; only used to clean up old builds
+    pub legacy: Vec<String>,
+}
+
+impl Default for AutoSuffixes {
+    fn default() -> Self {
+        Self::new(DEFAULT_AUTO_SUFFIX, Vec::new())
+    }
+}
+
+impl AutoSuffixes {
+    pub fn new(current: impl Into<String>, legacy: Vec<String>) -> Self {
+        Self {
+            current: current.into(),
+            legacy,
+        }
+    }
+
+    /// Check if a loadout carries the current or any legacy suffix
+    pub fn matches_any(&self, talent: &TalentLoadout) -> bool {
+        talent.is_auto_generated(&self.current) || self.legacy.iter().any(|s| talent.is_auto_generated(s))
+    }
+
+    /// Remove the current or a legacy suffix from a loadout name
+    pub fn strip<'a>(&self, name: &'a str) -> &'a str {
+        std::iter::once(&self.current)
+            .chain(&self.legacy)
+            .filter(|suffix| !suffix.is_empty())
+            .find_map(|suffix| name.strip_suffix(suffix.as_str()))
+            .unwrap_or(name)
+    }
+}
+
+/// A loadout addressed by class, spec and name
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct LoadoutRef {
+    pub class_name: String,
+    pub spec_index: u8,
+    pub name: String,
+}
+
+/// What to do with the auto-generated suffix when copying a loadout
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum SuffixOnCopy {
+    /// Use the destination name as given
+    Keep,
+    /// Remove the suffix, so the copy is a manual loadout that survives updates
+    Strip,
+    /// Replace any (legacy) suffix with the current one, or append it
+    Retag,
+}
+
+/// Raid loadouts with the same talent string replaced by one loadout (see
+/// `LuaTalentManager::collapse_raid_builds`)
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct CollapsedLoadout {
+    pub name: String,
+    /// Names of the loadouts it replaces, in spec order
+    pub members: Vec<String>,
+}
+
+/// How to handle auto-generated loadouts that share the same talent string
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DedupePolicy {
+    /// Keep every loadout, even if the talent strings are identical
+    KeepAll,
+    /// Keep the first occurrence and merge the other names into it
+    /// (e.g., "R-heroic-sikran+broodtwister_ARCT")
+    MergeNames,
+}
+
+/// How to resolve name collisions when merging loadouts from another file
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum MergeStrategy {
+    /// Keep the existing loadout and drop the incoming one
+    KeepExisting,
+    /// Replace the existing loadout with the incoming one
+    PreferOther,
+    /// Keep both, renaming the incoming one (e.g., "My Build (2)")
+    RenameWithSuffix,
+}
+
+/// Result of merging another file into this one
+#[derive(Debug, Default, Clone, PartialEq, Eq, Serialize)]
+pub struct MergeSummary {
+    /// Loadouts added without a name collision
+    pub added: usize,
+    /// Existing loadouts replaced by incoming ones
+    pub replaced: usize,
+    /// Incoming loadouts added under a new name
+    pub renamed: usize,
+    /// Incoming loadouts dropped (identical duplicates or kept existing)
+    pub skipped: usize,
+}
+
+/// A loadout that exists on only one side of a diff
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct DiffEntry {
+    pub class_name: String,
+    pub spec_index: u8,
+    /// Spec slug (e.g., "fury"), if the class and index are known
+    pub spec: Option<&'static str>,
+    pub name: String,
+    pub text: String,
+}
+
+/// A loadout present on both sides of a diff whose talent string changed
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct ChangedLoadout {
+    pub class_name: String,
+    pub spec_index: u8,
+    /// Spec slug (e.g., "fury"), if the class and index are known
+    pub spec: Option<&'static str>,
+    pub name: String,
+    pub old_text: String,
+    pub new_text: String,
+}
+
+/// Differences between two sets of loadouts, sorted by class, spec and name
+#[derive(Debug, Default, Clone, PartialEq, Eq, Serialize)]
+pub struct TalentDiff {
+    pub added: Vec<DiffEntry>,
+    pub removed: Vec<DiffEntry>,
+    pub changed: Vec<ChangedLoadout>,
+}
+
+impl TalentDiff {
+    /// Check if both sides contain the same loadouts
+    #[allow(dead_code)]
+    pub fn is_empty(&self) -> bool {
+        self.added.is_empty() && self.removed.is_empty() && self.changed.is_empty()
+    }
+}
+
+/// Something that reads back differently after writing a file and parsing the output
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct RoundTripDifference {
+    /// What differs, e.g. `TalentLoadoutEx["WARRIOR"][1][2]`
+    pub location: String,
+    /// Lua source as read from the file (None if it only exists after the round trip)
+    pub before: Option<String>,
+    /// Lua source as read back (None if the round trip lost it)
+    pub after: Option<String>,
+}
+
+/// Result of checking that a file survives a write unchanged
+#[derive(Debug, Default, Clone, PartialEq, Eq, Serialize)]
+pub struct RoundTripReport {
+    /// True if nothing would be lost or changed by writing the file
+    pub ok: bool,
+    /// Entries skipped while reading the file
+    pub issues: Vec<ParseIssue>,
+    /// Differences between the file as read and as read back after serializing it
+    pub differences: Vec<RoundTripDifference>,
+}
+
+/// Loadout counts of a single spec
+#[derive(Debug, Default, Clone, PartialEq, Eq, Serialize)]
+pub struct SpecStats {
+    /// Spec slug (e.g., "fury"), if the class and index are known
+    pub spec: Option<&'static str>,
+    pub manual: usize,
+    pub auto: usize,
+}
+
+/// Slug of a spec given by Lua class name and spec index
+fn spec_slug(class_name: &str, spec_index: u8) -> Option<&'static str> {
+    WowClass::from_lua_format(class_name)?.spec_name_for_index(spec_index)
+}
+
+/// Loadout counts of a class, overall and per spec index
+#[derive(Debug, Default, Clone, PartialEq, Eq, Serialize)]
+pub struct ClassStats {
+    pub total: usize,
+    pub manual: usize,
+    pub auto: usize,
+    pub specs: BTreeMap<u8, SpecStats>,
+}
+
+/// Loadout counts of a whole talent file
+#[derive(Debug, Default, Clone, PartialEq, Eq, Serialize)]
+pub struct TalentStats {
+    pub total: usize,
+    pub manual: usize,
+    pub auto: usize,
+    pub classes: BTreeMap<String, ClassStats>,
+    /// Distinct identifiers of auto-generated loadouts (name without suffix, e.g. "R-heroic-sikran")
+    pub identifiers: BTreeSet<String>,
+}
+
+/// Version of the JSON export format written by `to_json`
+pub const JSON_FORMAT_VERSION: u32 = 1;
+
+/// JSON export format for a whole loadout set:
+/// `{ "version": 1, "classes": { "WARRIOR": { "1": [ { "icon", "name", "text" } ] } } }`
+/// Classes use the Lua class names, specs are keyed by spec index.
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct JsonLoadoutSet {
+    version: u32,
+    classes: BTreeMap<String, BTreeMap<u8, Vec<JsonLoadout>>>,
+}
+
+/// A single loadout in the JSON export format
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct JsonLoadout {
+    #[serde(default)]
+    icon: i64,
+    name: String,
+    text: String,
+}
+
+/// Global variable the TalentLoadoutsEx addon stores its loadouts in
+pub const DEFAULT_TABLE_NAME: &str = "TalentLoadoutEx";
+
+/// Name of the GUI table that accompanies a loadout table (e.g. "TalentLoadoutExGUI")
+fn gui_table_name(table_name: &str) -> String {
+    format!("{}GUI", table_name)
+}
+
+/// Check if a table looks like a loadout table: class name keys -> spec index keys ->
+/// lists of loadouts. GUI tables have the same shape but list names instead of loadouts.
+fn is_talent_table(fields: &[TableField]) -> bool {
+    fields.iter().any(|class_field| {
+        string_key(&class_field.key).and_then(WowClass::from_lua_format).is_some()
+            && class_field.value.as_table().is_some_and(|specs| {
+                specs.iter().any(|spec| {
+                    spec.value.as_table().is_some_and(|loadouts| {
+                        loadouts.iter().any(|loadout| {
+                            loadout
+                                .value
+                                .as_table()
+                                .is_some_and(|t| t.iter().any(|f| f.key.as_str() == Some("text")))
+                        })
+                    })
+                })
+            })
+    })
+}
+
+/// An entry that was skipped (or only partly read) while parsing, and would be lost on write
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct ParseIssue {
+    /// Where the entry was found, e.g. `TalentLoadoutEx["WARRIOR"][1][3]`
+    pub location: String,
+    /// Why the entry was skipped
+    pub reason: String,
+}
+
+impl ParseIssue {
+    fn new(location: impl Into<String>, reason: impl Into<String>) -> Self {
+        Self {
+            location: location.into(),
+            reason: reason.into(),
+        }
+    }
+}
+
+/// Location of a table field for `ParseIssue`s; positional fields use their 1-based index
+fn field_location(parent: &str, field: &TableField, index: usize) -> String {
+    match &field.key {
+        Key::Value(Value::String(s)) => format!("{}[\"{}\"]", parent, lua_escape_string(s)),
+        Key::Value(Value::Number(n)) => format!("{}[{}]", parent, n),
+        Key::Value(_) => format!("{}[?]", parent),
+        Key::Name(name) => format!("{}.{}", parent, name),
+        Key::None => format!("{}[{}]", parent, index),
+    }
+}
+
+/// Append the loadouts of a duplicated class or spec block, skipping exact copies
+/// of loadouts that are already present
+fn merge_duplicate_loadouts(existing: &mut Vec<TalentLoadout>, talents: Vec<TalentLoadout>) {
+    for talent in talents {
+        if !existing.contains(&talent) {
+            existing.push(talent);
+        }
     }
 }
 
@@ -30,10 +648,53 @@ impl TalentLoadout {
 /// Organized by specialization index (1-4)
 pub type ClassTalents = HashMap<u8, Vec<TalentLoadout>>;
 
+/// Saved-variable format of a talent file
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum TalentFileFormat {
+    /// `TalentLoadoutEx` table of the TalentLoadoutsEx addon
+    #[default]
+    TalentLoadoutsEx,
+    /// `TalentLoadoutsDB` table of the TalentLoadouts addon
+    TalentLoadouts,
+    /// `BtWLoadoutsSets` table of the BtWLoadouts addon
+    BtwLoadouts,
+}
+
+/// UI state stored by the addon in the companion `TalentLoadoutExGUI` saved variable
+/// WoW writes all saved variables of an addon to its one SavedVariables file, so the table is
+/// only looked for in the file holding the loadouts, never in a sibling file.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct GuiState {
+    /// Display order of loadout names, by class name and spec index
+    pub order: HashMap<String, HashMap<u8, Vec<String>>>,
+    /// Any other fields of the table as (string key, Lua source), kept verbatim
+    pub extra_fields: Vec<(Option<String>, String)>,
+}
+
 /// Manager for reading and writing TalentLoadoutsEx.lua files
+#[derive(Debug, Clone)]
 pub struct LuaTalentManager {
     /// All talents organized by class name (e.g., "WARRIOR", "MAGE")
     talents: HashMap<String, ClassTalents>,
+    /// Companion GUI table, if the file contained one
+    gui: Option<GuiState>,
+    /// TalentLoadouts profiles ("Name - Realm" -> profile) the loadouts are written to
+    profiles: BTreeMap<String, Profile>,
+    /// Fields of a TalentLoadouts DB other than its profiles, kept verbatim as Lua source
+    db_fields: Vec<String>,
+    /// Addon options from the `OPTION` table (key -> Lua value source), if the file had one
+    options: Option<Vec<(String, String)>>,
+    /// Suffixes that mark loadouts as auto-generated
+    suffixes: AutoSuffixes,
+    /// Spec buckets that couldn't be parsed, by class name, kept verbatim as Lua source
+    raw_specs: HashMap<String, Vec<String>>,
+    /// Global variable the loadouts are read from and written to
+    table_name: String,
+    /// Whether the file was read with Windows (CRLF) line endings
+    crlf: bool,
+    /// Unmanaged parts of a BtWLoadouts file, if one was loaded
+    btw: Option<BtwState>,
 }
 
 impl LuaTalentManager {
@@ -41,175 +702,587 @@ impl LuaTalentManager {
     pub fn new() -> Self {
         Self {
             talents: HashMap::new(),
+            gui: None,
+            profiles: BTreeMap::new(),
+            db_fields: Vec::new(),
+            options: None,
+            suffixes: AutoSuffixes::default(),
+            raw_specs: HashMap::new(),
+            table_name: DEFAULT_TABLE_NAME.to_string(),
+            crlf: false,
+            btw: None,
         }
     }
 
     /// Load talents from a TalentLoadoutsEx.lua file
-    pub fn load_from_file(path: impl AsRef<Path>) -> Result<Self> {
+    /// See `parse_lua` for how `table_name` is used
+    /// Entries that couldn't be read are returned as `ParseIssue`s
+    pub fn load_from_file(path: impl AsRef<Path>, table_name: Option<&str>) -> Result<(Self, Vec<ParseIssue>)> {
+        let content = std::fs::read_to_string(path.as_ref())
+            .context("Failed to read TalentLoadoutsEx.lua file")?;
+
+        Self::parse_lua_with_issues(&content, table_name)
+    }
+
+    /// Load talents from a file in the given saved-variable format
+    /// `suffixes` decide which loadouts are auto-generated (and thus managed by this tool)
+    pub fn load_from_file_as(
+        path: impl AsRef<Path>,
+        format: TalentFileFormat,
+        suffixes: &AutoSuffixes,
+    ) -> Result<Self> {
+        let mut manager = match format {
+            TalentFileFormat::TalentLoadoutsEx => Self::load_from_file(path, None).map(|(manager, _)| manager),
+            TalentFileFormat::TalentLoadouts => {
+                let content = std::fs::read_to_string(path.as_ref())
+                    .context("Failed to read TalentLoadouts saved variables file")?;
+                talent_loadouts_db::parse_db(&content)
+            }
+            TalentFileFormat::BtwLoadouts => {
+                let content = std::fs::read_to_string(path.as_ref())
+                    .context("Failed to read BtWLoadouts saved variables file")?;
+                btw_loadouts::parse_sets(&content, suffixes)
+            }
+        }?;
+
+        manager.set_auto_suffixes(suffixes.clone());
+        Ok(manager)
+    }
+
+    /// Load talents like `load_from_file_as`, but recover what's possible from a
+    /// TalentLoadoutsEx.lua file that fails to parse instead of returning an error
+    pub fn load_from_file_recovering(
+        path: impl AsRef<Path>,
+        format: TalentFileFormat,
+        suffixes: &AutoSuffixes,
+    ) -> Result<(Self, ParseWarnings)> {
+        if format != TalentFileFormat::TalentLoadoutsEx {
+            return Ok((Self::load_from_file_as(path, format, suffixes)?, ParseWarnings::default()));
+        }
+
         let content = std::fs::read_to_string(path.as_ref())
             .context("Failed to read TalentLoadoutsEx.lua file")?;
+        let (mut manager, warnings) = Self::parse_lua_recovering(&content);
+        manager.set_auto_suffixes(suffixes.clone());
+
+        Ok((manager, warnings))
+    }
 
-        Self::parse_lua(&content)
+    /// Parse Lua content, falling back to a line-based extractor if the parser fails
+    pub fn parse_lua_recovering(content: &str) -> (Self, ParseWarnings) {
+        match Self::parse_lua_with_issues(content, None) {
+            Ok((manager, issues)) => (
+                manager,
+                ParseWarnings {
+                    issues,
+                    ..ParseWarnings::default()
+                },
+            ),
+            Err(e) => {
+                let (normalized, crlf) = normalize_source(content);
+                let (mut manager, warnings) = recovery::recover_loadouts(&normalized, format!("{:#}", e));
+                manager.set_crlf(crlf);
+                (manager, warnings)
+            }
+        }
     }
 
     /// Parse Lua content into talent structure
-    fn parse_lua(content: &str) -> Result<Self> {
-        let ast = full_moon::parse(content).context("Failed to parse Lua file")?;
+    ///
+    /// Loadouts are read from the `table_name` global (and its GUI companion). Without a
+    /// name, `TalentLoadoutEx` is used, or if that's missing the first table that looks
+    /// like a loadout table, as written by forks of the addon.
+    pub fn parse_lua(content: &str, table_name: Option<&str>) -> Result<Self> {
+        Self::parse_lua_with_issues(content, table_name).map(|(manager, _)| manager)
+    }
+
+    /// Parse Lua content like `parse_lua`, also returning the entries that were skipped
+    pub fn parse_lua_with_issues(content: &str, table_name: Option<&str>) -> Result<(Self, Vec<ParseIssue>)> {
+        let (content, crlf) = normalize_source(content);
+        let assignments = saved_variables::parse(&content)?;
+
+        let table_name = match table_name {
+            Some(name) => name.to_string(),
+            None => Self::detect_table_name(&assignments).unwrap_or(DEFAULT_TABLE_NAME).to_string(),
+        };
+        let gui_name = gui_table_name(&table_name);
 
         let mut talents: HashMap<String, ClassTalents> = HashMap::new();
+        let mut raw_specs = HashMap::new();
+        let mut gui = None;
+        let mut options = None;
+        let mut issues = Vec::new();
+
+        // Find the loadout table (and optional GUI table) assignments
+        for assignment in &assignments {
+            let Some(table) = assignment.value.as_table() else {
+                continue;
+            };
 
-        // Find the TalentLoadoutEx table assignment
-        for stmt in ast.nodes().stmts() {
-            if let Stmt::Assignment(assignment) = stmt {
-                // Check if this is assigning to TalentLoadoutEx
-                let var_names: Vec<_> = assignment
-                    .variables()
-                    .iter()
-                    .filter_map(|v| {
-                        if let Var::Name(name) = v {
-                            Some(name.token().to_string())
-                        } else {
-                            None
-                        }
-                    })
-                    .collect();
+            if assignment.name == table_name {
+                (talents, raw_specs) = Self::parse_talent_table(table, &table_name, &mut issues)?;
+                options = Self::parse_option_table(table, &table_name, &mut issues);
+            } else if assignment.name == gui_name {
+                gui = Some(Self::parse_gui_table(table));
+            }
+        }
 
-                if var_names.contains(&"TalentLoadoutEx".to_string()) {
-                    // Parse the table value
-                    if let Some(Expression::TableConstructor(table)) = assignment.expressions().iter().next() {
-                        talents = Self::parse_talent_table(table)?;
-                    }
+        let manager = Self {
+            talents,
+            gui,
+            profiles: BTreeMap::new(),
+            db_fields: Vec::new(),
+            options,
+            suffixes: AutoSuffixes::default(),
+            raw_specs,
+            table_name,
+            crlf,
+            btw: None,
+        };
+
+        Ok((manager, issues))
+    }
+
+    /// Find the global holding the loadouts when no name was given
+    fn detect_table_name<'a>(assignments: &'a [Assignment]) -> Option<&'a str> {
+        if assignments.iter().any(|a| a.name == DEFAULT_TABLE_NAME) {
+            return Some(DEFAULT_TABLE_NAME);
+        }
+
+        assignments
+            .iter()
+            .find(|a| a.value.as_table().is_some_and(is_talent_table))
+            .map(|a| a.name.as_ref())
+    }
+
+    /// Global variable the loadouts are read from and written to
+    #[allow(dead_code)]
+    pub fn table_name(&self) -> &str {
+        &self.table_name
+    }
+
+    /// Write the loadouts to a different global variable
+    #[allow(dead_code)]
+    pub fn set_table_name(&mut self, table_name: impl Into<String>) {
+        self.table_name = table_name.into();
+    }
+
+    /// Parse the GUI table (class -> specs -> ordered loadout names)
+    /// Fields that don't follow that shape are preserved verbatim
+    fn parse_gui_table(table: &[TableField]) -> GuiState {
+        let mut state = GuiState::default();
+
+        for field in table {
+            if let (Some(class_name), Some(spec_table)) = (string_key(&field.key), field.value.as_table()) {
+                if let Some(class_order) = Self::parse_gui_class_order(spec_table) {
+                    state.order.insert(class_name.to_string(), class_order);
+                    continue;
                 }
             }
+
+            let key = string_key(&field.key).map(str::to_string);
+            state.extra_fields.push((key, field.source.to_string()));
         }
 
-        Ok(Self { talents })
+        state
     }
 
-    /// Parse the main talent table (class -> specs -> talents)
-    fn parse_talent_table(table: &TableConstructor) -> Result<HashMap<String, ClassTalents>> {
+    /// Parse the per-spec name lists of a single class in the GUI table
+    fn parse_gui_class_order(spec_table: &[TableField]) -> Option<HashMap<u8, Vec<String>>> {
         let mut result = HashMap::new();
 
-        for field in table.fields() {
-            if let Field::ExpressionKey { key, value, .. } = field {
-                // Get class name (e.g., "WARRIOR")
-                if let Expression::String(string_token) = key {
-                    let class_name = string_token.token().to_string().replace("\"", "");
+        for field in spec_table {
+            let spec_index = spec_key(&field.key)?;
+            let names = field
+                .value
+                .as_table()?
+                .iter()
+                .map(|field| match field.key {
+                    Key::None => field.value.as_str().map(str::to_string),
+                    _ => None,
+                })
+                .collect::<Option<Vec<_>>>()?;
+
+            result.insert(spec_index, names);
+        }
 
-                    // Skip OPTION key
-                    if class_name == "OPTION" {
-                        continue;
-                    }
+        Some(result)
+    }
 
-                    // Parse spec tables
-                    if let Expression::TableConstructor(spec_table) = value {
-                        if let Ok(class_talents) = Self::parse_class_talents(spec_table) {
-                            result.insert(class_name, class_talents);
-                        }
+    /// Parse the main talent table (class -> specs -> talents)
+    /// Spec buckets that can't be parsed are returned separately as raw Lua source per class.
+    /// A class that appears twice (e.g. after a bad manual merge) keeps the loadouts of both blocks.
+    #[allow(clippy::type_complexity)]
+    fn parse_talent_table(
+        table: &[TableField],
+        table_name: &str,
+        issues: &mut Vec<ParseIssue>,
+    ) -> Result<(HashMap<String, ClassTalents>, HashMap<String, Vec<String>>)> {
+        let mut result: HashMap<String, ClassTalents> = HashMap::new();
+        let mut raw_specs: HashMap<String, Vec<String>> = HashMap::new();
+
+        for (index, field) in table.iter().enumerate() {
+            let location = field_location(table_name, field, index + 1);
+
+            // Get class name (e.g., "WARRIOR"), skipping the OPTION key
+            let Some(class_name) = string_key(&field.key) else {
+                issues.push(ParseIssue::new(location, "class key is not a string"));
+                continue;
+            };
+            if class_name == "OPTION" {
+                continue;
+            }
+
+            // Parse spec tables
+            let Some(spec_table) = field.value.as_table() else {
+                issues.push(ParseIssue::new(location, "class entry is not a table"));
+                continue;
+            };
+            let (class_talents, raw) = Self::parse_class_talents(spec_table, &location, issues)?;
+            if !raw.is_empty() {
+                raw_specs.entry(class_name.to_string()).or_default().extend(raw);
+            }
+
+            match result.get_mut(class_name) {
+                Some(existing) => {
+                    issues.push(ParseIssue::new(location, "duplicate class key, loadouts merged"));
+                    for (spec_index, talents) in class_talents {
+                        merge_duplicate_loadouts(existing.entry(spec_index).or_default(), talents);
                     }
                 }
+                None => {
+                    result.insert(class_name.to_string(), class_talents);
+                }
             }
         }
 
-        Ok(result)
+        Ok((result, raw_specs))
     }
 
-    /// Parse all specs for a class
-    fn parse_class_talents(spec_table: &TableConstructor) -> Result<ClassTalents> {
-        let mut result = HashMap::new();
+    /// Parse the key/value pairs of the `OPTION` table; values are kept as Lua source
+    fn parse_option_table(
+        table: &[TableField],
+        table_name: &str,
+        issues: &mut Vec<ParseIssue>,
+    ) -> Option<Vec<(String, String)>> {
+        let option_table = table
+            .iter()
+            .find(|field| string_key(&field.key) == Some("OPTION"))?
+            .value
+            .as_table()?;
+        let location = format!("{}[\"OPTION\"]", table_name);
+
+        let mut options = Vec::new();
+        for (index, option) in option_table.iter().enumerate() {
+            match option.key.as_str() {
+                Some(key) => options.push((key.to_string(), option.value_source.to_string())),
+                None => issues.push(ParseIssue::new(
+                    field_location(&location, option, index + 1),
+                    "option key is not a string",
+                )),
+            }
+        }
 
-        for field in spec_table.fields() {
-            if let Field::ExpressionKey { key, value, .. } = field {
-                // Get spec index (e.g., 1, 2, 3)
-                if let Expression::Number(num) = key {
-                    if let Ok(spec_index) = num.token().to_string().parse::<u8>() {
-                        // Parse talent list for this spec
-                        if let Expression::TableConstructor(talent_table) = value {
-                            let talents = Self::parse_talent_list(talent_table)?;
-                            result.insert(spec_index, talents);
-                        }
+        Some(options)
+    }
+
+    /// Parse all specs for a class
+    /// Spec keys may be numbers or numeric strings; buckets with other keys or values
+    /// are returned as raw Lua source so they survive a rewrite
+    fn parse_class_talents(
+        spec_table: &[TableField],
+        location: &str,
+        issues: &mut Vec<ParseIssue>,
+    ) -> Result<(ClassTalents, Vec<String>)> {
+        let mut result: ClassTalents = HashMap::new();
+        let mut raw = Vec::new();
+
+        for (index, field) in spec_table.iter().enumerate() {
+            // Get spec index (e.g., 1, 2, 3) and parse the talent list for this spec
+            if let (Some(spec_index), Some(talent_table)) = (spec_key(&field.key), field.value.as_table()) {
+                let location = field_location(location, field, index + 1);
+                let talents = Self::parse_loadouts(talent_table, &location, issues);
+                match result.get_mut(&spec_index) {
+                    Some(existing) => {
+                        issues.push(ParseIssue::new(location, "duplicate spec key, loadouts merged"));
+                        merge_duplicate_loadouts(existing, talents);
+                    }
+                    None => {
+                        result.insert(spec_index, talents);
                     }
                 }
+                continue;
             }
+
+            raw.push(field.source.to_string());
         }
 
-        Ok(result)
+        Ok((result, raw))
+    }
+
+    /// Parse a list of talent loadouts from a full_moon table
+    pub(crate) fn parse_talent_list(talent_table: &TableConstructor) -> Result<Vec<TalentLoadout>> {
+        Ok(Self::parse_loadouts(&saved_variables::table_fields(talent_table), "", &mut Vec::new()))
     }
 
     /// Parse a list of talent loadouts
-    fn parse_talent_list(talent_table: &TableConstructor) -> Result<Vec<TalentLoadout>> {
+    fn parse_loadouts(talent_table: &[TableField], location: &str, issues: &mut Vec<ParseIssue>) -> Vec<TalentLoadout> {
         let mut result = Vec::new();
 
-        for field in talent_table.fields() {
-            if let Field::NoKey(expression) = field {
-                if let Expression::TableConstructor(loadout_table) = expression {
-                    if let Ok(loadout) = Self::parse_single_talent(loadout_table) {
-                        result.push(loadout);
-                    }
+        for (index, field) in talent_table.iter().enumerate() {
+            let location = field_location(location, field, index + 1);
+
+            match (&field.key, field.value.as_table()) {
+                (Key::None, Some(loadout_table)) => {
+                    result.push(Self::parse_single_talent(loadout_table, &location, issues))
                 }
+                (Key::None, None) => issues.push(ParseIssue::new(location, "loadout is not a table")),
+                _ => issues.push(ParseIssue::new(location, "keyed entry in a loadout list")),
             }
         }
 
-        Ok(result)
+        result
     }
 
     /// Parse a single talent loadout entry
-    fn parse_single_talent(loadout_table: &TableConstructor) -> Result<TalentLoadout> {
+    fn parse_single_talent(loadout_table: &[TableField], location: &str, issues: &mut Vec<ParseIssue>) -> TalentLoadout {
         let mut icon: i64 = 0;
-        let mut name = String::new();
-        let mut text = String::new();
+        let mut name = None;
+        let mut text = None;
 
-        for field in loadout_table.fields() {
+        for (index, field) in loadout_table.iter().enumerate() {
             // Handle both ["key"] = value and key = value syntax
-            let (key_str, value) = match field {
-                Field::ExpressionKey { key, value, .. } => {
-                    if let Expression::String(s) = key {
-                        (s.token().to_string().trim_matches('"').to_string(), value)
-                    } else {
-                        continue;
-                    }
-                }
-                Field::NameKey { key, value, .. } => {
-                    (key.token().to_string(), value)
-                }
-                _ => continue,
-            };
-
-            match key_str.as_str() {
-                "icon" => {
-                    if let Expression::Number(num) = value {
-                        icon = num.token().to_string().parse().unwrap_or(0);
-                    }
-                }
-                "name" => {
-                    if let Expression::String(s) = value {
-                        name = s.token().to_string().trim_matches('"').to_string();
-                    }
+            match (field.key.as_str(), &field.value) {
+                (Some("icon"), Value::Number(num)) => icon = num.parse().unwrap_or(0),
+                (Some("name"), Value::String(s)) => name = Some(s.to_string()),
+                (Some("text"), Value::String(s)) => text = Some(s.to_string()),
+                (Some(key @ ("icon" | "name" | "text")), _) => {
+                    issues.push(ParseIssue::new(format!("{}.{}", location, key), "unexpected value type"))
                 }
-                "text" => {
-                    if let Expression::String(s) = value {
-                        text = s.token().to_string().trim_matches('"').to_string();
-                    }
-                }
-                _ => {}
+                _ => issues.push(ParseIssue::new(
+                    field_location(location, field, index + 1),
+                    "unknown loadout field",
+                )),
             }
         }
 
-        Ok(TalentLoadout { icon, name, text })
-    }
+        if name.is_none() {
+            issues.push(ParseIssue::new(location, "loadout has no name"));
+        }
+        if text.is_none() {
+            issues.push(ParseIssue::new(location, "loadout has no talent string"));
+        }
 
-    /// Get all talents for a specific class
-    #[allow(dead_code)]
-    pub fn get_class_talents(&self, class_name: &str) -> Option<&ClassTalents> {
-        self.talents.get(class_name)
+        TalentLoadout {
+            icon,
+            name: name.unwrap_or_default(),
+            text: text.unwrap_or_default(),
+        }
     }
 
-    /// Get all talents for a specific class and spec
-    #[allow(dead_code)]
+    /// Export all loadouts as JSON (see `JsonLoadoutSet` for the structure)
+    /// Classes and specs are sorted, loadouts keep their file order
+    pub fn to_json(&self) -> Result<String> {
+        let classes = self
+            .talents
+            .iter()
+            .map(|(class_name, class_talents)| {
+                let specs = class_talents
+                    .iter()
+                    .map(|(spec_index, talents)| {
+                        let loadouts = talents
+                            .iter()
+                            .map(|t| JsonLoadout {
+                                icon: t.icon,
+                                name: t.name.clone(),
+                                text: t.text.clone(),
+                            })
+                            .collect();
+                        (*spec_index, loadouts)
+                    })
+                    .collect();
+                (class_name.clone(), specs)
+            })
+            .collect();
+
+        let set = JsonLoadoutSet {
+            version: JSON_FORMAT_VERSION,
+            classes,
+        };
+
+        serde_json::to_string_pretty(&set).context("Failed to serialize loadouts to JSON")
+    }
+
+    /// Import loadouts from the JSON format produced by `to_json`
+    /// Loadout names are kept as-is, so imported `_ARCT` builds stay auto-generated
+    pub fn from_json(json: &str) -> Result<Self> {
+        let set: JsonLoadoutSet = serde_json::from_str(json).context("Invalid loadout JSON")?;
+
+        if set.version != JSON_FORMAT_VERSION {
+            anyhow::bail!(
+                "Unsupported loadout JSON version {} (expected {})",
+                set.version,
+                JSON_FORMAT_VERSION
+            );
+        }
+
+        let mut manager = Self::new();
+
+        for (class_name, specs) in set.classes {
+            if class_name.is_empty() || class_name == "OPTION" {
+                anyhow::bail!("Invalid class name '{}' in loadout JSON", class_name);
+            }
+
+            for (spec_index, loadouts) in specs {
+                if spec_index == 0 {
+                    anyhow::bail!("Invalid spec index 0 for class {} in loadout JSON", class_name);
+                }
+
+                for loadout in loadouts {
+                    if loadout.name.is_empty() {
+                        anyhow::bail!("Loadout without a name in {} spec {}", class_name, spec_index);
+                    }
+
+                    manager.add_talent(
+                        class_name.clone(),
+                        spec_index,
+                        TalentLoadout {
+                            icon: loadout.icon,
+                            name: loadout.name,
+                            text: loadout.text,
+                        },
+                    );
+                }
+            }
+        }
+
+        Ok(manager)
+    }
+
+    /// Names of all classes with loadouts, sorted
+    pub fn class_names(&self) -> Vec<&str> {
+        let mut names: Vec<_> = self.talents.keys().map(String::as_str).collect();
+        names.sort();
+        names
+    }
+
+    /// TalentLoadouts profiles ("Name - Realm" -> profile)
+    pub fn profiles(&self) -> &BTreeMap<String, Profile> {
+        &self.profiles
+    }
+
+    /// Register a TalentLoadouts profile of `class_name`, unless it exists; returns the profile
+    pub fn add_profile(&mut self, profile: String, class_name: String) -> &mut Profile {
+        self.profiles.entry(profile).or_insert_with(|| Profile { class_name, ..Profile::default() })
+    }
+
+    /// Fields of a loaded TalentLoadouts DB other than its profiles
+    pub fn db_fields(&self) -> &[String] {
+        &self.db_fields
+    }
+
+    /// Attach the fields of a TalentLoadouts DB other than its profiles
+    pub fn set_db_fields(&mut self, fields: Vec<String>) {
+        self.db_fields = fields;
+    }
+
+    /// Unmanaged parts of a loaded BtWLoadouts file
+    pub fn btw_state(&self) -> Option<&BtwState> {
+        self.btw.as_ref()
+    }
+
+    /// Attach the unmanaged parts of a BtWLoadouts file
+    pub fn set_btw_state(&mut self, state: BtwState) {
+        self.btw = Some(state);
+    }
+
+    /// Get the Lua source of an addon option from the `OPTION` table
+    #[allow(dead_code)]
+    pub fn option(&self, key: &str) -> Option<&str> {
+        self.options
+            .as_ref()?
+            .iter()
+            .find(|(k, _)| k == key)
+            .map(|(_, value)| value.as_str())
+    }
+
+    /// Get all talents for a specific class
+    pub fn get_class_talents(&self, class_name: &str) -> Option<&ClassTalents> {
+        self.talents.get(class_name)
+    }
+
+    /// Get all talents for a specific class and spec
+    #[allow(dead_code)]
     pub fn get_spec_talents(&self, class_name: &str, spec_index: u8) -> Option<&Vec<TalentLoadout>> {
         self.talents.get(class_name)?.get(&spec_index)
     }
 
+    /// Get the GUI display order for a class/spec, reconciled with the current loadouts
+    /// Returns None if the file had no TalentLoadoutExGUI table
+    #[allow(dead_code)]
+    pub fn get_gui_order(&self, class_name: &str, spec_index: u8) -> Option<Vec<String>> {
+        let gui = self.synced_gui()?;
+        Some(
+            gui.order
+                .get(class_name)
+                .and_then(|specs| specs.get(&spec_index))
+                .cloned()
+                .unwrap_or_default(),
+        )
+    }
+
+    /// Reconcile the GUI table with the current loadouts
+    /// Names of loadouts that no longer exist are dropped, new loadouts are appended
+    /// in file order, and specs without loadouts are removed
+    fn synced_gui(&self) -> Option<GuiState> {
+        let gui = self.gui.as_ref()?;
+        let mut order = HashMap::new();
+
+        for (class_name, class_talents) in &self.talents {
+            let mut class_order = HashMap::new();
+
+            for (spec_index, talents) in class_talents {
+                if talents.is_empty() {
+                    continue;
+                }
+
+                let existing = gui
+                    .order
+                    .get(class_name)
+                    .and_then(|specs| specs.get(spec_index))
+                    .map(Vec::as_slice)
+                    .unwrap_or_default();
+
+                let mut names: Vec<String> = existing
+                    .iter()
+                    .filter(|name| talents.iter().any(|t| &t.name == *name))
+                    .cloned()
+                    .collect();
+
+                for talent in talents {
+                    if !names.contains(&talent.name) {
+                        names.push(talent.name.clone());
+                    }
+                }
+
+                class_order.insert(*spec_index, names);
+            }
+
+            if !class_order.is_empty() {
+                order.insert(class_name.clone(), class_order);
+            }
+        }
+
+        // A class whose order couldn't be parsed is written from its loadouts instead
+        let extra_fields = gui
+            .extra_fields
+            .iter()
+            .filter(|(key, _)| !key.as_ref().is_some_and(|key| order.contains_key(key)))
+            .cloned()
+            .collect();
+        Some(GuiState { order, extra_fields })
+    }
+
     /// Set talents for a specific class and spec
     /// This replaces all talents for that spec
     #[allow(dead_code)]
@@ -220,22 +1293,574 @@ impl LuaTalentManager {
             .insert(spec_index, talents);
     }
 
-    /// Remove all auto-generated talents (with _ARCT suffix) for a specific class/spec
+    /// Suffixes that mark loadouts as auto-generated
+    pub fn auto_suffixes(&self) -> &AutoSuffixes {
+        &self.suffixes
+    }
+
+    /// Set the suffixes that mark loadouts as auto-generated
+    pub fn set_auto_suffixes(&mut self, suffixes: AutoSuffixes) {
+        self.suffixes = suffixes;
+    }
+
+    /// Keep the current auto-generated builds of a class/spec as previous versions dated
+    /// `date` ("MMDD") instead of removing them; a previous version of the same date is replaced
+    pub fn archive_auto_generated(&mut self, class_name: &str, spec_index: u8, date: &str) {
+        let Some(spec_talents) = self
+            .talents
+            .get_mut(class_name)
+            .and_then(|class_talents| class_talents.get_mut(&spec_index))
+        else {
+            return;
+        };
+
+        let archived: Vec<String> = spec_talents
+            .iter()
+            .filter(|t| self.suffixes.matches_any(t) && !t.is_previous_version())
+            .map(|t| format!("{}{}{}", t.name, VERSION_DATE_SEPARATOR, date))
+            .collect();
+        spec_talents.retain(|t| !archived.contains(&t.name));
+        for talent in spec_talents.iter_mut() {
+            if self.suffixes.matches_any(talent) && !talent.is_previous_version() {
+                talent.name = format!("{}{}{}", talent.name, VERSION_DATE_SEPARATOR, date);
+            }
+        }
+    }
+
+    /// Drop previous versions of a class/spec's builds that equal the current build, and keep
+    /// only the `keep` most recent of each build. Returns the number of versions removed.
+    pub fn prune_previous_versions(
+        &mut self,
+        class_name: &str,
+        spec_index: u8,
+        keep: usize,
+        today: NaiveDate,
+    ) -> usize {
+        let Some(spec_talents) = self
+            .talents
+            .get_mut(class_name)
+            .and_then(|class_talents| class_talents.get_mut(&spec_index))
+        else {
+            return 0;
+        };
+
+        let current: HashMap<String, String> = spec_talents
+            .iter()
+            .filter(|t| self.suffixes.matches_any(t) && !t.is_previous_version())
+            .map(|t| (t.name.clone(), t.text.clone()))
+            .collect();
+
+        // Versions of each build, newest first, without the ones equal to the current build
+        let mut versions: HashMap<&str, Vec<(i64, &str)>> = HashMap::new();
+        for talent in spec_talents.iter().filter(|t| self.suffixes.matches_any(t)) {
+            let (base, Some(date)) = split_version_date(&talent.name) else {
+                continue;
+            };
+            if current.get(base) != Some(&talent.text) {
+                versions.entry(base).or_default().push((version_age_days(date, today), talent.name.as_str()));
+            }
+        }
+        let kept: BTreeSet<String> = versions
+            .into_values()
+            .flat_map(|mut versions| {
+                versions.sort();
+                versions.into_iter().take(keep).map(|(_, name)| name.to_string())
+            })
+            .collect();
+
+        let before = spec_talents.len();
+        spec_talents.retain(|t| !t.is_previous_version() || !self.suffixes.matches_any(t) || kept.contains(&t.name));
+        before - spec_talents.len()
+    }
+
+    /// Remove all auto-generated talents (current or legacy suffix) for a specific class/spec
     pub fn remove_auto_generated(&mut self, class_name: &str, spec_index: u8) {
         if let Some(class_talents) = self.talents.get_mut(class_name) {
             if let Some(spec_talents) = class_talents.get_mut(&spec_index) {
-                spec_talents.retain(|t| !t.is_auto_generated());
+                spec_talents.retain(|t| !self.suffixes.matches_any(t));
+            }
+        }
+    }
+
+    /// Remove auto-generated loadouts (current or legacy suffix) matching `predicate`,
+    /// optionally limited to one class and/or spec. Manual loadouts are never touched.
+    /// Returns the removed loadouts with their class name and spec index.
+    pub fn remove_matching(
+        &mut self,
+        class_name: Option<&str>,
+        spec_index: Option<u8>,
+        predicate: impl Fn(&TalentLoadout) -> bool,
+    ) -> Vec<(String, u8, TalentLoadout)> {
+        let mut removed = Vec::new();
+
+        for (class, class_talents) in self.talents.iter_mut() {
+            if class_name.is_some_and(|c| c != class) {
+                continue;
+            }
+
+            for (spec, spec_talents) in class_talents.iter_mut() {
+                if spec_index.is_some_and(|s| s != *spec) {
+                    continue;
+                }
+
+                spec_talents.retain(|t| {
+                    let remove = self.suffixes.matches_any(t) && predicate(t);
+                    if remove {
+                        removed.push((class.clone(), *spec, t.clone()));
+                    }
+                    !remove
+                });
             }
         }
+
+        removed.sort_by(|a, b| (&a.0, a.1).cmp(&(&b.0, b.1)));
+        removed
+    }
+
+    /// Remove auto-generated loadouts whose name starts with `prefix` (e.g., "M+-")
+    pub fn remove_by_prefix(&mut self, prefix: &str) -> Vec<(String, u8, TalentLoadout)> {
+        self.remove_matching(None, None, |t| t.name.starts_with(prefix))
+    }
+
+    /// Remove all loadouts, manual and auto-generated
+    pub fn clear(&mut self) {
+        self.talents.clear();
     }
 
-    /// Remove all auto-generated talents across all classes and specs
+    /// Remove all auto-generated talents (current or legacy suffix) across all classes and specs
     pub fn remove_all_auto_generated(&mut self) {
         for class_talents in self.talents.values_mut() {
             for spec_talents in class_talents.values_mut() {
-                spec_talents.retain(|t| !t.is_auto_generated());
+                spec_talents.retain(|t| !self.suffixes.matches_any(t));
+            }
+        }
+    }
+
+    /// Collapse auto-generated loadouts with identical talent strings for a class/spec
+    /// Manual loadouts are never touched. Returns the number of loadouts merged away.
+    pub fn dedupe_spec(&mut self, class_name: &str, spec_index: u8, policy: DedupePolicy) -> usize {
+        if policy == DedupePolicy::KeepAll {
+            return 0;
+        }
+
+        let Some(spec_talents) = self
+            .talents
+            .get_mut(class_name)
+            .and_then(|class_talents| class_talents.get_mut(&spec_index))
+        else {
+            return 0;
+        };
+
+        let mut result: Vec<TalentLoadout> = Vec::with_capacity(spec_talents.len());
+        let mut merged = 0;
+
+        let suffix = self.suffixes.current.as_str();
+
+        // Previous versions keep their own names
+        for talent in spec_talents.drain(..) {
+            if talent.is_auto_generated(suffix) && !talent.is_previous_version() {
+                if let Some(existing) = result
+                    .iter_mut()
+                    .find(|t| t.is_auto_generated(suffix) && !t.is_previous_version() && t.text == talent.text)
+                {
+                    existing.name = merge_talent_names(&existing.name, &talent.name, suffix);
+                    merged += 1;
+                    continue;
+                }
+            }
+
+            result.push(talent);
+        }
+
+        *spec_talents = result;
+        merged
+    }
+
+    /// Collapse the raid loadouts `names` of a class/spec that share a talent string on the same
+    /// difficulty (and hero talent tree) into one loadout per talent string
+    /// The largest group of a difficulty becomes "R-<difficulty>-general", smaller groups get the
+    /// names of their bosses (see `collapsed_name`) and builds without a twin keep their name.
+    pub fn collapse_raid_builds(
+        &mut self,
+        class_name: &str,
+        spec_index: u8,
+        names: &[String],
+    ) -> Vec<CollapsedLoadout> {
+        let suffix = self.suffixes.current.clone();
+        let Some(spec_talents) = self
+            .talents
+            .get_mut(class_name)
+            .and_then(|class_talents| class_talents.get_mut(&spec_index))
+        else {
+            return Vec::new();
+        };
+
+        // Bosses and positions of the loadouts by difficulty, hero talent tree and talent string
+        type Group = ((RaidDifficulty, Option<HeroTalent>, String), Vec<(String, usize)>);
+        let mut groups: Vec<Group> = Vec::new();
+        for (position, talent) in spec_talents.iter().enumerate().filter(|(_, t)| names.contains(&t.name)) {
+            let Some(TalentIdentifier::Raid { difficulty, boss, hero }) =
+                TalentIdentifier::from_talent_name(&talent.name, &suffix)
+            else {
+                continue;
+            };
+            let key = (difficulty, hero, talent.text.clone());
+            match groups.iter_mut().find(|(group_key, _)| *group_key == key) {
+                Some((_, members)) => members.push((boss, position)),
+                None => groups.push((key, vec![(boss, position)])),
+            }
+        }
+
+        let mut collapsed = Vec::new();
+        let mut removed = HashSet::new();
+        for (index, ((difficulty, hero, _), members)) in groups.iter().enumerate() {
+            if members.len() < 2 {
+                continue;
+            }
+            // The largest group of its difficulty, the first one on a tie
+            let majority = !groups.iter().enumerate().any(|(other, ((d, h, _), m))| {
+                (d, h) == (difficulty, hero) && (m.len() > members.len() || (m.len() == members.len() && other < index))
+            });
+            let name = if majority {
+                let boss = GENERAL_BOSS.to_string();
+                TalentIdentifier::Raid { difficulty: *difficulty, boss, hero: *hero }.as_talent_name(&suffix)
+            } else {
+                let bosses: Vec<String> = members.iter().map(|(boss, _)| boss.clone()).collect();
+                collapsed_name(*difficulty, *hero, &bosses, &suffix)
+            };
+
+            let member_names = members.iter().map(|&(_, position)| spec_talents[position].name.clone()).collect();
+            spec_talents[members[0].1].name = name.clone();
+            removed.extend(members[1..].iter().map(|&(_, position)| position));
+            collapsed.push(CollapsedLoadout { name, members: member_names });
+        }
+
+        let mut position = 0;
+        spec_talents.retain(|_| {
+            position += 1;
+            !removed.contains(&(position - 1))
+        });
+        collapsed
+    }
+
+    /// Limit a class/spec to `max` loadouts by evicting auto-generated builds
+    /// Manual loadouts are never evicted. Lower-priority content goes first:
+    /// unrecognized names, then Normal, Heroic and Mythic raid builds, then M+ builds,
+    /// each group alphabetically. Returns the evicted loadouts.
+    pub fn enforce_cap(&mut self, class_name: &str, spec_index: u8, max: usize) -> Vec<TalentLoadout> {
+        let Some(spec_talents) = self
+            .talents
+            .get_mut(class_name)
+            .and_then(|class_talents| class_talents.get_mut(&spec_index))
+        else {
+            return Vec::new();
+        };
+
+        let excess = spec_talents.len().saturating_sub(max);
+        if excess == 0 {
+            return Vec::new();
+        }
+
+        let suffix = self.suffixes.current.as_str();
+        // By position rather than name, so a duplicate name doesn't evict both loadouts
+        let mut candidates: Vec<(u8, &str, usize)> = spec_talents
+            .iter()
+            .enumerate()
+            .filter(|(_, t)| t.is_auto_generated(suffix))
+            .map(|(position, t)| (eviction_rank(&t.name, suffix), t.name.as_str(), position))
+            .collect();
+        candidates.sort();
+        candidates.truncate(excess);
+        let candidates: HashSet<usize> = candidates.into_iter().map(|(_, _, position)| position).collect();
+
+        let mut evicted = Vec::with_capacity(candidates.len());
+        let mut position = 0;
+        spec_talents.retain(|t| {
+            let evict = candidates.contains(&position);
+            position += 1;
+            if evict {
+                evicted.push(t.clone());
+            }
+            !evict
+        });
+
+        evicted
+    }
+
+    /// Order a class/spec's loadouts by content (stable)
+    /// Manual loadouts stay first in their original order, followed by raid builds grouped
+    /// by difficulty in `encounter_order` (bosses not listed go last, alphabetically),
+    /// then M+ builds alphabetically.
+    pub fn sort_spec(&mut self, class_name: &str, spec_index: u8, encounter_order: &[String]) {
+        if let Some(spec_talents) = self
+            .talents
+            .get_mut(class_name)
+            .and_then(|class_talents| class_talents.get_mut(&spec_index))
+        {
+            spec_talents.sort_by_cached_key(|t| content_sort_key(t, encounter_order, &self.suffixes.current));
+        }
+    }
+
+    /// Merge all loadouts from another manager into this one
+    /// Collisions are loadouts with the same name in the same class/spec; exact duplicates
+    /// (same name and talent string) are always skipped
+    pub fn merge_from(&mut self, other: LuaTalentManager, strategy: MergeStrategy) -> MergeSummary {
+        let mut summary = MergeSummary::default();
+
+        for (class_name, class_talents) in other.talents {
+            for (spec_index, talents) in class_talents {
+                let spec_talents = self
+                    .talents
+                    .entry(class_name.clone())
+                    .or_default()
+                    .entry(spec_index)
+                    .or_default();
+
+                for talent in talents {
+                    let Some(position) = spec_talents.iter().position(|t| t.name == talent.name) else {
+                        spec_talents.push(talent);
+                        summary.added += 1;
+                        continue;
+                    };
+
+                    if spec_talents[position].text == talent.text {
+                        summary.skipped += 1;
+                        continue;
+                    }
+
+                    match strategy {
+                        MergeStrategy::KeepExisting => summary.skipped += 1,
+                        MergeStrategy::PreferOther => {
+                            spec_talents[position] = talent;
+                            summary.replaced += 1;
+                        }
+                        MergeStrategy::RenameWithSuffix => {
+                            let name = (2..)
+                                .map(|n| format!("{} ({})", talent.name, n))
+                                .find(|candidate| spec_talents.iter().all(|t| &t.name != candidate))
+                                .unwrap_or_default();
+                            spec_talents.push(TalentLoadout { name, ..talent });
+                            summary.renamed += 1;
+                        }
+                    }
+                }
+            }
+        }
+
+        summary
+    }
+
+    /// Count manual and auto-generated loadouts per class and spec
+    pub fn stats(&self) -> TalentStats {
+        let suffix = self.suffixes.current.as_str();
+        let mut stats = TalentStats::default();
+
+        for (class_name, class_talents) in &self.talents {
+            let class_stats = stats.classes.entry(class_name.clone()).or_default();
+
+            for (spec_index, talents) in class_talents {
+                let spec_stats = class_stats.specs.entry(*spec_index).or_default();
+                spec_stats.spec = spec_slug(class_name, *spec_index);
+
+                for talent in talents {
+                    if talent.is_auto_generated(suffix) {
+                        spec_stats.auto += 1;
+                        let identifier = talent.name.strip_suffix(suffix).unwrap_or(&talent.name);
+                        stats.identifiers.insert(identifier.to_string());
+                    } else {
+                        spec_stats.manual += 1;
+                    }
+                }
+
+                class_stats.manual += spec_stats.manual;
+                class_stats.auto += spec_stats.auto;
+            }
+
+            class_stats.total = class_stats.manual + class_stats.auto;
+            stats.manual += class_stats.manual;
+            stats.auto += class_stats.auto;
+        }
+
+        stats.total = stats.manual + stats.auto;
+        stats
+    }
+
+    /// Compute the changes needed to turn this manager's loadouts into `other`'s
+    /// Loadouts are matched by class, spec index and name
+    pub fn diff(&self, other: &LuaTalentManager) -> TalentDiff {
+        let before = self.loadouts_by_key();
+        let after = other.loadouts_by_key();
+        let mut diff = TalentDiff::default();
+
+        for ((class_name, spec_index, name), old_text) in &before {
+            match after.get(&(*class_name, *spec_index, *name)) {
+                None => diff.removed.push(DiffEntry {
+                    class_name: class_name.to_string(),
+                    spec_index: *spec_index,
+                    spec: spec_slug(class_name, *spec_index),
+                    name: name.to_string(),
+                    text: old_text.to_string(),
+                }),
+                Some(new_text) if new_text != old_text => diff.changed.push(ChangedLoadout {
+                    class_name: class_name.to_string(),
+                    spec_index: *spec_index,
+                    spec: spec_slug(class_name, *spec_index),
+                    name: name.to_string(),
+                    old_text: old_text.to_string(),
+                    new_text: new_text.to_string(),
+                }),
+                Some(_) => {}
+            }
+        }
+
+        for ((class_name, spec_index, name), text) in &after {
+            if !before.contains_key(&(*class_name, *spec_index, *name)) {
+                diff.added.push(DiffEntry {
+                    class_name: class_name.to_string(),
+                    spec_index: *spec_index,
+                    spec: spec_slug(class_name, *spec_index),
+                    name: name.to_string(),
+                    text: text.to_string(),
+                });
+            }
+        }
+
+        diff
+    }
+
+    /// Apply changes made to another file: loadouts removed there are removed here, added and
+    /// changed ones are copied from `updated`, the other file after the changes
+    /// Only auto-generated loadouts are removed, so this file's manual loadouts are kept.
+    /// Returns the class/specs that were touched.
+    pub fn apply_diff(&mut self, diff: &TalentDiff, updated: &LuaTalentManager) -> BTreeSet<(String, u8)> {
+        let mut touched = BTreeSet::new();
+        for entry in &diff.removed {
+            let removed =
+                self.remove_matching(Some(&entry.class_name), Some(entry.spec_index), |t| t.name == entry.name);
+            if !removed.is_empty() {
+                touched.insert((entry.class_name.clone(), entry.spec_index));
+            }
+        }
+
+        let upserts = diff
+            .added
+            .iter()
+            .map(|entry| (&entry.class_name, entry.spec_index, &entry.name))
+            .chain(diff.changed.iter().map(|entry| (&entry.class_name, entry.spec_index, &entry.name)));
+        for (class_name, spec_index, name) in upserts {
+            let talent = updated
+                .get_spec_talents(class_name, spec_index)
+                .and_then(|talents| talents.iter().find(|t| &t.name == name));
+            if let Some(talent) = talent {
+                self.upsert_talent(class_name.clone(), spec_index, talent.clone());
+                touched.insert((class_name.clone(), spec_index));
+            }
+        }
+        touched
+    }
+
+    /// Check that a file survives being written: load it, serialize it, parse the output
+    /// and compare both. Nothing is written to disk.
+    pub fn verify_roundtrip(path: impl AsRef<Path>) -> Result<RoundTripReport> {
+        let (original, issues) = Self::load_from_file(path, None)?;
+        let reparsed = Self::parse_lua(&original.to_lua_string(), Some(&original.table_name))
+            .context("Failed to parse the serialized loadouts")?;
+
+        let differences = original.round_trip_differences(&reparsed);
+        Ok(RoundTripReport {
+            ok: issues.is_empty() && differences.is_empty(),
+            issues,
+            differences,
+        })
+    }
+
+    /// Compare everything `to_lua_string` writes: loadouts in order, unparsed spec
+    /// buckets, addon options and the GUI table
+    fn round_trip_differences(&self, other: &LuaTalentManager) -> Vec<RoundTripDifference> {
+        let mut differences = Vec::new();
+        let mut compare = |location: String, before: Option<String>, after: Option<String>| {
+            if before != after {
+                differences.push(RoundTripDifference { location, before, after });
+            }
+        };
+
+        compare(
+            "table name".to_string(),
+            Some(self.table_name.clone()),
+            Some(other.table_name.clone()),
+        );
+
+        let class_names: BTreeSet<&String> = self
+            .talents
+            .keys()
+            .chain(other.talents.keys())
+            .chain(self.raw_specs.keys())
+            .chain(other.raw_specs.keys())
+            .collect();
+        let empty = ClassTalents::new();
+
+        for class_name in class_names {
+            let location = format!("{}[\"{}\"]", self.table_name, lua_escape_string(class_name));
+            let before = self.talents.get(class_name).unwrap_or(&empty);
+            let after = other.talents.get(class_name).unwrap_or(&empty);
+
+            let spec_indices: BTreeSet<u8> = before.keys().chain(after.keys()).copied().collect();
+            for spec_index in spec_indices {
+                let before = before.get(&spec_index).map(Vec::as_slice).unwrap_or_default();
+                let after = after.get(&spec_index).map(Vec::as_slice).unwrap_or_default();
+
+                for i in 0..before.len().max(after.len()) {
+                    compare(
+                        format!("{}[{}][{}]", location, spec_index, i + 1),
+                        before.get(i).map(talent_to_lua),
+                        after.get(i).map(talent_to_lua),
+                    );
+                }
+            }
+
+            compare(
+                format!("{} unparsed specs", location),
+                self.raw_specs.get(class_name).map(|raw| raw.join(", ")),
+                other.raw_specs.get(class_name).map(|raw| raw.join(", ")),
+            );
+        }
+
+        let options = |manager: &LuaTalentManager| {
+            manager.options.as_ref().map(|options| {
+                options
+                    .iter()
+                    .map(|(key, value)| format!("[\"{}\"] = {}", lua_escape_string(key), value))
+                    .collect::<Vec<_>>()
+                    .join(", ")
+            })
+        };
+        compare(format!("{}[\"OPTION\"]", self.table_name), options(self), options(other));
+
+        let gui_name = gui_table_name(&self.table_name);
+        compare(
+            gui_name.clone(),
+            self.gui.as_ref().map(|gui| Self::gui_to_lua_string(gui, &gui_name)),
+            other.gui.as_ref().map(|gui| Self::gui_to_lua_string(gui, &gui_name)),
+        );
+
+        differences
+    }
+
+    /// Index all loadouts by (class, spec, name), keeping the first loadout for duplicate names
+    fn loadouts_by_key(&self) -> BTreeMap<(&str, u8, &str), &str> {
+        let mut result = BTreeMap::new();
+
+        for (class_name, class_talents) in &self.talents {
+            for (spec_index, talents) in class_talents {
+                for talent in talents {
+                    result
+                        .entry((class_name.as_str(), *spec_index, talent.name.as_str()))
+                        .or_insert(talent.text.as_str());
+                }
             }
         }
+
+        result
     }
 
     /// Add a talent to a specific class/spec
@@ -248,51 +1873,289 @@ impl LuaTalentManager {
             .push(talent);
     }
 
+    /// Add a talent, or replace the loadout with the same name in the class/spec
+    /// A replaced loadout keeps its position, and its icon if the new one has none.
+    /// Returns true if an existing loadout was replaced.
+    pub fn upsert_talent(&mut self, class_name: String, spec_index: u8, mut talent: TalentLoadout) -> bool {
+        let spec_talents = self
+            .talents
+            .entry(class_name)
+            .or_default()
+            .entry(spec_index)
+            .or_default();
+
+        match spec_talents.iter_mut().find(|t| t.name == talent.name) {
+            Some(existing) => {
+                if talent.icon == 0 {
+                    talent.icon = existing.icon;
+                }
+                *existing = talent;
+                true
+            }
+            None => {
+                spec_talents.push(talent);
+                false
+            }
+        }
+    }
+
+    /// Rename a loadout, keeping its icon, talent string and position (also in the GUI order)
+    /// Fails if the loadout doesn't exist or the spec already has a loadout named `new_name`
+    pub fn rename_talent(&mut self, class_name: &str, spec_index: u8, old_name: &str, new_name: &str) -> Result<()> {
+        if new_name.is_empty() {
+            anyhow::bail!("Loadout name can't be empty");
+        }
+
+        let spec_talents = self
+            .talents
+            .get_mut(class_name)
+            .and_then(|class_talents| class_talents.get_mut(&spec_index))
+            .ok_or_else(|| anyhow::anyhow!("No loadouts for {} spec {}", class_name, spec_index))?;
+
+        let position = spec_talents
+            .iter()
+            .position(|t| t.name == old_name)
+            .ok_or_else(|| anyhow::anyhow!("Loadout {} not found in {} spec {}", old_name, class_name, spec_index))?;
+        if old_name == new_name {
+            return Ok(());
+        }
+        if spec_talents.iter().any(|t| t.name == new_name) {
+            anyhow::bail!("{} spec {} already has a loadout named {}", class_name, spec_index, new_name);
+        }
+
+        spec_talents[position].name = new_name.to_string();
+
+        if let Some(names) = self
+            .gui
+            .as_mut()
+            .and_then(|gui| gui.order.get_mut(class_name))
+            .and_then(|specs| specs.get_mut(&spec_index))
+        {
+            if let Some(name) = names.iter_mut().find(|name| name.as_str() == old_name) {
+                *name = new_name.to_string();
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Move a loadout to another class/spec (or name), keeping its icon and talent string
+    /// Fails if the source doesn't exist or the destination name is taken
+    pub fn move_talent(&mut self, from: &LoadoutRef, to: &LoadoutRef) -> Result<()> {
+        if from.class_name == to.class_name && from.spec_index == to.spec_index {
+            return self.rename_talent(&from.class_name, from.spec_index, &from.name, &to.name);
+        }
+
+        let talent = self.find_talent(from)?.clone();
+        self.check_destination(to)?;
+
+        if let Some(spec_talents) = self
+            .talents
+            .get_mut(&from.class_name)
+            .and_then(|class_talents| class_talents.get_mut(&from.spec_index))
+        {
+            if let Some(position) = spec_talents.iter().position(|t| t.name == from.name) {
+                spec_talents.remove(position);
+            }
+        }
+
+        self.add_talent(to.class_name.clone(), to.spec_index, TalentLoadout { name: to.name.clone(), ..talent });
+        Ok(())
+    }
+
+    /// Copy a loadout to another class/spec, e.g. as a starting point for a spec without data
+    /// Returns the name of the copy after applying `suffix`
+    pub fn copy_talent(&mut self, from: &LoadoutRef, to: &LoadoutRef, suffix: SuffixOnCopy) -> Result<String> {
+        let talent = self.find_talent(from)?.clone();
+
+        let name = match suffix {
+            SuffixOnCopy::Keep => to.name.clone(),
+            SuffixOnCopy::Strip => self.suffixes.strip(&to.name).to_string(),
+            SuffixOnCopy::Retag => format!("{}{}", self.suffixes.strip(&to.name), self.suffixes.current),
+        };
+        let to = LoadoutRef { name, ..to.clone() };
+        self.check_destination(&to)?;
+
+        self.add_talent(to.class_name, to.spec_index, TalentLoadout { name: to.name.clone(), ..talent });
+        Ok(to.name)
+    }
+
+    /// Find a loadout by reference
+    fn find_talent(&self, loadout: &LoadoutRef) -> Result<&TalentLoadout> {
+        self.get_spec_talents(&loadout.class_name, loadout.spec_index)
+            .and_then(|talents| talents.iter().find(|t| t.name == loadout.name))
+            .ok_or_else(|| {
+                anyhow::anyhow!(
+                    "Loadout {} not found in {} spec {}",
+                    loadout.name,
+                    loadout.class_name,
+                    loadout.spec_index
+                )
+            })
+    }
+
+    /// Check that a loadout can be added: the spec exists and the name is free
+    fn check_destination(&self, loadout: &LoadoutRef) -> Result<()> {
+        if loadout.name.is_empty() {
+            anyhow::bail!("Loadout name can't be empty");
+        }
+        let valid_spec = WowClass::from_lua_format(&loadout.class_name)
+            .is_some_and(|class| class.is_valid_spec_index(loadout.spec_index));
+        if !valid_spec {
+            anyhow::bail!("{} has no spec {}", loadout.class_name, loadout.spec_index);
+        }
+        if self.find_talent(loadout).is_ok() {
+            anyhow::bail!(
+                "{} spec {} already has a loadout named {}",
+                loadout.class_name,
+                loadout.spec_index,
+                loadout.name
+            );
+        }
+        Ok(())
+    }
+
     /// Write talents to a Lua file
+    /// The file is replaced atomically, so a crash never leaves it truncated
     pub fn write_to_file(&self, path: impl AsRef<Path>) -> Result<()> {
-        let lua_content = self.to_lua_string();
-        std::fs::write(path.as_ref(), lua_content)
+        let lua_content = self.with_line_endings(self.to_lua_string());
+        write_atomic(path.as_ref(), lua_content)
             .context("Failed to write TalentLoadoutsEx.lua file")?;
         Ok(())
     }
 
-    /// Convert talents to Lua string format
-    fn to_lua_string(&self) -> String {
-        let mut result = String::from("TalentLoadoutEx = {\n");
+    /// Write talents to a file in the given saved-variable format
+    /// The file is replaced atomically, so a crash never leaves it truncated
+    pub fn write_to_file_as(&self, path: impl AsRef<Path>, format: TalentFileFormat) -> Result<()> {
+        let file = match format {
+            TalentFileFormat::TalentLoadoutsEx => "TalentLoadoutsEx.lua file",
+            TalentFileFormat::TalentLoadouts => "TalentLoadouts saved variables file",
+            TalentFileFormat::BtwLoadouts => "BtWLoadouts saved variables file",
+        };
+        write_atomic(path.as_ref(), self.to_string_as(format)).with_context(|| format!("Failed to write {}", file))
+    }
+
+    /// File content in the given saved-variable format, as `write_to_file_as` writes it
+    pub fn to_string_as(&self, format: TalentFileFormat) -> String {
+        self.with_line_endings(match format {
+            TalentFileFormat::TalentLoadoutsEx => self.to_lua_string(),
+            TalentFileFormat::TalentLoadouts => talent_loadouts_db::to_db_lua_string(self),
+            TalentFileFormat::BtwLoadouts => btw_loadouts::to_sets_lua_string(self),
+        })
+    }
+
+    /// Mark the loadouts as read from a file with CRLF line endings
+    pub(crate) fn set_crlf(&mut self, crlf: bool) {
+        self.crlf = crlf;
+    }
+
+    /// Convert generated (LF) output to the line endings of the file it was read from
+    fn with_line_endings(&self, content: String) -> String {
+        if self.crlf {
+            content.replace('\n', "\r\n")
+        } else {
+            content
+        }
+    }
+
+    /// Convert talents to Lua string format
+    pub fn to_lua_string(&self) -> String {
+        // Reserve room for every loadout up front; large files have thousands of them
+        let capacity: usize = self
+            .talents
+            .values()
+            .flat_map(|class_talents| class_talents.values().flatten())
+            .map(|talent| talent.name.len() + talent.text.len() + 64)
+            .sum();
+        let mut result = String::with_capacity(capacity + 1024);
+        let _ = writeln!(result, "{} = {{", self.table_name);
+
+        // Sort class names for consistent output (classes with only raw buckets included)
+        let class_names: BTreeSet<_> = self.talents.keys().chain(self.raw_specs.keys()).collect();
+        let empty = ClassTalents::new();
+
+        for class_name in class_names {
+            let class_talents = self.talents.get(class_name).unwrap_or(&empty);
+
+            result.push_str(&format!("  [\"{}\"] = {{\n", lua_escape_string(class_name)));
+
+            // Sort spec indices
+            let mut spec_indices: Vec<_> = class_talents.keys().copied().collect();
+            spec_indices.sort();
+
+            for spec_index in spec_indices {
+                let talents = &class_talents[&spec_index];
+
+                result.push_str(&format!("    [{}] = {{\n", spec_index));
+
+                for talent in talents {
+                    result.push_str("      ");
+                    push_talent_lua(&mut result, talent);
+                    result.push_str(",\n");
+                }
+
+                result.push_str("    },\n");
+            }
+
+            // Unparsed spec buckets are written back unchanged
+            for raw in self.raw_specs.get(class_name).into_iter().flatten() {
+                result.push_str(&format!("    {},\n", raw));
+            }
+
+            result.push_str("  },\n");
+        }
+
+        // Write back the existing OPTION table; new files get the addon's default
+        match &self.options {
+            Some(options) => {
+                let fields: Vec<_> = options
+                    .iter()
+                    .map(|(key, value)| format!("[\"{}\"] = {}", lua_escape_string(key), value))
+                    .collect();
+                result.push_str(&format!("  [\"OPTION\"] = {{ {} }},\n", fields.join(", ")));
+            }
+            None => result.push_str("  [\"OPTION\"] = { [\"IsEnabledPvp\"] = false },\n"),
+        }
+
+        result.push_str("}\n");
+
+        if let Some(gui) = self.synced_gui() {
+            result.push_str(&Self::gui_to_lua_string(&gui, &gui_table_name(&self.table_name)));
+        }
+
+        result
+    }
+
+    /// Convert the GUI table to Lua string format
+    fn gui_to_lua_string(gui: &GuiState, gui_name: &str) -> String {
+        let mut result = format!("{} = {{\n", gui_name);
 
-        // Sort class names for consistent output
-        let mut class_names: Vec<_> = self.talents.keys().collect();
+        let mut class_names: Vec<_> = gui.order.keys().collect();
         class_names.sort();
 
         for class_name in class_names {
-            let class_talents = &self.talents[class_name];
+            let class_order = &gui.order[class_name];
 
-            result.push_str(&format!("  [\"{}\"] = {{\n", class_name));
+            result.push_str(&format!("  [\"{}\"] = {{\n", lua_escape_string(class_name)));
 
-            // Sort spec indices
-            let mut spec_indices: Vec<_> = class_talents.keys().copied().collect();
+            let mut spec_indices: Vec<_> = class_order.keys().copied().collect();
             spec_indices.sort();
 
             for spec_index in spec_indices {
-                let talents = &class_talents[&spec_index];
-
-                result.push_str(&format!("    [{}] = {{\n", spec_index));
-
-                for talent in talents {
-                    result.push_str(&format!(
-                        "      {{ [\"icon\"] = {}, [\"name\"] = \"{}\", [\"text\"] = \"{}\" }},\n",
-                        talent.icon, talent.name, talent.text
-                    ));
-                }
+                let names: Vec<String> = class_order[&spec_index]
+                    .iter()
+                    .map(|name| format!("\"{}\"", lua_escape_string(name)))
+                    .collect();
 
-                result.push_str("    },\n");
+                result.push_str(&format!("    [{}] = {{ {} }},\n", spec_index, names.join(", ")));
             }
 
             result.push_str("  },\n");
         }
 
-        // Add OPTION table (always false for IsEnabledPvp)
-        result.push_str("  [\"OPTION\"] = { [\"IsEnabledPvp\"] = false },\n");
+        for (_, field) in &gui.extra_fields {
+            result.push_str(&format!("  {},\n", field));
+        }
 
         result.push_str("}\n");
         result
@@ -308,6 +2171,7 @@ impl Default for LuaTalentManager {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::test_util::temp_dir;
 
     fn create_test_lua() -> String {
         r#"TalentLoadoutEx = {
@@ -329,10 +2193,212 @@ mod tests {
 }"#.to_string()
     }
 
+    #[test]
+    fn test_parse_tolerates_bom_crlf_and_comments() {
+        let plain = LuaTalentManager::parse_lua(include_str!("../tests/fixtures/TalentLoadoutsEx.lua"), None).unwrap();
+        let fixtures = [
+            include_str!("../tests/fixtures/TalentLoadoutsEx_bom.lua"),
+            include_str!("../tests/fixtures/TalentLoadoutsEx_crlf.lua"),
+            include_str!("../tests/fixtures/TalentLoadoutsEx_comments.lua"),
+        ];
+
+        for fixture in fixtures {
+            let manager = LuaTalentManager::parse_lua(fixture, None).unwrap();
+            assert_eq!(manager.stats().total, 4);
+            assert!(plain.diff(&manager).is_empty());
+        }
+    }
+
+    #[test]
+    fn test_parse_string_spec_keys() {
+        let manager =
+            LuaTalentManager::parse_lua(include_str!("../tests/fixtures/TalentLoadoutsEx_string_keys.lua"), None).unwrap();
+
+        // ["1"] and [1] are merged into the same spec
+        let arms = manager.get_spec_talents("WARRIOR", 1).unwrap();
+        assert_eq!(arms.len(), 2);
+        assert_eq!(arms[0].name, "My Arms Build");
+        assert_eq!(manager.get_spec_talents("MAGE", 3).unwrap()[0].name, "M+-ara-kara_ARCT");
+        assert_eq!(manager.stats().total, 4);
+
+        let reparsed = LuaTalentManager::parse_lua(&manager.to_lua_string(), None).unwrap();
+        assert!(manager.diff(&reparsed).is_empty());
+    }
+
+    #[test]
+    fn test_duplicate_class_blocks_are_merged() {
+        let content = include_str!("../tests/fixtures/TalentLoadoutsEx_duplicate_class.lua");
+        let (manager, issues) = LuaTalentManager::parse_lua_with_issues(content, None).unwrap();
+
+        // Loadouts of both WARRIOR blocks survive, the exact copy only once
+        let arms: Vec<_> = manager.get_spec_talents("WARRIOR", 1).unwrap().iter().map(|t| t.name.as_str()).collect();
+        assert_eq!(arms, vec!["My Arms Build", "R-heroic-sikran_ARCT", "Merged Arms Build"]);
+        assert_eq!(manager.get_spec_talents("WARRIOR", 2).unwrap()[0].name, "My Fury Build");
+        assert_eq!(manager.stats().total, 5);
+
+        assert_eq!(issues, vec![ParseIssue::new("TalentLoadoutEx[\"WARRIOR\"]", "duplicate class key, loadouts merged")]);
+
+        // Duplicate spec keys within a class are merged the same way
+        let (manager, issues) = LuaTalentManager::parse_lua_with_issues(
+            include_str!("../tests/fixtures/TalentLoadoutsEx_string_keys.lua"),
+            None,
+        )
+        .unwrap();
+        assert_eq!(manager.get_spec_talents("WARRIOR", 1).unwrap().len(), 2);
+        assert_eq!(issues.len(), 1);
+        assert_eq!(issues[0].location, "TalentLoadoutEx[\"WARRIOR\"][1]");
+    }
+
+    #[test]
+    fn test_spec_gaps_survive_round_trip() {
+        let manager =
+            LuaTalentManager::parse_lua(include_str!("../tests/fixtures/TalentLoadoutsEx_spec_gaps.lua"), None).unwrap();
+
+        assert_eq!(manager.get_spec_talents("WARRIOR", 4).unwrap()[0].name, "Future Spec Build");
+        assert_eq!(manager.get_spec_talents("WARRIOR", 7).unwrap()[0].name, "Gap Build");
+
+        let output = manager.to_lua_string();
+        for expected in ["Out Of Range", "[300] = {", "[\"legacy\"] = {", "Legacy Build", "[\"EVOKER\"] = {", "[5] = false"] {
+            assert!(output.contains(expected), "missing {}", expected);
+        }
+
+        // Writing the reparsed output again gives the same file
+        let reparsed = LuaTalentManager::parse_lua(&output, None).unwrap();
+        assert!(manager.diff(&reparsed).is_empty());
+        assert_eq!(reparsed.to_lua_string(), output);
+    }
+
+    #[test]
+    fn test_detect_forked_table_name() {
+        let manager = LuaTalentManager::parse_lua(include_str!("../tests/fixtures/TalentLoadoutsEx_fork.lua"), None).unwrap();
+
+        // The GUI table comes first but lists names, not loadouts
+        assert_eq!(manager.table_name(), "TLEPlus");
+        assert_eq!(manager.stats().total, 2);
+        assert_eq!(manager.option("IsEnabledPvp"), Some("true"));
+        assert_eq!(manager.get_gui_order("WARRIOR", 1).unwrap()[0], "R-heroic-sikran_ARCT");
+
+        let output = manager.to_lua_string();
+        assert!(output.starts_with("TLEPlus = {\n"));
+        assert!(output.contains("TLEPlusGUI = {\n"));
+        assert!(!output.contains(DEFAULT_TABLE_NAME));
+    }
+
+    #[test]
+    fn test_detect_skips_other_addon_tables() {
+        let lua = r#"BigWigs3DB = { ["WARRIOR"] = { ["enabled"] = true } }
+MyLoadouts = {
+  ["MAGE"] = { [3] = { { ["icon"] = 0, ["name"] = "Frost", ["text"] = "mage/frost/GHI789" } } },
+}"#;
+        let manager = LuaTalentManager::parse_lua(lua, None).unwrap();
+        assert_eq!(manager.table_name(), "MyLoadouts");
+        assert_eq!(manager.get_spec_talents("MAGE", 3).unwrap()[0].name, "Frost");
+
+        // Nothing that looks like loadouts: fall back to the default name
+        let manager = LuaTalentManager::parse_lua("BigWigs3DB = { [\"WARRIOR\"] = {} }", None).unwrap();
+        assert_eq!(manager.table_name(), DEFAULT_TABLE_NAME);
+        assert_eq!(manager.stats().total, 0);
+    }
+
+    #[test]
+    fn test_explicit_table_name() {
+        let content = include_str!("../tests/fixtures/TalentLoadoutsEx.lua");
+
+        let manager = LuaTalentManager::parse_lua(content, Some("TalentLoadoutEx")).unwrap();
+        assert_eq!(manager.stats().total, 4);
+
+        let mut manager = LuaTalentManager::parse_lua(content, Some("TLEPlus")).unwrap();
+        assert_eq!(manager.stats().total, 0);
+
+        manager.set_table_name("TalentLoadoutEx");
+        assert!(manager.to_lua_string().starts_with("TalentLoadoutEx = {\n"));
+    }
+
+    #[test]
+    fn test_parse_issues() {
+        let content = include_str!("../tests/fixtures/TalentLoadoutsEx_issues.lua");
+        let (manager, issues) = LuaTalentManager::parse_lua_with_issues(content, None).unwrap();
+
+        assert_eq!(manager.get_spec_talents("WARRIOR", 1).unwrap().len(), 3);
+        let issues: Vec<_> = issues.iter().map(|i| (i.location.as_str(), i.reason.as_str())).collect();
+        assert_eq!(
+            issues,
+            vec![
+                ("TalentLoadoutEx[\"WARRIOR\"][1][2]", "loadout is not a table"),
+                ("TalentLoadoutEx[\"WARRIOR\"][1][3]", "loadout has no talent string"),
+                ("TalentLoadoutEx[\"WARRIOR\"][1][4][\"note\"]", "unknown loadout field"),
+                ("TalentLoadoutEx[\"WARRIOR\"][1][\"extra\"]", "keyed entry in a loadout list"),
+                ("TalentLoadoutEx[\"MAGE\"]", "class entry is not a table"),
+                ("TalentLoadoutEx[42]", "class key is not a string"),
+            ]
+        );
+
+        // Clean files have no issues
+        let (_, issues) =
+            LuaTalentManager::parse_lua_with_issues(include_str!("../tests/fixtures/TalentLoadoutsEx.lua"), None).unwrap();
+        assert!(issues.is_empty());
+
+        // The recovering loader reports them as warnings without a parse error
+        let (_, warnings) = LuaTalentManager::parse_lua_recovering(content);
+        assert!(warnings.parse_error.is_none());
+        assert_eq!(warnings.issues.len(), 6);
+        assert!(!warnings.drops_data());
+    }
+
+    #[test]
+    fn test_verify_roundtrip() {
+        let dir = temp_dir("verify");
+
+        let clean = dir.join("clean.lua");
+        std::fs::write(&clean, include_str!("../tests/fixtures/TalentLoadoutsEx_spec_gaps.lua")).unwrap();
+        let report = LuaTalentManager::verify_roundtrip(&clean).unwrap();
+        assert!(report.ok, "{:?}", report);
+
+        // Skipped entries make the check fail even though the rest survives
+        let broken = dir.join("broken.lua");
+        std::fs::write(&broken, include_str!("../tests/fixtures/TalentLoadoutsEx_issues.lua")).unwrap();
+        let report = LuaTalentManager::verify_roundtrip(&broken).unwrap();
+        assert!(!report.ok);
+        assert_eq!(report.issues.len(), 6);
+        assert!(report.differences.is_empty());
+
+        // A GUI entry for a loadout that doesn't exist is dropped by the writer
+        let stale_gui = dir.join("stale_gui.lua");
+        std::fs::write(
+            &stale_gui,
+            format!(
+                "{}\nTalentLoadoutExGUI = {{ [\"MAGE\"] = {{ [3] = {{ \"Gone\", \"M+-ara-kara_ARCT\" }} }} }}\n",
+                include_str!("../tests/fixtures/TalentLoadoutsEx.lua")
+            ),
+        )
+        .unwrap();
+        let report = LuaTalentManager::verify_roundtrip(&stale_gui).unwrap();
+        assert!(!report.ok);
+        assert_eq!(report.differences.len(), 1);
+        assert_eq!(report.differences[0].location, "TalentLoadoutExGUI");
+        assert!(report.differences[0].before.as_ref().unwrap().contains("\"Gone\""));
+    }
+
+    #[test]
+    fn test_crlf_line_endings_are_kept() {
+        let manager = LuaTalentManager::parse_lua(include_str!("../tests/fixtures/TalentLoadoutsEx_crlf.lua"), None).unwrap();
+
+        let dir = temp_dir("crlf");
+        let path = dir.join("TalentLoadoutsEx.lua");
+        manager.write_to_file(&path).unwrap();
+        let written = std::fs::read_to_string(&path).unwrap();
+
+        assert!(written.contains("\r\n"));
+        assert_eq!(written.matches('\n').count(), written.matches("\r\n").count());
+
+        let reparsed = LuaTalentManager::parse_lua(&written, None).unwrap();
+        assert!(manager.diff(&reparsed).is_empty());
+    }
+
     #[test]
     fn test_parse_lua() {
         let lua = create_test_lua();
-        let manager = LuaTalentManager::parse_lua(&lua).unwrap();
+        let manager = LuaTalentManager::parse_lua(&lua, None).unwrap();
 
         // Check warrior talents
         let warrior_talents = manager.get_class_talents("WARRIOR").unwrap();
@@ -353,16 +2419,52 @@ mod tests {
     #[test]
     fn test_is_auto_generated() {
         let auto = TalentLoadout::new("R-heroic-sikran_ARCT".to_string(), "test".to_string());
-        assert!(auto.is_auto_generated());
+        assert!(auto.is_auto_generated(DEFAULT_AUTO_SUFFIX));
+        assert!(!auto.is_auto_generated("_TH"));
+        assert!(!auto.is_auto_generated(""));
 
         let manual = TalentLoadout::new("My Build".to_string(), "test".to_string());
-        assert!(!manual.is_auto_generated());
+        assert!(!manual.is_auto_generated(DEFAULT_AUTO_SUFFIX));
+    }
+
+    #[test]
+    fn test_custom_and_legacy_suffixes() {
+        let mut manager = LuaTalentManager::new();
+        for name in ["R-heroic-sikran_TH", "R-heroic-sikran_ARCT", "R-heroic-sikran_OLD", "My Build"] {
+            manager.add_talent(
+                "WARRIOR".to_string(),
+                1,
+                TalentLoadout::new(name.to_string(), "warrior/arms/X".to_string()),
+            );
+        }
+
+        // Without legacy suffixes only the configured suffix is cleaned
+        manager.set_auto_suffixes(AutoSuffixes::new("_TH", Vec::new()));
+        let mut current_only = manager.clone();
+        current_only.remove_auto_generated("WARRIOR", 1);
+        let names: Vec<_> = current_only
+            .get_spec_talents("WARRIOR", 1)
+            .unwrap()
+            .iter()
+            .map(|t| t.name.as_str())
+            .collect();
+        assert_eq!(names, vec!["R-heroic-sikran_ARCT", "R-heroic-sikran_OLD", "My Build"]);
+
+        manager.set_auto_suffixes(AutoSuffixes::new("_TH", vec!["_ARCT".to_string(), "_OLD".to_string()]));
+        manager.remove_all_auto_generated();
+        let names: Vec<_> = manager
+            .get_spec_talents("WARRIOR", 1)
+            .unwrap()
+            .iter()
+            .map(|t| t.name.as_str())
+            .collect();
+        assert_eq!(names, vec!["My Build"]);
     }
 
     #[test]
     fn test_remove_auto_generated() {
         let lua = create_test_lua();
-        let mut manager = LuaTalentManager::parse_lua(&lua).unwrap();
+        let mut manager = LuaTalentManager::parse_lua(&lua, None).unwrap();
 
         // Remove auto-generated from warrior arms
         manager.remove_auto_generated("WARRIOR", 1);
@@ -372,6 +2474,144 @@ mod tests {
         assert_eq!(arms_talents[0].name, "My Arms Build");
     }
 
+    #[test]
+    fn test_matches_pattern() {
+        assert!(matches_pattern("R-*-sikran_ARCT", "R-heroic-sikran_ARCT"));
+        assert!(matches_pattern("M+-*", "M+-ara-kara_ARCT"));
+        assert!(matches_pattern("*", "anything"));
+        assert!(matches_pattern("R-heroic-sikran_ARCT", "R-heroic-sikran_ARCT"));
+        assert!(!matches_pattern("R-heroic-sikran", "R-heroic-sikran_ARCT"));
+        assert!(!matches_pattern("R-*-sikran_ARCT", "R-heroic-queen-ansurek_ARCT"));
+        assert!(!matches_pattern("M+-*", "R-heroic-sikran_ARCT"));
+    }
+
+    #[test]
+    fn test_previous_versions_are_kept_and_pruned() {
+        let mut manager = LuaTalentManager::parse_lua(&create_test_lua(), None).unwrap();
+        let names = |manager: &LuaTalentManager| -> Vec<String> {
+            manager.get_spec_talents("WARRIOR", 1).unwrap().iter().map(|t| t.name.clone()).collect()
+        };
+        let fetch = |manager: &mut LuaTalentManager, text: &str| {
+            manager.upsert_talent(
+                "WARRIOR".to_string(),
+                1,
+                TalentLoadout::new("R-heroic-sikran_ARCT".to_string(), text.to_string()),
+            );
+        };
+        let today = |month, day| NaiveDate::from_ymd_opt(2026, month, day).unwrap();
+
+        let previous = TalentLoadout::new("R-heroic-sikran_ARCT~0614".to_string(), String::new());
+        assert!(previous.is_auto_generated(DEFAULT_AUTO_SUFFIX) && previous.is_previous_version());
+        assert!(!TalentLoadout::new("Build~0614".to_string(), String::new()).is_auto_generated(DEFAULT_AUTO_SUFFIX));
+        assert!(!TalentLoadout::new("R-heroic-sikran_ARCT~v2".to_string(), String::new()).is_previous_version());
+
+        // An unchanged build doesn't keep a copy
+        manager.archive_auto_generated("WARRIOR", 1, "0607");
+        fetch(&mut manager, "warrior/arms/XYZ789");
+        assert_eq!(manager.prune_previous_versions("WARRIOR", 1, 2, today(6, 7)), 1);
+        assert_eq!(names(&manager), ["My Arms Build", "R-heroic-sikran_ARCT"]);
+
+        // Changed builds keep the two most recent versions
+        for (date, text) in [("0614", "NEW1"), ("0621", "NEW2"), ("0628", "NEW3")] {
+            manager.archive_auto_generated("WARRIOR", 1, date);
+            fetch(&mut manager, text);
+            manager.prune_previous_versions("WARRIOR", 1, 2, today(6, date[2..].parse().unwrap()));
+        }
+        let mut kept = names(&manager);
+        kept.sort();
+        assert_eq!(
+            kept,
+            ["My Arms Build", "R-heroic-sikran_ARCT", "R-heroic-sikran_ARCT~0621", "R-heroic-sikran_ARCT~0628"]
+        );
+
+        // A second run on the same day replaces that day's version
+        manager.archive_auto_generated("WARRIOR", 1, "0628");
+        fetch(&mut manager, "NEW4");
+        assert_eq!(manager.get_spec_talents("WARRIOR", 1).unwrap().len(), 4);
+        manager.prune_previous_versions("WARRIOR", 1, 2, today(6, 28));
+        let mut kept = names(&manager);
+        kept.sort();
+        assert_eq!(
+            kept,
+            ["My Arms Build", "R-heroic-sikran_ARCT", "R-heroic-sikran_ARCT~0621", "R-heroic-sikran_ARCT~0628"]
+        );
+        let talents = manager.get_spec_talents("WARRIOR", 1).unwrap();
+        assert_eq!(talents.iter().find(|t| t.name.ends_with("~0628")).unwrap().text, "NEW3");
+
+        // Dates after today are from last year
+        assert_eq!(version_age_days("1231", today(1, 2)), 2);
+        assert_eq!(version_age_days("0101", today(1, 2)), 1);
+
+        // Clearing removes previous versions too
+        manager.remove_auto_generated("WARRIOR", 1);
+        assert_eq!(names(&manager), ["My Arms Build"]);
+    }
+
+    #[test]
+    fn test_remove_matching_only_touches_auto_generated() {
+        let mut manager = LuaTalentManager::parse_lua(&create_test_lua(), None).unwrap();
+        manager.add_talent(
+            "WARRIOR".to_string(),
+            2,
+            TalentLoadout::new("R-mythic-sikran_ARCT".to_string(), "warrior/fury/X".to_string()),
+        );
+        manager.add_talent(
+            "WARRIOR".to_string(),
+            2,
+            TalentLoadout::new("R-mythic-sikran (mine)".to_string(), "warrior/fury/Y".to_string()),
+        );
+
+        // Limited to one spec
+        let mut limited = manager.clone();
+        let removed = limited.remove_matching(Some("WARRIOR"), Some(1), |t| {
+            matches_pattern("R-*-sikran_ARCT", &t.name)
+        });
+        assert_eq!(removed.len(), 1);
+        assert_eq!(limited.get_spec_talents("WARRIOR", 2).unwrap().len(), 3);
+
+        let removed: Vec<_> = manager
+            .remove_by_prefix("R-")
+            .into_iter()
+            .map(|(class, spec, t)| (class, spec, t.name))
+            .collect();
+        assert_eq!(
+            removed,
+            vec![
+                ("WARRIOR".to_string(), 1, "R-heroic-sikran_ARCT".to_string()),
+                ("WARRIOR".to_string(), 2, "R-mythic-sikran_ARCT".to_string()),
+            ]
+        );
+        assert!(manager
+            .get_spec_talents("WARRIOR", 2)
+            .unwrap()
+            .iter()
+            .any(|t| t.name == "R-mythic-sikran (mine)"));
+        assert!(manager.remove_by_prefix("My").is_empty());
+    }
+
+    #[test]
+    fn test_stats() {
+        let mut manager = LuaTalentManager::parse_lua(&create_test_lua(), None).unwrap();
+        manager.add_talent(
+            "MAGE".to_string(),
+            1,
+            TalentLoadout::new("M+-ara-kara_ARCT".to_string(), "mage/arcane/X".to_string()),
+        );
+
+        let stats = manager.stats();
+
+        assert_eq!((stats.total, stats.manual, stats.auto), (5, 2, 3));
+        let warrior = &stats.classes["WARRIOR"];
+        assert_eq!((warrior.total, warrior.manual, warrior.auto), (3, 2, 1));
+        assert_eq!(warrior.specs[&1], SpecStats { spec: Some("arms"), manual: 1, auto: 1 });
+        assert_eq!(warrior.specs[&2], SpecStats { spec: Some("fury"), manual: 1, auto: 0 });
+        assert_eq!(stats.classes["MAGE"].auto, 2);
+        assert_eq!(
+            stats.identifiers.iter().map(String::as_str).collect::<Vec<_>>(),
+            vec!["M+-ara-kara", "R-heroic-sikran"]
+        );
+    }
+
     #[test]
     fn test_add_talent() {
         let mut manager = LuaTalentManager::new();
@@ -388,6 +2628,47 @@ mod tests {
         assert_eq!(talents[0].name, "R-normal-broodtwister_ARCT");
     }
 
+    #[test]
+    fn test_upsert_talent_replaces_in_place() {
+        let mut manager = LuaTalentManager::parse_lua(&create_test_lua(), None).unwrap();
+        manager.add_talent(
+            "WARRIOR".to_string(),
+            1,
+            TalentLoadout::new("Another Build".to_string(), "warrior/arms/ZZZ".to_string()),
+        );
+
+        let update = |manager: &mut LuaTalentManager| {
+            let builds = [
+                ("R-heroic-sikran_ARCT", "warrior/arms/NEW"),
+                ("M+-ara-kara_ARCT", "warrior/arms/MPLUS"),
+            ];
+            for (name, text) in builds {
+                manager.upsert_talent(
+                    "WARRIOR".to_string(),
+                    1,
+                    TalentLoadout::new(name.to_string(), text.to_string()),
+                );
+            }
+        };
+
+        update(&mut manager);
+        let once = manager.to_lua_string();
+        update(&mut manager);
+        assert_eq!(manager.to_lua_string(), once);
+
+        let arms = manager.get_spec_talents("WARRIOR", 1).unwrap();
+        let names: Vec<_> = arms.iter().map(|t| t.name.as_str()).collect();
+        assert_eq!(names, vec!["My Arms Build", "R-heroic-sikran_ARCT", "Another Build", "M+-ara-kara_ARCT"]);
+        assert_eq!(arms[1].text, "warrior/arms/NEW");
+
+        // An explicit icon replaces the existing one
+        let mut talent = TalentLoadout::new("My Arms Build".to_string(), "warrior/arms/ABC123".to_string());
+        assert_eq!(manager.get_spec_talents("WARRIOR", 1).unwrap()[0].icon, 132355);
+        talent.icon = 1;
+        assert!(manager.upsert_talent("WARRIOR".to_string(), 1, talent));
+        assert_eq!(manager.get_spec_talents("WARRIOR", 1).unwrap()[0].icon, 1);
+    }
+
     #[test]
     fn test_to_lua_string() {
         let mut manager = LuaTalentManager::new();
@@ -409,4 +2690,713 @@ mod tests {
         assert!(lua_string.contains("warrior/arms/ABC"));
         assert!(lua_string.contains("[\"OPTION\"]"));
     }
+
+    #[test]
+    fn test_rename_talent() {
+        let lua = format!(
+            "{}\nTalentLoadoutExGUI = {{ [\"WARRIOR\"] = {{ [1] = {{ \"R-heroic-sikran_ARCT\", \"My Arms Build\" }} }} }}",
+            create_test_lua()
+        );
+        let mut manager = LuaTalentManager::parse_lua(&lua, None).unwrap();
+        let before = manager.get_spec_talents("WARRIOR", 1).unwrap().clone();
+
+        manager.rename_talent("WARRIOR", 1, "R-heroic-sikran_ARCT", "Pinned Sikran").unwrap();
+        assert_eq!(manager.get_gui_order("WARRIOR", 1).unwrap(), vec!["Pinned Sikran", "My Arms Build"]);
+        let after = manager.get_spec_talents("WARRIOR", 1).unwrap();
+        assert_eq!(after.len(), before.len());
+        for (old, new) in before.iter().zip(after) {
+            assert_eq!((old.icon, &old.text), (new.icon, &new.text));
+        }
+        let position = before.iter().position(|t| t.name == "R-heroic-sikran_ARCT").unwrap();
+        assert_eq!(after[position].name, "Pinned Sikran");
+
+        // Missing loadouts and name collisions are errors
+        assert!(manager.rename_talent("WARRIOR", 1, "R-heroic-sikran_ARCT", "Other").is_err());
+        assert!(manager.rename_talent("WARRIOR", 1, "Pinned Sikran", &before[0].name).is_err());
+        assert!(manager.rename_talent("WARRIOR", 4, "Pinned Sikran", "Other").is_err());
+        assert!(manager.rename_talent("WARRIOR", 1, "Pinned Sikran", "").is_err());
+    }
+
+    fn loadout_ref(class_name: &str, spec_index: u8, name: &str) -> LoadoutRef {
+        LoadoutRef {
+            class_name: class_name.to_string(),
+            spec_index,
+            name: name.to_string(),
+        }
+    }
+
+    #[test]
+    fn test_move_talent() {
+        let mut manager = LuaTalentManager::parse_lua(&create_test_lua(), None).unwrap();
+
+        manager
+            .move_talent(&loadout_ref("WARRIOR", 1, "My Arms Build"), &loadout_ref("WARRIOR", 2, "Arms As Fury"))
+            .unwrap();
+        assert_eq!(manager.get_spec_talents("WARRIOR", 1).unwrap().len(), 1);
+        let moved = manager.get_spec_talents("WARRIOR", 2).unwrap().last().unwrap();
+        assert_eq!((moved.name.as_str(), moved.icon, moved.text.as_str()), ("Arms As Fury", 132355, "warrior/arms/ABC123"));
+
+        // Missing source, taken destination and invalid spec
+        let missing = loadout_ref("WARRIOR", 1, "My Arms Build");
+        assert!(manager.move_talent(&missing, &loadout_ref("WARRIOR", 2, "Other")).is_err());
+        let fury = loadout_ref("WARRIOR", 2, "Arms As Fury");
+        assert!(manager.move_talent(&fury, &loadout_ref("WARRIOR", 1, "R-heroic-sikran_ARCT")).is_err());
+        assert!(manager.move_talent(&fury, &loadout_ref("WARRIOR", 4, "Other")).is_err());
+        assert_eq!(manager.stats().total, 4);
+    }
+
+    #[test]
+    fn test_copy_talent() {
+        let mut manager = LuaTalentManager::parse_lua(&create_test_lua(), None).unwrap();
+        manager.set_auto_suffixes(AutoSuffixes::new("_ARCT", vec!["_OLD".to_string()]));
+        let source = loadout_ref("WARRIOR", 1, "R-heroic-sikran_ARCT");
+
+        let name = manager.copy_talent(&source, &loadout_ref("WARRIOR", 2, "R-heroic-sikran_ARCT"), SuffixOnCopy::Strip).unwrap();
+        assert_eq!(name, "R-heroic-sikran");
+        let name = manager.copy_talent(&source, &loadout_ref("WARRIOR", 2, "Sikran_OLD"), SuffixOnCopy::Retag).unwrap();
+        assert_eq!(name, "Sikran_ARCT");
+        let name = manager.copy_talent(&source, &loadout_ref("WARRIOR", 3, "Sikran_OLD"), SuffixOnCopy::Keep).unwrap();
+        assert_eq!(name, "Sikran_OLD");
+
+        // The source stays, the copies share its talent string
+        assert_eq!(manager.get_spec_talents("WARRIOR", 1).unwrap().len(), 2);
+        assert!(manager.get_spec_talents("WARRIOR", 2).unwrap().iter().any(|t| t.text == "warrior/arms/XYZ789"));
+
+        // Missing source and duplicate destination
+        assert!(manager.copy_talent(&loadout_ref("WARRIOR", 1, "Nope"), &loadout_ref("WARRIOR", 2, "X"), SuffixOnCopy::Keep).is_err());
+        assert!(manager.copy_talent(&source, &loadout_ref("WARRIOR", 2, "Sikran"), SuffixOnCopy::Retag).is_err());
+        assert!(manager.copy_talent(&source, &loadout_ref("ROGUE", 4, "X"), SuffixOnCopy::Keep).is_err());
+    }
+
+    #[test]
+    fn test_with_spec_icon() {
+        let talent = TalentLoadout::with_spec_icon("Frost".to_string(), "ABC".to_string(), WowClass::Mage, 3);
+        assert_eq!(talent.icon, 135846);
+        assert_eq!(TalentLoadout::with_spec_icon("X".to_string(), "ABC".to_string(), WowClass::Mage, 9).icon, 0);
+    }
+
+    #[test]
+    fn test_lua_escape_string() {
+        assert_eq!(lua_escape_string(r#"My "Big" AoE\Build"#), r#"My \"Big\" AoE\\Build"#);
+        assert_eq!(lua_escape_string("line1\nline2\ttab"), r#"line1\nline2\ttab"#);
+        assert_eq!(lua_escape_string("bell\u{7}"), r#"bell\007"#);
+        assert_eq!(lua_escape_string("Überbuild 火"), "Überbuild 火");
+    }
+
+    #[test]
+    fn test_lua_unescape_string() {
+        assert_eq!(lua_unescape_string(r#"My \"Big\" AoE\\Build"#), r#"My "Big" AoE\Build"#);
+        assert_eq!(lua_unescape_string(r#"a\nb\tc\'d"#), "a\nb\tc'd");
+        assert_eq!(lua_unescape_string(r#"\072\105"#), "Hi");
+        assert_eq!(lua_unescape_string(r#"\x48\x69"#), "Hi");
+        assert_eq!(lua_unescape_string(r#"\u{706B}"#), "火");
+        // Decimal byte escapes of a multi-byte UTF-8 sequence
+        assert_eq!(lua_unescape_string(r#"\195\156ber"#), "Über");
+    }
+
+    #[test]
+    fn test_special_characters_round_trip() {
+        let names = [
+            r#"My "Big" AoE\Build"#,
+            "Über AoE – Feuer 火",
+            "multi\nline\r\nname",
+            "control\u{1}char",
+        ];
+
+        let mut manager = LuaTalentManager::new();
+        for name in names {
+            manager.add_talent(
+                "WARRIOR".to_string(),
+                1,
+                TalentLoadout::new(name.to_string(), format!("{}\\text", name)),
+            );
+        }
+
+        let parsed = LuaTalentManager::parse_lua(&manager.to_lua_string(), None).unwrap();
+        let talents = parsed.get_spec_talents("WARRIOR", 1).unwrap();
+        assert_eq!(talents.len(), names.len());
+        for (talent, name) in talents.iter().zip(names) {
+            assert_eq!(talent.name, name);
+            assert_eq!(talent.text, format!("{}\\text", name));
+        }
+    }
+
+    #[test]
+    fn test_non_ascii_names_round_trip() {
+        let content = include_str!("../tests/fixtures/TalentLoadoutsEx_unicode.lua");
+        let manager = LuaTalentManager::parse_lua(content, None).unwrap();
+
+        // Raw UTF-8 and decimal byte escapes decode to the same characters
+        let expected = [
+            ("Überbuild Frost – Feuer", "mage/frost/ÄÖÜ"),
+            ("冰霜 法师 大秘境", "mage/frost/CJK"),
+            ("Mächtig \"AoE\"", "mage/frost/名"),
+            ("Gemischt äöü 名字", "mage/frost/MIX"),
+        ];
+        let talents: Vec<_> = manager
+            .get_spec_talents("MAGE", 3)
+            .unwrap()
+            .iter()
+            .map(|t| (t.name.as_str(), t.text.as_str()))
+            .collect();
+        assert_eq!(talents, expected);
+
+        // Escapes are written back as raw UTF-8, and further writes are stable
+        let output = manager.to_lua_string();
+        assert!(output.contains("[\"name\"] = \"Mächtig \\\"AoE\\\"\""));
+        assert!(!output.contains("\\195"));
+        let reparsed = LuaTalentManager::parse_lua(&output, None).unwrap();
+        assert!(manager.diff(&reparsed).is_empty());
+        assert_eq!(reparsed.to_lua_string(), output);
+
+        // The recovering parser decodes them the same way
+        let (recovered, warnings) = recovery::recover_loadouts(content, String::new());
+        assert!(warnings.skipped_lines.is_empty());
+        assert_eq!(recovered.get_spec_talents("MAGE", 3), manager.get_spec_talents("MAGE", 3));
+
+        // Invalid UTF-8 byte sequences can't be represented and become U+FFFD
+        assert_eq!(lua_unescape_string(r#"ab\195"#), "ab\u{FFFD}");
+    }
+
+    #[test]
+    fn test_option_table_round_trip() {
+        let lua = create_test_lua().replace(
+            "[\"OPTION\"] = { [\"IsEnabledPvp\"] = false },",
+            "[\"OPTION\"] = { [\"IsEnabledPvp\"] = true, [\"FrameScale\"] = 1.25, ShowMinimap = false },",
+        );
+        let mut manager = LuaTalentManager::parse_lua(&lua, None).unwrap();
+        assert_eq!(manager.option("IsEnabledPvp"), Some("true"));
+
+        // Update the file like the orchestrator does
+        manager.remove_auto_generated("WARRIOR", 1);
+        manager.add_talent(
+            "WARRIOR".to_string(),
+            1,
+            TalentLoadout::new("R-mythic-sikran_ARCT".to_string(), "warrior/arms/NEW".to_string()),
+        );
+
+        let reparsed = LuaTalentManager::parse_lua(&manager.to_lua_string(), None).unwrap();
+        assert_eq!(reparsed.option("IsEnabledPvp"), Some("true"));
+        assert_eq!(reparsed.option("FrameScale"), Some("1.25"));
+        assert_eq!(reparsed.option("ShowMinimap"), Some("false"));
+
+        // New files get the default
+        let fresh = LuaTalentManager::parse_lua(&LuaTalentManager::new().to_lua_string(), None).unwrap();
+        assert_eq!(fresh.option("IsEnabledPvp"), Some("false"));
+    }
+
+    #[test]
+    fn test_gui_table_round_trip() {
+        let lua = format!(
+            r#"{}
+TalentLoadoutExGUI = {{
+  ["WARRIOR"] = {{
+    [1] = {{ "R-heroic-sikran_ARCT", "Deleted Build", "My Arms Build" }},
+  }},
+  ["ROGUE"] = {{
+    [1] = {{ "Gone_ARCT" }},
+  }},
+  ["MAGE"] = {{
+    [3] = {{ "M+-ara-kara_ARCT", {{ ["collapsed"] = true }} }},
+  }},
+  ["point"] = "CENTER",
+}}"#,
+            create_test_lua()
+        );
+        let mut manager = LuaTalentManager::parse_lua(&lua, None).unwrap();
+
+        // Stale names are dropped, existing order is kept
+        assert_eq!(
+            manager.get_gui_order("WARRIOR", 1).unwrap(),
+            vec!["R-heroic-sikran_ARCT", "My Arms Build"]
+        );
+
+        // Replace the auto-generated build and add a new one
+        manager.remove_auto_generated("WARRIOR", 1);
+        manager.add_talent(
+            "WARRIOR".to_string(),
+            1,
+            TalentLoadout::new("R-mythic-sikran_ARCT".to_string(), "warrior/arms/NEW".to_string()),
+        );
+
+        let written = manager.to_lua_string();
+        let reparsed = LuaTalentManager::parse_lua(&written, None).unwrap();
+        let gui = reparsed.gui.as_ref().unwrap();
+
+        assert_eq!(
+            gui.order["WARRIOR"][&1],
+            vec!["My Arms Build", "R-mythic-sikran_ARCT"]
+        );
+        assert_eq!(gui.order["WARRIOR"][&2], vec!["My Fury Build"]);
+        assert_eq!(gui.order["MAGE"][&3], vec!["M+-ara-kara_ARCT"]);
+        assert!(!gui.order.contains_key("ROGUE"));
+        assert_eq!(gui.extra_fields, vec![(Some("point".to_string()), r#"["point"] = "CENTER""#.to_string())]);
+
+        // The MAGE order it couldn't parse isn't written next to the one rebuilt from the loadouts
+        let (_, gui_source) = written.split_once("TalentLoadoutExGUI = {").unwrap();
+        assert_eq!(gui_source.matches("[\"MAGE\"]").count(), 1);
+
+        // Every GUI entry refers to an existing loadout
+        for (class_name, specs) in &gui.order {
+            for (spec_index, names) in specs {
+                let talents = reparsed.get_spec_talents(class_name, *spec_index).unwrap();
+                assert_eq!(names.len(), talents.len());
+                assert!(names.iter().all(|n| talents.iter().any(|t| &t.name == n)));
+            }
+        }
+    }
+
+    #[test]
+    fn test_no_gui_table_is_not_written() {
+        let manager = LuaTalentManager::parse_lua(&create_test_lua(), None).unwrap();
+        assert!(manager.get_gui_order("WARRIOR", 1).is_none());
+        assert!(!manager.to_lua_string().contains("TalentLoadoutExGUI"));
+    }
+
+    #[test]
+    fn test_merge_talent_names() {
+        assert_eq!(
+            merge_talent_names("R-heroic-sikran_ARCT", "R-heroic-broodtwister_ARCT", DEFAULT_AUTO_SUFFIX),
+            "R-heroic-sikran+broodtwister_ARCT"
+        );
+        assert_eq!(
+            merge_talent_names("R-heroic-sikran+broodtwister_ARCT", "R-heroic-queen-ansurek_ARCT", DEFAULT_AUTO_SUFFIX),
+            "R-heroic-sikran+broodtwister+queen-ansurek_ARCT"
+        );
+        assert_eq!(
+            merge_talent_names("R-heroic-sikran_ARCT", "M+-ara-kara_ARCT", DEFAULT_AUTO_SUFFIX),
+            "R-heroic-sikran+M+-ara-kara_ARCT"
+        );
+    }
+
+    #[test]
+    fn test_dedupe_spec() {
+        let mut manager = LuaTalentManager::new();
+        let talents = [
+            ("My Build", "warrior/arms/SAME"),
+            ("R-heroic-sikran_ARCT", "warrior/arms/SAME"),
+            ("R-heroic-broodtwister_ARCT", "warrior/arms/SAME"),
+            ("R-heroic-ulgrax_ARCT", "warrior/arms/OTHER"),
+            ("R-heroic-queen-ansurek_ARCT", "warrior/arms/SAME"),
+        ];
+        for (name, text) in talents {
+            manager.add_talent(
+                "WARRIOR".to_string(),
+                1,
+                TalentLoadout::new(name.to_string(), text.to_string()),
+            );
+        }
+
+        assert_eq!(manager.dedupe_spec("WARRIOR", 1, DedupePolicy::KeepAll), 0);
+        assert_eq!(manager.get_spec_talents("WARRIOR", 1).unwrap().len(), 5);
+
+        assert_eq!(manager.dedupe_spec("WARRIOR", 1, DedupePolicy::MergeNames), 2);
+
+        let names: Vec<_> = manager
+            .get_spec_talents("WARRIOR", 1)
+            .unwrap()
+            .iter()
+            .map(|t| t.name.as_str())
+            .collect();
+        assert_eq!(
+            names,
+            vec![
+                "My Build",
+                "R-heroic-sikran+broodtwister+queen-ansurek_ARCT",
+                "R-heroic-ulgrax_ARCT",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_collapse_raid_builds() {
+        let mut manager = LuaTalentManager::new();
+        let talents = [
+            ("My Build", "SAME"),
+            ("R-heroic-ulgrax_ARCT", "SAME"),
+            ("R-heroic-broodtwister_ARCT", "OTHER"),
+            ("R-heroic-sikran_ARCT", "SAME"),
+            ("R-heroic-rashanan_ARCT", "OTHER"),
+            ("R-heroic-queen-ansurek_ARCT", "OUTLIER"),
+            ("R-heroic-kyveza_ARCT", "SAME"),
+            ("R-mythic-ulgrax_ARCT", "SAME"),
+            ("M+-ara-kara_ARCT", "SAME"),
+        ];
+        for (name, text) in talents {
+            manager.add_talent("WARRIOR".to_string(), 1, TalentLoadout::new(name.to_string(), text.to_string()));
+        }
+        let fetched: Vec<String> = talents[1..].iter().map(|(name, _)| name.to_string()).collect();
+
+        let collapsed = manager.collapse_raid_builds("WARRIOR", 1, &fetched);
+        assert_eq!(
+            collapsed,
+            [
+                CollapsedLoadout {
+                    name: "R-heroic-general_ARCT".to_string(),
+                    members: vec![
+                        "R-heroic-ulgrax_ARCT".to_string(),
+                        "R-heroic-sikran_ARCT".to_string(),
+                        "R-heroic-kyveza_ARCT".to_string(),
+                    ],
+                },
+                CollapsedLoadout {
+                    name: "R-heroic-broodtwister+rashanan_ARCT".to_string(),
+                    members: vec!["R-heroic-broodtwister_ARCT".to_string(), "R-heroic-rashanan_ARCT".to_string()],
+                },
+            ]
+        );
+
+        // Manual, Mythic+ and unmatched builds stay as they are
+        let names: Vec<_> = manager.get_spec_talents("WARRIOR", 1).unwrap().iter().map(|t| t.name.as_str()).collect();
+        assert_eq!(
+            names,
+            [
+                "My Build",
+                "R-heroic-general_ARCT",
+                "R-heroic-broodtwister+rashanan_ARCT",
+                "R-heroic-queen-ansurek_ARCT",
+                "R-mythic-ulgrax_ARCT",
+                "M+-ara-kara_ARCT",
+            ]
+        );
+
+        // Names over the limit list the first boss and a count
+        let bosses = ["nexus-princess-kyveza", "the-silken-court", "broodtwister-ovinax"].map(str::to_string);
+        assert_eq!(
+            collapsed_name(RaidDifficulty::Mythic, None, &bosses, "_ARCT"),
+            "R-mythic-nexus-princess-kyveza+2_ARCT"
+        );
+    }
+
+    #[test]
+    fn test_sort_spec_by_content() {
+        let mut manager = LuaTalentManager::new();
+        let names = [
+            "M+-city-of-threads_ARCT",
+            "Manual 1",
+            "R-heroic-queen-ansurek_ARCT",
+            "M+-ara-kara_ARCT",
+            "R-normal-sikran_ARCT",
+            "R-heroic-broodtwister_ARCT",
+            "Manual 2",
+            "R-heroic-sikran_ARCT",
+            "R-normal-queen-ansurek_ARCT",
+        ];
+        for name in names {
+            manager.add_talent(
+                "WARRIOR".to_string(),
+                1,
+                TalentLoadout::new(name.to_string(), "warrior/arms/X".to_string()),
+            );
+        }
+
+        let encounter_order = vec!["broodtwister".to_string(), "sikran".to_string(), "queen-ansurek".to_string()];
+        manager.sort_spec("WARRIOR", 1, &encounter_order);
+
+        let output = manager.to_lua_string();
+        let positions: Vec<_> = [
+            "Manual 1",
+            "Manual 2",
+            "R-normal-sikran_ARCT",
+            "R-normal-queen-ansurek_ARCT",
+            "R-heroic-broodtwister_ARCT",
+            "R-heroic-sikran_ARCT",
+            "R-heroic-queen-ansurek_ARCT",
+            "M+-ara-kara_ARCT",
+            "M+-city-of-threads_ARCT",
+        ]
+        .iter()
+        .map(|name| output.find(&format!("\"{}\"", name)).unwrap())
+        .collect();
+
+        assert!(positions.windows(2).all(|w| w[0] < w[1]));
+    }
+
+    #[test]
+    fn test_enforce_cap() {
+        let mut manager = LuaTalentManager::new();
+        let names = [
+            "M+-city-of-threads_ARCT",
+            "Manual 1",
+            "R-mythic-sikran_ARCT",
+            "M+-ara-kara_ARCT",
+            "R-heroic-sikran_ARCT",
+            "R-normal-sikran_ARCT",
+            "Manual 2",
+            "R-normal-broodtwister_ARCT",
+        ];
+        for name in names {
+            manager.add_talent(
+                "WARRIOR".to_string(),
+                1,
+                TalentLoadout::new(name.to_string(), "warrior/arms/X".to_string()),
+            );
+        }
+
+        assert!(manager.enforce_cap("WARRIOR", 1, 8).is_empty());
+
+        let evicted: Vec<_> = manager
+            .enforce_cap("WARRIOR", 1, 4)
+            .into_iter()
+            .map(|t| t.name)
+            .collect();
+        assert_eq!(
+            evicted,
+            vec![
+                "R-mythic-sikran_ARCT",
+                "R-heroic-sikran_ARCT",
+                "R-normal-sikran_ARCT",
+                "R-normal-broodtwister_ARCT",
+            ]
+        );
+
+        let remaining: Vec<_> = manager
+            .get_spec_talents("WARRIOR", 1)
+            .unwrap()
+            .iter()
+            .map(|t| t.name.as_str())
+            .collect();
+        assert_eq!(
+            remaining,
+            vec!["M+-city-of-threads_ARCT", "Manual 1", "M+-ara-kara_ARCT", "Manual 2"]
+        );
+
+        // Manual loadouts survive even if they alone exceed the cap
+        let evicted = manager.enforce_cap("WARRIOR", 1, 1);
+        assert_eq!(evicted.len(), 2);
+        assert_eq!(manager.get_spec_talents("WARRIOR", 1).unwrap().len(), 2);
+    }
+
+    #[test]
+    fn test_enforce_cap_with_duplicate_names() {
+        let mut manager = LuaTalentManager::new();
+        for (name, text) in [
+            ("R-normal-sikran_ARCT", "A"),
+            ("M+-ara-kara_ARCT", "B"),
+            ("R-normal-sikran_ARCT", "C"),
+        ] {
+            manager.add_talent("WARRIOR".to_string(), 1, TalentLoadout::new(name.to_string(), text.to_string()));
+        }
+
+        let evicted = manager.enforce_cap("WARRIOR", 1, 2);
+        assert_eq!(evicted.len(), 1);
+        assert_eq!(evicted[0].text, "A");
+        let remaining = manager.get_spec_talents("WARRIOR", 1).unwrap();
+        assert_eq!(remaining.iter().map(|t| t.text.as_str()).collect::<Vec<_>>(), ["B", "C"]);
+    }
+
+    fn merge_fixture() -> (LuaTalentManager, LuaTalentManager) {
+        let existing = LuaTalentManager::parse_lua(&create_test_lua(), None).unwrap();
+
+        let mut other = LuaTalentManager::new();
+        // Same name and text as an existing loadout
+        other.add_talent(
+            "WARRIOR".to_string(),
+            1,
+            TalentLoadout::new("My Arms Build".to_string(), "warrior/arms/ABC123".to_string()),
+        );
+        // Same name, different text
+        other.add_talent(
+            "WARRIOR".to_string(),
+            2,
+            TalentLoadout::new("My Fury Build".to_string(), "warrior/fury/CHANGED".to_string()),
+        );
+        // Same name as a WARRIOR loadout, but in another class: not a collision
+        other.add_talent(
+            "MAGE".to_string(),
+            1,
+            TalentLoadout::new("My Fury Build".to_string(), "mage/arcane/XYZ".to_string()),
+        );
+        // Same name as a WARRIOR spec 1 loadout, but in spec 3: not a collision
+        other.add_talent(
+            "WARRIOR".to_string(),
+            3,
+            TalentLoadout::new("My Arms Build".to_string(), "warrior/protection/XYZ".to_string()),
+        );
+
+        (existing, other)
+    }
+
+    #[test]
+    fn test_merge_keep_existing() {
+        let (mut manager, other) = merge_fixture();
+        let summary = manager.merge_from(other, MergeStrategy::KeepExisting);
+
+        assert_eq!(
+            summary,
+            MergeSummary { added: 2, replaced: 0, renamed: 0, skipped: 2 }
+        );
+        assert_eq!(manager.get_spec_talents("WARRIOR", 1).unwrap().len(), 2);
+        assert_eq!(manager.get_spec_talents("WARRIOR", 2).unwrap()[0].text, "warrior/fury/DEF456");
+        assert_eq!(manager.get_spec_talents("WARRIOR", 3).unwrap()[0].name, "My Arms Build");
+        assert_eq!(manager.get_spec_talents("MAGE", 1).unwrap()[0].name, "My Fury Build");
+    }
+
+    #[test]
+    fn test_merge_prefer_other() {
+        let (mut manager, other) = merge_fixture();
+        let summary = manager.merge_from(other, MergeStrategy::PreferOther);
+
+        assert_eq!(
+            summary,
+            MergeSummary { added: 2, replaced: 1, renamed: 0, skipped: 1 }
+        );
+        let fury = manager.get_spec_talents("WARRIOR", 2).unwrap();
+        assert_eq!(fury.len(), 1);
+        assert_eq!(fury[0].text, "warrior/fury/CHANGED");
+    }
+
+    #[test]
+    fn test_merge_rename_with_suffix() {
+        let (mut manager, mut other) = merge_fixture();
+        other.add_talent(
+            "WARRIOR".to_string(),
+            2,
+            TalentLoadout::new("My Fury Build".to_string(), "warrior/fury/THIRD".to_string()),
+        );
+        let summary = manager.merge_from(other, MergeStrategy::RenameWithSuffix);
+
+        assert_eq!(
+            summary,
+            MergeSummary { added: 2, replaced: 0, renamed: 2, skipped: 1 }
+        );
+        let names: Vec<_> = manager
+            .get_spec_talents("WARRIOR", 2)
+            .unwrap()
+            .iter()
+            .map(|t| (t.name.as_str(), t.text.as_str()))
+            .collect();
+        assert_eq!(
+            names,
+            vec![
+                ("My Fury Build", "warrior/fury/DEF456"),
+                ("My Fury Build (2)", "warrior/fury/CHANGED"),
+                ("My Fury Build (3)", "warrior/fury/THIRD"),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_diff() {
+        let before = LuaTalentManager::parse_lua(&create_test_lua(), None).unwrap();
+        let mut after = before.clone();
+        assert!(before.diff(&after).is_empty());
+
+        // Same _ARCT name with a new talent string
+        after.remove_auto_generated("WARRIOR", 1);
+        after.add_talent(
+            "WARRIOR".to_string(),
+            1,
+            TalentLoadout::new("R-heroic-sikran_ARCT".to_string(), "warrior/arms/NEW".to_string()),
+        );
+        after.add_talent(
+            "WARRIOR".to_string(),
+            1,
+            TalentLoadout::new("R-heroic-ulgrax_ARCT".to_string(), "warrior/arms/ULG".to_string()),
+        );
+        after.remove_auto_generated("MAGE", 3);
+
+        let diff = before.diff(&after);
+
+        assert_eq!(
+            diff.changed,
+            vec![ChangedLoadout {
+                class_name: "WARRIOR".to_string(),
+                spec_index: 1,
+                spec: Some("arms"),
+                name: "R-heroic-sikran_ARCT".to_string(),
+                old_text: "warrior/arms/XYZ789".to_string(),
+                new_text: "warrior/arms/NEW".to_string(),
+            }]
+        );
+        assert_eq!(diff.added.len(), 1);
+        assert_eq!(diff.added[0].name, "R-heroic-ulgrax_ARCT");
+        assert_eq!(diff.removed.len(), 1);
+        assert_eq!(diff.removed[0].class_name, "MAGE");
+        assert_eq!(diff.removed[0].name, "M+-ara-kara_ARCT");
+    }
+
+    #[test]
+    fn test_apply_diff_keeps_manual_loadouts() {
+        let before = LuaTalentManager::parse_lua(&create_test_lua(), None).unwrap();
+        let mut after = before.clone();
+        after.remove_auto_generated("WARRIOR", 1);
+        after.add_talent(
+            "WARRIOR".to_string(),
+            1,
+            TalentLoadout::new("R-heroic-sikran_ARCT".to_string(), "warrior/arms/NEW".to_string()),
+        );
+        after.remove_auto_generated("MAGE", 3);
+
+        // A mirrored file with its own manual loadout
+        let manual = TalentLoadout::new("PTR testing".to_string(), "warrior/arms/PTR".to_string());
+        let mut mirror = before.clone();
+        mirror.add_talent("WARRIOR".to_string(), 1, manual.clone());
+        let touched = mirror.apply_diff(&before.diff(&after), &after);
+        assert_eq!(touched.into_iter().collect::<Vec<_>>(), [("MAGE".to_string(), 3), ("WARRIOR".to_string(), 1)]);
+
+        let mut expected = after.clone();
+        expected.add_talent("WARRIOR".to_string(), 1, manual);
+        assert!(mirror.diff(&expected).is_empty());
+    }
+
+    #[test]
+    fn test_json_round_trip() {
+        let manager = LuaTalentManager::parse_lua(&create_test_lua(), None).unwrap();
+        let json = manager.to_json().unwrap();
+
+        let imported = LuaTalentManager::from_json(&json).unwrap();
+        assert!(manager.diff(&imported).is_empty());
+        assert_eq!(imported.get_spec_talents("WARRIOR", 1).unwrap()[0].icon, 132355);
+        assert!(imported.get_spec_talents("MAGE", 3).unwrap()[0].is_auto_generated(DEFAULT_AUTO_SUFFIX));
+
+        // Output is stable
+        assert_eq!(imported.to_json().unwrap(), json);
+    }
+
+    #[test]
+    fn test_json_structure() {
+        let json = r#"{
+            "version": 1,
+            "classes": {
+                "WARRIOR": {
+                    "1": [ { "icon": 1, "name": "Build", "text": "warrior/arms/ABC" } ]
+                }
+            }
+        }"#;
+
+        let manager = LuaTalentManager::from_json(json).unwrap();
+        let talents = manager.get_spec_talents("WARRIOR", 1).unwrap();
+        assert_eq!(
+            talents,
+            &vec![TalentLoadout {
+                icon: 1,
+                name: "Build".to_string(),
+                text: "warrior/arms/ABC".to_string(),
+            }]
+        );
+    }
+
+    #[test]
+    fn test_json_import_validation() {
+        // Wrong version
+        assert!(LuaTalentManager::from_json(r#"{ "version": 2, "classes": {} }"#).is_err());
+        // Unknown field
+        assert!(LuaTalentManager::from_json(r#"{ "version": 1, "classes": {}, "extra": 1 }"#).is_err());
+        // Non-numeric spec key
+        assert!(LuaTalentManager::from_json(
+            r#"{ "version": 1, "classes": { "WARRIOR": { "arms": [] } } }"#
+        )
+        .is_err());
+        // Missing loadout name
+        assert!(LuaTalentManager::from_json(
+            r#"{ "version": 1, "classes": { "WARRIOR": { "1": [ { "text": "x" } ] } } }"#
+        )
+        .is_err());
+        // Empty loadout name
+        assert!(LuaTalentManager::from_json(
+            r#"{ "version": 1, "classes": { "WARRIOR": { "1": [ { "name": "", "text": "x" } ] } } }"#
+        )
+        .is_err());
+    }
 }
@@ -0,0 +1,266 @@
+use crate::atomic_write::write_atomic;
+use crate::lua_talent::LuaTalentManager;
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+
+/// Name of the sidecar file written next to the talent file
+pub const METADATA_FILE_NAME: &str = "talent-heron.meta.json";
+
+/// Where a fetched build came from
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct LoadoutMetadata {
+    /// Archon.gg page the build was fetched from
+    pub source_url: String,
+    /// When the build was fetched (RFC 3339)
+    pub fetched_at: String,
+    /// Share of players using the build, if Archon reported it
+    #[serde(default)]
+    pub popularity: Option<f64>,
+    /// Hash of the talent string, to tell whether the loadout was changed since
+    pub talent_string_hash: String,
+    /// Fetched loadouts this one replaces because they share its talent string (see
+    /// `Config::collapse_identical`); the source is that of the first
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub collapsed_from: Vec<String>,
+}
+
+impl LoadoutMetadata {
+    /// Metadata for a build fetched now
+    pub fn fetched_now(source_url: impl Into<String>, talent_string: &str) -> Self {
+        Self {
+            source_url: source_url.into(),
+            fetched_at: chrono::Utc::now().to_rfc3339(),
+            popularity: None,
+            talent_string_hash: talent_string_hash(talent_string),
+            collapsed_from: Vec::new(),
+        }
+    }
+}
+
+/// Metadata of a loadout together with the loadout it belongs to
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct LoadoutMetadataEntry {
+    pub class_name: String,
+    pub spec_index: u8,
+    pub name: String,
+    #[serde(flatten)]
+    pub metadata: LoadoutMetadata,
+}
+
+/// Contents of the sidecar file: class name -> spec index -> loadout name -> metadata
+/// Loadout names are only unique within a spec, hence the nesting.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct MetadataStore {
+    #[serde(default)]
+    pub loadouts: BTreeMap<String, BTreeMap<u8, BTreeMap<String, LoadoutMetadata>>>,
+}
+
+/// Sidecar path for a talent file (same directory)
+pub fn sidecar_path(talent_file: &Path) -> PathBuf {
+    talent_file.with_file_name(METADATA_FILE_NAME)
+}
+
+/// Stable 64-bit FNV-1a hash of a talent string, as hex
+pub fn talent_string_hash(talent_string: &str) -> String {
+    let hash = talent_string.bytes().fold(0xcbf29ce484222325u64, |hash, byte| {
+        (hash ^ u64::from(byte)).wrapping_mul(0x100000001b3)
+    });
+    format!("{:016x}", hash)
+}
+
+impl MetadataStore {
+    /// Load the sidecar file, or start empty if it doesn't exist
+    pub fn load(path: impl AsRef<Path>) -> Result<Self> {
+        let path = path.as_ref();
+        if !path.exists() {
+            return Ok(Self::default());
+        }
+
+        let content = std::fs::read_to_string(path).context("Failed to read loadout metadata")?;
+        serde_json::from_str(&content).context("Invalid loadout metadata file")
+    }
+
+    /// Write the sidecar file atomically
+    pub fn save(&self, path: impl AsRef<Path>) -> Result<()> {
+        let json = serde_json::to_string_pretty(self).context("Failed to serialize loadout metadata")?;
+        write_atomic(path.as_ref(), json).context("Failed to write loadout metadata")
+    }
+
+    /// Record (or replace) the metadata of a loadout
+    pub fn record(&mut self, class_name: &str, spec_index: u8, name: &str, metadata: LoadoutMetadata) {
+        self.loadouts
+            .entry(class_name.to_string())
+            .or_default()
+            .entry(spec_index)
+            .or_default()
+            .insert(name.to_string(), metadata);
+    }
+
+    /// Metadata of a loadout, if it was fetched
+    pub fn get(&self, class_name: &str, spec_index: u8, name: &str) -> Option<&LoadoutMetadata> {
+        self.loadouts.get(class_name)?.get(&spec_index)?.get(name)
+    }
+
+    /// Name and metadata of the loadout a fetched loadout was collapsed into (see `collapse`)
+    pub fn collapsed_into(&self, class_name: &str, spec_index: u8, member: &str) -> Option<(&str, &LoadoutMetadata)> {
+        self.loadouts
+            .get(class_name)?
+            .get(&spec_index)?
+            .iter()
+            .find(|(_, metadata)| metadata.collapsed_from.iter().any(|name| name == member))
+            .map(|(name, metadata)| (name.as_str(), metadata))
+    }
+
+    /// Metadata of a fetched loadout, also once it was collapsed into another loadout
+    pub fn fetched(&self, class_name: &str, spec_index: u8, name: &str) -> Option<&LoadoutMetadata> {
+        self.get(class_name, spec_index, name)
+            .or_else(|| self.collapsed_into(class_name, spec_index, name).map(|(_, metadata)| metadata))
+    }
+
+    /// Move the metadata of a renamed loadout to its new name
+    pub fn rename(&mut self, class_name: &str, spec_index: u8, old_name: &str, new_name: &str) {
+        let Some(loadouts) = self.loadouts.get_mut(class_name).and_then(|specs| specs.get_mut(&spec_index)) else {
+            return;
+        };
+        if let Some(metadata) = loadouts.remove(old_name) {
+            loadouts.insert(new_name.to_string(), metadata);
+        }
+    }
+
+    /// Move the metadata of fetched loadouts collapsed into one (see
+    /// `LuaTalentManager::collapse_raid_builds`) to that loadout
+    pub fn collapse(&mut self, class_name: &str, spec_index: u8, name: &str, members: &[String]) {
+        let Some(loadouts) = self.loadouts.get_mut(class_name).and_then(|specs| specs.get_mut(&spec_index)) else {
+            return;
+        };
+        let mut collapsed = None;
+        for member in members {
+            if let Some(metadata) = loadouts.remove(member) {
+                collapsed.get_or_insert(metadata);
+            }
+        }
+        if let Some(mut metadata) = collapsed {
+            metadata.collapsed_from = members.to_vec();
+            loadouts.insert(name.to_string(), metadata);
+        }
+    }
+
+    /// Drop metadata of loadouts that no longer exist or whose talent string changed
+    /// Returns the number of entries removed
+    pub fn reconcile(&mut self, manager: &LuaTalentManager) -> usize {
+        let mut removed = 0;
+
+        for (class_name, specs) in &mut self.loadouts {
+            for (spec_index, loadouts) in specs.iter_mut() {
+                let talents = manager.get_spec_talents(class_name, *spec_index);
+                let before = loadouts.len();
+                loadouts.retain(|name, metadata| {
+                    talents.is_some_and(|talents| {
+                        talents
+                            .iter()
+                            .any(|t| &t.name == name && talent_string_hash(&t.text) == metadata.talent_string_hash)
+                    })
+                });
+                removed += before - loadouts.len();
+            }
+            specs.retain(|_, loadouts| !loadouts.is_empty());
+        }
+        self.loadouts.retain(|_, specs| !specs.is_empty());
+
+        removed
+    }
+
+    /// All metadata entries for loadouts with the given name, in class and spec order
+    pub fn find(&self, name: &str) -> Vec<LoadoutMetadataEntry> {
+        self.loadouts
+            .iter()
+            .flat_map(|(class_name, specs)| {
+                specs.iter().filter_map(move |(spec_index, loadouts)| {
+                    loadouts.get(name).map(|metadata| LoadoutMetadataEntry {
+                        class_name: class_name.clone(),
+                        spec_index: *spec_index,
+                        name: name.to_string(),
+                        metadata: metadata.clone(),
+                    })
+                })
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::lua_talent::TalentLoadout;
+    use crate::test_util::temp_dir;
+
+    #[test]
+    fn test_talent_string_hash_is_stable() {
+        assert_eq!(talent_string_hash(""), "cbf29ce484222325");
+        assert_eq!(talent_string_hash("a"), "af63dc4c8601ec8c");
+        assert_ne!(talent_string_hash("CcEAAA"), talent_string_hash("CcEAAB"));
+    }
+
+    #[test]
+    fn test_record_find_and_reconcile() {
+        let mut manager = LuaTalentManager::new();
+        manager.add_talent("WARRIOR".to_string(), 1, TalentLoadout::new("R-heroic-sikran_ARCT".to_string(), "ABC".to_string()));
+        manager.add_talent("MAGE".to_string(), 3, TalentLoadout::new("R-heroic-sikran_ARCT".to_string(), "DEF".to_string()));
+
+        let mut store = MetadataStore::default();
+        store.record("WARRIOR", 1, "R-heroic-sikran_ARCT", LoadoutMetadata::fetched_now("https://archon.gg/a", "ABC"));
+        store.record("MAGE", 3, "R-heroic-sikran_ARCT", LoadoutMetadata::fetched_now("https://archon.gg/b", "OLD"));
+        store.record("MAGE", 3, "M+-ara-kara_ARCT", LoadoutMetadata::fetched_now("https://archon.gg/c", "GHI"));
+
+        let found = store.find("R-heroic-sikran_ARCT");
+        assert_eq!(found.len(), 2);
+        assert_eq!(found[0].class_name, "MAGE");
+        assert_eq!(found[1].metadata.source_url, "https://archon.gg/a");
+
+        store.rename("MAGE", 3, "M+-ara-kara_ARCT", "Pinned Ara-Kara");
+        assert_eq!(store.find("Pinned Ara-Kara")[0].metadata.source_url, "https://archon.gg/c");
+        assert!(store.find("M+-ara-kara_ARCT").is_empty());
+
+        // The mage build changed since it was recorded and the dungeon build is gone
+        assert_eq!(store.reconcile(&manager), 2);
+        assert_eq!(store.find("R-heroic-sikran_ARCT").len(), 1);
+        assert!(!store.loadouts.contains_key("MAGE"));
+    }
+
+    #[test]
+    fn test_collapse() {
+        let mut store = MetadataStore::default();
+        store.record("WARRIOR", 1, "R-heroic-sikran_ARCT", LoadoutMetadata::fetched_now("https://archon.gg/a", "ABC"));
+        store.record("WARRIOR", 1, "R-heroic-ulgrax_ARCT", LoadoutMetadata::fetched_now("https://archon.gg/b", "ABC"));
+        let members = ["R-heroic-sikran_ARCT".to_string(), "R-heroic-ulgrax_ARCT".to_string()];
+        store.collapse("WARRIOR", 1, "R-heroic-general_ARCT", &members);
+
+        let collapsed = store.get("WARRIOR", 1, "R-heroic-general_ARCT").unwrap();
+        assert_eq!(collapsed.source_url, "https://archon.gg/a");
+        assert_eq!(collapsed.collapsed_from, members);
+        assert!(store.get("WARRIOR", 1, "R-heroic-ulgrax_ARCT").is_none());
+
+        // Its builds are still found under their own names
+        let (name, _) = store.collapsed_into("WARRIOR", 1, "R-heroic-ulgrax_ARCT").unwrap();
+        assert_eq!(name, "R-heroic-general_ARCT");
+        assert_eq!(store.fetched("WARRIOR", 1, "R-heroic-ulgrax_ARCT"), Some(collapsed));
+        assert!(store.fetched("WARRIOR", 1, "R-heroic-broodtwister_ARCT").is_none());
+    }
+
+    #[test]
+    fn test_save_and_load() {
+        let dir = temp_dir("meta");
+        let path = dir.join("metadata.json");
+        assert_eq!(MetadataStore::load(&path).unwrap(), MetadataStore::default());
+
+        let mut store = MetadataStore::default();
+        store.record("WARRIOR", 1, "M+-ara-kara_ARCT", LoadoutMetadata::fetched_now("https://archon.gg/a", "ABC"));
+        store.save(&path).unwrap();
+
+        let json = std::fs::read_to_string(&path).unwrap();
+        assert!(json.contains("\"source_url\": \"https://archon.gg/a\""));
+        assert_eq!(MetadataStore::load(&path).unwrap(), store);
+    }
+}
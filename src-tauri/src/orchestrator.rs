@@ -1,221 +1,1909 @@
-use crate::archon::{ArchonUrlBuilder, MythicPlusTimespan, RaidDifficulty, TalentIdentifier};
-use crate::config::Config;
-use crate::fetcher::ArchonFetcher;
-use crate::lua_talent::{LuaTalentManager, TalentLoadout};
-use crate::wow::WowClass;
+use crate::archon::{ArchonUrlBuilder, MythicPlusTimespan, TalentIdentifier};
+use crate::atomic_write::write_atomic;
+use crate::{backup, backups};
+use crate::config::{Character, ClearScope, Config, ContentWarning};
+use crate::icons;
+use crate::fetcher::{ArchonFetcher, FetchError, TalentFetcher, REQUEST_TIMEOUT, RETRY_TIMEOUT};
+use crate::lua_talent::{
+    AutoSuffixes, CollapsedLoadout, DedupePolicy, LuaTalentManager, TalentDiff, TalentFileFormat, TalentLoadout,
+    TalentStats,
+};
+use crate::metadata::{self, LoadoutMetadata, MetadataStore};
+use crate::plaintext::{self, SpecBuilds};
+use crate::progress::{ItemResult, ProgressEvent, ProgressReporter, ProgressUpdate};
+use crate::recovery::ParseWarnings;
+use crate::talent_loadouts_db;
+use crate::talent_string;
+use crate::warcraft_logs::WarcraftLogsService;
+use crate::wow::{GameFlavor, HeroTalent, WowSpec};
+use crate::wow_scanner::{wow_path_for_talent_file, WowScanner};
 use anyhow::{Context, Result};
+use chrono::{DateTime, Duration, Utc};
 use serde::Serialize;
+use std::collections::HashSet;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicU64, Ordering};
+use tracing::{error, field, info, info_span, warn, Instrument};
+
+/// An auto-generated build removed to stay within the per-spec loadout cap
+#[derive(Debug, Serialize)]
+pub struct EvictedTalent {
+    pub class_name: String,
+    pub spec_index: u8,
+    pub name: String,
+}
+
+/// A fetched build that was not written because its talent string doesn't match the spec
+#[derive(Debug, Serialize)]
+pub struct RejectedTalent {
+    pub class_name: String,
+    pub spec_index: u8,
+    pub name: String,
+    pub reason: String,
+}
+
+/// Content a build is for
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ContentType {
+    Raid,
+    MythicPlus,
+}
+
+/// A fetched build added to the talent file
+#[derive(Debug, Serialize)]
+pub struct AddedLoadout {
+    pub name: String,
+    pub content_type: ContentType,
+}
+
+/// Builds fetched for one character, by spec
+#[derive(Debug, Serialize)]
+pub struct CharacterSummary {
+    pub character: String,
+    pub class_name: String,
+    pub specs: Vec<SpecSummary>,
+}
+
+/// Builds fetched for one spec of a character
+#[derive(Debug, Serialize)]
+pub struct SpecSummary {
+    /// Spec slug from the config (e.g., "beast-mastery")
+    pub spec: String,
+    /// Spec name for display (e.g., "Beast Mastery")
+    pub spec_name: String,
+    /// Hero talent tree the builds were limited to, for display (e.g., "Mountain Thane")
+    pub hero_talent: Option<String>,
+    pub raid_talents: usize,
+    pub mythic_plus_talents: usize,
+    /// Loadouts added for the spec, in fetch order
+    pub added: Vec<AddedLoadout>,
+    /// Builds Archon.gg has no data for
+    pub not_available: usize,
+    /// Builds whose talent string belongs to another spec (see `UpdateSummary::rejected_talents`)
+    pub rejected: usize,
+    /// Builds that failed to fetch (see `UpdateSummary::errors`)
+    pub failed: usize,
+    /// Builds kept without fetching because they are recent (see `Config::update_if_older_than_hours`)
+    pub fresh: usize,
+}
+
+impl SpecSummary {
+    /// Count the outcome of each build fetched for a content type, by loadout name
+    fn record(&mut self, content_type: ContentType, outcomes: Vec<(String, ItemResult)>) {
+        for (name, result) in outcomes {
+            match result {
+                ItemResult::Added => {
+                    match content_type {
+                        ContentType::Raid => self.raid_talents += 1,
+                        ContentType::MythicPlus => self.mythic_plus_talents += 1,
+                    }
+                    self.added.push(AddedLoadout { name, content_type });
+                }
+                ItemResult::NotAvailable => self.not_available += 1,
+                ItemResult::Rejected => self.rejected += 1,
+                ItemResult::Failed => self.failed += 1,
+                ItemResult::Fresh => self.fresh += 1,
+            }
+        }
+    }
+
+    /// Names of the raid loadouts added for the spec
+    fn raid_names(&self) -> Vec<String> {
+        self.added
+            .iter()
+            .filter(|added| added.content_type == ContentType::Raid)
+            .map(|added| added.name.clone())
+            .collect()
+    }
+}
+
+/// A fetched build compared with its version from the previous update
+#[derive(Debug, Serialize)]
+pub struct BuildChange {
+    pub character: String,
+    pub class_name: String,
+    /// Spec slug (e.g., "arms")
+    pub spec: String,
+    /// Build identifier (e.g., "R-heroic-sikran")
+    pub identifier: String,
+    /// Hashes of the previous and the fetched talent string
+    pub old_hash: String,
+    pub new_hash: String,
+    /// Talent nodes chosen differently, None if the strings can't be compared node by node
+    pub changed_nodes: Option<usize>,
+}
+
+/// Raid builds of a spec written as one loadout (see `Config::collapse_identical`)
+#[derive(Debug, Serialize)]
+pub struct CollapsedBuild {
+    pub character: String,
+    pub class_name: String,
+    /// Spec slug (e.g., "arms")
+    pub spec: String,
+    #[serde(flatten)]
+    pub loadout: CollapsedLoadout,
+}
+
+/// A build or spec that couldn't be fetched; the other builds are written anyway
+#[derive(Debug, Serialize)]
+pub struct UpdateError {
+    pub character: String,
+    /// Spec as configured (e.g., "arms")
+    pub spec: String,
+    /// Build that failed (e.g., "R-heroic-sikran"), None if the whole spec failed
+    pub identifier: Option<String>,
+    pub message: String,
+    /// The build failed for a reason that may pass, such as a timeout, even after the retry
+    /// at the end of the run
+    pub transient: bool,
+}
+
+/// How the builds of a run ended up, after failed builds were retried
+#[derive(Debug, Default, Serialize)]
+pub struct BuildOutcomes {
+    /// Builds fetched and added
+    pub succeeded: usize,
+    /// Builds Archon.gg has no data for
+    pub no_data: usize,
+    /// Builds that failed for transient reasons (timeouts, connection problems, server overload)
+    pub failed_transient: usize,
+    /// Builds that failed for reasons a retry won't fix, including rejected talent strings
+    pub failed_permanent: usize,
+    /// Builds fetched once more at the end of the run after a transient failure
+    pub retried: usize,
+    /// Retried builds that were fetched on the second attempt
+    pub recovered: usize,
+}
+
+/// The talent file wasn't written because WoW is running (see `Config::ignore_running_client`)
+#[derive(Debug, thiserror::Error)]
+#[error("World of Warcraft is running and would overwrite the talent file on logout; log out or close the game and retry")]
+pub struct WowRunningError;
+
+impl WowRunningError {
+    /// Prefix of the error message returned to the frontend
+    pub const CODE: &'static str = "WOW_RUNNING";
+}
 
 /// Summary of the talent update operation
 #[derive(Debug, Serialize)]
 pub struct UpdateSummary {
+    /// Set for a preview (see `Config::dry_run`): nothing was written and `files` holds the
+    /// changes an update would make
+    pub dry_run: bool,
     pub total_talents_updated: usize,
     pub raid_talents: usize,
     pub mythic_plus_talents: usize,
     pub characters_processed: usize,
+    /// Raid and Mythic+ builds not fetched because a spec's `fetchRaid` / `fetchMythicPlus` is off
+    pub raid_talents_skipped: usize,
+    pub mythic_plus_talents_skipped: usize,
+    /// Builds kept without fetching because they are recent (see `Config::update_if_older_than_hours`)
+    pub skipped_fresh: usize,
+    /// Configured bosses and dungeons left out by `skipRaidBosses` / `skipDungeons`
+    pub skipped_raid_bosses: Vec<String>,
+    pub skipped_dungeons: Vec<String>,
+    /// Builds fetched per character and spec, in config order
+    pub characters: Vec<CharacterSummary>,
+    /// Deprecated settings found in the config
+    pub config_warnings: Vec<String>,
+    /// Configured bosses and dungeons missing from the current season, if `check_content` is set
+    pub content_warnings: Vec<ContentWarning>,
+    /// Fetched builds whose talent string differs from the previous update
+    pub changed_builds: Vec<BuildChange>,
+    /// Fetched builds identical to the previous update; builds new to the file are in neither list
+    pub unchanged_builds: Vec<BuildChange>,
+    /// Builds merged into another loadout with the same talent string
+    pub merged_talents: usize,
+    /// Raid builds sharing a loadout, if `collapse_identical` is set
+    pub collapsed_builds: Vec<CollapsedBuild>,
+    /// Builds evicted to respect `max_loadouts_per_spec`
+    pub evicted_talents: Vec<EvictedTalent>,
+    /// Fetched builds rejected because their talent string belongs to another spec
+    pub rejected_talents: Vec<RejectedTalent>,
+    /// Builds and specs that failed to fetch (see `Config::abort_on_error`); a failed build keeps
+    /// its version from the previous update
+    pub errors: Vec<UpdateError>,
+    /// Builds by outcome, with transient failures retried once
+    pub outcomes: BuildOutcomes,
+    /// Markdown build list written alongside the talent file, if enabled
+    pub plaintext_path: Option<PathBuf>,
+    /// Talent file found on this machine for a config without `outputPath` (see
+    /// `Config::resolve_output_path`); set by the caller resolving it
+    pub resolved_output_path: Option<PathBuf>,
+    /// What changed in each talent file written, in config order
+    pub files: Vec<FileSummary>,
+    /// Outcome for each of `additional_output_paths`; a failed one doesn't fail the update
+    pub mirrors: Vec<MirrorSummary>,
+}
+
+/// Changes to one talent file
+#[derive(Debug, Serialize)]
+pub struct FileSummary {
+    pub output_path: PathBuf,
+    /// Characters whose builds were written to the file
+    pub characters: Vec<String>,
+    /// Loadouts added, removed or changed compared to the file before the update
+    pub diff: TalentDiff,
+    /// Problems recovering a file that failed to parse (empty if it parsed cleanly)
+    pub parse_warnings: ParseWarnings,
+    /// Loadout counts of the file before and after the update
+    pub stats_before: TalentStats,
+    pub stats_after: TalentStats,
+    /// Backup of the previous file taken before writing, if any (never in a dry run)
+    pub backup_path: Option<PathBuf>,
+    /// Whether the file was written; false in a dry run or when it is unchanged
+    pub file_written: bool,
+    /// The update left the file exactly as it was, so it wasn't rewritten (see `Config::force_write`)
+    pub unchanged: bool,
+    /// Provenance sidecar written alongside the talent file
+    pub metadata_path: PathBuf,
+    /// Set when the talent file belongs to a WoW installation without the TalentLoadoutsEx addon,
+    /// so the loadouts won't show up in-game
+    pub addon_warning: Option<String>,
+}
+
+/// Result of copying the changes to one of `Config::additional_output_paths`
+#[derive(Debug, Serialize)]
+pub struct MirrorSummary {
+    pub output_path: PathBuf,
+    /// Loadouts added, removed or changed in the file; None if it couldn't be updated
+    pub diff: Option<TalentDiff>,
+    /// Backup of the previous file taken before writing, if any
+    pub backup_path: Option<PathBuf>,
+    /// The changes left the file exactly as it was, so it wasn't rewritten
+    pub unchanged: bool,
+    /// Why the file couldn't be updated
+    pub error: Option<String>,
+}
+
+/// A talent file being updated and the characters written to it
+struct OutputFile<'a> {
+    path: PathBuf,
+    characters: Vec<&'a Character>,
+    talent_manager: LuaTalentManager,
+    /// The file as loaded, to diff against
+    original_talents: LuaTalentManager,
+    parse_warnings: ParseWarnings,
+    metadata_path: PathBuf,
+    metadata: MetadataStore,
+    /// The metadata as loaded, to find builds collapsed by the previous update
+    original_metadata: MetadataStore,
+}
+
+/// Loadouts collapsed, merged and evicted while finishing specs (see `TalentOrchestrator::finish_spec`)
+#[derive(Default)]
+struct SpecCleanup {
+    collapsed_builds: Vec<CollapsedBuild>,
+    merged_talents: usize,
+    evicted_talents: Vec<EvictedTalent>,
+}
+
+/// A build that failed to fetch; it keeps its previous version unless the retry at the end
+/// of the run recovers it
+struct FailedBuild<'a> {
+    /// Index of its talent file in the outputs
+    output: usize,
+    character: &'a Character,
+    /// Position of its spec summary in the character summaries and the character's specs
+    character_index: usize,
+    spec_position: usize,
+    /// Spec as configured (e.g., "arms")
+    selection: String,
+    spec: WowSpec,
+    identifier: TalentIdentifier,
+    error: anyhow::Error,
+    recovered: bool,
 }
 
 /// Orchestrates the entire talent fetch and update process
 pub struct TalentOrchestrator {
     config: Config,
-    fetcher: ArchonFetcher,
+    fetcher: Box<dyn TalentFetcher>,
     url_builder: ArchonUrlBuilder,
+    reporter: Option<Box<dyn ProgressReporter>>,
+    /// Sequence number of the last progress event
+    sequence: AtomicU64,
 }
 
 impl TalentOrchestrator {
     /// Create a new orchestrator with the given configuration
     pub fn new(config: Config) -> Self {
         Self {
+            url_builder: ArchonUrlBuilder::for_flavor(config.flavor),
             config,
-            fetcher: ArchonFetcher::new(),
-            url_builder: ArchonUrlBuilder::new(),
+            fetcher: Box::new(ArchonFetcher::new()),
+            reporter: None,
+            sequence: AtomicU64::new(0),
         }
     }
 
-    /// Run the full talent update process
+    /// Report the progress of `run` to `reporter`
+    pub fn with_progress_reporter(mut self, reporter: impl ProgressReporter + 'static) -> Self {
+        self.reporter = Some(Box::new(reporter));
+        self
+    }
+
+    /// Fetch builds with `fetcher` instead of from Archon.gg
+    #[cfg(test)]
+    pub fn with_fetcher(mut self, fetcher: impl TalentFetcher + 'static) -> Self {
+        self.fetcher = Box::new(fetcher);
+        self
+    }
+
+    fn report(&self, event: ProgressEvent<'_>) {
+        if let Some(reporter) = &self.reporter {
+            let sequence = self.sequence.fetch_add(1, Ordering::Relaxed) + 1;
+            reporter.report(&ProgressUpdate { sequence, event });
+        }
+    }
+
+    /// Number of builds `run` fetches, for progress reporting
+    fn total_items(&self) -> usize {
+        let raid_items = self.config.raid_builds_per_spec();
+        let mythic_plus_items = self.config.dungeons_to_fetch().len();
+        self.config
+            .output_files()
+            .iter()
+            .flat_map(|(_, characters)| characters.iter().flat_map(|character| character.wanted_specializations()))
+            .map(|selection| {
+                let raid = if selection.fetch_raid { raid_items } else { 0 };
+                let mythic_plus = if selection.fetch_mythic_plus { mythic_plus_items } else { 0 };
+                raid + mythic_plus
+            })
+            .sum()
+    }
+
+    /// Run the full talent update process in an `update` span that records the outcome
+    /// Characters sharing a talent file are updated together; each file is written once at the end.
     pub async fn run(&self) -> Result<UpdateSummary> {
-        println!("Starting talent fetch from Archon.gg...");
+        let span = info_span!(
+            "update",
+            characters = self.config.characters.len(),
+            dry_run = self.config.dry_run,
+            total_talents_updated = field::Empty,
+            errors = field::Empty,
+        );
+        let result = self.update().instrument(span.clone()).await;
+        let _entered = span.enter();
+        match &result {
+            Ok(summary) => {
+                span.record("total_talents_updated", summary.total_talents_updated);
+                span.record("errors", summary.errors.len());
+                info!(
+                    raid = summary.raid_talents,
+                    mythic_plus = summary.mythic_plus_talents,
+                    rejected = summary.rejected_talents.len(),
+                    files_written = summary.files.iter().filter(|file| file.file_written).count(),
+                    "Update finished"
+                );
+            }
+            Err(e) => error!("Update failed: {:#}", e),
+        }
+        result
+    }
+
+    async fn update(&self) -> Result<UpdateSummary> {
+        // Fail on config mistakes before anything is fetched
+        self.config.validate()?;
+        info!("Starting talent fetch from Archon.gg");
 
         let mut raid_talents = 0;
         let mut mythic_plus_talents = 0;
+        let mut raid_talents_skipped = 0;
+        let mut mythic_plus_talents_skipped = 0;
+        let mut skipped_fresh = 0;
+        let mut changed_builds = Vec::new();
+        let mut unchanged_builds = Vec::new();
+        let mut cleanup = SpecCleanup::default();
+        let mut rejected_talents = Vec::new();
+        let mut errors = Vec::new();
+        let mut failed_builds = Vec::new();
+        let mut plaintext_builds = Vec::new();
+        let mut character_summaries = Vec::new();
+        // Specs finished once their failed builds are retried, see `finish_spec`
+        let mut unfinished_specs = Vec::new();
 
-        // Load existing talents
-        let mut talent_manager = if self.config.output_path.exists() {
-            println!("Loading existing talents from {:?}", self.config.output_path);
-            LuaTalentManager::load_from_file(&self.config.output_path)
-                .context("Failed to load existing talents")?
-        } else {
-            println!("No existing talent file found, creating new one");
-            LuaTalentManager::new()
-        };
+        let config_warnings = self.config.warnings();
+        for warning in &config_warnings {
+            warn!("{}", warning);
+        }
+
+        let content_warnings = self.content_warnings().await;
+
+        let raid_bosses = self.config.raid_bosses_to_fetch();
+        let raid_builds = self.config.raid_builds_per_spec();
+        let dungeons = self.config.dungeons_to_fetch();
+        let skipped_raid_bosses: Vec<String> =
+            self.config.all_raid_bosses().into_iter().filter(|boss| !raid_bosses.contains(boss)).collect();
+        let skipped_dungeons: Vec<String> =
+            self.config.dungeons.iter().filter(|dungeon| !dungeons.contains(dungeon)).cloned().collect();
+        if !skipped_raid_bosses.is_empty() || !skipped_dungeons.is_empty() {
+            info!(bosses = ?skipped_raid_bosses, dungeons = ?skipped_dungeons, "Skipping content");
+        }
 
-        // Clear previous auto-generated builds if requested
-        if self.config.clear_previous_builds {
-            println!("Clearing all previous auto-generated builds");
-            talent_manager.remove_all_auto_generated();
+        let suffixes = AutoSuffixes::new(self.config.auto_suffix.clone(), self.config.legacy_suffixes.clone());
+        // Date previous versions of replaced builds are kept with, see `keep_previous_builds`
+        let version_date = chrono::Local::now().format("%m%d").to_string();
+
+        // Load every file before fetching, so one that can't be updated fails the run early
+        let mut outputs = Vec::new();
+        for (path, characters) in self.config.output_files() {
+            outputs.push(self.load_output(path, characters, &suffixes)?);
         }
+        self.report(ProgressEvent::RunStarted { total_items: self.total_items() });
+
+        for (output_index, output) in outputs.iter_mut().enumerate() {
+            let OutputFile {
+                path,
+                characters,
+                talent_manager,
+                original_talents,
+                metadata: loadout_metadata,
+                original_metadata,
+                ..
+            } = output;
+            info!(path = %path.display(), "Updating talent file");
+            // Specs whose previous builds were cleared; a spec listed with several hero talents is cleared once
+            let mut cleared_specs = HashSet::new();
+
+            // Process each character
+            for character in characters.iter() {
+                info!(character = %character.name, class = %character.class, "Processing character");
 
-        // Process each character
-        for character in &self.config.characters {
-            println!("\nProcessing character: {} ({})", character.name, character.class);
+                let wow_class = character.class;
 
-            let wow_class = WowClass::from_str(&character.class)
-                .ok_or_else(|| anyhow::anyhow!("Invalid class: {}", character.class))?;
+                // The character's TalentLoadouts profile receives the builds of its specs
+                let profile_key = (self.config.output_format == TalentFileFormat::TalentLoadouts).then(|| {
+                    let realm = character.realm.as_deref();
+                    let key = talent_loadouts_db::profile_key(talent_manager, &character.name, realm);
+                    talent_manager.add_profile(key.clone(), wow_class.to_lua_format().to_string());
+                    key
+                });
 
-            for spec in &character.specializations {
-                println!("  Specialization: {}", spec);
+                let mut character_summary = CharacterSummary {
+                    character: character.name.clone(),
+                    class_name: wow_class.to_lua_format().to_string(),
+                    specs: Vec::new(),
+                };
 
-                // Validate spec for this class
-                let spec_index = wow_class
-                    .spec_index(spec)
-                    .ok_or_else(|| anyhow::anyhow!("Invalid spec {} for class {}", spec, character.class))?;
+                for selection in character.wanted_specializations() {
+                    info!(character = %character.name, spec = %selection, "Processing specialization");
 
-                // Clear auto-generated talents for this spec
-                if !self.config.clear_previous_builds {
-                    talent_manager.remove_auto_generated(wow_class.to_lua_format(), spec_index);
+                    // Validate spec for this class; aliases and deprecated names resolve to the current slug
+                    let resolved = wow_class.spec_for(self.config.flavor, &selection.spec).and_then(|spec| {
+                        let hero = selection
+                            .hero_talent
+                            .as_deref()
+                            .map(|hero| wow_class.parse_hero_talent(spec.index, hero))
+                            .transpose()?;
+                        Ok((spec, hero))
+                    });
+                    let (spec, hero) = match resolved {
+                        Ok(resolved) => resolved,
+                        Err(e) if !self.config.abort_on_error => {
+                            warn!(character = %character.name, spec = %selection, "Skipping spec: {:#}", e);
+                            errors.push(UpdateError {
+                                character: character.name.clone(),
+                                spec: selection.to_string(),
+                                identifier: None,
+                                message: format!("{:#}", e),
+                                transient: false,
+                            });
+                            continue;
+                        }
+                        Err(e) => return Err(e),
+                    };
+                    let spec_index = spec.index;
+                    if let Some(key) = &profile_key {
+                        let profile = talent_manager.add_profile(key.clone(), wow_class.to_lua_format().to_string());
+                        profile.specs.insert(spec_index);
+                    }
+
+                    // Clear auto-generated talents for this spec, or keep them as previous versions
+                    if self.config.clear_previous_builds == ClearScope::ConfiguredSpecsOnly
+                        && cleared_specs.insert((wow_class, spec_index))
+                    {
+                        let class_name = wow_class.to_lua_format();
+                        match self.config.keep_previous_builds {
+                            Some(keep) if keep > 0 => {
+                                talent_manager.archive_auto_generated(class_name, spec_index, &version_date)
+                            }
+                            _ => talent_manager.remove_auto_generated(class_name, spec_index),
+                        }
+                    }
+
+                    let mut spec_summary = SpecSummary {
+                        spec: spec.slug.to_string(),
+                        spec_name: spec.display_name().to_string(),
+                        hero_talent: hero.map(|hero| hero.display_name().to_string()),
+                        raid_talents: 0,
+                        mythic_plus_talents: 0,
+                        added: Vec::new(),
+                        not_available: 0,
+                        rejected: 0,
+                        failed: 0,
+                        fresh: 0,
+                    };
+
+                    let mut failures = Vec::new();
+                    // Loadouts recent enough to be kept rather than fetched again
+                    let mut fresh = Vec::new();
+
+                    // Fetch raid builds
+                    if raid_builds > 0 {
+                        if selection.fetch_raid {
+                            let outcomes = self
+                                .fetch_raid_builds(
+                                    talent_manager,
+                                    &mut rejected_talents,
+                                    &mut failures,
+                                    loadout_metadata,
+                                    spec,
+                                    hero,
+                                )
+                                .await?;
+                            fresh.extend(fresh_names(&outcomes));
+                            spec_summary.record(ContentType::Raid, outcomes);
+                        } else {
+                            info!(spec = %selection, "Skipping raid builds (fetchRaid is off)");
+                            raid_talents_skipped += raid_builds;
+                        }
+                    }
+
+                    // Fetch Mythic+ builds
+                    if !dungeons.is_empty() {
+                        if selection.fetch_mythic_plus {
+                            let outcomes = self
+                                .fetch_mythic_plus_builds(
+                                    talent_manager,
+                                    &mut rejected_talents,
+                                    &mut failures,
+                                    loadout_metadata,
+                                    spec,
+                                    hero,
+                                )
+                                .await?;
+                            fresh.extend(fresh_names(&outcomes));
+                            spec_summary.record(ContentType::MythicPlus, outcomes);
+                        } else {
+                            info!(spec = %selection, "Skipping Mythic+ builds (fetchMythicPlus is off)");
+                            mythic_plus_talents_skipped += dungeons.len();
+                        }
+                    }
+
+                    // Compare with the builds of the previous update before anything else is put back
+                    for added in &spec_summary.added {
+                        let previous = (&*original_talents, &*original_metadata);
+                        if let Some(change) =
+                            self.compare_with_previous(talent_manager, previous, character, spec, &added.name)
+                        {
+                            if change.old_hash == change.new_hash {
+                                unchanged_builds.push(change);
+                            } else {
+                                changed_builds.push(change);
+                            }
+                        }
+                    }
+
+                    let previous = (&*original_talents, &*original_metadata);
+                    for name in &fresh {
+                        self.keep_previous(talent_manager, loadout_metadata, previous, spec, name);
+                    }
+                    skipped_fresh += fresh.len();
+
+                    spec_summary.failed = failures.len();
+                    let retrying = failures.iter().any(|(_, error)| is_transient(error));
+                    // Builds of the previous update put back, collapsed again with the fetched ones
+                    let mut kept = fresh;
+                    for (identifier, error) in failures {
+                        // Keep the build of the previous update rather than losing it to the failure
+                        let name = identifier.as_talent_name(&self.config.auto_suffix);
+                        self.keep_previous(talent_manager, loadout_metadata, previous, spec, &name);
+                        kept.push(name);
+                        failed_builds.push(FailedBuild {
+                            output: output_index,
+                            character,
+                            character_index: character_summaries.len(),
+                            spec_position: character_summary.specs.len(),
+                            selection: selection.to_string(),
+                            spec,
+                            identifier,
+                            error,
+                            recovered: false,
+                        });
+                    }
+
+                    info!(
+                        character = %character.name,
+                        spec = %selection,
+                        raid = spec_summary.raid_talents,
+                        mythic_plus = spec_summary.mythic_plus_talents,
+                        failed = spec_summary.failed,
+                        fresh = spec_summary.fresh,
+                        "Fetched builds"
+                    );
+                    raid_talents += spec_summary.raid_talents;
+                    mythic_plus_talents += spec_summary.mythic_plus_talents;
+
+                    // A spec with builds to retry is finished once they had their chance
+                    if retrying {
+                        let position = (character_summaries.len(), character_summary.specs.len());
+                        unfinished_specs.push((output_index, *character, position, spec, kept));
+                    } else {
+                        kept.extend(spec_summary.raid_names());
+                        self.finish_spec(talent_manager, loadout_metadata, character, spec, &kept, &mut cleanup);
+                    }
+                    character_summary.specs.push(spec_summary);
+
+                    if self.config.also_write_plaintext {
+                        plaintext_builds.push(SpecBuilds {
+                            character: character.name.clone(),
+                            class: character.class,
+                            spec: spec.slug.to_string(),
+                            talents: self.plaintext_talents(talent_manager, spec),
+                        });
+                    }
+                }
+
+                character_summaries.push(character_summary);
+            }
+        }
+
+        // One more attempt, with a longer timeout, at builds that failed for reasons that may pass
+        let mut retried = 0;
+        let mut recovered = 0;
+        for failed in failed_builds.iter_mut().filter(|failed| is_transient(&failed.error)) {
+            retried += 1;
+            let (spec, identifier) = (failed.spec, failed.identifier.clone());
+            info!(character = %failed.character.name, identifier = %identifier.as_identifier(), "Retrying build");
+            self.report_item_started(&identifier, spec);
+
+            let OutputFile {
+                talent_manager,
+                original_talents,
+                metadata: loadout_metadata,
+                original_metadata,
+                ..
+            } = &mut outputs[failed.output];
+            let result = match self.fetch_build(&identifier, spec, RETRY_TIMEOUT).await {
+                Ok(fetched) => {
+                    let rejected = &mut rejected_talents;
+                    self.add_fetched(talent_manager, rejected, loadout_metadata, spec, &identifier, fetched)
+                }
+                Err(e) => {
+                    warn!(identifier = %identifier.as_identifier(), "Retry failed: {:#}", e);
+                    failed.error = e;
+                    ItemResult::Failed
+                }
+            };
+            self.report(ProgressEvent::ItemFinished { identifier: identifier.as_identifier(), result });
+            if result == ItemResult::Failed {
+                continue;
+            }
+            failed.recovered = true;
+            recovered += 1;
+
+            // Builds that turn out unavailable or rejected keep their previous version
+            let name = identifier.as_talent_name(&self.config.auto_suffix);
+            let content_type = content_type(&identifier);
+            let spec_summary = &mut character_summaries[failed.character_index].specs[failed.spec_position];
+            spec_summary.failed -= 1;
+            spec_summary.record(content_type, vec![(name.clone(), result)]);
+            if result == ItemResult::Added {
+                match content_type {
+                    ContentType::Raid => raid_talents += 1,
+                    ContentType::MythicPlus => mythic_plus_talents += 1,
+                }
+                let previous = (&*original_talents, &*original_metadata);
+                if let Some(change) =
+                    self.compare_with_previous(talent_manager, previous, failed.character, spec, &name)
+                {
+                    if change.old_hash == change.new_hash {
+                        unchanged_builds.push(change);
+                    } else {
+                        changed_builds.push(change);
+                    }
                 }
+            }
+        }
+        if retried > 0 {
+            info!(retried, recovered, "Retried failed builds");
+        }
 
-                // Fetch raid builds
-                if !self.config.raid_bosses.is_empty() && !self.config.raid_difficulties.is_empty() {
-                    raid_talents += self.fetch_raid_builds(&mut talent_manager, wow_class, spec, spec_index)
-                        .await?;
+        for (output, character, (character_index, spec_position), spec, mut kept) in unfinished_specs {
+            let OutputFile { talent_manager, metadata: loadout_metadata, .. } = &mut outputs[output];
+            kept.extend(character_summaries[character_index].specs[spec_position].raid_names());
+            self.finish_spec(talent_manager, loadout_metadata, character, spec, &kept, &mut cleanup);
+            if self.config.also_write_plaintext {
+                let talents = self.plaintext_talents(talent_manager, spec);
+                for builds in plaintext_builds
+                    .iter_mut()
+                    .filter(|builds| builds.character == character.name && builds.spec == spec.slug)
+                {
+                    builds.talents = talents.clone();
                 }
+            }
+        }
+
+        for failed in failed_builds.into_iter().filter(|failed| !failed.recovered) {
+            errors.push(UpdateError {
+                character: failed.character.name.clone(),
+                spec: failed.selection,
+                identifier: Some(failed.identifier.as_identifier()),
+                transient: is_transient(&failed.error),
+                message: format!("{:#}", failed.error),
+            });
+        }
+        let outcomes = BuildOutcomes {
+            succeeded: raid_talents + mythic_plus_talents,
+            no_data: character_summaries
+                .iter()
+                .flat_map(|character| &character.specs)
+                .map(|spec| spec.not_available)
+                .sum(),
+            failed_transient: errors.iter().filter(|error| error.transient).count(),
+            failed_permanent: errors.iter().filter(|error| error.identifier.is_some() && !error.transient).count()
+                + rejected_talents.len(),
+            retried,
+            recovered,
+        };
 
-                // Fetch Mythic+ builds
-                if !self.config.dungeons.is_empty() {
-                    mythic_plus_talents += self.fetch_mythic_plus_builds(&mut talent_manager, wow_class, spec, spec_index)
-                        .await?;
+        // Every fetch failed, e.g. without a connection; keep the files as they are
+        if !errors.is_empty() && raid_talents + mythic_plus_talents + skipped_fresh == 0 {
+            anyhow::bail!(
+                "No builds could be fetched ({} failed, first: {})",
+                errors.len(),
+                errors[0].message
+            );
+        }
+
+        // Content of each file to write, None for files the update left as they were
+        let contents: Vec<_> = outputs
+            .iter()
+            .map(|output| self.changed_content(&output.path, &output.talent_manager))
+            .collect();
+
+        // The client would overwrite the files on logout; checked before writing any of them
+        if !self.config.ignore_running_client && !self.config.dry_run {
+            for (output, _) in outputs.iter().zip(&contents).filter(|(_, content)| content.is_some()) {
+                if let Some(wow_path) = wow_path_for_talent_file(&output.path) {
+                    if WowScanner::new(wow_path).is_wow_running() {
+                        return Err(WowRunningError.into());
+                    }
                 }
             }
         }
 
-        // Write updated talents back to file
-        println!("\nWriting talents to {:?}", self.config.output_path);
-        talent_manager
-            .write_to_file(&self.config.output_path)
-            .context("Failed to write talents to file")?;
+        let plaintext_default = outputs.first().map(|output| output.path.with_file_name(plaintext::PLAINTEXT_FILE_NAME));
+        let mut files = Vec::new();
+        // Final loadouts of each file, to copy to the additional output paths
+        let mut updated_talents = Vec::new();
+        for (output, content) in outputs.into_iter().zip(contents) {
+            if !self.config.additional_output_paths.is_empty() {
+                updated_talents.push(output.talent_manager.clone());
+            }
+            files.push(self.write_output(output, content)?);
+        }
+        let mirrors = self
+            .config
+            .additional_output_paths
+            .iter()
+            .map(|path| self.write_mirror(path, &files, &updated_talents, &suffixes))
+            .collect();
+
+        // Write the copy-paste build list
+        let plaintext_path = match self.config.plaintext_path.clone().or(plaintext_default) {
+            Some(path) if self.config.also_write_plaintext && !self.config.dry_run => {
+                info!(path = %path.display(), "Writing build list");
+                if let Some(parent) = path.parent() {
+                    std::fs::create_dir_all(parent).context("Failed to create build list directory")?;
+                }
+                write_atomic(&path, plaintext::render_builds(&plaintext_builds, &self.config.auto_suffix))
+                    .context("Failed to write build list")?;
+                Some(path)
+            }
+            _ => None,
+        };
 
         let summary = UpdateSummary {
+            dry_run: self.config.dry_run,
             total_talents_updated: raid_talents + mythic_plus_talents,
             raid_talents,
             mythic_plus_talents,
             characters_processed: self.config.characters.len(),
+            raid_talents_skipped,
+            mythic_plus_talents_skipped,
+            skipped_fresh,
+            skipped_raid_bosses,
+            skipped_dungeons,
+            characters: character_summaries,
+            changed_builds,
+            unchanged_builds,
+            config_warnings,
+            content_warnings,
+            merged_talents: cleanup.merged_talents,
+            collapsed_builds: cleanup.collapsed_builds,
+            evicted_talents: cleanup.evicted_talents,
+            rejected_talents,
+            errors,
+            outcomes,
+            plaintext_path,
+            resolved_output_path: None,
+            files,
+            mirrors,
         };
 
-        println!("Talent fetch complete!");
-        println!("Summary: {} total talents updated ({} raid, {} M+)",
-            summary.total_talents_updated, summary.raid_talents, summary.mythic_plus_talents);
-
+        self.report(ProgressEvent::RunFinished { summary: &summary });
         Ok(summary)
     }
 
+    /// Configured bosses and dungeons missing from the current season, if enabled
+    /// Outdated content only means fewer builds, so problems are reported rather than failing the run.
+    async fn content_warnings(&self) -> Vec<ContentWarning> {
+        if !self.config.check_content {
+            return Vec::new();
+        }
+
+        match WarcraftLogsService::discover_current_content().await {
+            Ok(content) => {
+                let warnings = self.config.validate_against_content(&content);
+                for warning in &warnings {
+                    warn!("{}", warning.message);
+                }
+                warnings
+            }
+            Err(e) => {
+                warn!("Couldn't check bosses and dungeons against the current season: {:#}", e);
+                Vec::new()
+            }
+        }
+    }
+
+    /// Load a talent file and its metadata sidecar, and clear previous builds if requested
+    fn load_output<'a>(
+        &self,
+        path: PathBuf,
+        characters: Vec<&'a Character>,
+        suffixes: &AutoSuffixes,
+    ) -> Result<OutputFile<'a>> {
+        // Load existing talents
+        let (mut talent_manager, parse_warnings) = if path.exists() {
+            info!(path = %path.display(), "Loading existing talents");
+            LuaTalentManager::load_from_file_recovering(&path, self.config.output_format, suffixes)
+                .with_context(|| format!("Failed to load existing talents from {}", path.display()))?
+        } else {
+            info!(path = %path.display(), "No existing talent file found, creating a new one");
+            let mut manager = LuaTalentManager::new();
+            manager.set_auto_suffixes(suffixes.clone());
+            (manager, ParseWarnings::default())
+        };
+
+        // A recovered file is missing whatever couldn't be parsed; don't overwrite it silently
+        if !parse_warnings.is_empty() {
+            warn!(?parse_warnings, "Existing file could not be parsed cleanly");
+        }
+        if parse_warnings.drops_data() && !self.config.force_write {
+            anyhow::bail!(
+                "Existing talent file {} could not be parsed ({}); {} line(s) could not be recovered. \
+                 Fix the file or enable forceWrite to overwrite it with the recovered loadouts",
+                path.display(),
+                parse_warnings.parse_error.as_deref().unwrap_or("unknown error"),
+                parse_warnings.skipped_lines.len()
+            );
+        }
+        let original_talents = talent_manager.clone();
+
+        // Where each fetched build came from; a broken sidecar is rebuilt rather than failing the update
+        let metadata_path = metadata::sidecar_path(&path);
+        let mut metadata = MetadataStore::load(&metadata_path).unwrap_or_else(|e| {
+            warn!("Ignoring unreadable loadout metadata: {:#}", e);
+            MetadataStore::default()
+        });
+        // Loadouts deleted or edited in-game since the last update count as never fetched
+        metadata.reconcile(&talent_manager);
+        let original_metadata = metadata.clone();
+
+        // Clear previous auto-generated builds across the file if requested
+        if self.config.clear_previous_builds != ClearScope::ConfiguredSpecsOnly {
+            if !parse_warnings.issues.is_empty() && !self.config.confirm_clear_with_issues {
+                anyhow::bail!(
+                    "{} entries of the existing talent file {} could not be read (first: {} {}). \
+                     Check the file or enable confirmClearWithIssues to clear previous builds anyway",
+                    parse_warnings.issues.len(),
+                    path.display(),
+                    parse_warnings.issues[0].location,
+                    parse_warnings.issues[0].reason
+                );
+            }
+
+            match self.config.clear_previous_builds {
+                ClearScope::ContentTypes { raid, mythic_plus } => {
+                    info!(raid, mythic_plus, "Clearing previous auto-generated builds");
+                    if raid {
+                        talent_manager.remove_by_prefix("R-");
+                    }
+                    if mythic_plus {
+                        talent_manager.remove_by_prefix("M+-");
+                    }
+                }
+                _ => {
+                    info!("Clearing all previous auto-generated builds");
+                    talent_manager.remove_all_auto_generated();
+                }
+            }
+        }
+
+        Ok(OutputFile {
+            path,
+            characters,
+            talent_manager,
+            original_talents,
+            parse_warnings,
+            metadata_path,
+            metadata,
+            original_metadata,
+        })
+    }
+
+    /// Content to write for a talent file, or None if it would stay byte for byte the same
+    fn changed_content(&self, path: &Path, talent_manager: &LuaTalentManager) -> Option<String> {
+        let content = talent_manager.to_string_as(self.config.output_format);
+        let unchanged = std::fs::read(path).is_ok_and(|existing| existing == content.as_bytes());
+        if unchanged && !self.config.force_write {
+            None
+        } else {
+            Some(content)
+        }
+    }
+
+    /// Back up and write an updated talent file and its metadata sidecar
+    /// In a dry run, or when `content` is None because nothing changed, only the summary is built.
+    fn write_output(&self, output: OutputFile<'_>, content: Option<String>) -> Result<FileSummary> {
+        let OutputFile {
+            path,
+            characters,
+            talent_manager,
+            original_talents,
+            parse_warnings,
+            metadata_path,
+            mut metadata,
+            ..
+        } = output;
+
+        let addon_warning = self.addon_warning(&path);
+        if let Some(warning) = &addon_warning {
+            warn!("{}", warning);
+        }
+
+        let unchanged = content.is_none();
+        let (backup_path, file_written) = match content {
+            _ if self.config.dry_run => {
+                info!(path = %path.display(), "Dry run, not writing talent file");
+                (None, false)
+            }
+            None => {
+                info!(path = %path.display(), "No changes, leaving talent file as it is");
+                // Builds fetched again with the same talent string still count as fresh
+                metadata.reconcile(&talent_manager);
+                metadata.save(&metadata_path)?;
+                (None, false)
+            }
+            Some(content) => {
+                // Back up the existing file before overwriting it
+                let backup_path = self.create_backup(&path)?;
+                if let Some(backup) = &backup_path {
+                    info!(path = %backup.display(), "Backup created");
+                }
+
+                // Write updated talents back to file
+                info!(path = %path.display(), "Writing talents");
+                write_atomic(&path, content).context("Failed to write talents to file")?;
+
+                // Forget builds that were removed, merged or evicted during this run
+                metadata.reconcile(&talent_manager);
+                metadata.save(&metadata_path)?;
+                self.report(ProgressEvent::FileWritten { path: &path });
+                (backup_path, true)
+            }
+        };
+
+        Ok(FileSummary {
+            characters: characters.iter().map(|character| character.name.clone()).collect(),
+            diff: original_talents.diff(&talent_manager),
+            parse_warnings,
+            stats_before: original_talents.stats(),
+            stats_after: talent_manager.stats(),
+            backup_path,
+            file_written,
+            unchanged,
+            metadata_path,
+            addon_warning,
+            output_path: path,
+        })
+    }
+
+    /// Apply the changes of every written file to an additional output path
+    /// Errors are reported in the summary instead of failing the update.
+    fn write_mirror(
+        &self,
+        path: &Path,
+        files: &[FileSummary],
+        updated_talents: &[LuaTalentManager],
+        suffixes: &AutoSuffixes,
+    ) -> MirrorSummary {
+        info!(path = %path.display(), "Mirroring changes");
+        match self.try_write_mirror(path, files, updated_talents, suffixes) {
+            Ok((diff, backup_path, unchanged)) => MirrorSummary {
+                output_path: path.to_path_buf(),
+                diff: Some(diff),
+                backup_path,
+                unchanged,
+                error: None,
+            },
+            Err(e) => {
+                warn!(path = %path.display(), "Failed to update additional output: {:#}", e);
+                MirrorSummary {
+                    output_path: path.to_path_buf(),
+                    diff: None,
+                    backup_path: None,
+                    unchanged: false,
+                    error: Some(format!("{:#}", e)),
+                }
+            }
+        }
+    }
+
+    fn try_write_mirror(
+        &self,
+        path: &Path,
+        files: &[FileSummary],
+        updated_talents: &[LuaTalentManager],
+        suffixes: &AutoSuffixes,
+    ) -> Result<(TalentDiff, Option<PathBuf>, bool)> {
+        let (mut talent_manager, parse_warnings) = if path.exists() {
+            LuaTalentManager::load_from_file_recovering(path, self.config.output_format, suffixes)
+                .with_context(|| format!("Failed to load existing talents from {}", path.display()))?
+        } else {
+            let mut manager = LuaTalentManager::new();
+            manager.set_auto_suffixes(suffixes.clone());
+            (manager, ParseWarnings::default())
+        };
+        if parse_warnings.drops_data() && !self.config.force_write {
+            anyhow::bail!(
+                "Existing talent file could not be parsed ({}); enable forceWrite to overwrite it",
+                parse_warnings.parse_error.as_deref().unwrap_or("unknown error")
+            );
+        }
+        let original_talents = talent_manager.clone();
+
+        for (file, updated) in files.iter().zip(updated_talents) {
+            // Profiles of the mirror receive the builds of the same specs
+            for (key, profile) in updated.profiles() {
+                talent_manager.add_profile(key.clone(), profile.class_name.clone()).specs.extend(&profile.specs);
+            }
+            for (class_name, spec_index) in talent_manager.apply_diff(&file.diff, updated) {
+                talent_manager.sort_spec(&class_name, spec_index, &self.config.all_raid_bosses());
+            }
+        }
+        let diff = original_talents.diff(&talent_manager);
+
+        let Some(content) = self.changed_content(path, &talent_manager) else {
+            return Ok((diff, None, true));
+        };
+        if self.config.dry_run {
+            return Ok((diff, None, false));
+        }
+        let backup_path = self.create_backup(path)?;
+        if let Some(parent) = path.parent() {
+            std::fs::create_dir_all(parent).context("Failed to create talent file directory")?;
+        }
+        write_atomic(path, content).context("Failed to write talents to file")?;
+        self.report(ProgressEvent::FileWritten { path });
+        Ok((diff, backup_path, false))
+    }
+
+    /// Back up a talent file before overwriting it and register the backup so it can be restored
+    /// An index that can't be updated only costs the backup's timestamp and format in listings.
+    fn create_backup(&self, path: &Path) -> Result<Option<PathBuf>> {
+        let backup_path =
+            backup::create_backup(path, self.config.backup_count).context("Failed to back up existing talents")?;
+        if let Some(backup_path) = &backup_path {
+            if let Err(e) = backups::register(backup_path, path, self.config.output_format) {
+                warn!(path = %backup_path.display(), "Couldn't register backup: {:#}", e);
+            }
+        }
+        Ok(backup_path)
+    }
+
+    /// Warning for writing into the SavedVariables of an installation that doesn't have the addon
+    fn addon_warning(&self, output_path: &Path) -> Option<String> {
+        let wow_path = wow_path_for_talent_file(output_path)?;
+        if WowScanner::new(&wow_path).addon_status().installed {
+            return None;
+        }
+        Some(format!(
+            "TalentLoadoutsEx is not installed in {}; the loadouts will only show up in-game once it is",
+            wow_path.display()
+        ))
+    }
+
     /// Fetch raid builds for a specific class/spec
+    /// Builds that fail to fetch are added to `failures`, unless `abort_on_error` is set.
     async fn fetch_raid_builds(
         &self,
         talent_manager: &mut LuaTalentManager,
-        wow_class: WowClass,
-        spec: &str,
-        spec_index: u8,
-    ) -> Result<usize> {
-        let mut count = 0;
-
-        for boss in &self.config.raid_bosses {
-            for difficulty_str in &self.config.raid_difficulties {
-                let difficulty = RaidDifficulty::from_str(difficulty_str)
-                    .ok_or_else(|| anyhow::anyhow!("Invalid difficulty: {}", difficulty_str))?;
+        rejected: &mut Vec<RejectedTalent>,
+        failures: &mut Vec<(TalentIdentifier, anyhow::Error)>,
+        metadata: &mut MetadataStore,
+        spec: WowSpec,
+        hero: Option<HeroTalent>,
+    ) -> Result<Vec<(String, ItemResult)>> {
+        let mut outcomes = Vec::new();
 
+        // By boss, then difficulty (see `Config::raid_content_to_fetch`)
+        let content = self.config.raid_content_to_fetch();
+        for boss in self.config.raid_bosses_to_fetch() {
+            let difficulties = content.iter().filter(|(_, bosses)| bosses.contains(&&boss));
+            for &(difficulty, _) in difficulties {
                 let identifier = TalentIdentifier::Raid {
                     difficulty,
                     boss: boss.clone(),
+                    hero,
                 };
 
-                let url = self.url_builder.build_raid_url(wow_class, spec, difficulty, boss);
-
-                println!("    Fetching: {} from {}", identifier.as_identifier(), url);
+                if self.skip_fresh(metadata, spec, &identifier, &mut outcomes) {
+                    continue;
+                }
+                self.report_item_started(&identifier, spec);
 
-                match self.fetcher.fetch_talent_build(&url).await? {
-                    Some(talent_string) => {
-                        let talent = TalentLoadout::new(identifier.as_talent_name(), talent_string);
-                        talent_manager.add_talent(
-                            wow_class.to_lua_format().to_string(),
-                            spec_index,
-                            talent,
-                        );
-                        println!("      Found talent build");
-                        count += 1;
-                    }
-                    None => {
-                        println!("      No talent build available");
+                let result = match self.fetch_build(&identifier, spec, REQUEST_TIMEOUT).await {
+                    Ok(fetched) => self.add_fetched(talent_manager, rejected, metadata, spec, &identifier, fetched),
+                    Err(e) => {
+                        self.item_failed(identifier, e, failures)?;
+                        continue;
                     }
-                }
+                };
+                self.report(ProgressEvent::ItemFinished { identifier: identifier.as_identifier(), result });
+                outcomes.push((identifier.as_talent_name(&self.config.auto_suffix), result));
             }
         }
 
-        Ok(count)
+        Ok(outcomes)
     }
 
     /// Fetch Mythic+ builds for a specific class/spec
+    /// Builds that fail to fetch are added to `failures`, unless `abort_on_error` is set.
     async fn fetch_mythic_plus_builds(
         &self,
         talent_manager: &mut LuaTalentManager,
-        wow_class: WowClass,
-        spec: &str,
-        spec_index: u8,
-    ) -> Result<usize> {
-        let mut count = 0;
+        rejected: &mut Vec<RejectedTalent>,
+        failures: &mut Vec<(TalentIdentifier, anyhow::Error)>,
+        metadata: &mut MetadataStore,
+        spec: WowSpec,
+        hero: Option<HeroTalent>,
+    ) -> Result<Vec<(String, ItemResult)>> {
+        let mut outcomes = Vec::new();
 
-        for dungeon in &self.config.dungeons {
+        for dungeon in self.config.dungeons_to_fetch() {
             let identifier = TalentIdentifier::MythicPlus {
                 dungeon: dungeon.clone(),
+                hero,
             };
 
-            // Try primary timespan first
-            let primary_timespan = MythicPlusTimespan::primary_for_today();
-            let url = self.url_builder.build_mythic_plus_url(wow_class, spec, dungeon, primary_timespan);
-
-            println!("    Fetching: {} from {}", identifier.as_identifier(), url);
-
-            let talent_string = match self.fetcher.fetch_talent_build(&url).await? {
-                Some(talent) => {
-                    println!("      Found talent build ({})", primary_timespan.as_str());
-                    Some(talent)
-                }
-                None => {
-                    // Try fallback timespan
-                    let fallback_timespan = primary_timespan.fallback();
-                    let fallback_url = self.url_builder.build_mythic_plus_url(
-                        wow_class,
-                        spec,
-                        dungeon,
-                        fallback_timespan,
-                    );
-
-                    println!("      Trying fallback: {}", fallback_timespan.as_str());
-
-                    match self.fetcher.fetch_talent_build(&fallback_url).await? {
-                        Some(talent) => {
-                            println!("      Found talent build ({})", fallback_timespan.as_str());
-                            Some(talent)
-                        }
-                        None => {
-                            println!("      No talent build available");
-                            None
-                        }
-                    }
+            if self.skip_fresh(metadata, spec, &identifier, &mut outcomes) {
+                continue;
+            }
+            self.report_item_started(&identifier, spec);
+            let result = match self.fetch_build(&identifier, spec, REQUEST_TIMEOUT).await {
+                Ok(fetched) => self.add_fetched(talent_manager, rejected, metadata, spec, &identifier, fetched),
+                Err(e) => {
+                    self.item_failed(identifier, e, failures)?;
+                    continue;
                 }
             };
+            self.report(ProgressEvent::ItemFinished { identifier: identifier.as_identifier(), result });
+            outcomes.push((identifier.as_talent_name(&self.config.auto_suffix), result));
+        }
 
-            if let Some(talent_string) = talent_string {
-                let talent = TalentLoadout::new(identifier.as_talent_name(), talent_string);
-                talent_manager.add_talent(
-                    wow_class.to_lua_format().to_string(),
-                    spec_index,
-                    talent,
+        Ok(outcomes)
+    }
+
+    /// Fetch a build, returning its talent string and the URL it came from
+    async fn fetch_build(
+        &self,
+        identifier: &TalentIdentifier,
+        spec: WowSpec,
+        timeout: std::time::Duration,
+    ) -> Result<Option<(String, String)>> {
+        let TalentIdentifier::Raid { difficulty, boss, hero } = identifier else {
+            return self.fetch_mythic_plus_build(identifier, spec, timeout).await;
+        };
+        let url = self.url_builder.build_raid_url(spec, *difficulty, boss, *hero);
+
+        info!(identifier = %identifier.as_identifier(), %url, "Fetching build");
+        match self.fetcher.fetch(&url, timeout).await? {
+            Some(talent) => {
+                info!(identifier = %identifier.as_identifier(), "Found talent build");
+                Ok(Some((talent, url)))
+            }
+            None => {
+                info!(identifier = %identifier.as_identifier(), "No talent build available");
+                Ok(None)
+            }
+        }
+    }
+
+    /// Fetch a Mythic+ build for the current timespan, falling back to the other one
+    async fn fetch_mythic_plus_build(
+        &self,
+        identifier: &TalentIdentifier,
+        spec: WowSpec,
+        timeout: std::time::Duration,
+    ) -> Result<Option<(String, String)>> {
+        // Try primary timespan first
+        let primary_timespan = MythicPlusTimespan::primary_for_today();
+        let url = identifier.archon_url(&self.url_builder, spec, primary_timespan);
+
+        info!(identifier = %identifier.as_identifier(), %url, "Fetching build");
+
+        if let Some(talent) = self.fetcher.fetch(&url, timeout).await? {
+            info!(identifier = %identifier.as_identifier(), timespan = primary_timespan.as_str(), "Found talent build");
+            return Ok(Some((talent, url)));
+        }
+
+        // Try fallback timespan
+        let fallback_timespan = primary_timespan.fallback();
+        let fallback_url = identifier.archon_url(&self.url_builder, spec, fallback_timespan);
+
+        info!(
+            identifier = %identifier.as_identifier(),
+            timespan = fallback_timespan.as_str(),
+            url = %fallback_url,
+            "Trying fallback timespan"
+        );
+
+        match self.fetcher.fetch(&fallback_url, timeout).await? {
+            Some(talent) => {
+                info!(
+                    identifier = %identifier.as_identifier(),
+                    timespan = fallback_timespan.as_str(),
+                    "Found talent build"
                 );
-                count += 1;
+                Ok(Some((talent, fallback_url)))
+            }
+            None => {
+                info!(identifier = %identifier.as_identifier(), "No talent build available");
+                Ok(None)
+            }
+        }
+    }
+
+    /// Tidy up a spec once its builds are in: collapse identical raid builds among `names`,
+    /// prune previous versions, merge identical builds, enforce the loadout limit and sort
+    fn finish_spec(
+        &self,
+        talent_manager: &mut LuaTalentManager,
+        metadata: &mut MetadataStore,
+        character: &Character,
+        spec: WowSpec,
+        names: &[String],
+        cleanup: &mut SpecCleanup,
+    ) {
+        let (class_name, spec_index) = (spec.class.to_lua_format(), spec.index);
+        let raid_bosses = self.config.all_raid_bosses();
+
+        // One loadout for the raid builds of a difficulty that share a talent string; sorted first, so
+        // builds put back from the previous update don't change the order of a loadout's bosses
+        if self.config.collapse_identical {
+            talent_manager.sort_spec(class_name, spec_index, &raid_bosses);
+            for loadout in talent_manager.collapse_raid_builds(class_name, spec_index, names) {
+                let builds = loadout.members.len();
+                info!(name = %loadout.name, builds, "Collapsed identical raid builds");
+                metadata.collapse(class_name, spec_index, &loadout.name, &loadout.members);
+                cleanup.collapsed_builds.push(CollapsedBuild {
+                    character: character.name.clone(),
+                    class_name: class_name.to_string(),
+                    spec: spec.slug.to_string(),
+                    loadout,
+                });
+            }
+        }
+
+        if let Some(keep) = self.config.keep_previous_builds.filter(|keep| *keep > 0) {
+            let today = chrono::Local::now().date_naive();
+            let pruned = talent_manager.prune_previous_versions(class_name, spec_index, keep, today);
+            if pruned > 0 {
+                info!(count = pruned, "Removed previous build versions");
+            }
+        }
+
+        // Collapse builds that ended up with the same talent string
+        if self.config.merge_identical_builds {
+            let merged = talent_manager.dedupe_spec(class_name, spec_index, DedupePolicy::MergeNames);
+            if merged > 0 {
+                info!(count = merged, "Merged identical builds");
+            }
+            cleanup.merged_talents += merged;
+        }
+
+        // Stay within the addon's per-spec loadout limit
+        if let Some(max) = self.config.max_loadouts_per_spec {
+            for talent in talent_manager.enforce_cap(class_name, spec_index, max) {
+                info!(name = %talent.name, limit = max, "Evicted loadout over the loadout limit");
+                cleanup.evicted_talents.push(EvictedTalent {
+                    class_name: class_name.to_string(),
+                    spec_index,
+                    name: talent.name,
+                });
+            }
+        }
+
+        // Raid builds in encounter order (raid_bosses comes from content discovery), then M+
+        talent_manager.sort_spec(class_name, spec_index, &raid_bosses);
+    }
+
+    /// Compare a fetched loadout with its version in the file and metadata as loaded (`previous`)
+    /// None if the file didn't have the build yet.
+    fn compare_with_previous(
+        &self,
+        talent_manager: &LuaTalentManager,
+        previous: (&LuaTalentManager, &MetadataStore),
+        character: &Character,
+        spec: WowSpec,
+        name: &str,
+    ) -> Option<BuildChange> {
+        let old = previous_loadout(previous, spec, name)?.text.clone();
+        let new = talent_manager
+            .get_spec_talents(spec.class.to_lua_format(), spec.index)
+            .and_then(|talents| talents.iter().find(|talent| talent.name == name))?
+            .text
+            .clone();
+
+        let change = BuildChange {
+            character: character.name.clone(),
+            class_name: spec.class.to_lua_format().to_string(),
+            spec: spec.slug.to_string(),
+            identifier: name.strip_suffix(self.config.auto_suffix.as_str()).unwrap_or(name).to_string(),
+            old_hash: metadata::talent_string_hash(&old),
+            new_hash: metadata::talent_string_hash(&new),
+            changed_nodes: talent_string::count_changed_nodes(&old, &new),
+        };
+        if change.old_hash != change.new_hash {
+            info!(
+                identifier = %change.identifier,
+                changed_nodes = ?change.changed_nodes,
+                "Build changed since the previous update"
+            );
+        }
+        Some(change)
+    }
+
+    /// Report a build as fresh if it was fetched within `update_if_older_than_hours`, so it isn't
+    /// fetched again; returns whether it was skipped
+    fn skip_fresh(
+        &self,
+        metadata: &MetadataStore,
+        spec: WowSpec,
+        identifier: &TalentIdentifier,
+        outcomes: &mut Vec<(String, ItemResult)>,
+    ) -> bool {
+        let Some(hours) = self.config.update_if_older_than_hours else {
+            return false;
+        };
+        let name = identifier.as_talent_name(&self.config.auto_suffix);
+        let Some(fetched_at) = metadata
+            .fetched(spec.class.to_lua_format(), spec.index, &name)
+            .and_then(|metadata| DateTime::parse_from_rfc3339(&metadata.fetched_at).ok())
+        else {
+            return false;
+        };
+        // Beyond what a duration can hold, every build is fresh
+        let max_age = i64::try_from(hours).ok().and_then(Duration::try_hours);
+        if max_age.is_some_and(|max_age| Utc::now().signed_duration_since(fetched_at) >= max_age) {
+            return false;
+        }
+
+        info!(identifier = %identifier.as_identifier(), %fetched_at, "Keeping recent build");
+        self.report(ProgressEvent::ItemFinished { identifier: identifier.as_identifier(), result: ItemResult::Fresh });
+        outcomes.push((name, ItemResult::Fresh));
+        true
+    }
+
+    /// Record a build that failed to fetch, or fail the run if `abort_on_error` is set
+    fn item_failed(
+        &self,
+        identifier: TalentIdentifier,
+        error: anyhow::Error,
+        failures: &mut Vec<(TalentIdentifier, anyhow::Error)>,
+    ) -> Result<()> {
+        if self.config.abort_on_error {
+            return Err(error);
+        }
+        warn!(identifier = %identifier.as_identifier(), "Fetch failed: {:#}", error);
+        self.report(ProgressEvent::ItemFinished {
+            identifier: identifier.as_identifier(),
+            result: ItemResult::Failed,
+        });
+        failures.push((identifier, error));
+        Ok(())
+    }
+
+    /// Add the result of fetching a build to the spec and record where it came from
+    fn add_fetched(
+        &self,
+        talent_manager: &mut LuaTalentManager,
+        rejected: &mut Vec<RejectedTalent>,
+        metadata: &mut MetadataStore,
+        spec: WowSpec,
+        identifier: &TalentIdentifier,
+        fetched: Option<(String, String)>,
+    ) -> ItemResult {
+        let Some((talent_string, source_url)) = fetched else {
+            return ItemResult::NotAvailable;
+        };
+        let name = identifier.as_talent_name(&self.config.auto_suffix);
+        let fetched = LoadoutMetadata::fetched_now(source_url, &talent_string);
+        let talent = TalentLoadout::with_spec_icon(name.clone(), talent_string, spec.class, spec.index);
+        if self.add_validated(talent_manager, rejected, spec, talent) {
+            metadata.record(spec.class.to_lua_format(), spec.index, &name, fetched);
+            ItemResult::Added
+        } else {
+            ItemResult::Rejected
+        }
+    }
+
+    /// Current auto-generated builds of a spec, for the build list
+    fn plaintext_talents(&self, talent_manager: &LuaTalentManager, spec: WowSpec) -> Vec<TalentLoadout> {
+        talent_manager
+            .get_spec_talents(spec.class.to_lua_format(), spec.index)
+            .map(|talents| {
+                talents
+                    .iter()
+                    .filter(|t| t.is_auto_generated(&self.config.auto_suffix) && !t.is_previous_version())
+                    .cloned()
+                    .collect()
+            })
+            .unwrap_or_default()
+    }
+
+    /// Put the loadout `name` of the file as it was before the update (`previous`) back into the spec
+    /// A build the previous update collapsed is put back under its own name, with its metadata.
+    fn keep_previous(
+        &self,
+        talent_manager: &mut LuaTalentManager,
+        metadata: &mut MetadataStore,
+        previous: (&LuaTalentManager, &MetadataStore),
+        spec: WowSpec,
+        name: &str,
+    ) {
+        let (class_name, spec_index) = (spec.class.to_lua_format(), spec.index);
+        let Some(loadout) = previous_loadout(previous, spec, name) else {
+            return;
+        };
+        let mut loadout = loadout.clone();
+        if loadout.name != name {
+            if let Some((_, collapsed)) = previous.1.collapsed_into(class_name, spec_index, name) {
+                let mut fetched = collapsed.clone();
+                fetched.collapsed_from.clear();
+                metadata.record(class_name, spec_index, name, fetched);
             }
+            loadout.name = name.to_string();
+            loadout.icon =
+                icons::icon_for(name, &self.config.auto_suffix, spec.class, spec_index, &self.config.icon_overrides);
+        }
+        talent_manager.upsert_talent(class_name.to_string(), spec_index, loadout);
+    }
+
+    fn report_item_started(&self, identifier: &TalentIdentifier, spec: WowSpec) {
+        self.report(ProgressEvent::ItemStarted {
+            identifier: identifier.as_identifier(),
+            class: spec.class.to_lua_format(),
+            spec: spec.slug,
+        });
+    }
+
+    /// Add a fetched build (with its content icon) if its talent string belongs to the
+    /// target spec, otherwise record it as rejected. Returns whether the build was added.
+    fn add_validated(
+        &self,
+        talent_manager: &mut LuaTalentManager,
+        rejected: &mut Vec<RejectedTalent>,
+        spec: WowSpec,
+        mut talent: TalentLoadout,
+    ) -> bool {
+        let (wow_class, spec_index) = (spec.class, spec.index);
+        // Spec IDs in talent strings are only known for retail
+        let validation = match self.config.flavor {
+            GameFlavor::Retail => talent_string::validate_spec(&talent.text, wow_class, spec_index),
+            _ => Ok(()),
+        };
+        if let Err(e) = validation {
+            warn!(name = %talent.name, "Rejected build: {}", e);
+            rejected.push(RejectedTalent {
+                class_name: wow_class.to_lua_format().to_string(),
+                spec_index,
+                name: talent.name,
+                reason: e.to_string(),
+            });
+            return false;
         }
 
-        Ok(count)
+        talent.icon = icons::icon_for(&talent.name, &self.config.auto_suffix, wow_class, spec_index, &self.config.icon_overrides);
+        talent_manager.upsert_talent(wow_class.to_lua_format().to_string(), spec_index, talent);
+        true
+    }
+}
+
+/// Whether a build failed for a reason that may pass on a retry
+fn is_transient(error: &anyhow::Error) -> bool {
+    error.downcast_ref::<FetchError>().is_some_and(|error| error.transient)
+}
+
+fn content_type(identifier: &TalentIdentifier) -> ContentType {
+    match identifier {
+        TalentIdentifier::Raid { .. } => ContentType::Raid,
+        TalentIdentifier::MythicPlus { .. } => ContentType::MythicPlus,
+    }
+}
+
+/// Names of the loadouts kept because they are recent
+fn fresh_names(outcomes: &[(String, ItemResult)]) -> impl Iterator<Item = String> + '_ {
+    outcomes
+        .iter()
+        .filter(|(_, result)| *result == ItemResult::Fresh)
+        .map(|(name, _)| name.clone())
+}
+
+/// The loadout `name` of a spec in the file and metadata as loaded: the loadout of that name, or
+/// the one it was collapsed into
+fn previous_loadout<'a>(
+    (original_talents, original_metadata): (&'a LuaTalentManager, &MetadataStore),
+    spec: WowSpec,
+    name: &str,
+) -> Option<&'a TalentLoadout> {
+    let class_name = spec.class.to_lua_format();
+    let talents = original_talents.get_spec_talents(class_name, spec.index)?;
+    talents.iter().find(|talent| talent.name == name).or_else(|| {
+        let (collapsed, _) = original_metadata.collapsed_into(class_name, spec.index, name)?;
+        talents.iter().find(|talent| talent.name == collapsed)
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_util::temp_dir;
+    use crate::wow::WowClass;
+    use futures_util::future::BoxFuture;
+    use std::sync::{Arc, Mutex};
+
+    /// Fails the first request to each URL with a timeout, then serves an Arms build
+    struct FlakyFetcher {
+        requests: Arc<Mutex<Vec<(String, std::time::Duration)>>>,
+    }
+
+    impl TalentFetcher for FlakyFetcher {
+        fn fetch<'a>(&'a self, url: &'a str, timeout: std::time::Duration) -> BoxFuture<'a, Result<Option<String>>> {
+            let mut requests = self.requests.lock().unwrap();
+            let first = !requests.iter().any(|(requested, _)| requested == url);
+            requests.push((url.to_string(), timeout));
+            Box::pin(async move {
+                if first {
+                    Err(FetchError::transient("Request timed out").into())
+                } else {
+                    Ok(Some("CcEAAAAAAAAAAAAAAAAAAAAAAAAAAAAAA".to_string()))
+                }
+            })
+        }
+    }
+
+    /// Serves the same Arms build for every page, failing pages whose URL contains `failing`
+    struct StaticFetcher {
+        failing: Option<String>,
+    }
+
+    impl TalentFetcher for StaticFetcher {
+        fn fetch<'a>(&'a self, url: &'a str, _timeout: std::time::Duration) -> BoxFuture<'a, Result<Option<String>>> {
+            let failing = self.failing.as_deref().is_some_and(|failing| url.contains(failing));
+            Box::pin(async move {
+                if failing {
+                    Err(FetchError::permanent("Page not found").into())
+                } else {
+                    Ok(Some("CcEAAAAAAAAAAAAAAAAAAAAAAAAAAAAAA".to_string()))
+                }
+            })
+        }
+    }
+
+    #[tokio::test]
+    async fn test_progress_events() {
+        let dir = temp_dir("progress");
+        let path = dir.join("TalentLoadoutsEx.lua");
+        let mut config = Config::example();
+        config.output_path = Some(path.clone());
+        config.backup_count = 0;
+        // Nothing to fetch, so the run stays offline
+        for selection in config.characters.iter_mut().flat_map(|character| &mut character.specializations) {
+            selection.fetch_raid = false;
+            selection.fetch_mythic_plus = false;
+        }
+
+        let events = Arc::new(Mutex::new(Vec::new()));
+        let collected = Arc::clone(&events);
+        let summary = TalentOrchestrator::new(config)
+            .with_progress_reporter(move |update: &ProgressUpdate<'_>| {
+                collected.lock().unwrap().push(serde_json::to_value(update).unwrap());
+            })
+            .run()
+            .await
+            .unwrap();
+        assert_eq!(summary.total_talents_updated, 0);
+
+        let events = events.lock().unwrap();
+        let types: Vec<_> = events
+            .iter()
+            .map(|event| (event["sequence"].as_u64().unwrap(), event["type"].as_str().unwrap()))
+            .collect();
+        assert_eq!(types, [(1, "runStarted"), (2, "fileWritten"), (3, "runFinished")]);
+        assert_eq!(events[0]["totalItems"], 0);
+        assert_eq!(events[1]["path"], path.to_str().unwrap());
+        assert_eq!(events[2]["summary"]["total_talents_updated"], 0);
+
+        // Every configured character and spec is listed, even without builds
+        assert_eq!(summary.characters.len(), 2);
+        assert!(summary.characters.iter().all(|character| character.specs.len() == 2));
+    }
+
+    #[tokio::test]
+    async fn test_unchanged_file_is_not_rewritten() {
+        let dir = temp_dir("unchanged");
+        let path = dir.join("TalentLoadoutsEx.lua");
+        let mut config = Config::example();
+        config.output_path = Some(path.clone());
+        config.backup_count = 3;
+        for selection in config.characters.iter_mut().flat_map(|character| &mut character.specializations) {
+            selection.fetch_raid = false;
+            selection.fetch_mythic_plus = false;
+        }
+
+        let first = TalentOrchestrator::new(config.clone()).run().await.unwrap();
+        assert!(first.files[0].file_written);
+        assert!(!first.files[0].unchanged);
+
+        let second = TalentOrchestrator::new(config.clone()).run().await.unwrap();
+        assert!(!second.files[0].file_written);
+        assert!(second.files[0].unchanged);
+        assert_eq!(second.files[0].backup_path, None);
+
+        config.force_write = true;
+        let forced = TalentOrchestrator::new(config).run().await.unwrap();
+        assert!(forced.files[0].file_written);
+        assert!(forced.files[0].backup_path.is_some());
+    }
+
+    #[tokio::test]
+    async fn test_fresh_builds_are_kept() {
+        let dir = temp_dir("fresh");
+        let path = dir.join("TalentLoadoutsEx.lua");
+        let mut config = Config::example();
+        config.output_path = Some(path.clone());
+        config.backup_count = 0;
+        config.characters.truncate(1);
+        config.characters[0].specializations.truncate(1);
+        config.characters[0].specializations[0].fetch_raid = false;
+        config.dungeons.truncate(1);
+        config.update_if_older_than_hours = Some(24);
+
+        // A Mythic+ build fetched an hour ago, so the run stays offline
+        let spec = WowClass::Warrior.spec("arms").unwrap();
+        let identifier = TalentIdentifier::MythicPlus { dungeon: config.dungeons[0].clone(), hero: None };
+        let name = identifier.as_talent_name(&config.auto_suffix);
+        let mut manager = LuaTalentManager::new();
+        manager.upsert_talent("WARRIOR".to_string(), 1, TalentLoadout::new(name.clone(), "TALENTS".to_string()));
+        manager.write_to_file(&path).unwrap();
+        let mut fetched = LoadoutMetadata::fetched_now("https://archon.gg", "TALENTS");
+        fetched.fetched_at = (Utc::now() - Duration::hours(1)).to_rfc3339();
+        let mut store = MetadataStore::default();
+        store.record("WARRIOR", 1, &name, fetched);
+        store.save(metadata::sidecar_path(&path)).unwrap();
+
+        let mut outcomes = Vec::new();
+        let orchestrator = TalentOrchestrator::new(config);
+        assert!(orchestrator.skip_fresh(&store, spec, &identifier, &mut outcomes));
+        assert_eq!(outcomes, [(name.clone(), ItemResult::Fresh)]);
+
+        let summary = orchestrator.run().await.unwrap();
+        assert_eq!(summary.skipped_fresh, 1);
+        assert_eq!(summary.characters[0].specs[0].fresh, 1);
+        assert!(summary.files[0].unchanged);
+        let (kept, _) = LuaTalentManager::load_from_file(&path, None).unwrap();
+        assert_eq!(kept.get_spec_talents("WARRIOR", 1).unwrap()[0].text, "TALENTS");
+
+        // Older than the limit: stale
+        let mut stale = store.clone();
+        stale.loadouts.get_mut("WARRIOR").unwrap().get_mut(&1).unwrap().get_mut(&name).unwrap().fetched_at =
+            (Utc::now() - Duration::hours(25)).to_rfc3339();
+        assert!(!orchestrator.skip_fresh(&stale, spec, &identifier, &mut outcomes));
+    }
+
+    #[tokio::test]
+    async fn test_transient_failures_are_retried() {
+        let dir = temp_dir("retry");
+        let path = dir.join("TalentLoadoutsEx.lua");
+        let mut config = Config::example();
+        config.output_path = Some(path.clone());
+        config.backup_count = 0;
+        config.characters.truncate(1);
+        config.characters[0].specializations.truncate(1);
+        config.characters[0].specializations[0].fetch_mythic_plus = false;
+        config.raid_bosses.truncate(1);
+        config.raid_difficulties.truncate(1);
+
+        let requests = Arc::new(Mutex::new(Vec::new()));
+        let fetcher = FlakyFetcher { requests: Arc::clone(&requests) };
+        let summary = TalentOrchestrator::new(config.clone()).with_fetcher(fetcher).run().await.unwrap();
+
+        let timeouts: Vec<_> = requests.lock().unwrap().iter().map(|(_, timeout)| *timeout).collect();
+        assert_eq!(timeouts, [REQUEST_TIMEOUT, RETRY_TIMEOUT]);
+        assert!(summary.errors.is_empty());
+        assert_eq!(summary.raid_talents, 1);
+        assert_eq!(summary.characters[0].specs[0].failed, 0);
+        assert_eq!(summary.characters[0].specs[0].raid_talents, 1);
+        let outcomes = serde_json::to_value(&summary).unwrap()["outcomes"].clone();
+        assert_eq!(
+            outcomes,
+            serde_json::json!({
+                "succeeded": 1,
+                "no_data": 0,
+                "failed_transient": 0,
+                "failed_permanent": 0,
+                "retried": 1,
+                "recovered": 1,
+            })
+        );
+
+        let identifier = TalentIdentifier::Raid {
+            difficulty: config.difficulties()[0],
+            boss: config.raid_bosses[0].clone(),
+            hero: None,
+        };
+        let (written, _) = LuaTalentManager::load_from_file(&path, None).unwrap();
+        let talents = written.get_spec_talents("WARRIOR", 1).unwrap();
+        assert_eq!(talents[0].name, identifier.as_talent_name(&config.auto_suffix));
+        assert_eq!(talents[0].text, "CcEAAAAAAAAAAAAAAAAAAAAAAAAAAAAAA");
+
+        // A recovered build counts towards the loadout limit like any other
+        let mut manual = written.clone();
+        let single_target = TalentLoadout::new("Single target".to_string(), "TALENTS".to_string());
+        manual.upsert_talent("WARRIOR".to_string(), 1, single_target);
+        manual.write_to_file(&path).unwrap();
+        config.max_loadouts_per_spec = Some(1);
+        let fetcher = FlakyFetcher { requests: Arc::new(Mutex::new(Vec::new())) };
+        let summary = TalentOrchestrator::new(config.clone()).with_fetcher(fetcher).run().await.unwrap();
+        assert_eq!(summary.outcomes.recovered, 1);
+        let evicted: Vec<_> = summary.evicted_talents.iter().map(|talent| talent.name.as_str()).collect();
+        assert_eq!(evicted, [identifier.as_talent_name(&config.auto_suffix)]);
+        let (written, _) = LuaTalentManager::load_from_file(&path, None).unwrap();
+        let names: Vec<_> = written.get_spec_talents("WARRIOR", 1).unwrap().iter().map(|t| t.name.as_str()).collect();
+        assert_eq!(names, ["Single target"]);
+    }
+
+    #[tokio::test]
+    async fn test_collapsed_builds_are_found_by_boss() {
+        let dir = temp_dir("collapse");
+        let path = dir.join("TalentLoadoutsEx.lua");
+        let mut config = Config::example();
+        config.output_path = Some(path.clone());
+        config.backup_count = 0;
+        config.characters.truncate(1);
+        config.characters[0].specializations.truncate(1);
+        config.characters[0].specializations[0].fetch_mythic_plus = false;
+        config.raid_difficulties.truncate(1);
+        config.collapse_identical = true;
+        let members: Vec<_> = config
+            .raid_bosses
+            .iter()
+            .map(|boss| {
+                let difficulty = config.difficulties()[0];
+                let identifier = TalentIdentifier::Raid { difficulty, boss: boss.clone(), hero: None };
+                identifier.as_talent_name(&config.auto_suffix)
+            })
+            .collect();
+        async fn run(config: &Config, failing: Option<&str>) -> Result<UpdateSummary> {
+            let fetcher = StaticFetcher { failing: failing.map(str::to_string) };
+            TalentOrchestrator::new(config.clone()).with_fetcher(fetcher).run().await
+        }
+
+        let summary = run(&config, None).await.unwrap();
+        assert_eq!(summary.collapsed_builds[0].loadout.members, members);
+        let general = summary.collapsed_builds[0].loadout.name.clone();
+
+        // A boss that fails keeps its build from the collapsed loadout, the others are compared with it
+        let summary = run(&config, Some(&config.raid_bosses[1])).await.unwrap();
+        assert_eq!(summary.errors.len(), 1);
+        assert_eq!(summary.unchanged_builds.len(), 2);
+        assert_eq!(summary.collapsed_builds[0].loadout.name, general);
+        assert_eq!(summary.collapsed_builds[0].loadout.members, members);
+        let (written, _) = LuaTalentManager::load_from_file(&path, None).unwrap();
+        let names: Vec<_> = written.get_spec_talents("WARRIOR", 1).unwrap().iter().map(|t| t.name.as_str()).collect();
+        assert_eq!(names, [general.as_str()]);
+
+        // Every boss of the collapsed loadout is recent, so nothing is fetched
+        config.update_if_older_than_hours = Some(24);
+        let summary = run(&config, Some("")).await.unwrap();
+        assert_eq!(summary.skipped_fresh, members.len());
+        assert!(summary.errors.is_empty());
+        assert!(summary.files[0].unchanged);
+    }
+
+    #[test]
+    fn test_total_items() {
+        // 2 characters with 2 specs, each with 3 bosses on 2 difficulties and 3 dungeons
+        let mut config = Config::example();
+        assert_eq!(TalentOrchestrator::new(config.clone()).total_items(), 36);
+
+        config.characters[0].specializations[0].fetch_raid = false;
+        config.skip_dungeons = vec!["ara-kara".to_string()];
+        assert_eq!(TalentOrchestrator::new(config).total_items(), 26);
+    }
+
+    #[test]
+    fn test_spec_summary_record() {
+        let mut summary = SpecSummary {
+            spec: "arms".to_string(),
+            spec_name: "Arms".to_string(),
+            hero_talent: None,
+            raid_talents: 0,
+            mythic_plus_talents: 0,
+            added: Vec::new(),
+            not_available: 0,
+            rejected: 0,
+            failed: 0,
+            fresh: 0,
+        };
+        summary.record(
+            ContentType::Raid,
+            vec![
+                ("Boss Heroic".to_string(), ItemResult::Added),
+                ("Boss Mythic".to_string(), ItemResult::NotAvailable),
+            ],
+        );
+        summary.record(
+            ContentType::MythicPlus,
+            vec![("Dungeon".to_string(), ItemResult::Added), ("Other".to_string(), ItemResult::Rejected)],
+        );
+
+        assert_eq!((summary.raid_talents, summary.mythic_plus_talents), (1, 1));
+        assert_eq!((summary.not_available, summary.rejected), (1, 1));
+        let added = serde_json::to_value(&summary.added).unwrap();
+        assert_eq!(
+            added,
+            serde_json::json!([
+                { "name": "Boss Heroic", "content_type": "raid" },
+                { "name": "Dungeon", "content_type": "mythic_plus" },
+            ])
+        );
     }
 }
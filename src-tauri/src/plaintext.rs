@@ -0,0 +1,109 @@
+use crate::archon::TalentIdentifier;
+use crate::lua_talent::TalentLoadout;
+use crate::talent_string::import_string;
+use crate::wow::WowClass;
+
+/// Default file name of the plaintext build list
+pub const PLAINTEXT_FILE_NAME: &str = "talent-builds.md";
+
+/// Fetched builds of one character spec, for the plaintext build list
+#[derive(Debug, Clone)]
+pub struct SpecBuilds {
+    pub character: String,
+    pub class: WowClass,
+    pub spec: String,
+    pub talents: Vec<TalentLoadout>,
+}
+
+/// Render a markdown build list grouped by character, spec and content type
+/// `suffix` is the auto-generated name suffix used to recognize the content of a build
+pub fn render_builds(builds: &[SpecBuilds], suffix: &str) -> String {
+    let mut result = String::from("# Talent builds\n\n");
+    result.push_str(&format!(
+        "Generated {}. Paste a string into the in-game talent import dialog.\n",
+        chrono::Local::now().format("%Y-%m-%d %H:%M")
+    ));
+
+    let mut current_character = None;
+
+    for spec_builds in builds {
+        if current_character != Some((&spec_builds.character, &spec_builds.class)) {
+            current_character = Some((&spec_builds.character, &spec_builds.class));
+            result.push_str(&format!("\n## {} ({})\n", spec_builds.character, spec_builds.class));
+        }
+
+        let spec_name = spec_builds.class.spec_display_name(&spec_builds.spec).unwrap_or(&spec_builds.spec);
+        result.push_str(&format!("\n### {}\n", spec_name));
+
+        if spec_builds.talents.is_empty() {
+            result.push_str("\nNo builds available.\n");
+            continue;
+        }
+
+        let raid: Vec<_> = spec_builds
+            .talents
+            .iter()
+            .filter(|t| matches!(TalentIdentifier::from_talent_name(&t.name, suffix), Some(TalentIdentifier::Raid { .. })))
+            .collect();
+        let mythic_plus: Vec<_> = spec_builds
+            .talents
+            .iter()
+            .filter(|t| matches!(TalentIdentifier::from_talent_name(&t.name, suffix), Some(TalentIdentifier::MythicPlus { .. })))
+            .collect();
+        let other: Vec<_> = spec_builds
+            .talents
+            .iter()
+            .filter(|t| TalentIdentifier::from_talent_name(&t.name, suffix).is_none())
+            .collect();
+
+        for (heading, talents) in [("Raid", raid), ("Mythic+", mythic_plus), ("Other", other)] {
+            if talents.is_empty() {
+                continue;
+            }
+
+            result.push_str(&format!("\n#### {}\n\n", heading));
+            for talent in talents {
+                result.push_str(&format!("- {}\n  `{}`\n", talent.name, import_string(&talent.text)));
+            }
+        }
+    }
+
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::archon::DEFAULT_AUTO_SUFFIX;
+
+    #[test]
+    fn test_render_builds_groups_by_content() {
+        let builds = vec![
+            SpecBuilds {
+                character: "MyWarrior".to_string(),
+                class: WowClass::Warrior,
+                spec: "arms".to_string(),
+                talents: vec![
+                    TalentLoadout::new("M+-ara-kara_ARCT".to_string(), "warrior/arms/MPLUS".to_string()),
+                    TalentLoadout::new("R-heroic-sikran_ARCT".to_string(), "warrior/arms/RAID".to_string()),
+                ],
+            },
+            SpecBuilds {
+                character: "MyWarrior".to_string(),
+                class: WowClass::Warrior,
+                spec: "fury".to_string(),
+                talents: Vec::new(),
+            },
+        ];
+
+        let output = render_builds(&builds, DEFAULT_AUTO_SUFFIX);
+
+        assert_eq!(output.matches("## MyWarrior (Warrior)").count(), 1);
+        let raid = output.find("#### Raid").unwrap();
+        let mythic_plus = output.find("#### Mythic+").unwrap();
+        assert!(raid < mythic_plus);
+        assert!(output.contains("- R-heroic-sikran_ARCT\n  `RAID`"));
+        assert!(output.contains("- M+-ara-kara_ARCT\n  `MPLUS`"));
+        assert!(output.contains("### Fury\n\nNo builds available."));
+    }
+}
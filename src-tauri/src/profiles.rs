@@ -0,0 +1,213 @@
+use crate::atomic_write::write_atomic;
+use crate::config::{self, Config};
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// Folder of the profiles in the app data directory
+pub const PROFILES_DIR_NAME: &str = "profiles";
+
+/// Longest profile name accepted, in characters
+const MAX_NAME_LENGTH: usize = 64;
+
+/// Names Windows reserves for devices, which can't be used as file names
+const RESERVED_FILE_NAMES: &[&str] = &[
+    "con", "prn", "aux", "nul", "com1", "com2", "com3", "com4", "com5", "com6", "com7", "com8", "com9", "lpt1",
+    "lpt2", "lpt3", "lpt4", "lpt5", "lpt6", "lpt7", "lpt8", "lpt9",
+];
+
+/// A named config as stored in its profile file
+/// The name is kept as entered; the file name is derived from it by `file_stem`.
+#[derive(Serialize, Deserialize)]
+struct ProfileFile {
+    name: String,
+    config: serde_json::Value,
+}
+
+/// Named configs, one JSON file each in a folder of the app data directory
+pub struct ProfileStore {
+    dir: PathBuf,
+}
+
+impl ProfileStore {
+    pub fn new(dir: impl Into<PathBuf>) -> Self {
+        Self { dir: dir.into() }
+    }
+
+    /// Names of all profiles, sorted case-insensitively
+    /// Files that can't be read are skipped.
+    pub fn list(&self) -> Result<Vec<String>> {
+        if !self.dir.exists() {
+            return Ok(Vec::new());
+        }
+
+        let mut names = Vec::new();
+        for entry in fs::read_dir(&self.dir).context("Failed to read profiles folder")? {
+            let path = entry.context("Failed to read profiles folder")?.path();
+            if path.extension().is_some_and(|ext| ext == "json") {
+                match Self::read(&path) {
+                    Ok(profile) => names.push(profile.name),
+                    Err(e) => tracing::warn!("Skipping profile {}: {:#}", path.display(), e),
+                }
+            }
+        }
+        names.sort_by_key(|name| name.to_lowercase());
+        Ok(names)
+    }
+
+    /// Config of a profile, upgraded to the current config version
+    pub fn get(&self, name: &str) -> Result<Config> {
+        let path = self.path_for(name)?;
+        let profile = self.existing(name, &path)?;
+        config::migrate(profile.config).with_context(|| format!("Profile '{}' is invalid", name))
+    }
+
+    /// Create or replace a profile
+    /// Fails if another profile maps to the same file (e.g. "Alt/Raid" and "Alt Raid").
+    pub fn save(&self, name: &str, config: &Config) -> Result<()> {
+        let path = self.path_for(name)?;
+        if path.exists() {
+            let existing = Self::read(&path)?;
+            if existing.name != name {
+                anyhow::bail!(
+                    "Profile name '{}' conflicts with the existing profile '{}'; choose another name",
+                    name,
+                    existing.name
+                );
+            }
+        }
+        self.write(&path, name, config)
+    }
+
+    /// Remove a profile
+    pub fn delete(&self, name: &str) -> Result<()> {
+        let path = self.path_for(name)?;
+        self.existing(name, &path)?;
+        fs::remove_file(&path).with_context(|| format!("Failed to delete profile '{}'", name))
+    }
+
+    /// Copy a profile to a new name, which must not be taken yet
+    pub fn duplicate(&self, from: &str, to: &str) -> Result<()> {
+        let config = self.get(from)?;
+        let path = self.path_for(to)?;
+        if path.exists() {
+            let existing = Self::read(&path).map(|profile| profile.name).unwrap_or_else(|_| to.to_string());
+            anyhow::bail!("Profile '{}' already exists", existing);
+        }
+        self.write(&path, to, &config)
+    }
+
+    /// File of a profile; fails for names that are empty or too long after sanitizing
+    fn path_for(&self, name: &str) -> Result<PathBuf> {
+        let stem = file_stem(name)?;
+        Ok(self.dir.join(format!("{}.json", stem)))
+    }
+
+    /// Read the profile at `path`, which must exist and belong to `name`
+    fn existing(&self, name: &str, path: &Path) -> Result<ProfileFile> {
+        if !path.exists() {
+            anyhow::bail!("Profile '{}' doesn't exist", name);
+        }
+        let profile = Self::read(path)?;
+        if profile.name != name {
+            anyhow::bail!("Profile '{}' doesn't exist (did you mean '{}'?)", name, profile.name);
+        }
+        Ok(profile)
+    }
+
+    fn read(path: &Path) -> Result<ProfileFile> {
+        let content = fs::read_to_string(path).with_context(|| format!("Failed to read {}", path.display()))?;
+        serde_json::from_str(&content).with_context(|| format!("Failed to parse {}", path.display()))
+    }
+
+    fn write(&self, path: &Path, name: &str, config: &Config) -> Result<()> {
+        fs::create_dir_all(&self.dir).context("Failed to create profiles folder")?;
+        let profile = ProfileFile {
+            name: name.to_string(),
+            config: serde_json::to_value(config).context("Failed to serialize config")?,
+        };
+        let json = serde_json::to_string_pretty(&profile).context("Failed to serialize profile")?;
+        write_atomic(path, json).with_context(|| format!("Failed to save profile '{}'", name))
+    }
+}
+
+/// File name (without extension) for a profile name: lowercase, with anything but letters,
+/// digits, `-` and `_` replaced by `_`, so names differing only in case or punctuation share a file
+fn file_stem(name: &str) -> Result<String> {
+    let name = name.trim();
+    if name.is_empty() {
+        anyhow::bail!("Profile name must not be empty");
+    }
+    if name.chars().count() > MAX_NAME_LENGTH {
+        anyhow::bail!("Profile name must be at most {} characters", MAX_NAME_LENGTH);
+    }
+
+    let stem: String = name
+        .to_lowercase()
+        .chars()
+        .map(|c| if c.is_alphanumeric() || c == '-' || c == '_' { c } else { '_' })
+        .collect();
+    if stem.chars().all(|c| c == '_') {
+        anyhow::bail!("Profile name '{}' needs at least one letter or digit", name);
+    }
+    if RESERVED_FILE_NAMES.contains(&stem.as_str()) {
+        return Ok(format!("{}_", stem));
+    }
+    Ok(stem)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_util::{temp_dir, TempDir};
+
+    fn store(dir: &TempDir) -> ProfileStore {
+        ProfileStore::new(dir.join("profiles"))
+    }
+
+    #[test]
+    fn test_profile_crud() {
+        let dir = temp_dir("profiles-crud");
+        let store = store(&dir);
+        assert!(store.list().unwrap().is_empty());
+
+        let mut config = Config::example();
+        store.save("Main raid week", &config).unwrap();
+        store.save("alt catch-up", &config).unwrap();
+        assert_eq!(store.list().unwrap(), ["alt catch-up", "Main raid week"]);
+        assert_eq!(store.get("Main raid week").unwrap(), config);
+
+        config.dungeons.clear();
+        store.save("Main raid week", &config).unwrap();
+        assert!(store.get("Main raid week").unwrap().dungeons.is_empty());
+
+        store.duplicate("Main raid week", "PTR testing").unwrap();
+        assert_eq!(store.get("PTR testing").unwrap(), config);
+        assert!(store.duplicate("alt catch-up", "PTR testing").is_err());
+        assert!(store.duplicate("Missing", "Other").is_err());
+
+        store.delete("alt catch-up").unwrap();
+        assert_eq!(store.list().unwrap(), ["Main raid week", "PTR testing"]);
+        assert!(store.delete("alt catch-up").is_err());
+    }
+
+    #[test]
+    fn test_profile_names_are_sanitized_and_collisions_rejected() {
+        assert_eq!(file_stem("PTR: testing/11.1").unwrap(), "ptr__testing_11_1");
+        assert_eq!(file_stem("  Nul ").unwrap(), "nul_");
+        assert!(file_stem("   ").is_err());
+        assert!(file_stem("../..").is_err());
+        assert!(file_stem(&"x".repeat(MAX_NAME_LENGTH + 1)).is_err());
+
+        let dir = temp_dir("profiles-collisions");
+        let store = store(&dir);
+        let config = Config::example();
+        store.save("Alt/Raid", &config).unwrap();
+        let error = store.save("alt raid", &config).unwrap_err().to_string();
+        assert!(error.contains("conflicts with the existing profile 'Alt/Raid'"), "{}", error);
+        assert!(store.get("alt raid").unwrap_err().to_string().contains("did you mean 'Alt/Raid'"));
+        assert!(store.duplicate("Alt/Raid", "ALT RAID").is_err());
+        assert_eq!(store.list().unwrap(), ["Alt/Raid"]);
+    }
+}
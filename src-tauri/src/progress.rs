@@ -0,0 +1,57 @@
+use crate::orchestrator::UpdateSummary;
+use serde::Serialize;
+use std::path::Path;
+
+/// Outcome of fetching one build
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub enum ItemResult {
+    /// The build was added
+    Added,
+    /// Archon.gg has no build for it
+    NotAvailable,
+    /// The talent string belongs to another spec (see `UpdateSummary::rejected_talents`)
+    Rejected,
+    /// Fetching failed (see `UpdateSummary::errors`)
+    Failed,
+    /// Not fetched because the existing build is recent enough (see `Config::update_if_older_than_hours`)
+    Fresh,
+}
+
+/// A step of a talent update
+#[derive(Debug, Serialize)]
+#[serde(tag = "type", rename_all = "camelCase", rename_all_fields = "camelCase")]
+pub enum ProgressEvent<'a> {
+    /// Fetching starts; `total_items` builds are fetched
+    RunStarted { total_items: usize },
+    /// A build is being fetched
+    ItemStarted {
+        identifier: String,
+        class: &'a str,
+        spec: &'a str,
+    },
+    ItemFinished { identifier: String, result: ItemResult },
+    /// A talent file was written (not sent in a dry run)
+    FileWritten { path: &'a Path },
+    RunFinished { summary: &'a UpdateSummary },
+}
+
+/// An event numbered in the order it was reported, starting at 1
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ProgressUpdate<'a> {
+    pub sequence: u64,
+    #[serde(flatten)]
+    pub event: ProgressEvent<'a>,
+}
+
+/// Receives the progress of a talent update, e.g. to forward it to the UI
+pub trait ProgressReporter: Send + Sync {
+    fn report(&self, update: &ProgressUpdate<'_>);
+}
+
+impl<F: Fn(&ProgressUpdate<'_>) + Send + Sync> ProgressReporter for F {
+    fn report(&self, update: &ProgressUpdate<'_>) {
+        self(update)
+    }
+}
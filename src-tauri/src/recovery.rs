@@ -0,0 +1,286 @@
+use crate::lua_talent::{lua_unescape_string, LuaTalentManager, ParseIssue, TalentLoadout, DEFAULT_TABLE_NAME};
+use serde::Serialize;
+
+/// What was lost while reading a file, or recovering loadouts from one that failed to parse
+#[derive(Debug, Default, Clone, PartialEq, Eq, Serialize)]
+pub struct ParseWarnings {
+    /// The parser error that triggered the recovery
+    pub parse_error: Option<String>,
+    /// Lines that looked like loadout data but couldn't be recovered (1-based line number, content)
+    pub skipped_lines: Vec<(usize, String)>,
+    /// Entries of a file that parsed but didn't have the expected shape
+    pub issues: Vec<ParseIssue>,
+}
+
+impl ParseWarnings {
+    /// Check if the file parsed cleanly
+    pub fn is_empty(&self) -> bool {
+        self.parse_error.is_none() && self.skipped_lines.is_empty() && self.issues.is_empty()
+    }
+
+    /// Check if writing the recovered loadouts back would lose data
+    /// Besides skipped loadouts, the GUI table and addon options can't be recovered
+    pub fn drops_data(&self) -> bool {
+        self.parse_error.is_some()
+    }
+}
+
+/// A loadout being assembled from `["icon"]`, `["name"]` and `["text"]` fields
+#[derive(Default)]
+struct PendingLoadout {
+    icon: Option<i64>,
+    name: Option<String>,
+    text: Option<String>,
+    first_line: usize,
+}
+
+impl PendingLoadout {
+    fn is_empty(&self) -> bool {
+        self.icon.is_none() && self.name.is_none() && self.text.is_none()
+    }
+}
+
+/// Recover as many loadouts as possible from TalentLoadoutsEx.lua source that full_moon
+/// rejected. Lines are scanned for `["CLASS"] = {` and `[spec] = {` headers and for
+/// loadout fields, which may be spread over several lines.
+pub fn recover_loadouts(content: &str, parse_error: String) -> (LuaTalentManager, ParseWarnings) {
+    let mut manager = LuaTalentManager::new();
+    let mut warnings = ParseWarnings {
+        parse_error: Some(parse_error),
+        ..ParseWarnings::default()
+    };
+
+    let mut in_main_table = false;
+    let mut class_name: Option<String> = None;
+    let mut spec_index: Option<u8> = None;
+    let mut pending = PendingLoadout::default();
+
+    for (index, line) in content.lines().enumerate() {
+        let line_number = index + 1;
+        let trimmed = line.trim();
+
+        // Top-level assignments switch between TalentLoadoutEx and other saved variables
+        if let Some(variable) = top_level_assignment(line) {
+            in_main_table = variable == DEFAULT_TABLE_NAME;
+            class_name = None;
+            spec_index = None;
+            continue;
+        }
+
+        if !in_main_table {
+            continue;
+        }
+
+        if let Some(key) = table_header(trimmed) {
+            match key {
+                HeaderKey::Class(name) if name != "OPTION" => {
+                    class_name = Some(name);
+                    spec_index = None;
+                }
+                HeaderKey::Class(_) => class_name = None,
+                HeaderKey::Spec(index) => spec_index = Some(index),
+            }
+        }
+
+        for (key, value) in loadout_fields(trimmed) {
+            let already_set = match key {
+                "icon" => pending.icon.is_some(),
+                "name" => pending.name.is_some(),
+                _ => pending.text.is_some(),
+            };
+            if already_set {
+                flush(&mut pending, &mut manager, &mut warnings, &class_name, spec_index, content);
+            }
+
+            if pending.is_empty() {
+                pending.first_line = line_number;
+            }
+            match key {
+                "icon" => pending.icon = value.parse().ok().or(Some(0)),
+                "name" => pending.name = Some(lua_unescape_string(&value)),
+                _ => pending.text = Some(lua_unescape_string(&value)),
+            }
+        }
+
+        if trimmed.contains('}') && !pending.is_empty() {
+            flush(&mut pending, &mut manager, &mut warnings, &class_name, spec_index, content);
+        }
+    }
+
+    if !pending.is_empty() {
+        flush(&mut pending, &mut manager, &mut warnings, &class_name, spec_index, content);
+    }
+
+    (manager, warnings)
+}
+
+/// Add a complete pending loadout to the manager, or record it as skipped
+fn flush(
+    pending: &mut PendingLoadout,
+    manager: &mut LuaTalentManager,
+    warnings: &mut ParseWarnings,
+    class_name: &Option<String>,
+    spec_index: Option<u8>,
+    content: &str,
+) {
+    let loadout = std::mem::take(pending);
+
+    match (class_name, spec_index, loadout.name, loadout.text) {
+        (Some(class_name), Some(spec_index), Some(name), Some(text)) => {
+            let mut talent = TalentLoadout::new(name, text);
+            talent.icon = loadout.icon.unwrap_or(0);
+            manager.add_talent(class_name.clone(), spec_index, talent);
+        }
+        _ => {
+            let line = content.lines().nth(loadout.first_line.saturating_sub(1)).unwrap_or("");
+            warnings.skipped_lines.push((loadout.first_line, line.trim().to_string()));
+        }
+    }
+}
+
+/// Name of the variable assigned on an unindented `Name = ...` line
+fn top_level_assignment(line: &str) -> Option<&str> {
+    if line.starts_with(char::is_whitespace) {
+        return None;
+    }
+
+    let (variable, _) = line.split_once('=')?;
+    let variable = variable.trim();
+    (!variable.is_empty() && variable.chars().all(|c| c.is_ascii_alphanumeric() || c == '_')).then_some(variable)
+}
+
+enum HeaderKey {
+    Class(String),
+    Spec(u8),
+}
+
+/// Key of a `["CLASS"] = {` or `[1] = {` (also `["1"] = {`) line
+fn table_header(line: &str) -> Option<HeaderKey> {
+    let rest = line.strip_prefix('[')?;
+    let (key, rest) = rest.split_once(']')?;
+    let rest = rest.trim_start().strip_prefix('=')?.trim_start();
+    if !rest.starts_with('{') || rest.contains("\"name\"") {
+        return None;
+    }
+
+    let key = key.trim();
+    let quoted = key.strip_prefix('"').and_then(|k| k.strip_suffix('"'));
+    if let Ok(index) = quoted.unwrap_or(key).parse::<u8>() {
+        return Some(HeaderKey::Spec(index));
+    }
+
+    quoted.filter(|k| !k.is_empty()).map(|k| HeaderKey::Class(lua_unescape_string(k)))
+}
+
+/// Find `["icon"] = 123`, `["name"] = "..."` and `["text"] = "..."` fields in a line
+/// String values are returned still escaped
+fn loadout_fields(line: &str) -> Vec<(&'static str, String)> {
+    let mut fields = Vec::new();
+    let mut rest = line;
+
+    while let Some(start) = rest.find("[\"") {
+        let after_key = &rest[start + 2..];
+        let Some(key_end) = after_key.find("\"]") else {
+            break;
+        };
+
+        let key = match &after_key[..key_end] {
+            "icon" => "icon",
+            "name" => "name",
+            "text" => "text",
+            _ => {
+                rest = &after_key[key_end + 2..];
+                continue;
+            }
+        };
+
+        let value_part = after_key[key_end + 2..].trim_start();
+        let Some(value_part) = value_part.strip_prefix('=') else {
+            rest = &after_key[key_end + 2..];
+            continue;
+        };
+        let value_part = value_part.trim_start();
+
+        let (value, remaining) = if let Some(string) = value_part.strip_prefix('"') {
+            match closing_quote(string) {
+                Some(end) => (string[..end].to_string(), &string[end + 1..]),
+                None => break,
+            }
+        } else {
+            let end = value_part
+                .find(|c: char| !(c.is_ascii_digit() || c == '-'))
+                .unwrap_or(value_part.len());
+            (value_part[..end].to_string(), &value_part[end..])
+        };
+
+        fields.push((key, value));
+        rest = remaining;
+    }
+
+    fields
+}
+
+/// Index of the unescaped closing quote of a string body
+fn closing_quote(s: &str) -> Option<usize> {
+    let mut escaped = false;
+    for (i, c) in s.char_indices() {
+        match c {
+            '\\' if !escaped => escaped = true,
+            '"' if !escaped => return Some(i),
+            _ => escaped = false,
+        }
+    }
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_recover_from_trailing_garbage() {
+        let content = format!(
+            "{}\n\u{1}\u{2} garbage from a crashed session",
+            include_str!("../tests/fixtures/TalentLoadoutsEx.lua")
+        );
+
+        let (manager, warnings) = LuaTalentManager::parse_lua_recovering(&content);
+
+        assert!(warnings.parse_error.is_some());
+        assert!(warnings.skipped_lines.is_empty());
+        assert_eq!(manager.stats().total, 4);
+        assert_eq!(manager.get_spec_talents("MAGE", 3).unwrap()[0].name, "M+-ara-kara_ARCT");
+    }
+
+    #[test]
+    fn test_recover_multiline_entries_and_skips() {
+        let content = r#"TalentLoadoutEx = {
+  ["WARRIOR"] = {
+    [1] = {
+      {
+        ["icon"] = 132355,
+        ["name"] = "My \"Arms\" Build",
+        ["text"] = "warrior/arms/ABC123",
+      },
+      { ["icon"] = 0, ["name"] = "Broken Build", ["text"] = "warrior/arms/XY
+    },
+  },
+  ["OPTION"] = { ["IsEnabledPvp"] = false },
+}
+TalentLoadoutExGUI = {
+  ["WARRIOR"] = {
+    [1] = { "My Arms Build" },
+  },
+}"#;
+
+        let (manager, warnings) = recover_loadouts(content, "unterminated string".to_string());
+
+        let arms = manager.get_spec_talents("WARRIOR", 1).unwrap();
+        assert_eq!(arms.len(), 1);
+        assert_eq!(arms[0].name, "My \"Arms\" Build");
+        assert_eq!(arms[0].icon, 132355);
+        assert_eq!(warnings.skipped_lines.len(), 1);
+        assert_eq!(warnings.skipped_lines[0].0, 9);
+        assert!(warnings.drops_data());
+    }
+}
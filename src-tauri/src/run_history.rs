@@ -0,0 +1,195 @@
+use crate::atomic_write::write_atomic;
+use crate::config::Config;
+use crate::metadata;
+use crate::orchestrator::UpdateSummary;
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+/// File in the app data directory holding the previous updates
+pub const RUN_HISTORY_FILE_NAME: &str = "run_history.json";
+
+/// Updates kept in the history; older ones are dropped
+const MAX_RUNS: usize = 50;
+
+/// Previous updates, oldest first
+#[derive(Debug, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RunHistory {
+    runs: Vec<RunRecord>,
+}
+
+/// One finished update, manual or scheduled
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RunRecord {
+    /// When the update finished (RFC 3339)
+    pub finished_at: String,
+    pub duration_ms: u64,
+    /// Hash of the config the update ran with, to tell apart runs of different configs
+    pub config_hash: String,
+    pub app_version: String,
+    pub scheduled: bool,
+    /// Counts of a successful update, None if it failed
+    pub summary: Option<RunSummary>,
+    /// Why the update failed
+    pub error: Option<String>,
+}
+
+/// Counts of a successful update
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RunSummary {
+    pub total_talents_updated: usize,
+    pub raid_talents: usize,
+    pub mythic_plus_talents: usize,
+    pub characters_processed: usize,
+    /// Builds and specs that failed to fetch
+    pub failures: usize,
+    pub files_written: usize,
+}
+
+impl From<&UpdateSummary> for RunSummary {
+    fn from(summary: &UpdateSummary) -> Self {
+        Self {
+            total_talents_updated: summary.total_talents_updated,
+            raid_talents: summary.raid_talents,
+            mythic_plus_talents: summary.mythic_plus_talents,
+            characters_processed: summary.characters_processed,
+            failures: summary.errors.len(),
+            files_written: summary.files.iter().filter(|file| file.file_written).count(),
+        }
+    }
+}
+
+impl RunRecord {
+    /// Record of an update that just finished
+    pub fn new(config: &Config, result: &Result<UpdateSummary>, duration: Duration, scheduled: bool) -> Self {
+        let (summary, error) = match result {
+            Ok(summary) => (Some(RunSummary::from(summary)), None),
+            Err(e) => (None, Some(format!("{:#}", e))),
+        };
+        Self {
+            finished_at: chrono::Local::now().to_rfc3339(),
+            duration_ms: duration.as_millis().try_into().unwrap_or(u64::MAX),
+            config_hash: config_hash(config),
+            app_version: env!("CARGO_PKG_VERSION").to_string(),
+            scheduled,
+            summary,
+            error,
+        }
+    }
+}
+
+/// Stable hash of a config
+pub fn config_hash(config: &Config) -> String {
+    metadata::talent_string_hash(&serde_json::to_string(config).unwrap_or_default())
+}
+
+impl RunHistory {
+    /// Read the history; a missing file is an empty history
+    /// A file that can't be parsed is moved aside to `<name>.corrupt` and the history starts over.
+    pub fn load(path: &Path) -> Result<Self> {
+        if !path.exists() {
+            return Ok(Self::default());
+        }
+        let content = fs::read_to_string(path).with_context(|| format!("Failed to read {}", path.display()))?;
+        match serde_json::from_str(&content) {
+            Ok(history) => Ok(history),
+            Err(e) => {
+                let aside = corrupt_path(path);
+                tracing::warn!(path = %path.display(), aside = %aside.display(), "Run history is corrupt: {}", e);
+                fs::rename(path, &aside).with_context(|| format!("Failed to move aside {}", path.display()))?;
+                Ok(Self::default())
+            }
+        }
+    }
+
+    pub fn save(&self, path: &Path) -> Result<()> {
+        if let Some(dir) = path.parent() {
+            fs::create_dir_all(dir).context("Failed to create app data directory")?;
+        }
+        let json = serde_json::to_string_pretty(self).context("Failed to serialize run history")?;
+        write_atomic(path, json).context("Failed to save run history")
+    }
+
+    /// Add a finished update, dropping the oldest beyond `MAX_RUNS`
+    pub fn push(&mut self, record: RunRecord) {
+        self.runs.push(record);
+        let excess = self.runs.len().saturating_sub(MAX_RUNS);
+        self.runs.drain(..excess);
+    }
+
+    /// Previous updates, newest first
+    pub fn runs(&self) -> impl Iterator<Item = &RunRecord> {
+        self.runs.iter().rev()
+    }
+
+    pub fn last(&self) -> Option<&RunRecord> {
+        self.runs.last()
+    }
+}
+
+/// Add a finished update to the history file
+pub fn append(path: &Path, record: RunRecord) -> Result<()> {
+    let mut history = RunHistory::load(path)?;
+    history.push(record);
+    history.save(path)
+}
+
+/// Where a corrupt history file is moved
+fn corrupt_path(path: &Path) -> PathBuf {
+    let mut name = path.file_name().unwrap_or_default().to_os_string();
+    name.push(".corrupt");
+    path.with_file_name(name)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_util::temp_dir;
+
+    fn record(total_talents_updated: usize) -> RunRecord {
+        RunRecord {
+            finished_at: chrono::Local::now().to_rfc3339(),
+            duration_ms: 1500,
+            config_hash: config_hash(&Config::example()),
+            app_version: "0.1.6".to_string(),
+            scheduled: false,
+            summary: Some(RunSummary {
+                total_talents_updated,
+                raid_talents: total_talents_updated,
+                mythic_plus_talents: 0,
+                characters_processed: 1,
+                failures: 0,
+                files_written: 1,
+            }),
+            error: None,
+        }
+    }
+
+    #[test]
+    fn test_history_is_capped_and_survives_corruption() {
+        let dir = temp_dir("run-history");
+        let path = dir.join(RUN_HISTORY_FILE_NAME);
+        assert!(RunHistory::load(&path).unwrap().last().is_none());
+
+        for total in 0..MAX_RUNS + 5 {
+            append(&path, record(total)).unwrap();
+        }
+        let history = RunHistory::load(&path).unwrap();
+        let totals: Vec<_> = history.runs().map(|run| run.summary.as_ref().unwrap().total_talents_updated).collect();
+        assert_eq!(totals.len(), MAX_RUNS);
+        assert_eq!((totals[0], totals[MAX_RUNS - 1]), (MAX_RUNS + 4, 5));
+        assert_eq!(history.last().unwrap().summary.as_ref().unwrap().total_talents_updated, MAX_RUNS + 4);
+
+        fs::write(&path, "{\"runs\": [{\"finishedAt\"").unwrap();
+        assert!(RunHistory::load(&path).unwrap().last().is_none());
+        assert!(!path.exists());
+        assert!(corrupt_path(&path).exists());
+        append(&path, record(1)).unwrap();
+        assert_eq!(RunHistory::load(&path).unwrap().runs().count(), 1);
+    }
+}
@@ -0,0 +1,540 @@
+use crate::lua_talent::{lua_unescape_string, string_value};
+use anyhow::{bail, Context, Result};
+use full_moon::ast::{Expression, Field, Stmt, TableConstructor, UnOp, Var};
+use std::borrow::Cow;
+
+/// Deepest table nesting the fast parser accepts before handing the file to full_moon
+const MAX_DEPTH: usize = 64;
+
+/// A value in a saved variables file
+#[derive(Debug, Clone, PartialEq)]
+pub enum Value<'a> {
+    /// Unescaped string contents
+    String(Cow<'a, str>),
+    /// Number as written in the source (e.g. "132355", "-1", "0x1F")
+    Number(Cow<'a, str>),
+    Boolean(bool),
+    Nil,
+    Table(Vec<TableField<'a>>),
+    /// Any other expression (only produced by the full_moon fallback)
+    Other,
+}
+
+impl Value<'_> {
+    /// Get the string contents if this is a string
+    pub fn as_str(&self) -> Option<&str> {
+        match self {
+            Value::String(s) => Some(s),
+            _ => None,
+        }
+    }
+
+    /// Get the table fields if this is a table
+    pub fn as_table(&self) -> Option<&[TableField<'_>]> {
+        match self {
+            Value::Table(fields) => Some(fields),
+            _ => None,
+        }
+    }
+}
+
+/// Key of a table field
+#[derive(Debug, Clone, PartialEq)]
+pub enum Key<'a> {
+    /// `["key"] = value`, `[1] = value`
+    Value(Value<'a>),
+    /// `key = value`
+    Name(Cow<'a, str>),
+    /// Positional `value`
+    None,
+}
+
+impl Key<'_> {
+    /// Get the key as a string for `["key"]` and `key` fields
+    pub fn as_str(&self) -> Option<&str> {
+        match self {
+            Key::Value(value) => value.as_str(),
+            Key::Name(name) => Some(name),
+            Key::None => None,
+        }
+    }
+}
+
+/// A table field with the Lua source it was parsed from
+#[derive(Debug, Clone, PartialEq)]
+pub struct TableField<'a> {
+    pub key: Key<'a>,
+    pub value: Value<'a>,
+    /// Source of the whole field, without surrounding whitespace and separator
+    pub source: Cow<'a, str>,
+    /// Source of the value alone
+    pub value_source: Cow<'a, str>,
+}
+
+/// A top-level `Name = value` statement
+#[derive(Debug, Clone, PartialEq)]
+pub struct Assignment<'a> {
+    pub name: Cow<'a, str>,
+    pub value: Value<'a>,
+}
+
+/// Parse the assignments of a saved variables file
+///
+/// WoW writes a small subset of Lua (tables, strings, numbers, booleans, nil), which is
+/// read by a hand-written parser. Anything outside that subset is parsed with full_moon
+/// instead, which is much slower on large files but accepts all of Lua.
+pub fn parse(source: &str) -> Result<Vec<Assignment<'_>>> {
+    match Parser::new(source).parse_chunk() {
+        Ok(assignments) => Ok(assignments),
+        Err(_) => parse_full_moon(source),
+    }
+}
+
+/// Parse with full_moon and convert the top-level assignments
+fn parse_full_moon(source: &str) -> Result<Vec<Assignment<'static>>> {
+    let ast = full_moon::parse(source).context("Failed to parse Lua file")?;
+    let mut assignments = Vec::new();
+
+    for stmt in ast.nodes().stmts() {
+        if let Stmt::Assignment(assignment) = stmt {
+            for (var, expression) in assignment.variables().iter().zip(assignment.expressions().iter()) {
+                if let Var::Name(name) = var {
+                    assignments.push(Assignment {
+                        name: Cow::Owned(name.token().to_string()),
+                        value: convert_expression(expression),
+                    });
+                }
+            }
+        }
+    }
+
+    Ok(assignments)
+}
+
+/// Convert the fields of a full_moon table constructor
+pub fn table_fields(table: &TableConstructor) -> Vec<TableField<'static>> {
+    table
+        .fields()
+        .iter()
+        .map(|field| {
+            let source = field.to_string().trim().to_string();
+            let (key, value) = match field {
+                Field::ExpressionKey { key, value, .. } => (Key::Value(convert_expression(key)), Some(value)),
+                Field::NameKey { key, value, .. } => (Key::Name(Cow::Owned(key.token().to_string())), Some(value)),
+                Field::NoKey(value) => (Key::None, Some(value)),
+                _ => (Key::None, None),
+            };
+
+            TableField {
+                key,
+                value: value.map_or(Value::Other, convert_expression),
+                value_source: Cow::Owned(value.map_or_else(|| source.clone(), |v| v.to_string().trim().to_string())),
+                source: Cow::Owned(source),
+            }
+        })
+        .collect()
+}
+
+fn convert_expression(expression: &Expression) -> Value<'static> {
+    match expression {
+        Expression::String(_) => string_value(expression).map_or(Value::Other, |s| Value::String(Cow::Owned(s))),
+        Expression::Number(num) => Value::Number(Cow::Owned(num.token().to_string())),
+        Expression::Symbol(symbol) => match symbol.token().to_string().as_str() {
+            "true" => Value::Boolean(true),
+            "false" => Value::Boolean(false),
+            "nil" => Value::Nil,
+            _ => Value::Other,
+        },
+        Expression::TableConstructor(table) => Value::Table(table_fields(table)),
+        Expression::UnaryOperator {
+            unop: UnOp::Minus(_),
+            expression,
+        } => match expression.as_ref() {
+            Expression::Number(num) => Value::Number(Cow::Owned(format!("-{}", num.token()))),
+            _ => Value::Other,
+        },
+        _ => Value::Other,
+    }
+}
+
+struct Parser<'a> {
+    source: &'a str,
+    bytes: &'a [u8],
+    position: usize,
+    depth: usize,
+}
+
+impl<'a> Parser<'a> {
+    fn new(source: &'a str) -> Self {
+        Self {
+            source,
+            bytes: source.as_bytes(),
+            position: 0,
+            depth: 0,
+        }
+    }
+
+    fn parse_chunk(&mut self) -> Result<Vec<Assignment<'a>>> {
+        let mut assignments = Vec::new();
+
+        loop {
+            self.skip_trivia()?;
+            if self.position >= self.bytes.len() {
+                return Ok(assignments);
+            }
+            if self.eat(b';') {
+                continue;
+            }
+
+            let Some(name) = self.name() else {
+                bail!("Expected an assignment at byte {}", self.position);
+            };
+            self.skip_trivia()?;
+            self.expect(b'=')?;
+            let value = self.value()?;
+
+            assignments.push(Assignment {
+                name: Cow::Borrowed(name),
+                value,
+            });
+        }
+    }
+
+    fn value(&mut self) -> Result<Value<'a>> {
+        self.skip_trivia()?;
+
+        match self.peek() {
+            Some(b'{') => self.table(),
+            Some(b'"' | b'\'') => self.quoted_string(),
+            Some(b'[') => self.long_bracket().map(|s| Value::String(Cow::Borrowed(s))),
+            Some(b'-' | b'.' | b'0'..=b'9') => self.number(),
+            _ => match self.name() {
+                Some("true") => Ok(Value::Boolean(true)),
+                Some("false") => Ok(Value::Boolean(false)),
+                Some("nil") => Ok(Value::Nil),
+                Some(other) => bail!("Unsupported expression '{}'", other),
+                None => bail!("Expected a value at byte {}", self.position),
+            },
+        }
+    }
+
+    fn table(&mut self) -> Result<Value<'a>> {
+        self.expect(b'{')?;
+        self.depth += 1;
+        if self.depth > MAX_DEPTH {
+            bail!("Tables are nested too deeply");
+        }
+
+        let mut fields = Vec::new();
+        loop {
+            self.skip_trivia()?;
+            if self.eat(b'}') {
+                break;
+            }
+
+            fields.push(self.field()?);
+
+            self.skip_trivia()?;
+            if !self.eat(b',') && !self.eat(b';') {
+                self.expect(b'}')?;
+                break;
+            }
+        }
+
+        self.depth -= 1;
+        Ok(Value::Table(fields))
+    }
+
+    fn field(&mut self) -> Result<TableField<'a>> {
+        let start = self.position;
+
+        let key = if self.peek() == Some(b'[') && !matches!(self.peek_at(1), Some(b'[' | b'=')) {
+            self.position += 1;
+            let key = self.value()?;
+            self.skip_trivia()?;
+            self.expect(b']')?;
+            self.skip_trivia()?;
+            self.expect(b'=')?;
+            Key::Value(key)
+        } else if let Some(name) = self.name_key()? {
+            Key::Name(Cow::Borrowed(name))
+        } else {
+            Key::None
+        };
+
+        self.skip_trivia()?;
+        let value_start = self.position;
+        let value = self.value()?;
+        let end = self.position;
+
+        Ok(TableField {
+            key,
+            value,
+            source: Cow::Borrowed(&self.source[start..end]),
+            value_source: Cow::Borrowed(&self.source[value_start..end]),
+        })
+    }
+
+    /// Consume `name =` if the field starts with one
+    fn name_key(&mut self) -> Result<Option<&'a str>> {
+        let start = self.position;
+        let Some(name) = self.name() else {
+            return Ok(None);
+        };
+
+        self.skip_trivia()?;
+        if self.peek() == Some(b'=') && self.peek_at(1) != Some(b'=') {
+            self.position += 1;
+            Ok(Some(name))
+        } else {
+            self.position = start;
+            Ok(None)
+        }
+    }
+
+    fn name(&mut self) -> Option<&'a str> {
+        let start = self.position;
+        while let Some(c) = self.peek() {
+            if c.is_ascii_alphanumeric() || c == b'_' {
+                self.position += 1;
+            } else {
+                break;
+            }
+        }
+
+        if start == self.position || self.bytes[start].is_ascii_digit() {
+            self.position = start;
+            return None;
+        }
+        Some(&self.source[start..self.position])
+    }
+
+    fn number(&mut self) -> Result<Value<'a>> {
+        let start = self.position;
+        self.eat(b'-');
+        self.skip_trivia()?;
+        let digits_start = self.position;
+
+        while let Some(c) = self.peek() {
+            let exponent_sign = matches!(c, b'+' | b'-')
+                && matches!(self.bytes[self.position - 1], b'e' | b'E' | b'p' | b'P')
+                && !self.source[digits_start..self.position].to_ascii_lowercase().starts_with("0x");
+            if c.is_ascii_alphanumeric() || c == b'.' || exponent_sign {
+                self.position += 1;
+            } else {
+                break;
+            }
+        }
+
+        if digits_start == self.position {
+            bail!("Expected a number at byte {}", start);
+        }
+
+        let digits = &self.source[digits_start..self.position];
+        if digits_start == start {
+            Ok(Value::Number(Cow::Borrowed(digits)))
+        } else {
+            Ok(Value::Number(Cow::Owned(format!("-{}", digits))))
+        }
+    }
+
+    fn quoted_string(&mut self) -> Result<Value<'a>> {
+        let quote = self.bytes[self.position];
+        self.position += 1;
+        let start = self.position;
+        let mut escaped = false;
+
+        while let Some(c) = self.peek() {
+            match c {
+                b'\\' => {
+                    escaped = true;
+                    // Skip the escaped character so an escaped quote doesn't end the string
+                    self.position += 2;
+                }
+                b'\n' => bail!("Unterminated string at byte {}", start),
+                c if c == quote => {
+                    let body = &self.source[start..self.position];
+                    self.position += 1;
+                    return Ok(Value::String(if escaped {
+                        Cow::Owned(lua_unescape_string(body))
+                    } else {
+                        Cow::Borrowed(body)
+                    }));
+                }
+                _ => self.position += 1,
+            }
+        }
+
+        bail!("Unterminated string at byte {}", start)
+    }
+
+    /// Parse a `[[...]]` or `[==[...]==]` long bracket and return its contents
+    fn long_bracket(&mut self) -> Result<&'a str> {
+        let start = self.position;
+        self.expect(b'[')?;
+        let mut level = 0;
+        while self.eat(b'=') {
+            level += 1;
+        }
+        self.expect(b'[')?;
+
+        let close = format!("]{}]", "=".repeat(level));
+        let body_start = self.position;
+        let Some(length) = self.source[body_start..].find(&close) else {
+            bail!("Unterminated long bracket at byte {}", start);
+        };
+
+        self.position = body_start + length + close.len();
+        Ok(&self.source[body_start..body_start + length])
+    }
+
+    /// Skip whitespace and comments
+    fn skip_trivia(&mut self) -> Result<()> {
+        loop {
+            while self.peek().is_some_and(|c| c.is_ascii_whitespace()) {
+                self.position += 1;
+            }
+
+            if !self.source[self.position..].starts_with("--") {
+                return Ok(());
+            }
+
+            self.position += 2;
+            if self.peek() == Some(b'[') && matches!(self.peek_at(1), Some(b'[' | b'=')) {
+                let checkpoint = self.position;
+                if self.long_bracket().is_ok() {
+                    continue;
+                }
+                self.position = checkpoint;
+            }
+
+            while self.peek().is_some_and(|c| c != b'\n') {
+                self.position += 1;
+            }
+        }
+    }
+
+    fn peek(&self) -> Option<u8> {
+        self.bytes.get(self.position).copied()
+    }
+
+    fn peek_at(&self, offset: usize) -> Option<u8> {
+        self.bytes.get(self.position + offset).copied()
+    }
+
+    fn eat(&mut self, c: u8) -> bool {
+        if self.peek() == Some(c) {
+            self.position += 1;
+            true
+        } else {
+            false
+        }
+    }
+
+    fn expect(&mut self, c: u8) -> Result<()> {
+        if !self.eat(c) {
+            bail!("Expected '{}' at byte {}", c as char, self.position);
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SOURCE: &str = r#"-- header comment
+TalentLoadoutEx = {
+  ["WARRIOR"] = {
+    [1] = {
+      { ["icon"] = 132355, ["name"] = "My \"Arms\" Build", text = 'warrior/arms/ABC' }, -- trailing
+    },
+    --[[ block
+         comment ]]
+    [300] = false;
+  },
+  ["OPTION"] = { ["IsEnabledPvp"] = false, Scale = -1.5e+2, Raw = [[x\y]] },
+}
+TalentLoadoutExGUI = nil
+"#;
+
+    fn field<'a>(fields: &'a [TableField<'a>], key: &str) -> &'a TableField<'a> {
+        fields.iter().find(|f| f.key.as_str() == Some(key)).unwrap()
+    }
+
+    #[test]
+    fn test_parse_saved_variables() {
+        let assignments = Parser::new(SOURCE).parse_chunk().unwrap();
+        assert_eq!(assignments.len(), 2);
+        assert_eq!(assignments[1].name, "TalentLoadoutExGUI");
+        assert_eq!(assignments[1].value, Value::Nil);
+
+        let main = assignments[0].value.as_table().unwrap();
+        let warrior = field(main, "WARRIOR").value.as_table().unwrap();
+        assert_eq!(warrior[0].key, Key::Value(Value::Number(Cow::Borrowed("1"))));
+        assert_eq!(warrior[1].source, "[300] = false");
+
+        let loadout = warrior[0].value.as_table().unwrap()[0].value.as_table().unwrap();
+        assert_eq!(field(loadout, "icon").value, Value::Number(Cow::Borrowed("132355")));
+        assert_eq!(field(loadout, "name").value.as_str(), Some("My \"Arms\" Build"));
+        assert_eq!(field(loadout, "text").value.as_str(), Some("warrior/arms/ABC"));
+
+        let options = field(main, "OPTION").value.as_table().unwrap();
+        assert_eq!(field(options, "Scale").value_source, "-1.5e+2");
+        assert_eq!(field(options, "Raw").value.as_str(), Some("x\\y"));
+    }
+
+    /// Drop the field sources, which differ in the comments full_moon attaches to tokens
+    fn without_sources(value: &Value) -> Value<'static> {
+        match value {
+            Value::Table(fields) => Value::Table(
+                fields
+                    .iter()
+                    .map(|f| TableField {
+                        key: match &f.key {
+                            Key::Value(key) => Key::Value(without_sources(key)),
+                            Key::Name(name) => Key::Name(Cow::Owned(name.to_string())),
+                            Key::None => Key::None,
+                        },
+                        value: without_sources(&f.value),
+                        source: Cow::Borrowed(""),
+                        value_source: Cow::Borrowed(""),
+                    })
+                    .collect(),
+            ),
+            Value::String(s) => Value::String(Cow::Owned(s.to_string())),
+            Value::Number(n) => Value::Number(Cow::Owned(n.to_string())),
+            Value::Boolean(b) => Value::Boolean(*b),
+            Value::Nil => Value::Nil,
+            Value::Other => Value::Other,
+        }
+    }
+
+    #[test]
+    fn test_fast_parser_matches_full_moon() {
+        for source in [SOURCE, include_str!("../tests/fixtures/TalentLoadoutsEx_unicode.lua")] {
+            let fast = Parser::new(source).parse_chunk().unwrap();
+            let slow = parse_full_moon(source).unwrap();
+
+            assert_eq!(fast.len(), slow.len());
+            for (fast, slow) in fast.iter().zip(&slow) {
+                assert_eq!(fast.name, slow.name);
+                assert_eq!(without_sources(&fast.value), without_sources(&slow.value));
+            }
+        }
+    }
+
+    #[test]
+    fn test_falls_back_to_full_moon() {
+        let source = "TalentLoadoutEx = { [1] = 2 + 3, [2] = \"ok\" }";
+        assert!(Parser::new(source).parse_chunk().is_err());
+
+        let assignments = parse(source).unwrap();
+        let fields = assignments[0].value.as_table().unwrap();
+        assert_eq!(fields[0].value, Value::Other);
+        assert_eq!(fields[0].source, "[1] = 2 + 3");
+        assert_eq!(fields[1].value.as_str(), Some("ok"));
+
+        assert!(parse("TalentLoadoutEx = {").is_err());
+    }
+}
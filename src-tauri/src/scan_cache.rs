@@ -0,0 +1,157 @@
+use crate::atomic_write::write_atomic;
+use crate::wow_scanner::{AccountScan, ScanProgress, ScanResult, WowScanner};
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::path::Path;
+use std::sync::atomic::AtomicBool;
+use std::time::{Duration, SystemTime};
+
+/// File name of the scan cache in the app data directory
+pub const SCAN_CACHE_FILE_NAME: &str = "scan-cache.json";
+
+/// Character scans of each installation, keyed by installation path
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct ScanCache {
+    installations: HashMap<String, CachedScan>,
+}
+
+/// The last scan of one installation
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct CachedScan {
+    scanned_at: SystemTime,
+    accounts: Vec<AccountScan>,
+}
+
+impl ScanCache {
+    /// Load the cache file; a missing or unreadable cache just starts empty
+    pub fn load(path: impl AsRef<Path>) -> Self {
+        std::fs::read_to_string(path)
+            .ok()
+            .and_then(|content| serde_json::from_str(&content).ok())
+            .unwrap_or_default()
+    }
+
+    /// Write the cache file atomically
+    pub fn save(&self, path: impl AsRef<Path>) -> Result<()> {
+        let json = serde_json::to_string(self).context("Failed to serialize scan cache")?;
+        write_atomic(path.as_ref(), json).context("Failed to write scan cache")
+    }
+
+    /// Accounts from the last scan of `wow_path`, unless that scan is older than `max_age`
+    pub fn accounts(&self, wow_path: &Path, max_age: Duration, now: SystemTime) -> &[AccountScan] {
+        match self.installations.get(&wow_path.to_string_lossy().into_owned()) {
+            Some(cached) if now.duration_since(cached.scanned_at).is_ok_and(|age| age <= max_age) => &cached.accounts,
+            _ => &[],
+        }
+    }
+
+    /// Replace the cached scan of `wow_path`
+    pub fn store(&mut self, wow_path: &Path, accounts: Vec<AccountScan>, now: SystemTime) {
+        self.installations.insert(
+            wow_path.to_string_lossy().into_owned(),
+            CachedScan {
+                scanned_at: now,
+                accounts,
+            },
+        );
+    }
+}
+
+/// Scan the characters of an installation, reusing the accounts of a cached scan younger than
+/// `max_age_secs` whose folders haven't changed, and update the cache file
+/// Failing to write the cache doesn't fail the scan.
+pub fn scan_characters_cached(
+    scanner: &WowScanner,
+    cache_path: &Path,
+    max_age_secs: u64,
+    cancel: &AtomicBool,
+    on_progress: impl FnMut(&ScanProgress),
+) -> Result<ScanResult> {
+    let mut cache = ScanCache::load(cache_path);
+    let now = SystemTime::now();
+    let previous = cache.accounts(scanner.wow_path(), Duration::from_secs(max_age_secs), now);
+    let (result, accounts) = scanner.scan_characters_incremental(previous, cancel, on_progress)?;
+
+    cache.store(scanner.wow_path(), accounts, now);
+    if let Err(e) = cache.save(cache_path) {
+        tracing::warn!("Failed to save scan cache: {:#}", e);
+    }
+    Ok(result)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_util::temp_dir;
+    use std::fs;
+    use std::path::PathBuf;
+
+    const DAY: u64 = 24 * 60 * 60;
+
+    fn character(wow_path: &Path, account: &str, name: &str, class: u8) -> PathBuf {
+        let path = wow_path.join("WTF/Account").join(account).join("Blackrock").join(name);
+        fs::create_dir_all(&path).unwrap();
+        fs::write(path.join("config-cache.wtf"), format!("SET EJLootClass \"{}\"\n", class)).unwrap();
+        path
+    }
+
+    fn names(result: &ScanResult) -> Vec<String> {
+        let mut names: Vec<_> = result
+            .characters
+            .iter()
+            .map(|c| format!("{}/{}/{}", c.account_id, c.name, c.class))
+            .collect();
+        names.sort();
+        names
+    }
+
+    #[test]
+    fn test_cached_scan_reuses_unchanged_accounts() {
+        let dir = temp_dir("scan-cache");
+        let wow_path = dir.join("_retail_");
+        let cache_path = dir.join(SCAN_CACHE_FILE_NAME);
+        let warrior = character(&wow_path, "WOW1", "Tank", 1);
+        character(&wow_path, "WOW2", "Healer", 2);
+
+        let scanner = WowScanner::new(&wow_path);
+        let scan = |max_age_secs| {
+            scan_characters_cached(&scanner, &cache_path, max_age_secs, &AtomicBool::new(false), |_| {}).unwrap()
+        };
+        assert_eq!(names(&scan(DAY)), ["WOW1/Tank/Warrior", "WOW2/Healer/Paladin"]);
+        assert!(cache_path.exists());
+
+        // Files inside a character folder don't invalidate the account, so the cached class is kept
+        fs::write(warrior.join("config-cache.wtf"), "SET EJLootClass \"8\"\n").unwrap();
+        assert_eq!(names(&scan(DAY)), ["WOW1/Tank/Warrior", "WOW2/Healer/Paladin"]);
+
+        // ...until the cache is older than the maximum age
+        assert_eq!(names(&scan(0)), ["WOW1/Tank/Mage", "WOW2/Healer/Paladin"]);
+
+        // A new character folder changes its realm folder, so the account is rescanned
+        character(&wow_path, "WOW2", "Dps", 4);
+        assert_eq!(names(&scan(DAY)), ["WOW1/Tank/Mage", "WOW2/Dps/Rogue", "WOW2/Healer/Paladin"]);
+
+        // A removed account drops out
+        fs::remove_dir_all(wow_path.join("WTF/Account/WOW1")).unwrap();
+        assert_eq!(names(&scan(DAY)), ["WOW2/Dps/Rogue", "WOW2/Healer/Paladin"]);
+    }
+
+    #[test]
+    fn test_cache_round_trip_and_corrupt_file() {
+        let mut cache = ScanCache::default();
+        let scanned_at = SystemTime::UNIX_EPOCH + Duration::from_secs(1_000_000);
+        let wow_path = Path::new("/games/World of Warcraft/_retail_");
+        cache.store(wow_path, Vec::new(), scanned_at);
+        assert!(cache.installations.contains_key(&wow_path.to_string_lossy().into_owned()));
+
+        let dir = temp_dir("scan-cache-file");
+        let path = dir.join(SCAN_CACHE_FILE_NAME);
+        fs::write(&path, "{not json").unwrap();
+        assert!(ScanCache::load(&path).installations.is_empty());
+        cache.save(&path).unwrap();
+        let loaded = ScanCache::load(&path);
+        assert_eq!(loaded.installations[&wow_path.to_string_lossy().into_owned()].scanned_at, scanned_at);
+    }
+}
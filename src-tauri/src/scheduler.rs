@@ -0,0 +1,240 @@
+use crate::atomic_write::write_atomic;
+use crate::config::{self, Config};
+use anyhow::{Context, Result};
+use chrono::{DateTime, Datelike, Duration, Local, NaiveTime, TimeZone, Weekday};
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::Path;
+use std::sync::Arc;
+
+/// File in the app data directory holding the config used by scheduled updates and the last run
+pub const SCHEDULE_STATE_FILE_NAME: &str = "schedule.json";
+
+/// How often the background task checks whether an update is due
+/// The check compares against the wall clock, so a run missed during sleep happens once on wake.
+pub const TICK_INTERVAL: std::time::Duration = std::time::Duration::from_secs(60);
+
+/// Interval used when a schedule has neither `interval_hours` nor a weekday or time (one week)
+const DEFAULT_INTERVAL_HOURS: u32 = 168;
+
+/// When talents are updated automatically
+/// With a weekday and/or time, runs happen at the next such slot that is at least
+/// `interval_hours` after the last run; otherwise every `interval_hours` (default one week).
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Schedule {
+    /// Minimum hours between runs
+    #[serde(default)]
+    pub interval_hours: Option<u32>,
+
+    /// Preferred day of the week, e.g. "wednesday" or "wed" (after the weekly reset)
+    #[serde(default)]
+    pub weekday: Option<String>,
+
+    /// Preferred local time of day as "HH:MM" (default midnight when a weekday is set)
+    #[serde(default)]
+    pub time: Option<String>,
+
+    /// Wait until WoW isn't running, even if `ignoreRunningClient` is set
+    #[serde(default)]
+    pub only_when_idle: bool,
+}
+
+impl Schedule {
+    pub fn validate(&self) -> Result<()> {
+        if self.interval_hours == Some(0) {
+            anyhow::bail!("Schedule interval must be at least one hour");
+        }
+        self.preferred_weekday()?;
+        self.preferred_time()?;
+        Ok(())
+    }
+
+    fn preferred_weekday(&self) -> Result<Option<Weekday>> {
+        self.weekday
+            .as_deref()
+            .map(|day| {
+                day.trim()
+                    .parse::<Weekday>()
+                    .map_err(|_| anyhow::anyhow!("Invalid schedule weekday '{}' (e.g. wednesday)", day))
+            })
+            .transpose()
+    }
+
+    fn preferred_time(&self) -> Result<Option<NaiveTime>> {
+        self.time
+            .as_deref()
+            .map(|time| {
+                NaiveTime::parse_from_str(time.trim(), "%H:%M")
+                    .map_err(|_| anyhow::anyhow!("Invalid schedule time '{}' (expected HH:MM)", time))
+            })
+            .transpose()
+    }
+
+    /// First run after `last_run`
+    pub fn next_run(&self, last_run: DateTime<Local>) -> Result<DateTime<Local>> {
+        let weekday = self.preferred_weekday()?;
+        let time = self.preferred_time()?;
+        if weekday.is_none() && time.is_none() {
+            let hours = self.interval_hours.unwrap_or(DEFAULT_INTERVAL_HOURS);
+            return Ok(last_run + Duration::hours(hours.into()));
+        }
+
+        let earliest = last_run + Duration::hours(self.interval_hours.unwrap_or(0).into());
+        let time = time.unwrap_or(NaiveTime::MIN);
+        // 8 days cover a full week plus a slot skipped by a DST change
+        for day in earliest.date_naive().iter_days().take(8) {
+            if weekday.is_some_and(|weekday| day.weekday() != weekday) {
+                continue;
+            }
+            // Slots in a DST gap don't exist; ambiguous ones use the first occurrence
+            let Some(slot) = Local.from_local_datetime(&day.and_time(time)).earliest() else {
+                continue;
+            };
+            if slot > last_run && slot >= earliest {
+                return Ok(slot);
+            }
+        }
+        anyhow::bail!("No scheduled run found after {}", last_run.to_rfc3339())
+    }
+}
+
+/// Config and last run of scheduled updates, kept apart from the UI settings so the schedule
+/// works while no window is open and survives config reloads
+#[derive(Debug, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ScheduleState {
+    /// Config of the last update, with its `schedule`
+    #[serde(default)]
+    pub config: Option<serde_json::Value>,
+
+    /// Time of the last update (RFC 3339), manual or scheduled
+    #[serde(default)]
+    pub last_run: Option<String>,
+}
+
+impl ScheduleState {
+    /// Read the state; a missing file is an empty state
+    pub fn load(path: &Path) -> Result<Self> {
+        if !path.exists() {
+            return Ok(Self::default());
+        }
+        let content = fs::read_to_string(path).with_context(|| format!("Failed to read {}", path.display()))?;
+        serde_json::from_str(&content).with_context(|| format!("Failed to parse {}", path.display()))
+    }
+
+    pub fn save(&self, path: &Path) -> Result<()> {
+        if let Some(dir) = path.parent() {
+            fs::create_dir_all(dir).context("Failed to create app data directory")?;
+        }
+        let json = serde_json::to_string_pretty(self).context("Failed to serialize schedule")?;
+        write_atomic(path, json).context("Failed to save schedule")
+    }
+
+    /// Stored config, upgraded to the current config version
+    pub fn config(&self) -> Result<Option<Config>> {
+        self.config
+            .clone()
+            .map(config::migrate)
+            .transpose()
+            .context("Scheduled config is invalid")
+    }
+
+    pub fn last_run(&self) -> Result<Option<DateTime<Local>>> {
+        self.last_run
+            .as_deref()
+            .map(|time| {
+                DateTime::parse_from_rfc3339(time)
+                    .map(|time| time.with_timezone(&Local))
+                    .with_context(|| format!("Invalid last run time '{}'", time))
+            })
+            .transpose()
+    }
+
+    /// Remember a finished update; its config becomes the one scheduled updates use
+    pub fn record_run(&mut self, config: &Config, time: DateTime<Local>) -> Result<()> {
+        self.config = Some(serde_json::to_value(config).context("Failed to serialize config")?);
+        self.last_run = Some(time.to_rfc3339());
+        Ok(())
+    }
+
+    /// Next scheduled update, or None without a stored config or schedule
+    /// Before the first run the schedule counts from `now`.
+    pub fn next_run(&self, now: DateTime<Local>) -> Result<Option<DateTime<Local>>> {
+        let Some(schedule) = self.config()?.and_then(|config| config.schedule) else {
+            return Ok(None);
+        };
+        let last_run = self.last_run()?.unwrap_or(now);
+        schedule.next_run(last_run).map(Some)
+    }
+}
+
+/// Serializes updates: manual runs wait for a running update, the scheduler skips its tick
+#[derive(Default, Clone)]
+pub struct UpdateLock(pub Arc<tokio::sync::Mutex<()>>);
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_util::temp_dir;
+
+    fn local(year: i32, month: u32, day: u32, hour: u32, minute: u32) -> DateTime<Local> {
+        Local.with_ymd_and_hms(year, month, day, hour, minute, 0).unwrap()
+    }
+
+    #[test]
+    fn test_next_run() {
+        let interval = Schedule { interval_hours: Some(12), ..Default::default() };
+        assert_eq!(interval.next_run(local(2025, 2, 4, 8, 0)).unwrap(), local(2025, 2, 4, 20, 0));
+        assert_eq!(Schedule::default().next_run(local(2025, 2, 4, 8, 0)).unwrap(), local(2025, 2, 11, 8, 0));
+
+        // 2025-02-04 is a Tuesday
+        let weekly = Schedule {
+            weekday: Some("wednesday".to_string()),
+            time: Some("09:30".to_string()),
+            ..Default::default()
+        };
+        assert_eq!(weekly.next_run(local(2025, 2, 4, 8, 0)).unwrap(), local(2025, 2, 5, 9, 30));
+        assert_eq!(weekly.next_run(local(2025, 2, 5, 9, 30)).unwrap(), local(2025, 2, 12, 9, 30));
+
+        // A manual run shortly before the slot pushes the next run by a week with a minimum interval
+        let spaced = Schedule { interval_hours: Some(48), ..weekly.clone() };
+        assert_eq!(spaced.next_run(local(2025, 2, 4, 20, 0)).unwrap(), local(2025, 2, 12, 9, 30));
+
+        let daily = Schedule { time: Some("06:00".to_string()), ..Default::default() };
+        assert_eq!(daily.next_run(local(2025, 2, 4, 8, 0)).unwrap(), local(2025, 2, 5, 6, 0));
+        assert_eq!(daily.next_run(local(2025, 2, 4, 5, 0)).unwrap(), local(2025, 2, 4, 6, 0));
+
+        assert!(Schedule { weekday: Some("someday".to_string()), ..Default::default() }.validate().is_err());
+        assert!(Schedule { time: Some("25:00".to_string()), ..Default::default() }.validate().is_err());
+        assert!(Schedule { interval_hours: Some(0), ..Default::default() }.validate().is_err());
+    }
+
+    #[test]
+    fn test_missed_runs_fire_once_after_wake() {
+        let mut config = Config::example();
+        config.schedule = Some(Schedule {
+            weekday: Some("wed".to_string()),
+            time: Some("09:30".to_string()),
+            ..Default::default()
+        });
+        let mut state = ScheduleState::default();
+        state.record_run(&config, local(2025, 2, 4, 8, 0)).unwrap();
+
+        // Asleep for three weeks: the first slot is overdue and a single run catches up
+        let wake = local(2025, 2, 25, 18, 0);
+        let next = state.next_run(wake).unwrap().unwrap();
+        assert!(next <= wake);
+        state.record_run(&config, wake).unwrap();
+        assert_eq!(state.next_run(wake).unwrap(), Some(local(2025, 2, 26, 9, 30)));
+
+        let dir = temp_dir("schedule");
+        let path = dir.join("schedule.json");
+        state.save(&path).unwrap();
+        let loaded = ScheduleState::load(&path).unwrap();
+        assert_eq!(loaded.config().unwrap(), Some(config));
+        assert_eq!(loaded.last_run().unwrap(), Some(wake));
+
+        assert_eq!(ScheduleState::default().next_run(wake).unwrap(), None);
+    }
+}
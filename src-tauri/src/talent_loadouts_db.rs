@@ -0,0 +1,406 @@
+use crate::archon::TalentIdentifier;
+use crate::lua_talent::{
+    lua_escape_string, normalize_source, string_value, talent_to_lua, AutoSuffixes, LuaTalentManager, TalentLoadout,
+};
+use anyhow::{Context, Result};
+use full_moon::ast::{Expression, Field, Stmt, TableConstructor, Var};
+use std::collections::{BTreeMap, BTreeSet, HashSet};
+
+/// Saved variable written by the TalentLoadouts addon
+pub const DB_VARIABLE_NAME: &str = "TalentLoadoutsDB";
+
+/// Category for manual loadouts
+const CATEGORY_CUSTOM: &str = "Custom";
+/// Category for auto-generated raid builds
+const CATEGORY_RAID: &str = "Raid";
+/// Category for auto-generated Mythic+ builds
+const CATEGORY_MYTHIC_PLUS: &str = "Mythic+";
+
+/// A TalentLoadouts profile, keyed by "Name - Realm" like the addon does
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Profile {
+    pub class_name: String,
+    /// Names of the loadouts the profile had in the file, by spec index
+    pub loadouts: BTreeMap<u8, BTreeSet<String>>,
+    /// Specs configured for the profile's character, whose auto-generated loadouts it receives
+    pub specs: BTreeSet<u8>,
+    /// Other fields of the profile, kept verbatim as Lua source
+    pub extra_fields: Vec<String>,
+}
+
+impl Profile {
+    /// Whether the profile is written with `talent`: loadouts it had, and the auto-generated
+    /// loadouts of its configured specs
+    fn receives(&self, spec_index: u8, talent: &TalentLoadout, suffixes: &AutoSuffixes) -> bool {
+        self.loadouts.get(&spec_index).is_some_and(|names| names.contains(&talent.name))
+            || (self.specs.contains(&spec_index) && suffixes.matches_any(talent))
+    }
+}
+
+/// Profile key of a character: "Name - Realm", or without a realm the character's only profile
+/// in the file, falling back to the name
+pub fn profile_key(manager: &LuaTalentManager, name: &str, realm: Option<&str>) -> String {
+    if let Some(realm) = realm {
+        return format!("{} - {}", name, realm);
+    }
+    let prefix = format!("{} - ", name);
+    let mut existing = manager.profiles().keys().filter(|key| key.starts_with(&prefix));
+    match (existing.next(), existing.next()) {
+        (Some(key), None) => key.clone(),
+        _ => name.to_string(),
+    }
+}
+
+/// Parse a TalentLoadouts saved variables file into a talent manager
+///
+/// Layout: `TalentLoadoutsDB.profiles["Name - Realm"] = { class = "WARRIOR", categories = {
+/// [<category>] = { [<spec>] = { loadouts } } } }`.
+/// Loadouts of all profiles of a class are combined; identical entries are kept once, and each
+/// profile remembers which of them it had. Other fields are kept verbatim.
+pub fn parse_db(content: &str) -> Result<LuaTalentManager> {
+    let (content, crlf) = normalize_source(content);
+    let ast = full_moon::parse(&content).context("Failed to parse Lua file")?;
+    let mut manager = LuaTalentManager::new();
+    manager.set_crlf(crlf);
+
+    for stmt in ast.nodes().stmts() {
+        let Stmt::Assignment(assignment) = stmt else {
+            continue;
+        };
+
+        let is_db = assignment
+            .variables()
+            .iter()
+            .any(|v| matches!(v, Var::Name(name) if name.token().to_string() == DB_VARIABLE_NAME));
+
+        if let (true, Some(Expression::TableConstructor(table))) =
+            (is_db, assignment.expressions().iter().next())
+        {
+            parse_db_table(table, &mut manager)?;
+        }
+    }
+
+    Ok(manager)
+}
+
+/// Parse the top-level DB table; fields other than `profiles` are kept verbatim
+fn parse_db_table(table: &TableConstructor, manager: &mut LuaTalentManager) -> Result<()> {
+    let mut extra_fields = Vec::new();
+
+    for field in table.fields() {
+        if let Field::ExpressionKey {
+            key,
+            value: Expression::TableConstructor(profiles),
+            ..
+        } = field
+        {
+            if string_value(key).as_deref() == Some("profiles") {
+                for profile_field in profiles.fields() {
+                    if let Field::ExpressionKey {
+                        key,
+                        value: Expression::TableConstructor(profile),
+                        ..
+                    } = profile_field
+                    {
+                        if let Some(profile_key) = string_value(key) {
+                            parse_profile(profile_key, profile, manager)?;
+                        }
+                    }
+                }
+                continue;
+            }
+        }
+
+        extra_fields.push(field.to_string().trim().to_string());
+    }
+
+    manager.set_db_fields(extra_fields);
+    Ok(())
+}
+
+/// Parse a single character profile and add its loadouts to the manager
+fn parse_profile(profile_key: String, profile: &TableConstructor, manager: &mut LuaTalentManager) -> Result<()> {
+    let mut class_name = None;
+    let mut categories = None;
+    let mut extra_fields = Vec::new();
+
+    for field in profile.fields() {
+        if let Field::ExpressionKey { key, value, .. } = field {
+            match (string_value(key).as_deref(), value) {
+                (Some("class"), value) if string_value(value).is_some() => {
+                    class_name = string_value(value);
+                    continue;
+                }
+                (Some("categories"), Expression::TableConstructor(table)) => {
+                    categories = Some(table);
+                    continue;
+                }
+                _ => {}
+            }
+        }
+        extra_fields.push(field.to_string().trim().to_string());
+    }
+
+    let Some(class_name) = class_name else {
+        return Ok(());
+    };
+    let mut loadouts: BTreeMap<u8, BTreeSet<String>> = BTreeMap::new();
+
+    for category in categories.iter().flat_map(|categories| categories.fields()) {
+        let Field::ExpressionKey {
+            value: Expression::TableConstructor(spec_table),
+            ..
+        } = category
+        else {
+            continue;
+        };
+
+        // WoW writes specs 1..n as a list without keys
+        let mut position = 0;
+        for spec_field in spec_table.fields() {
+            let (spec_index, talent_table) = match spec_field {
+                Field::ExpressionKey {
+                    key: Expression::Number(num),
+                    value: Expression::TableConstructor(talent_table),
+                    ..
+                } => (num.token().to_string().parse::<u8>().ok(), talent_table),
+                Field::NoKey(Expression::TableConstructor(talent_table)) => {
+                    position += 1;
+                    (Some(position), talent_table)
+                }
+                _ => continue,
+            };
+            let Some(spec_index) = spec_index else {
+                continue;
+            };
+
+            for talent in LuaTalentManager::parse_talent_list(talent_table)? {
+                loadouts.entry(spec_index).or_default().insert(talent.name.clone());
+                let exists = manager
+                    .get_spec_talents(&class_name, spec_index)
+                    .is_some_and(|talents| talents.contains(&talent));
+                if !exists {
+                    manager.add_talent(class_name.clone(), spec_index, talent);
+                }
+            }
+        }
+    }
+
+    let profile = manager.add_profile(profile_key, class_name);
+    profile.loadouts = loadouts;
+    profile.extra_fields = extra_fields;
+    Ok(())
+}
+
+/// Content category a loadout is filed under
+fn content_category(talent: &TalentLoadout, suffix: &str) -> &'static str {
+    match TalentIdentifier::from_talent_name(&talent.name, suffix) {
+        Some(TalentIdentifier::Raid { .. }) => CATEGORY_RAID,
+        Some(TalentIdentifier::MythicPlus { .. }) => CATEGORY_MYTHIC_PLUS,
+        None => CATEGORY_CUSTOM,
+    }
+}
+
+/// Loadouts written to one profile, by spec index
+type ProfileLoadouts<'a> = BTreeMap<u8, Vec<&'a TalentLoadout>>;
+
+/// Convert the manager's loadouts to the TalentLoadouts saved variables format
+/// Every profile receives its own loadouts and the auto-generated ones of its configured specs
+/// (see `Profile::receives`); loadouts no profile receives are written to a profile named after
+/// their class so nothing is lost
+pub fn to_db_lua_string(manager: &LuaTalentManager) -> String {
+    let suffixes = manager.auto_suffixes();
+    let mut profiles: BTreeMap<&str, (&str, ProfileLoadouts, &[String])> = BTreeMap::new();
+    // Class name, spec index and position of the loadouts written to a profile
+    let mut written = HashSet::new();
+
+    for (key, profile) in manager.profiles() {
+        let mut specs = ProfileLoadouts::new();
+        let class_name = profile.class_name.as_str();
+        for (spec_index, talents) in manager.get_class_talents(class_name).into_iter().flatten() {
+            for (position, talent) in talents.iter().enumerate() {
+                if profile.receives(*spec_index, talent, suffixes) {
+                    specs.entry(*spec_index).or_default().push(talent);
+                    written.insert((class_name, *spec_index, position));
+                }
+            }
+        }
+        profiles.insert(key, (class_name, specs, &profile.extra_fields));
+    }
+
+    for class_name in manager.class_names() {
+        for (spec_index, talents) in manager.get_class_talents(class_name).into_iter().flatten() {
+            for (position, talent) in talents.iter().enumerate() {
+                if !written.contains(&(class_name, *spec_index, position)) {
+                    let (_, specs, _) = profiles.entry(class_name).or_insert((class_name, ProfileLoadouts::new(), &[]));
+                    specs.entry(*spec_index).or_default().push(talent);
+                }
+            }
+        }
+    }
+
+    let mut result = format!("{} = {{\n  [\"profiles\"] = {{\n", DB_VARIABLE_NAME);
+
+    for (profile, (class_name, specs, extra_fields)) in profiles {
+        result.push_str(&format!("    [\"{}\"] = {{\n", lua_escape_string(profile)));
+        result.push_str(&format!("      [\"class\"] = \"{}\",\n", lua_escape_string(class_name)));
+        result.push_str("      [\"categories\"] = {\n");
+
+        for category in [CATEGORY_CUSTOM, CATEGORY_RAID, CATEGORY_MYTHIC_PLUS] {
+            let category_specs: Vec<(u8, Vec<&TalentLoadout>)> = specs
+                .iter()
+                .map(|(spec_index, talents)| {
+                    let talents: Vec<&TalentLoadout> = talents
+                        .iter()
+                        .copied()
+                        .filter(|t| content_category(t, &suffixes.current) == category)
+                        .collect();
+                    (*spec_index, talents)
+                })
+                .filter(|(_, talents)| !talents.is_empty())
+                .collect();
+
+            if category_specs.is_empty() {
+                continue;
+            }
+
+            result.push_str(&format!("        [\"{}\"] = {{\n", category));
+            for (spec_index, talents) in category_specs {
+                result.push_str(&format!("          [{}] = {{\n", spec_index));
+                for talent in talents {
+                    result.push_str(&format!("            {},\n", talent_to_lua(talent)));
+                }
+                result.push_str("          },\n");
+            }
+            result.push_str("        },\n");
+        }
+
+        result.push_str("      },\n");
+        for field in extra_fields {
+            result.push_str(&format!("      {},\n", field));
+        }
+        result.push_str("    },\n");
+    }
+
+    result.push_str("  },\n");
+    for field in manager.db_fields() {
+        result.push_str(&format!("  {},\n", field));
+    }
+    result.push_str("}\n");
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::lua_talent::{AutoSuffixes, TalentFileFormat};
+    use crate::test_util::temp_dir;
+
+    const DB_FIXTURE: &str = include_str!("../tests/fixtures/TalentLoadouts.lua");
+    const EX_FIXTURE: &str = include_str!("../tests/fixtures/TalentLoadoutsEx.lua");
+    /// Written by the client: tabs, specs 1..n as lists and fields the addon keeps for itself
+    const EXPORT_FIXTURE: &str = include_str!("../tests/fixtures/TalentLoadouts_export.lua");
+
+    #[test]
+    fn test_parse_db_fixture() {
+        let manager = parse_db(DB_FIXTURE).unwrap();
+
+        let arms = manager.get_spec_talents("WARRIOR", 1).unwrap();
+        let names: Vec<_> = arms.iter().map(|t| t.name.as_str()).collect();
+        assert_eq!(names, vec!["My Arms Build", "R-heroic-sikran_ARCT"]);
+        assert_eq!(manager.get_spec_talents("MAGE", 3).unwrap()[0].name, "M+-ara-kara_ARCT");
+
+        assert_eq!(manager.profiles()["Thrall - Draenor"].class_name, "WARRIOR");
+        assert_eq!(manager.profiles()["Jaina - Draenor"].class_name, "MAGE");
+    }
+
+    #[test]
+    fn test_both_formats_hold_the_same_loadouts() {
+        let dir = temp_dir("db");
+        let db_path = dir.join("TalentLoadouts.lua");
+        let ex_path = dir.join("TalentLoadoutsEx.lua");
+        std::fs::write(&db_path, DB_FIXTURE).unwrap();
+        std::fs::write(&ex_path, EX_FIXTURE).unwrap();
+
+        let from_db = LuaTalentManager::load_from_file_as(&db_path, TalentFileFormat::TalentLoadouts, &AutoSuffixes::default()).unwrap();
+        let from_ex = LuaTalentManager::load_from_file_as(&ex_path, TalentFileFormat::TalentLoadoutsEx, &AutoSuffixes::default()).unwrap();
+
+        assert!(from_ex.diff(&from_db).is_empty());
+    }
+
+    #[test]
+    fn test_db_round_trip_keeps_manual_entries() {
+        let mut manager = parse_db(DB_FIXTURE).unwrap();
+        manager.add_profile("Thrall - Draenor".to_string(), "WARRIOR".to_string()).specs.insert(1);
+        manager.remove_auto_generated("WARRIOR", 1);
+        manager.add_talent(
+            "WARRIOR".to_string(),
+            1,
+            TalentLoadout::new("R-mythic-sikran_ARCT".to_string(), "warrior/arms/NEW".to_string()),
+        );
+
+        let output = to_db_lua_string(&manager);
+        let reparsed = parse_db(&output).unwrap();
+
+        assert!(manager.diff(&reparsed).is_empty());
+        assert!(reparsed.profiles().keys().eq(manager.profiles().keys()));
+        assert!(reparsed.profiles()["Thrall - Draenor"].loadouts[&1].contains("R-mythic-sikran_ARCT"));
+        assert!(output.contains("[\"Custom\"]"));
+        assert!(output.contains("[\"Raid\"]"));
+        assert!(output.contains("[\"Mythic+\"]"));
+    }
+
+    #[test]
+    fn test_classes_without_profile_are_written() {
+        let mut manager = LuaTalentManager::new();
+        manager.add_talent(
+            "EVOKER".to_string(),
+            1,
+            TalentLoadout::new("Build".to_string(), "evoker/devastation/X".to_string()),
+        );
+
+        let reparsed = parse_db(&to_db_lua_string(&manager)).unwrap();
+        assert_eq!(reparsed.get_spec_talents("EVOKER", 1).unwrap()[0].name, "Build");
+        assert_eq!(reparsed.profiles()["EVOKER"].class_name, "EVOKER");
+    }
+
+    #[test]
+    fn test_export_round_trip() {
+        let mut manager = parse_db(EXPORT_FIXTURE).unwrap();
+        let keys: Vec<_> = manager.profiles().keys().map(String::as_str).collect();
+        assert_eq!(keys, ["Garrosh - Draenor", "Jaina - Twisting Nether", "Thrall - Draenor"]);
+        let arms: Vec<_> = manager.get_spec_talents("WARRIOR", 1).unwrap().iter().map(|t| t.name.as_str()).collect();
+        assert_eq!(arms, ["My Arms Build", "R-heroic-sikran_ARCT", "Garrosh Arms"]);
+        assert_eq!(manager.get_spec_talents("WARRIOR", 2).unwrap()[0].name, "My Fury Build");
+
+        // Each profile gets back its own loadouts and fields
+        let reparsed = parse_db(&to_db_lua_string(&manager)).unwrap();
+        assert!(manager.diff(&reparsed).is_empty());
+        assert_eq!(reparsed.profiles(), manager.profiles());
+        assert_eq!(reparsed.profiles()["Thrall - Draenor"].extra_fields, [r#"["minimized"] = false"#]);
+        assert_eq!(reparsed.db_fields(), [r#"["version"] = 2"#]);
+
+        // New builds of a spec only go to the profile of the character it is configured for
+        manager.add_profile("Thrall - Draenor".to_string(), "WARRIOR".to_string()).specs.insert(1);
+        manager.remove_auto_generated("WARRIOR", 1);
+        manager.add_talent(
+            "WARRIOR".to_string(),
+            1,
+            TalentLoadout::new("R-mythic-sikran_ARCT".to_string(), "warrior/arms/NEW".to_string()),
+        );
+        let reparsed = parse_db(&to_db_lua_string(&manager)).unwrap();
+        let names = |profile: &str| -> Vec<String> {
+            reparsed.profiles()[profile].loadouts[&1].iter().cloned().collect()
+        };
+        assert_eq!(names("Thrall - Draenor"), ["My Arms Build", "R-mythic-sikran_ARCT"]);
+        assert_eq!(names("Garrosh - Draenor"), ["Garrosh Arms"]);
+    }
+
+    #[test]
+    fn test_profile_key() {
+        let manager = parse_db(EXPORT_FIXTURE).unwrap();
+        assert_eq!(profile_key(&manager, "Jaina", Some("Draenor")), "Jaina - Draenor");
+        assert_eq!(profile_key(&manager, "Jaina", None), "Jaina - Twisting Nether");
+        assert_eq!(profile_key(&manager, "Sylvanas", None), "Sylvanas");
+    }
+}
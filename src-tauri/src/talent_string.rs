@@ -0,0 +1,265 @@
+use crate::wow::WowClass;
+use anyhow::{bail, Result};
+
+/// Characters of the base64 alphabet used by Blizzard's talent export strings
+const BASE64_CHARS: &[u8] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+
+/// Bit widths of the export header fields
+const VERSION_BITS: u32 = 8;
+const SPEC_ID_BITS: u32 = 16;
+const TREE_HASH_BITS: u32 = 128;
+const RANKS_BITS: u32 = 6;
+const CHOICE_BITS: u32 = 2;
+
+/// Header of a Blizzard talent export string
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct TalentStringHeader {
+    /// Serialization version of the export format
+    pub version: u8,
+    /// Blizzard specialization ID the loadout was exported from
+    pub spec_id: u16,
+}
+
+/// Choices of one talent node in an export string
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+struct NodeSelection {
+    selected: bool,
+    purchased: bool,
+    /// Ranks of a partially ranked node
+    ranks: Option<u32>,
+    /// Entry picked on a choice node
+    choice: Option<u32>,
+}
+
+/// Get the string accepted by the in-game talent importer
+/// Loadout texts may carry a "class/spec/" prefix from the Wowhead link; only the
+/// last path segment is the actual export string
+pub fn import_string(text: &str) -> &str {
+    text.rsplit('/').next().unwrap_or(text)
+}
+
+/// Decode the header (version + spec ID) of a talent export string
+///
+/// The string is base64 encoded and its bits are read least significant first,
+/// matching the game's `ExportUtil` bit stream.
+pub fn decode_header(text: &str) -> Result<TalentStringHeader> {
+    let values = decode_base64(text)?;
+    read_header(&mut BitReader { values: &values, position: 0 })
+}
+
+fn decode_base64(text: &str) -> Result<Vec<u32>> {
+    import_string(text)
+        .bytes()
+        .map(|c| {
+            BASE64_CHARS
+                .iter()
+                .position(|&b| b == c)
+                .map(|v| v as u32)
+                .ok_or_else(|| anyhow::anyhow!("Invalid character '{}' in talent string", c as char))
+        })
+        .collect()
+}
+
+fn read_header(reader: &mut BitReader<'_>) -> Result<TalentStringHeader> {
+    let version = reader.read(VERSION_BITS)?;
+    let spec_id = reader.read(SPEC_ID_BITS)?;
+
+    Ok(TalentStringHeader {
+        version: version as u8,
+        spec_id: spec_id as u16,
+    })
+}
+
+/// Decode the node choices following the header, in the game's tree order
+///
+/// The node list itself is game data, so nodes are read until the bits run out; the padding
+/// at the end reads as unselected nodes. Version 1 strings have no "purchased" flag.
+fn decode_nodes(text: &str) -> Result<(TalentStringHeader, Vec<NodeSelection>)> {
+    let values = decode_base64(text)?;
+    let mut reader = BitReader { values: &values, position: 0 };
+    let header = read_header(&mut reader)?;
+    if !matches!(header.version, 1 | 2) {
+        bail!("Unsupported talent string version {}", header.version);
+    }
+    reader.skip(TREE_HASH_BITS)?;
+
+    let mut nodes = Vec::new();
+    // A record cut short by the end of the string is padding
+    while let Ok(node) = read_node(&mut reader, header.version) {
+        nodes.push(node);
+    }
+    Ok((header, nodes))
+}
+
+fn read_node(reader: &mut BitReader<'_>, version: u8) -> Result<NodeSelection> {
+    let mut node = NodeSelection { selected: reader.read(1)? == 1, ..NodeSelection::default() };
+    if !node.selected {
+        return Ok(node);
+    }
+    node.purchased = version == 1 || reader.read(1)? == 1;
+    if node.purchased {
+        if reader.read(1)? == 1 {
+            node.ranks = Some(reader.read(RANKS_BITS)?);
+        }
+        if reader.read(1)? == 1 {
+            node.choice = Some(reader.read(CHOICE_BITS)?);
+        }
+    }
+    Ok(node)
+}
+
+/// Number of talent nodes chosen differently in two export strings
+/// None if the strings can't be decoded or aren't for the same spec and format version.
+pub fn count_changed_nodes(old: &str, new: &str) -> Option<usize> {
+    let (old_header, old_nodes) = decode_nodes(old).ok()?;
+    let (new_header, new_nodes) = decode_nodes(new).ok()?;
+    if old_header != new_header {
+        return None;
+    }
+
+    let unselected = NodeSelection::default();
+    let changed = (0..old_nodes.len().max(new_nodes.len()))
+        .filter(|&i| old_nodes.get(i).unwrap_or(&unselected) != new_nodes.get(i).unwrap_or(&unselected))
+        .count();
+    Some(changed)
+}
+
+/// Check that a talent export string belongs to the given class and spec index
+pub fn validate_spec(text: &str, wow_class: WowClass, spec_index: u8) -> Result<()> {
+    let Some(expected) = wow_class.spec_id_for_index(spec_index) else {
+        bail!("Unknown spec index {} for {}", spec_index, wow_class.to_lua_format());
+    };
+
+    let header = decode_header(text)?;
+    if header.spec_id != expected {
+        let actual = WowClass::spec_index_from_id(header.spec_id)
+            .map(|(class, index)| format!("{} spec {}", class.to_lua_format(), index))
+            .unwrap_or_else(|| "an unknown spec".to_string());
+        bail!(
+            "Talent string is for spec ID {} ({}), expected {} ({} spec {})",
+            header.spec_id,
+            actual,
+            expected,
+            wow_class.to_lua_format(),
+            spec_index
+        );
+    }
+
+    Ok(())
+}
+
+/// Reads little-endian bit fields from 6-bit base64 values
+struct BitReader<'a> {
+    values: &'a [u32],
+    position: usize,
+}
+
+impl BitReader<'_> {
+    fn read(&mut self, bits: u32) -> Result<u32> {
+        let mut result = 0;
+
+        for bit in 0..bits {
+            let Some(value) = self.values.get(self.position / 6) else {
+                bail!("Talent string is too short");
+            };
+            result |= ((value >> (self.position % 6)) & 1) << bit;
+            self.position += 1;
+        }
+
+        Ok(result)
+    }
+
+    fn skip(&mut self, bits: u32) -> Result<()> {
+        if self.position + bits as usize > self.values.len() * 6 {
+            bail!("Talent string is too short");
+        }
+        self.position += bits as usize;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Encode a header the way the game does, padded with empty tree data
+    fn encode_header(version: u8, spec_id: u16) -> String {
+        let bits = u64::from(version) | (u64::from(spec_id) << VERSION_BITS);
+        (0..8)
+            .map(|i| BASE64_CHARS[((bits >> (i * 6)) & 0x3f) as usize] as char)
+            .collect()
+    }
+
+    #[test]
+    fn test_decode_header() {
+        // Real Arms Warrior strings start with "CcE"
+        let header = decode_header("CcEAAAAAAAAAAAAAAAAAAAAAAAAAAAAAA").unwrap();
+        assert_eq!(header, TalentStringHeader { version: 2, spec_id: 71 });
+
+        let header = decode_header(&format!("evoker/augmentation/{}", encode_header(2, 1473))).unwrap();
+        assert_eq!(header.spec_id, 1473);
+    }
+
+    #[test]
+    fn test_decode_header_invalid() {
+        assert!(decode_header("CcE").is_err());
+        assert!(decode_header("Cc-EAAAAA").is_err());
+    }
+
+    /// Encode a version 2 string: per node, None if unselected, else (ranks, choice)
+    fn encode_nodes(spec_id: u16, nodes: &[Option<(Option<u32>, Option<u32>)>]) -> String {
+        let mut bits = Vec::new();
+        let mut push = |value: u32, width: u32| bits.extend((0..width).map(|bit| (value >> bit) & 1));
+        push(2, VERSION_BITS);
+        push(u32::from(spec_id), SPEC_ID_BITS);
+        push(0, 32);
+        push(0, 32);
+        push(0, 32);
+        push(0, 32);
+        for node in nodes {
+            let Some((ranks, choice)) = node else {
+                push(0, 1);
+                continue;
+            };
+            push(1, 1);
+            push(1, 1);
+            push(u32::from(ranks.is_some()), 1);
+            if let Some(ranks) = ranks {
+                push(*ranks, RANKS_BITS);
+            }
+            push(u32::from(choice.is_some()), 1);
+            if let Some(choice) = choice {
+                push(*choice, CHOICE_BITS);
+            }
+        }
+        bits.chunks(6)
+            .map(|chunk| {
+                let value = chunk.iter().enumerate().fold(0, |value, (i, bit)| value | (bit << i));
+                BASE64_CHARS[value as usize] as char
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_count_changed_nodes() {
+        let old = encode_nodes(71, &[Some((None, None)), None, Some((Some(1), None)), Some((None, Some(0)))]);
+        let same = encode_nodes(71, &[Some((None, None)), None, Some((Some(1), None)), Some((None, Some(0)))]);
+        // A node dropped, the other choice picked and a node taken further down the tree
+        let new = encode_nodes(71, &[Some((None, None)), None, None, Some((None, Some(1))), None, Some((None, None))]);
+
+        assert_eq!(count_changed_nodes(&old, &same), Some(0));
+        assert_eq!(count_changed_nodes(&old, &new), Some(3));
+        assert_eq!(count_changed_nodes(&old, &encode_nodes(72, &[])), None);
+        assert_eq!(count_changed_nodes(&old, "not a talent string"), None);
+    }
+
+    #[test]
+    fn test_validate_spec() {
+        let fury = encode_header(2, 72);
+
+        assert!(validate_spec(&fury, WowClass::Warrior, 2).is_ok());
+
+        let err = validate_spec(&format!("warrior/arms/{}", fury), WowClass::Warrior, 1).unwrap_err();
+        assert!(err.to_string().contains("spec ID 72 (WARRIOR spec 2), expected 71"));
+    }
+}
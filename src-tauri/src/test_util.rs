@@ -0,0 +1,43 @@
+use std::ffi::OsStr;
+use std::fs;
+use std::ops::Deref;
+use std::path::{Path, PathBuf};
+
+/// Directory for a test, removed when dropped, also when the test fails
+pub struct TempDir(PathBuf);
+
+/// Create an empty directory `talent-heron-<name>-<pid>` in the system temp directory
+/// The path is canonical, like scanned paths (the temp directory is a link on macOS).
+pub fn temp_dir(name: &str) -> TempDir {
+    let temp_dir = dunce::canonicalize(std::env::temp_dir()).unwrap();
+    let dir = temp_dir.join(format!("talent-heron-{}-{}", name, std::process::id()));
+    let _ = fs::remove_dir_all(&dir);
+    fs::create_dir_all(&dir).unwrap();
+    TempDir(dir)
+}
+
+impl Deref for TempDir {
+    type Target = Path;
+
+    fn deref(&self) -> &Path {
+        &self.0
+    }
+}
+
+impl AsRef<Path> for TempDir {
+    fn as_ref(&self) -> &Path {
+        &self.0
+    }
+}
+
+impl AsRef<OsStr> for TempDir {
+    fn as_ref(&self) -> &OsStr {
+        self.0.as_os_str()
+    }
+}
+
+impl Drop for TempDir {
+    fn drop(&mut self) {
+        let _ = fs::remove_dir_all(&self.0);
+    }
+}
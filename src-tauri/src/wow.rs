@@ -1,4 +1,10 @@
-use std::collections::HashMap;
+use anyhow::Result;
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+use std::fmt;
+use std::str::FromStr;
+
+/// Icon shown for unknown specs (0 is the addon's question mark)
+pub const FALLBACK_SPEC_ICON: i64 = 0;
 
 /// WoW class representation
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
@@ -19,171 +25,1089 @@ pub enum WowClass {
 }
 
 impl WowClass {
-    /// Parse class from string (PascalCase)
-    pub fn from_str(s: &str) -> Option<Self> {
-        match s {
-            "Warrior" => Some(Self::Warrior),
-            "Paladin" => Some(Self::Paladin),
-            "Hunter" => Some(Self::Hunter),
-            "Rogue" => Some(Self::Rogue),
-            "Priest" => Some(Self::Priest),
-            "DeathKnight" => Some(Self::DeathKnight),
-            "Shaman" => Some(Self::Shaman),
-            "Mage" => Some(Self::Mage),
-            "Warlock" => Some(Self::Warlock),
-            "Monk" => Some(Self::Monk),
-            "Druid" => Some(Self::Druid),
-            "DemonHunter" => Some(Self::DemonHunter),
-            "Evoker" => Some(Self::Evoker),
-            _ => None,
-        }
+    /// Number of variants, from the last one (a new class goes last, after Evoker)
+    const COUNT: usize = WowClass::Evoker as usize + 1;
+
+    /// Class name as used in the config (PascalCase, e.g., "DeathKnight")
+    pub fn as_str(&self) -> &'static str {
+        self.info().name
+    }
+
+    /// Class name for display (e.g., "Death Knight")
+    pub fn display_name(&self) -> &'static str {
+        self.info().display_name
+    }
+
+    /// Official class color as a hex string (e.g., "#C69B6D")
+    pub fn color_hex(&self) -> &'static str {
+        self.info().color
+    }
+
+    /// Name of the in-game class icon texture (e.g., "classicon_deathknight")
+    pub fn icon_name(&self) -> String {
+        format!("classicon_{}", self.to_lua_format().to_ascii_lowercase())
     }
 
     /// Convert class to URL-safe format for Archon.gg
     /// Most classes use lowercase, but DeathKnight and DemonHunter use hyphens
     pub fn to_url_format(&self) -> &'static str {
-        match self {
-            Self::Warrior => "warrior",
-            Self::Paladin => "paladin",
-            Self::Hunter => "hunter",
-            Self::Rogue => "rogue",
-            Self::Priest => "priest",
-            Self::DeathKnight => "death-knight",
-            Self::Shaman => "shaman",
-            Self::Mage => "mage",
-            Self::Warlock => "warlock",
-            Self::Monk => "monk",
-            Self::Druid => "druid",
-            Self::DemonHunter => "demon-hunter",
-            Self::Evoker => "evoker",
-        }
+        self.info().url_slug
     }
 
     /// Convert class to uppercase format for Lua files (e.g., "WARRIOR")
     pub fn to_lua_format(&self) -> &'static str {
-        match self {
-            Self::Warrior => "WARRIOR",
-            Self::Paladin => "PALADIN",
-            Self::Hunter => "HUNTER",
-            Self::Rogue => "ROGUE",
-            Self::Priest => "PRIEST",
-            Self::DeathKnight => "DEATHKNIGHT",
-            Self::Shaman => "SHAMAN",
-            Self::Mage => "MAGE",
-            Self::Warlock => "WARLOCK",
-            Self::Monk => "MONK",
-            Self::Druid => "DRUID",
-            Self::DemonHunter => "DEMONHUNTER",
-            Self::Evoker => "EVOKER",
+        self.info().lua_token
+    }
+
+    /// Parse class from the uppercase Lua format (e.g., "DEATHKNIGHT")
+    pub fn from_lua_format(s: &str) -> Option<Self> {
+        Self::all().iter().copied().find(|c| c.to_lua_format() == s)
+    }
+
+    /// Class from a name as stored by a game client in any supported locale
+    /// ("Todesritter", "Chevalier de la mort", "死亡骑士"); English names and Lua tokens work too
+    pub fn from_localized(s: &str) -> Option<Self> {
+        let name = s.trim();
+        let lowercase = name.to_lowercase();
+
+        LOCALIZED_CLASS_NAMES
+            .iter()
+            .find(|(_, names)| names.iter().any(|localized| localized.to_lowercase() == lowercase))
+            .map(|(class, _)| *class)
+            .or_else(|| {
+                Self::all()
+                    .iter()
+                    .copied()
+                    .find(|class| class.display_name().eq_ignore_ascii_case(name))
+            })
+            .or_else(|| Self::from_lua_format(name))
+    }
+
+    /// All classes, in Blizzard class ID order
+    pub fn all() -> &'static [WowClass] {
+        &ALL_CLASSES
+    }
+
+    /// Number of classes
+    pub fn count() -> usize {
+        Self::COUNT
+    }
+
+    /// Static data of this class
+    fn info(&self) -> &'static ClassInfo {
+        &CLASSES[*self as usize]
+    }
+
+    /// Specs of this class, in spec index order
+    pub fn specs(&self) -> &'static [SpecInfo] {
+        self.info().specs
+    }
+
+    /// Static data of a spec, or None for an unknown spec index
+    fn spec_info(&self, spec_index: u8) -> Option<&'static SpecInfo> {
+        self.specs().get(usize::from(spec_index).checked_sub(1)?)
+    }
+
+    /// Whether the class has a spec with this index (1-based)
+    pub fn is_valid_spec_index(&self, spec_index: u8) -> bool {
+        self.spec_info(spec_index).is_some()
+    }
+
+    /// Get the Blizzard specialization ID for a spec index (e.g., Arms Warrior = 71)
+    pub fn spec_id_for_index(&self, spec_index: u8) -> Option<u16> {
+        self.spec_info(spec_index).map(|spec| spec.id)
+    }
+
+    /// Icon (fileDataID) of a spec, or `FALLBACK_SPEC_ICON` for an unknown index
+    pub fn spec_icon(&self, spec_index: u8) -> i64 {
+        self.spec_info(spec_index).map_or(FALLBACK_SPEC_ICON, |spec| spec.icon)
+    }
+
+    /// Group role of a spec slug (aliases accepted), or None for an unknown spec
+    pub fn spec_role(&self, spec_slug: &str) -> Option<Role> {
+        self.spec_role_for_index(self.spec_index(spec_slug)?)
+    }
+
+    /// Group role of a spec, or None for an unknown spec index
+    pub fn spec_role_for_index(&self, spec_index: u8) -> Option<Role> {
+        self.spec_info(spec_index).map(|spec| spec.role)
+    }
+
+    /// Hero talent trees available to a spec (empty for an unknown spec index)
+    pub fn hero_talents(&self, spec_index: u8) -> &'static [HeroTalent] {
+        self.spec_info(spec_index).map_or(&[], |spec| &spec.hero_talents)
+    }
+
+    /// Parse a hero talent tree from the config and check that the spec can use it
+    pub fn parse_hero_talent(&self, spec_index: u8, s: &str) -> Result<HeroTalent> {
+        let valid = self.hero_talents(spec_index);
+        match HeroTalent::from_slug(s) {
+            Some(hero) if valid.contains(&hero) => Ok(hero),
+            _ => {
+                let names: Vec<_> = valid.iter().map(|hero| hero.slug()).collect();
+                anyhow::bail!(
+                    "Invalid hero talent {} for {} spec {} (valid: {})",
+                    s,
+                    self.to_lua_format(),
+                    spec_index,
+                    names.join(", ")
+                )
+            }
         }
     }
 
+    /// Find the class and spec index for a Blizzard specialization ID
+    pub fn spec_index_from_id(spec_id: u16) -> Option<(Self, u8)> {
+        Self::all().iter().find_map(|class| {
+            let position = class.specs().iter().position(|spec| spec.id == spec_id)?;
+            Some((*class, u8::try_from(position + 1).ok()?))
+        })
+    }
+
+    /// Blizzard specialization ID of a spec slug (e.g., "arms" -> 71)
+    pub fn spec_id(&self, spec_slug: &str) -> Option<u16> {
+        self.spec_id_for_index(self.spec_index(spec_slug)?)
+    }
+
+    /// Find the class and spec slug for a Blizzard specialization ID
+    pub fn from_spec_id(spec_id: u16) -> Option<(Self, &'static str)> {
+        let (class, index) = Self::spec_index_from_id(spec_id)?;
+        Some((class, class.spec_name_for_index(index)?))
+    }
+
+    /// Blizzard class ID (1 = Warrior ... 13 = Evoker)
+    pub fn class_id(&self) -> u8 {
+        *self as u8 + 1
+    }
+
+    /// Find the class for a Blizzard class ID
+    pub fn from_class_id(class_id: u8) -> Option<Self> {
+        Self::all().iter().copied().find(|class| class.class_id() == class_id)
+    }
+
+    /// Spec slugs of this class in a game flavor, in spec index order (empty if unavailable)
+    pub fn spec_names_for(&self, flavor: GameFlavor) -> Vec<&'static str> {
+        if !flavor.classes().contains(self) {
+            return Vec::new();
+        }
+
+        match (flavor, self) {
+            (GameFlavor::Retail, _) => self.valid_specs(),
+            // Classic talent trees; Feral covers both cat and bear, Outlaw was still Combat
+            (_, Self::Druid) => vec!["balance", "feral", "restoration"],
+            (_, Self::Rogue) => vec!["assassination", "combat", "subtlety"],
+            _ => self.valid_specs(),
+        }
+    }
+
+    /// Validated retail spec of this class; aliases and deprecated names resolve to the current slug
+    pub fn spec(&self, spec_name: &str) -> Result<WowSpec> {
+        self.spec_for(GameFlavor::Retail, spec_name)
+    }
+
+    /// Validated spec of this class in a game flavor
+    pub fn spec_for(&self, flavor: GameFlavor, spec_name: &str) -> Result<WowSpec> {
+        let names = self.spec_names_for(flavor);
+        let index = self.spec_index_for(flavor, spec_name).ok_or_else(|| {
+            anyhow::anyhow!(
+                "Invalid spec '{}' for {}{} (valid: {})",
+                spec_name,
+                self,
+                did_you_mean(self.closest_spec_in(&names, spec_name)),
+                names.join(", ")
+            )
+        })?;
+
+        Ok(WowSpec {
+            class: *self,
+            slug: names[usize::from(index) - 1],
+            index,
+        })
+    }
+
+    /// Closest of `names` (slugs of a flavor) to a mistyped spec name, see `closest_spec`
+    fn closest_spec_in(&self, names: &[&'static str], input: &str) -> Option<&'static str> {
+        let aliases = self.info().spec_aliases.iter().filter(|(_, slug)| names.contains(slug)).copied();
+        closest_match(&normalize_spec_name(input), names.iter().map(|name| (*name, *name)).chain(aliases))
+    }
+
+    /// Get the specialization index for a spec name in a game flavor
+    pub fn spec_index_for(&self, flavor: GameFlavor, spec_name: &str) -> Option<u8> {
+        if flavor == GameFlavor::Retail {
+            return self.spec_index(spec_name);
+        }
+
+        let names = self.spec_names_for(flavor);
+        let spec_name = normalize_spec_name(spec_name);
+        let spec_name = self
+            .spec_alias(&spec_name)
+            .filter(|alias| names.contains(alias))
+            .unwrap_or(spec_name.as_str());
+        let position = names.iter().position(|name| *name == spec_name)?;
+        u8::try_from(position + 1).ok()
+    }
+
+    /// Spec slug for a spec index (e.g., Warrior 2 -> "fury"), the reverse of `spec_index`
+    pub fn spec_name_for_index(&self, spec_index: u8) -> Option<&'static str> {
+        self.spec_info(spec_index).map(|spec| spec.slug)
+    }
+
     /// Get the specialization index for a given spec name
+    /// Case and spaces are ignored ("Beast Mastery"); deprecated names (`deprecated_spec_name`)
+    /// and aliases (`spec_alias`) are accepted as well
     pub fn spec_index(&self, spec_name: &str) -> Option<u8> {
-        let spec_map = self.get_spec_map();
-        spec_map.get(spec_name).copied()
+        // Current slugs are the common case and don't need normalizing
+        if let Some(index) = self.slug_index(spec_name) {
+            return Some(index);
+        }
+
+        let spec_name = normalize_spec_name(spec_name);
+        let spec_name = self
+            .deprecated_spec_name(&spec_name)
+            .or_else(|| self.spec_alias(&spec_name))
+            .unwrap_or(spec_name.as_str());
+        self.slug_index(spec_name)
+    }
+
+    /// Spec index of a current retail slug, from the static spec table
+    fn slug_index(&self, slug: &str) -> Option<u8> {
+        let position = self.specs().iter().position(|spec| spec.slug == slug)?;
+        u8::try_from(position + 1).ok()
+    }
+
+    /// Current name of a spec that was renamed in the game (e.g., Rogue "combat" -> "outlaw")
+    /// Returns None if `spec_name` isn't a deprecated name
+    pub fn deprecated_spec_name(&self, spec_name: &str) -> Option<&'static str> {
+        match (self, normalize_spec_name(spec_name).as_str()) {
+            (Self::Rogue, "combat") => Some("outlaw"),
+            _ => None,
+        }
+    }
+
+    /// Spec slug for a common abbreviation (e.g., Hunter "bm" -> "beast-mastery", Monk "bm" -> "brewmaster")
+    pub fn spec_alias(&self, alias: &str) -> Option<&'static str> {
+        let alias = normalize_spec_name(alias);
+        self.info()
+            .spec_aliases
+            .iter()
+            .find(|(name, _)| *name == alias)
+            .map(|(_, slug)| *slug)
+    }
+
+    /// Spec name as it should be stored in the config: lowercase and hyphenated, with
+    /// abbreviations replaced by their slug ("Resto" -> "restoration")
+    /// Deprecated and unknown names are kept (normalized), so Classic names and validation
+    /// errors still see what the user wrote.
+    pub fn canonical_spec_name(&self, spec_name: &str) -> String {
+        let normalized = normalize_spec_name(spec_name);
+        match self.spec_alias(&normalized) {
+            Some(slug) => slug.to_string(),
+            None => normalized,
+        }
     }
 
-    /// Get all valid specializations for this class
-    #[allow(dead_code)]
+    /// Get all valid specializations for this class, in spec index order
     pub fn valid_specs(&self) -> Vec<&'static str> {
-        self.get_spec_map().keys().copied().collect()
+        self.specs().iter().map(|spec| spec.slug).collect()
     }
 
-    /// Internal helper to get the spec name -> index mapping
-    fn get_spec_map(&self) -> HashMap<&'static str, u8> {
-        match self {
-            Self::Warrior => {
-                let mut map = HashMap::new();
-                map.insert("arms", 1);
-                map.insert("fury", 2);
-                map.insert("protection", 3);
-                map
-            }
-            Self::Paladin => {
-                let mut map = HashMap::new();
-                map.insert("holy", 1);
-                map.insert("protection", 2);
-                map.insert("retribution", 3);
-                map
-            }
-            Self::Hunter => {
-                let mut map = HashMap::new();
-                map.insert("beast-mastery", 1);
-                map.insert("marksmanship", 2);
-                map.insert("survival", 3);
-                map
-            }
-            Self::Rogue => {
-                let mut map = HashMap::new();
-                map.insert("assassination", 1);
-                map.insert("combat", 2);
-                map.insert("subtlety", 3);
-                map
-            }
-            Self::Priest => {
-                let mut map = HashMap::new();
-                map.insert("discipline", 1);
-                map.insert("holy", 2);
-                map.insert("shadow", 3);
-                map
-            }
-            Self::DeathKnight => {
-                let mut map = HashMap::new();
-                map.insert("blood", 1);
-                map.insert("frost", 2);
-                map.insert("unholy", 3);
-                map
-            }
-            Self::Shaman => {
-                let mut map = HashMap::new();
-                map.insert("elemental", 1);
-                map.insert("enhancement", 2);
-                map.insert("restoration", 3);
-                map
-            }
-            Self::Mage => {
-                let mut map = HashMap::new();
-                map.insert("arcane", 1);
-                map.insert("fire", 2);
-                map.insert("frost", 3);
-                map
-            }
-            Self::Warlock => {
-                let mut map = HashMap::new();
-                map.insert("affliction", 1);
-                map.insert("demonology", 2);
-                map.insert("destruction", 3);
-                map
-            }
-            Self::Monk => {
-                let mut map = HashMap::new();
-                map.insert("brewmaster", 1);
-                map.insert("mistweaver", 2);
-                map.insert("windwalker", 3);
-                map
-            }
-            Self::Druid => {
-                let mut map = HashMap::new();
-                map.insert("balance", 1);
-                map.insert("feral", 2);
-                map.insert("guardian", 3);
-                map.insert("restoration", 4);
-                map
-            }
-            Self::DemonHunter => {
-                let mut map = HashMap::new();
-                map.insert("havoc", 1);
-                map.insert("vengeance", 2);
-                map
+    /// Display name of a spec slug (e.g., "beast-mastery" -> "Beast Mastery")
+    pub fn spec_display_name(&self, spec_slug: &str) -> Option<&'static str> {
+        self.specs()
+            .iter()
+            .find(|spec| spec.slug == spec_slug)
+            .map(|spec| spec.display_name)
+    }
+}
+
+/// Static data of a specialization
+#[derive(Debug)]
+pub struct SpecInfo {
+    /// Spec slug as used in the config and Archon URLs (e.g., "beast-mastery")
+    pub slug: &'static str,
+    pub display_name: &'static str,
+    /// Blizzard specialization ID
+    pub id: u16,
+    /// Specialization icon (fileDataID)
+    pub icon: i64,
+    pub role: Role,
+    pub hero_talents: [HeroTalent; 2],
+}
+
+const fn spec(
+    slug: &'static str,
+    display_name: &'static str,
+    id: u16,
+    icon: i64,
+    role: Role,
+    hero_talents: [HeroTalent; 2],
+) -> SpecInfo {
+    SpecInfo {
+        slug,
+        display_name,
+        id,
+        icon,
+        role,
+        hero_talents,
+    }
+}
+
+/// Static data of a class
+struct ClassInfo {
+    class: WowClass,
+    /// PascalCase name as used in the config
+    name: &'static str,
+    display_name: &'static str,
+    /// Uppercase class token as used in Lua files
+    lua_token: &'static str,
+    /// Class segment of Archon URLs
+    url_slug: &'static str,
+    color: &'static str,
+    specs: &'static [SpecInfo],
+    /// Common spec abbreviations and the slugs they stand for
+    spec_aliases: &'static [(&'static str, &'static str)],
+}
+
+/// Static data of every class, in Blizzard class ID order (the order of the `WowClass`
+/// variants); adding a class means adding a variant and an entry here
+const CLASSES: [ClassInfo; WowClass::COUNT] = [
+    ClassInfo {
+        class: WowClass::Warrior,
+        name: "Warrior",
+        display_name: "Warrior",
+        lua_token: "WARRIOR",
+        url_slug: "warrior",
+        color: "#C69B6D",
+        specs: &[
+            spec("arms", "Arms", 71, 132355, Role::Damage, [HeroTalent::Colossus, HeroTalent::Slayer]),
+            spec("fury", "Fury", 72, 132347, Role::Damage, [HeroTalent::MountainThane, HeroTalent::Slayer]),
+            spec("protection", "Protection", 73, 132341, Role::Tank, [HeroTalent::Colossus, HeroTalent::MountainThane]),
+        ],
+        spec_aliases: &[("prot", "protection")],
+    },
+    ClassInfo {
+        class: WowClass::Paladin,
+        name: "Paladin",
+        display_name: "Paladin",
+        lua_token: "PALADIN",
+        url_slug: "paladin",
+        color: "#F48CBA",
+        specs: &[
+            spec("holy", "Holy", 65, 135920, Role::Healer, [HeroTalent::HeraldOfTheSun, HeroTalent::Lightsmith]),
+            spec("protection", "Protection", 66, 236264, Role::Tank, [HeroTalent::Lightsmith, HeroTalent::Templar]),
+            spec("retribution", "Retribution", 70, 135873, Role::Damage,
+                [HeroTalent::HeraldOfTheSun, HeroTalent::Templar]),
+        ],
+        spec_aliases: &[("prot", "protection"), ("ret", "retribution")],
+    },
+    ClassInfo {
+        class: WowClass::Hunter,
+        name: "Hunter",
+        display_name: "Hunter",
+        lua_token: "HUNTER",
+        url_slug: "hunter",
+        color: "#AAD372",
+        specs: &[
+            spec("beast-mastery", "Beast Mastery", 253, 461112, Role::Damage,
+                [HeroTalent::PackLeader, HeroTalent::DarkRanger]),
+            spec("marksmanship", "Marksmanship", 254, 236179, Role::Damage,
+                [HeroTalent::DarkRanger, HeroTalent::Sentinel]),
+            spec("survival", "Survival", 255, 461113, Role::Damage, [HeroTalent::PackLeader, HeroTalent::Sentinel]),
+        ],
+        spec_aliases: &[
+            ("bm", "beast-mastery"),
+            ("mm", "marksmanship"),
+            ("marks", "marksmanship"),
+            ("sv", "survival"),
+            ("surv", "survival"),
+        ],
+    },
+    ClassInfo {
+        class: WowClass::Rogue,
+        name: "Rogue",
+        display_name: "Rogue",
+        lua_token: "ROGUE",
+        url_slug: "rogue",
+        color: "#FFF468",
+        specs: &[
+            spec("assassination", "Assassination", 259, 236270, Role::Damage,
+                [HeroTalent::Deathstalker, HeroTalent::Fatebound]),
+            spec("outlaw", "Outlaw", 260, 236286, Role::Damage, [HeroTalent::Fatebound, HeroTalent::Trickster]),
+            spec("subtlety", "Subtlety", 261, 132320, Role::Damage, [HeroTalent::Deathstalker, HeroTalent::Trickster]),
+        ],
+        spec_aliases: &[("sin", "assassination"), ("assa", "assassination"), ("sub", "subtlety"), ("subt", "subtlety")],
+    },
+    ClassInfo {
+        class: WowClass::Priest,
+        name: "Priest",
+        display_name: "Priest",
+        lua_token: "PRIEST",
+        url_slug: "priest",
+        color: "#FFFFFF",
+        specs: &[
+            spec("discipline", "Discipline", 256, 135940, Role::Healer, [HeroTalent::Oracle, HeroTalent::Voidweaver]),
+            spec("holy", "Holy", 257, 237542, Role::Healer, [HeroTalent::Archon, HeroTalent::Oracle]),
+            spec("shadow", "Shadow", 258, 136207, Role::Damage, [HeroTalent::Archon, HeroTalent::Voidweaver]),
+        ],
+        spec_aliases: &[("disc", "discipline"), ("sp", "shadow")],
+    },
+    ClassInfo {
+        class: WowClass::DeathKnight,
+        name: "DeathKnight",
+        display_name: "Death Knight",
+        lua_token: "DEATHKNIGHT",
+        url_slug: "death-knight",
+        color: "#C41E3A",
+        specs: &[
+            spec("blood", "Blood", 250, 135770, Role::Tank, [HeroTalent::Deathbringer, HeroTalent::SanLayn]),
+            spec("frost", "Frost", 251, 135773, Role::Damage,
+                [HeroTalent::Deathbringer, HeroTalent::RiderOfTheApocalypse]),
+            spec("unholy", "Unholy", 252, 135775, Role::Damage,
+                [HeroTalent::RiderOfTheApocalypse, HeroTalent::SanLayn]),
+        ],
+        spec_aliases: &[("uh", "unholy")],
+    },
+    ClassInfo {
+        class: WowClass::Shaman,
+        name: "Shaman",
+        display_name: "Shaman",
+        lua_token: "SHAMAN",
+        url_slug: "shaman",
+        color: "#0070DD",
+        specs: &[
+            spec("elemental", "Elemental", 262, 136048, Role::Damage, [HeroTalent::Farseer, HeroTalent::Stormbringer]),
+            spec("enhancement", "Enhancement", 263, 237581, Role::Damage,
+                [HeroTalent::Stormbringer, HeroTalent::Totemic]),
+            spec("restoration", "Restoration", 264, 136052, Role::Healer, [HeroTalent::Farseer, HeroTalent::Totemic]),
+        ],
+        spec_aliases: &[
+            ("ele", "elemental"),
+            ("enh", "enhancement"),
+            ("enhance", "enhancement"),
+            ("resto", "restoration"),
+        ],
+    },
+    ClassInfo {
+        class: WowClass::Mage,
+        name: "Mage",
+        display_name: "Mage",
+        lua_token: "MAGE",
+        url_slug: "mage",
+        color: "#3FC7EB",
+        specs: &[
+            spec("arcane", "Arcane", 62, 135932, Role::Damage, [HeroTalent::Spellslinger, HeroTalent::Sunfury]),
+            spec("fire", "Fire", 63, 135810, Role::Damage, [HeroTalent::Frostfire, HeroTalent::Sunfury]),
+            spec("frost", "Frost", 64, 135846, Role::Damage, [HeroTalent::Frostfire, HeroTalent::Spellslinger]),
+        ],
+        spec_aliases: &[("arc", "arcane")],
+    },
+    ClassInfo {
+        class: WowClass::Warlock,
+        name: "Warlock",
+        display_name: "Warlock",
+        lua_token: "WARLOCK",
+        url_slug: "warlock",
+        color: "#8788EE",
+        specs: &[
+            spec("affliction", "Affliction", 265, 136145, Role::Damage,
+                [HeroTalent::Hellcaller, HeroTalent::SoulHarvester]),
+            spec("demonology", "Demonology", 266, 136172, Role::Damage,
+                [HeroTalent::Diabolist, HeroTalent::SoulHarvester]),
+            spec("destruction", "Destruction", 267, 136186, Role::Damage,
+                [HeroTalent::Diabolist, HeroTalent::Hellcaller]),
+        ],
+        spec_aliases: &[
+            ("aff", "affliction"),
+            ("affli", "affliction"),
+            ("demo", "demonology"),
+            ("destro", "destruction"),
+        ],
+    },
+    ClassInfo {
+        class: WowClass::Monk,
+        name: "Monk",
+        display_name: "Monk",
+        lua_token: "MONK",
+        url_slug: "monk",
+        color: "#00FF98",
+        specs: &[
+            spec("brewmaster", "Brewmaster", 268, 608951, Role::Tank,
+                [HeroTalent::MasterOfHarmony, HeroTalent::ShadoPan]),
+            spec("mistweaver", "Mistweaver", 270, 608952, Role::Healer,
+                [HeroTalent::ConduitOfTheCelestials, HeroTalent::MasterOfHarmony]),
+            spec("windwalker", "Windwalker", 269, 608953, Role::Damage,
+                [HeroTalent::ConduitOfTheCelestials, HeroTalent::ShadoPan]),
+        ],
+        spec_aliases: &[
+            ("bm", "brewmaster"),
+            ("brew", "brewmaster"),
+            ("mw", "mistweaver"),
+            ("mist", "mistweaver"),
+            ("ww", "windwalker"),
+            ("wind", "windwalker"),
+        ],
+    },
+    ClassInfo {
+        class: WowClass::Druid,
+        name: "Druid",
+        display_name: "Druid",
+        lua_token: "DRUID",
+        url_slug: "druid",
+        color: "#FF7C0A",
+        specs: &[
+            spec("balance", "Balance", 102, 136096, Role::Damage,
+                [HeroTalent::ElunesChosen, HeroTalent::KeeperOfTheGrove]),
+            spec("feral", "Feral", 103, 132115, Role::Damage, [HeroTalent::DruidOfTheClaw, HeroTalent::Wildstalker]),
+            spec("guardian", "Guardian", 104, 132276, Role::Tank,
+                [HeroTalent::DruidOfTheClaw, HeroTalent::ElunesChosen]),
+            spec("restoration", "Restoration", 105, 136041, Role::Healer,
+                [HeroTalent::KeeperOfTheGrove, HeroTalent::Wildstalker]),
+        ],
+        spec_aliases: &[
+            ("boomkin", "balance"),
+            ("moonkin", "balance"),
+            ("cat", "feral"),
+            ("bear", "guardian"),
+            ("resto", "restoration"),
+        ],
+    },
+    ClassInfo {
+        class: WowClass::DemonHunter,
+        name: "DemonHunter",
+        display_name: "Demon Hunter",
+        lua_token: "DEMONHUNTER",
+        url_slug: "demon-hunter",
+        color: "#A330C9",
+        specs: &[
+            spec("havoc", "Havoc", 577, 1247264, Role::Damage, [HeroTalent::AldrachiReaver, HeroTalent::FelScarred]),
+            spec("vengeance", "Vengeance", 581, 1247265, Role::Tank,
+                [HeroTalent::AldrachiReaver, HeroTalent::FelScarred]),
+        ],
+        spec_aliases: &[("veng", "vengeance")],
+    },
+    ClassInfo {
+        class: WowClass::Evoker,
+        name: "Evoker",
+        display_name: "Evoker",
+        lua_token: "EVOKER",
+        url_slug: "evoker",
+        color: "#33937F",
+        specs: &[
+            spec("devastation", "Devastation", 1467, 4511811, Role::Damage,
+                [HeroTalent::Flameshaper, HeroTalent::Scalecommander]),
+            spec("preservation", "Preservation", 1468, 4511812, Role::Healer,
+                [HeroTalent::Chronowarden, HeroTalent::Flameshaper]),
+            spec("augmentation", "Augmentation", 1473, 5198700, Role::Damage,
+                [HeroTalent::Chronowarden, HeroTalent::Scalecommander]),
+        ],
+        spec_aliases: &[
+            ("dev", "devastation"),
+            ("deva", "devastation"),
+            ("pres", "preservation"),
+            ("aug", "augmentation"),
+        ],
+    },
+];
+
+const _: () = assert!(CLASSES.len() == WowClass::COUNT);
+
+/// `WowClass::all()`, taken from the class table
+const ALL_CLASSES: [WowClass; WowClass::COUNT] = {
+    let mut all = [WowClass::Warrior; WowClass::COUNT];
+    let mut i = 0;
+    while i < WowClass::COUNT {
+        // `info()` indexes the table by variant, so the orders must match
+        assert!(CLASSES[i].class as usize == i);
+        all[i] = CLASSES[i].class;
+        i += 1;
+    }
+    all
+};
+
+/// Localized class names (male and female forms) of deDE, frFR, esES/esMX, ptBR, itIT, ruRU,
+/// koKR, zhCN and zhTW clients; names identical to the English one are left out
+const LOCALIZED_CLASS_NAMES: &[(WowClass, &[&str])] = &[
+    (WowClass::Warrior, &[
+        "Krieger", "Kriegerin", "Guerrier", "Guerrière", "Guerrero", "Guerrera", "Guerreiro", "Guerreira",
+        "Guerriero", "Guerriera", "Воин", "Воительница", "전사", "战士", "戰士",
+    ]),
+    (WowClass::Paladin, &["Paladín", "Paladino", "Paladina", "Паладин", "성기사", "圣骑士", "聖騎士"]),
+    (WowClass::Hunter, &[
+        "Jäger", "Jägerin", "Chasseur", "Chasseresse", "Cazador", "Cazadora", "Caçador", "Caçadora",
+        "Cacciatore", "Cacciatrice", "Охотник", "Охотница", "사냥꾼", "猎人", "獵人",
+    ]),
+    (WowClass::Rogue, &[
+        "Schurke", "Schurkin", "Voleur", "Voleuse", "Pícaro", "Pícara", "Ladino", "Ladina", "Ladro", "Ladra",
+        "Разбойник", "Разбойница", "도적", "潜行者", "盜賊",
+    ]),
+    (WowClass::Priest, &[
+        "Priester", "Priesterin", "Prêtre", "Prêtresse", "Sacerdote", "Sacerdotisa", "Sacerdotessa", "Жрец",
+        "Жрица", "사제", "牧师", "牧師",
+    ]),
+    (WowClass::DeathKnight, &[
+        "Todesritter", "Todesritterin", "Chevalier de la mort", "Caballero de la Muerte", "Cavaleiro da Morte",
+        "Cavaleira da Morte", "Cavaliere della Morte", "Рыцарь смерти", "죽음의 기사", "死亡骑士", "死亡騎士",
+    ]),
+    (WowClass::Shaman, &[
+        "Schamane", "Schamanin", "Chaman", "Chamane", "Chamán", "Xamã", "Sciamano", "Sciamana", "Шаман",
+        "Шаманка", "주술사", "萨满祭司", "薩滿",
+    ]),
+    (WowClass::Mage, &["Magier", "Magierin", "Mago", "Maga", "Маг", "마법사", "法师", "法師"]),
+    (WowClass::Warlock, &[
+        "Hexenmeister", "Hexenmeisterin", "Démoniste", "Brujo", "Bruja", "Bruxo", "Bruxa", "Stregone", "Strega",
+        "Чернокнижник", "Чернокнижница", "흑마법사", "术士", "術士",
+    ]),
+    (WowClass::Monk, &[
+        "Mönch", "Moine", "Moniale", "Monje", "Monge", "Monja", "Monaco", "Monaca", "Монах", "Монахиня", "수도사",
+        "武僧",
+    ]),
+    (WowClass::Druid, &[
+        "Druide", "Druidin", "Druidesse", "Druida", "Druidesa", "Druido", "Друид", "드루이드", "德鲁伊", "德魯伊",
+    ]),
+    (WowClass::DemonHunter, &[
+        "Dämonenjäger", "Dämonenjägerin", "Chasseur de démons", "Chasseresse de démons", "Cazador de demonios",
+        "Cazadora de demonios", "Caçador de Demônios", "Caçadora de Demônios", "Cacciatore di Demoni",
+        "Cacciatrice di Demoni", "Охотник на демонов", "Охотница на демонов", "악마사냥꾼", "恶魔猎手", "惡魔獵人",
+    ]),
+    (WowClass::Evoker, &[
+        "Rufer", "Ruferin", "Évocateur", "Évocatrice", "Evocador", "Evocadora", "Conjurante", "Evocatore",
+        "Evocatrice", "Пробудитель", "Пробудительница", "기원사", "唤魔师", "喚能師",
+    ]),
+];
+
+/// A specialization of a class, validated once when the config is read
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct WowSpec {
+    pub class: WowClass,
+    /// Current spec slug as used in Archon URLs (e.g., "outlaw")
+    pub slug: &'static str,
+    /// Spec index as used by the talent addons (1-based)
+    pub index: u8,
+}
+
+impl WowSpec {
+    /// Display name of the spec (e.g., "Beast Mastery"), or the slug for Classic-only specs
+    pub fn display_name(&self) -> &'static str {
+        self.class.spec_display_name(self.slug).unwrap_or(self.slug)
+    }
+}
+
+/// Common abbreviations of class names, lowercase
+const CLASS_ALIASES: &[(&str, WowClass)] = &[
+    ("warr", WowClass::Warrior),
+    ("pala", WowClass::Paladin),
+    ("pally", WowClass::Paladin),
+    ("hunt", WowClass::Hunter),
+    ("dk", WowClass::DeathKnight),
+    ("sham", WowClass::Shaman),
+    ("shammy", WowClass::Shaman),
+    ("lock", WowClass::Warlock),
+    ("dh", WowClass::DemonHunter),
+    ("evo", WowClass::Evoker),
+];
+
+impl FromStr for WowClass {
+    type Err = anyhow::Error;
+
+    /// Parse a class name; PascalCase as in the config ("DeathKnight"), but case, spaces,
+    /// hyphens and underscores are ignored, so "Death Knight" and "DEATHKNIGHT" work too,
+    /// as do common abbreviations like "DK" or "Pally"
+    fn from_str(s: &str) -> Result<Self> {
+        let normalized: String = s
+            .chars()
+            .filter(|c| !matches!(c, ' ' | '-' | '_'))
+            .collect::<String>()
+            .to_ascii_lowercase();
+
+        let class = Self::all()
+            .iter()
+            .copied()
+            .find(|class| class.as_str().eq_ignore_ascii_case(&normalized))
+            .or_else(|| {
+                CLASS_ALIASES
+                    .iter()
+                    .find(|(alias, _)| *alias == normalized)
+                    .map(|(_, class)| *class)
+            });
+
+        class.ok_or_else(|| {
+            let valid: Vec<_> = Self::all().iter().map(|class| class.as_str()).collect();
+            anyhow::anyhow!(
+                "Invalid class '{}'{} (valid: {})",
+                s,
+                did_you_mean(closest_match(s, valid.iter().map(|name| (*name, *name)))),
+                valid.join(", ")
+            )
+        })
+    }
+}
+
+/// Lowercase a spec name and hyphenate its words ("Beast Mastery", "beast_mastery" and
+/// "BeastMastery" -> "beast-mastery")
+/// A capital only starts a word when followed by a lowercase letter, so abbreviations like "BrM" stay whole.
+fn normalize_spec_name(spec_name: &str) -> String {
+    let chars: Vec<char> = spec_name.trim().chars().collect();
+    let mut slug = String::with_capacity(chars.len() + 2);
+    for (i, &c) in chars.iter().enumerate() {
+        let starts_word = c.is_uppercase()
+            && i > 0
+            && chars[i - 1].is_lowercase()
+            && chars.get(i + 1).is_some_and(|next| next.is_lowercase());
+        if matches!(c, ' ' | '_' | '-') || starts_word {
+            if !slug.is_empty() && !slug.ends_with('-') {
+                slug.push('-');
             }
-            Self::Evoker => {
-                let mut map = HashMap::new();
-                map.insert("devastation", 1);
-                map.insert("preservation", 2);
-                map
+            if !starts_word {
+                continue;
             }
         }
+        slug.extend(c.to_lowercase());
+    }
+    slug
+}
+
+/// Closest valid spec slug to a mistyped spec name (e.g., Shaman "restauration" -> "restoration"),
+/// comparing against slugs and abbreviations; None if nothing is close enough
+pub fn closest_spec(class: WowClass, input: &str) -> Option<&'static str> {
+    class.closest_spec_in(&class.valid_specs(), input)
+}
+
+/// Suggestion for the `(name, suggestion)` candidate whose name is closest to a mistyped value
+/// Allows one edit per three characters of the shorter string, so short names need a near match
+pub(crate) fn closest_match<'a>(input: &str, candidates: impl IntoIterator<Item = (&'a str, &'a str)>) -> Option<&'a str> {
+    let input = input.to_lowercase();
+
+    candidates
+        .into_iter()
+        .map(|(name, suggestion)| (edit_distance(&input, &name.to_lowercase()), name, suggestion))
+        .filter(|(distance, name, _)| {
+            *distance <= (input.chars().count().min(name.chars().count()) / 3).max(1)
+        })
+        .min_by_key(|(distance, _, _)| *distance)
+        .map(|(_, _, suggestion)| suggestion)
+}
+
+/// ", did you mean 'x'?" for a suggestion, or an empty string
+pub(crate) fn did_you_mean(suggestion: Option<&str>) -> String {
+    suggestion
+        .map(|suggestion| format!(", did you mean '{}'?", suggestion))
+        .unwrap_or_default()
+}
+
+/// Levenshtein distance between two strings
+fn edit_distance(a: &str, b: &str) -> usize {
+    let b: Vec<char> = b.chars().collect();
+    let mut previous: Vec<usize> = (0..=b.len()).collect();
+
+    for (i, ca) in a.chars().enumerate() {
+        let mut current = vec![i + 1; b.len() + 1];
+        for (j, cb) in b.iter().enumerate() {
+            let substitution = previous[j] + usize::from(ca != *cb);
+            current[j + 1] = substitution.min(previous[j + 1] + 1).min(current[j] + 1);
+        }
+        previous = current;
+    }
+
+    previous[b.len()]
+}
+
+impl fmt::Display for WowClass {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+impl Serialize for WowClass {
+    fn serialize<S: Serializer>(&self, serializer: S) -> std::result::Result<S::Ok, S::Error> {
+        serializer.serialize_str(self.as_str())
+    }
+}
+
+impl<'de> Deserialize<'de> for WowClass {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> std::result::Result<Self, D::Error> {
+        let s = String::deserialize(deserializer)?;
+        s.parse().map_err(serde::de::Error::custom)
+    }
+}
+
+/// Game version a config targets; Classic flavors have fewer classes and their own talent trees
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum GameFlavor {
+    #[default]
+    Retail,
+    ClassicEra,
+    Cataclysm,
+}
+
+impl GameFlavor {
+    /// Classes playable in this flavor, in Blizzard class ID order
+    pub fn classes(&self) -> &'static [WowClass] {
+        use WowClass::*;
+
+        match self {
+            Self::Retail => WowClass::all(),
+            Self::ClassicEra => &[Warrior, Paladin, Hunter, Rogue, Priest, Shaman, Mage, Warlock, Druid],
+            Self::Cataclysm => &[Warrior, Paladin, Hunter, Rogue, Priest, DeathKnight, Shaman, Mage, Warlock, Druid],
+        }
+    }
+
+    /// Root of Archon.gg build pages for this flavor
+    pub fn archon_base_url(&self) -> &'static str {
+        match self {
+            Self::Retail => "https://www.archon.gg/wow/builds",
+            Self::ClassicEra => "https://www.archon.gg/classic/builds",
+            Self::Cataclysm => "https://www.archon.gg/cata/builds",
+        }
+    }
+}
+
+/// Group role of a specialization
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum Role {
+    Tank,
+    Healer,
+    #[serde(alias = "dps")]
+    Damage,
+}
+
+/// Display metadata of a spec, for the frontend
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SpecMetadata {
+    pub index: u8,
+    pub slug: &'static str,
+    pub display_name: &'static str,
+    pub role: Role,
+    /// Spec icon (fileDataID)
+    pub icon: i64,
+}
+
+/// Display metadata of a class and its specs, for the frontend
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ClassMetadata {
+    /// Class name as used in the config (e.g., "DeathKnight")
+    pub class: &'static str,
+    pub display_name: &'static str,
+    pub color: &'static str,
+    pub icon_name: String,
+    pub specs: Vec<SpecMetadata>,
+}
+
+/// Metadata of all classes, in Blizzard class ID order
+pub fn class_metadata() -> Vec<ClassMetadata> {
+    WowClass::all()
+        .iter()
+        .map(|class| ClassMetadata {
+            class: class.as_str(),
+            display_name: class.display_name(),
+            color: class.color_hex(),
+            icon_name: class.icon_name(),
+            specs: class
+                .specs()
+                .iter()
+                .zip(1u8..)
+                .map(|(spec, index)| SpecMetadata {
+                    index,
+                    slug: spec.slug,
+                    display_name: spec.display_name,
+                    role: spec.role,
+                    icon: class.spec_icon(index),
+                })
+                .collect(),
+        })
+        .collect()
+}
+
+/// Hero talent tree (The War Within); each spec can pick one of two
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum HeroTalent {
+    Colossus,
+    Slayer,
+    MountainThane,
+    HeraldOfTheSun,
+    Lightsmith,
+    Templar,
+    PackLeader,
+    DarkRanger,
+    Sentinel,
+    Deathstalker,
+    Fatebound,
+    Trickster,
+    Oracle,
+    Voidweaver,
+    Archon,
+    Deathbringer,
+    SanLayn,
+    RiderOfTheApocalypse,
+    Farseer,
+    Stormbringer,
+    Totemic,
+    Spellslinger,
+    Sunfury,
+    Frostfire,
+    Hellcaller,
+    SoulHarvester,
+    Diabolist,
+    MasterOfHarmony,
+    ShadoPan,
+    ConduitOfTheCelestials,
+    ElunesChosen,
+    KeeperOfTheGrove,
+    DruidOfTheClaw,
+    Wildstalker,
+    AldrachiReaver,
+    FelScarred,
+    Flameshaper,
+    Scalecommander,
+    Chronowarden,
+}
+
+impl HeroTalent {
+    /// All hero talent trees
+    pub fn all() -> &'static [HeroTalent] {
+        &[
+            Self::Colossus,
+            Self::Slayer,
+            Self::MountainThane,
+            Self::HeraldOfTheSun,
+            Self::Lightsmith,
+            Self::Templar,
+            Self::PackLeader,
+            Self::DarkRanger,
+            Self::Sentinel,
+            Self::Deathstalker,
+            Self::Fatebound,
+            Self::Trickster,
+            Self::Oracle,
+            Self::Voidweaver,
+            Self::Archon,
+            Self::Deathbringer,
+            Self::SanLayn,
+            Self::RiderOfTheApocalypse,
+            Self::Farseer,
+            Self::Stormbringer,
+            Self::Totemic,
+            Self::Spellslinger,
+            Self::Sunfury,
+            Self::Frostfire,
+            Self::Hellcaller,
+            Self::SoulHarvester,
+            Self::Diabolist,
+            Self::MasterOfHarmony,
+            Self::ShadoPan,
+            Self::ConduitOfTheCelestials,
+            Self::ElunesChosen,
+            Self::KeeperOfTheGrove,
+            Self::DruidOfTheClaw,
+            Self::Wildstalker,
+            Self::AldrachiReaver,
+            Self::FelScarred,
+            Self::Flameshaper,
+            Self::Scalecommander,
+            Self::Chronowarden,
+        ]
+    }
+
+    /// Slug used in Archon.gg URLs and config files (e.g., "mountain-thane")
+    pub fn slug(&self) -> &'static str {
+        match self {
+            Self::Colossus => "colossus",
+            Self::Slayer => "slayer",
+            Self::MountainThane => "mountain-thane",
+            Self::HeraldOfTheSun => "herald-of-the-sun",
+            Self::Lightsmith => "lightsmith",
+            Self::Templar => "templar",
+            Self::PackLeader => "pack-leader",
+            Self::DarkRanger => "dark-ranger",
+            Self::Sentinel => "sentinel",
+            Self::Deathstalker => "deathstalker",
+            Self::Fatebound => "fatebound",
+            Self::Trickster => "trickster",
+            Self::Oracle => "oracle",
+            Self::Voidweaver => "voidweaver",
+            Self::Archon => "archon",
+            Self::Deathbringer => "deathbringer",
+            Self::SanLayn => "san-layn",
+            Self::RiderOfTheApocalypse => "rider-of-the-apocalypse",
+            Self::Farseer => "farseer",
+            Self::Stormbringer => "stormbringer",
+            Self::Totemic => "totemic",
+            Self::Spellslinger => "spellslinger",
+            Self::Sunfury => "sunfury",
+            Self::Frostfire => "frostfire",
+            Self::Hellcaller => "hellcaller",
+            Self::SoulHarvester => "soul-harvester",
+            Self::Diabolist => "diabolist",
+            Self::MasterOfHarmony => "master-of-harmony",
+            Self::ShadoPan => "shado-pan",
+            Self::ConduitOfTheCelestials => "conduit-of-the-celestials",
+            Self::ElunesChosen => "elunes-chosen",
+            Self::KeeperOfTheGrove => "keeper-of-the-grove",
+            Self::DruidOfTheClaw => "druid-of-the-claw",
+            Self::Wildstalker => "wildstalker",
+            Self::AldrachiReaver => "aldrachi-reaver",
+            Self::FelScarred => "fel-scarred",
+            Self::Flameshaper => "flameshaper",
+            Self::Scalecommander => "scalecommander",
+            Self::Chronowarden => "chronowarden",
+        }
+    }
+
+    /// Name shown in game (e.g., "Mountain Thane")
+    pub fn display_name(&self) -> &'static str {
+        match self {
+            Self::Colossus => "Colossus",
+            Self::Slayer => "Slayer",
+            Self::MountainThane => "Mountain Thane",
+            Self::HeraldOfTheSun => "Herald of the Sun",
+            Self::Lightsmith => "Lightsmith",
+            Self::Templar => "Templar",
+            Self::PackLeader => "Pack Leader",
+            Self::DarkRanger => "Dark Ranger",
+            Self::Sentinel => "Sentinel",
+            Self::Deathstalker => "Deathstalker",
+            Self::Fatebound => "Fatebound",
+            Self::Trickster => "Trickster",
+            Self::Oracle => "Oracle",
+            Self::Voidweaver => "Voidweaver",
+            Self::Archon => "Archon",
+            Self::Deathbringer => "Deathbringer",
+            Self::SanLayn => "San'layn",
+            Self::RiderOfTheApocalypse => "Rider of the Apocalypse",
+            Self::Farseer => "Farseer",
+            Self::Stormbringer => "Stormbringer",
+            Self::Totemic => "Totemic",
+            Self::Spellslinger => "Spellslinger",
+            Self::Sunfury => "Sunfury",
+            Self::Frostfire => "Frostfire",
+            Self::Hellcaller => "Hellcaller",
+            Self::SoulHarvester => "Soul Harvester",
+            Self::Diabolist => "Diabolist",
+            Self::MasterOfHarmony => "Master of Harmony",
+            Self::ShadoPan => "Shado-Pan",
+            Self::ConduitOfTheCelestials => "Conduit of the Celestials",
+            Self::ElunesChosen => "Elune's Chosen",
+            Self::KeeperOfTheGrove => "Keeper of the Grove",
+            Self::DruidOfTheClaw => "Druid of the Claw",
+            Self::Wildstalker => "Wildstalker",
+            Self::AldrachiReaver => "Aldrachi Reaver",
+            Self::FelScarred => "Fel-Scarred",
+            Self::Flameshaper => "Flameshaper",
+            Self::Scalecommander => "Scalecommander",
+            Self::Chronowarden => "Chronowarden",
+        }
+    }
+
+    /// Parse a hero talent tree from its slug or display name (case-insensitive)
+    pub fn from_slug(s: &str) -> Option<Self> {
+        let s = s.trim();
+        Self::all()
+            .iter()
+            .copied()
+            .find(|hero| hero.slug().eq_ignore_ascii_case(s) || hero.display_name().eq_ignore_ascii_case(s))
     }
 }
 
@@ -193,16 +1117,295 @@ mod tests {
 
     #[test]
     fn test_parse_class_from_string() {
-        assert_eq!(WowClass::from_str("Warrior"), Some(WowClass::Warrior));
+        assert_eq!(WowClass::from_str("Warrior").ok(), Some(WowClass::Warrior));
         assert_eq!(
-            WowClass::from_str("DeathKnight"),
+            WowClass::from_str("DeathKnight").ok(),
             Some(WowClass::DeathKnight)
         );
         assert_eq!(
-            WowClass::from_str("DemonHunter"),
+            WowClass::from_str("DemonHunter").ok(),
             Some(WowClass::DemonHunter)
         );
-        assert_eq!(WowClass::from_str("InvalidClass"), None);
+        assert_eq!(WowClass::from_str("InvalidClass").ok(), None);
+    }
+
+    #[test]
+    fn test_class_parse_display_and_serde() {
+        assert_eq!("Death Knight".parse::<WowClass>().unwrap(), WowClass::DeathKnight);
+        assert_eq!("DEMONHUNTER".parse::<WowClass>().unwrap(), WowClass::DemonHunter);
+        let error = "Paladine".parse::<WowClass>().unwrap_err().to_string();
+        assert!(error.contains("valid: Warrior, Paladin"), "{}", error);
+
+        for class in WowClass::all() {
+            assert_eq!(class.to_string().parse::<WowClass>().unwrap(), *class);
+        }
+
+        assert_eq!(serde_json::to_string(&WowClass::DeathKnight).unwrap(), "\"DeathKnight\"");
+        assert_eq!(serde_json::from_str::<WowClass>("\"Evoker\"").unwrap(), WowClass::Evoker);
+        assert!(serde_json::from_str::<WowClass>("\"Bard\"").is_err());
+    }
+
+    #[test]
+    fn test_class_and_spec_aliases() {
+        assert_eq!("DK".parse::<WowClass>().unwrap(), WowClass::DeathKnight);
+        assert_eq!("dh".parse::<WowClass>().unwrap(), WowClass::DemonHunter);
+        assert_eq!("Pally".parse::<WowClass>().unwrap(), WowClass::Paladin);
+        assert_eq!("death-knight".parse::<WowClass>().unwrap(), WowClass::DeathKnight);
+        // Aliases are only accepted, the class is still written in PascalCase
+        assert_eq!(serde_json::from_str::<WowClass>("\"lock\"").unwrap().to_string(), "Warlock");
+
+        assert_eq!(WowClass::Hunter.spec_index("BM"), Some(1));
+        assert_eq!(WowClass::Monk.spec_index("bm"), Some(1));
+        assert_eq!(WowClass::Hunter.spec_index("Beast Mastery"), Some(1));
+        assert_eq!(WowClass::Shaman.spec_index("Resto"), Some(3));
+        assert_eq!(WowClass::Paladin.spec_index("prot"), Some(2));
+        assert_eq!(WowClass::Rogue.spec_index("Combat"), Some(2));
+        assert_eq!(WowClass::Mage.spec_index("resto"), None);
+        assert_eq!(WowClass::Druid.spec_index_for(GameFlavor::Cataclysm, "Resto"), Some(3));
+        assert_eq!(WowClass::Druid.spec_index_for(GameFlavor::Cataclysm, "bear"), None);
+
+        for class in WowClass::all() {
+            for spec in class.spec_names_for(GameFlavor::Retail) {
+                assert_eq!(class.spec_alias(spec), None, "{} alias shadows {}", class, spec);
+            }
+        }
+    }
+
+    #[test]
+    fn test_every_spec_code() {
+        let codes: &[(WowClass, &str, &str)] = &[
+            (WowClass::Warrior, "Prot", "protection"),
+            (WowClass::Paladin, "Prot", "protection"),
+            (WowClass::Paladin, "Ret", "retribution"),
+            (WowClass::Hunter, "BM", "beast-mastery"),
+            (WowClass::Hunter, "MM", "marksmanship"),
+            (WowClass::Hunter, "Marks", "marksmanship"),
+            (WowClass::Hunter, "SV", "survival"),
+            (WowClass::Hunter, "Surv", "survival"),
+            (WowClass::Rogue, "Sin", "assassination"),
+            (WowClass::Rogue, "Assa", "assassination"),
+            (WowClass::Rogue, "Sub", "subtlety"),
+            (WowClass::Rogue, "Subt", "subtlety"),
+            (WowClass::Priest, "Disc", "discipline"),
+            (WowClass::Priest, "SP", "shadow"),
+            (WowClass::DeathKnight, "UH", "unholy"),
+            (WowClass::Shaman, "Ele", "elemental"),
+            (WowClass::Shaman, "Enh", "enhancement"),
+            (WowClass::Shaman, "Enhance", "enhancement"),
+            (WowClass::Shaman, "Resto", "restoration"),
+            (WowClass::Mage, "Arc", "arcane"),
+            (WowClass::Warlock, "Aff", "affliction"),
+            (WowClass::Warlock, "Affli", "affliction"),
+            (WowClass::Warlock, "Demo", "demonology"),
+            (WowClass::Warlock, "Destro", "destruction"),
+            (WowClass::Monk, "BM", "brewmaster"),
+            (WowClass::Monk, "Brew", "brewmaster"),
+            (WowClass::Monk, "MW", "mistweaver"),
+            (WowClass::Monk, "Mist", "mistweaver"),
+            (WowClass::Monk, "WW", "windwalker"),
+            (WowClass::Monk, "Wind", "windwalker"),
+            (WowClass::Druid, "Boomkin", "balance"),
+            (WowClass::Druid, "Moonkin", "balance"),
+            (WowClass::Druid, "Cat", "feral"),
+            (WowClass::Druid, "Bear", "guardian"),
+            (WowClass::Druid, "Resto", "restoration"),
+            (WowClass::DemonHunter, "Veng", "vengeance"),
+            (WowClass::Evoker, "Dev", "devastation"),
+            (WowClass::Evoker, "Deva", "devastation"),
+            (WowClass::Evoker, "Pres", "preservation"),
+            (WowClass::Evoker, "Aug", "augmentation"),
+        ];
+
+        for (class, code, slug) in codes {
+            assert_eq!(class.spec_index(code), class.spec_index(slug), "{} {}", class, code);
+            assert_eq!(class.canonical_spec_name(code), *slug);
+        }
+        let table_codes: usize = WowClass::all().iter().map(|class| class.info().spec_aliases.len()).sum();
+        assert_eq!(codes.len(), table_codes, "every code in the table is covered above");
+
+        // Codes are unique within a class and never shadow a slug
+        for class in WowClass::all() {
+            let aliases = class.info().spec_aliases;
+            for (i, (code, slug)) in aliases.iter().enumerate() {
+                assert!(class.valid_specs().contains(slug), "{} {}", class, code);
+                assert!(!aliases[i + 1..].iter().any(|(other, _)| other == code), "{} {}", class, code);
+            }
+        }
+
+        // Ambiguous within the class, so no guess is made
+        assert_eq!(WowClass::Warlock.spec_index("d"), None);
+        assert_eq!(WowClass::Warlock.spec_index("de"), None);
+        assert_eq!(WowClass::Priest.spec_index("holy-disc"), None);
+        assert_eq!(WowClass::Rogue.canonical_spec_name("Combat"), "combat");
+        assert_eq!(WowClass::Warrior.canonical_spec_name("Furry"), "furry");
+    }
+
+    #[test]
+    fn test_spec_name_casing_is_normalized() {
+        let inputs = [
+            "BeastMastery",
+            "beastMastery",
+            "beast_mastery",
+            "Beast Mastery",
+            "BEAST-MASTERY",
+            " beast__mastery ",
+        ];
+        for input in inputs {
+            assert_eq!(WowClass::Hunter.canonical_spec_name(input), "beast-mastery", "{}", input);
+        }
+        assert_eq!(WowClass::Mage.canonical_spec_name("Frost"), "frost");
+        assert_eq!(WowClass::Monk.canonical_spec_name("MW"), "mistweaver");
+    }
+
+    #[test]
+    fn test_closest_spec() {
+        assert_eq!(closest_spec(WowClass::Shaman, "restauration"), Some("restoration"));
+        assert_eq!(closest_spec(WowClass::Shaman, "enhancment"), Some("enhancement"));
+        assert_eq!(closest_spec(WowClass::Paladin, "retribtion"), Some("retribution"));
+        assert_eq!(closest_spec(WowClass::Warrior, "Protecion"), Some("protection"));
+        assert_eq!(closest_spec(WowClass::Warrior, "furry"), Some("fury"));
+        assert_eq!(closest_spec(WowClass::Hunter, "beast-mastry"), Some("beast-mastery"));
+        assert_eq!(closest_spec(WowClass::Druid, "rsto"), Some("restoration"));
+        assert_eq!(closest_spec(WowClass::Evoker, "augmentaton"), Some("augmentation"));
+
+        // Nothing close enough
+        assert_eq!(closest_spec(WowClass::Warrior, "frost"), None);
+        assert_eq!(closest_spec(WowClass::Mage, "xyz"), None);
+        assert_eq!(closest_spec(WowClass::Warlock, ""), None);
+
+        assert_eq!(did_you_mean(closest_match("Warior", [("Warrior", "Warrior")])), ", did you mean 'Warrior'?");
+        assert_eq!(did_you_mean(None), "");
+        assert_eq!(edit_distance("kitten", "sitting"), 3);
+    }
+
+    #[test]
+    fn test_all_classes_are_covered() {
+        // Exhaustive on purpose: a new variant fails to compile here until it gets a table entry
+        let table_position = |class: WowClass| match class {
+            WowClass::Warrior
+            | WowClass::Paladin
+            | WowClass::Hunter
+            | WowClass::Rogue
+            | WowClass::Priest
+            | WowClass::DeathKnight
+            | WowClass::Shaman
+            | WowClass::Mage
+            | WowClass::Warlock
+            | WowClass::Monk
+            | WowClass::Druid
+            | WowClass::DemonHunter
+            | WowClass::Evoker => WowClass::all().iter().position(|c| *c == class),
+        };
+
+        assert_eq!(WowClass::all().len(), WowClass::count());
+        for (position, class) in WowClass::all().iter().enumerate() {
+            assert_eq!(table_position(*class), Some(position));
+            assert_eq!(class.info().class, *class);
+        }
+
+        assert!(WowClass::Druid.is_valid_spec_index(4));
+        assert!(!WowClass::DemonHunter.is_valid_spec_index(3));
+        assert!(!WowClass::Warrior.is_valid_spec_index(0));
+    }
+
+    #[test]
+    fn test_from_localized() {
+        assert_eq!(WowClass::from_localized("Todesritter"), Some(WowClass::DeathKnight));
+        assert_eq!(WowClass::from_localized("Chevalier de la mort"), Some(WowClass::DeathKnight));
+        assert_eq!(WowClass::from_localized("Dämonenjägerin"), Some(WowClass::DemonHunter));
+        assert_eq!(WowClass::from_localized("ÉVOCATEUR"), Some(WowClass::Evoker));
+        assert_eq!(WowClass::from_localized("Caçadora de Demônios"), Some(WowClass::DemonHunter));
+        assert_eq!(WowClass::from_localized("Чернокнижник"), Some(WowClass::Warlock));
+        assert_eq!(WowClass::from_localized("рыцарь смерти"), Some(WowClass::DeathKnight));
+        assert_eq!(WowClass::from_localized("죽음의 기사"), Some(WowClass::DeathKnight));
+        assert_eq!(WowClass::from_localized("萨满祭司"), Some(WowClass::Shaman));
+        assert_eq!(WowClass::from_localized(" Druida "), Some(WowClass::Druid));
+        assert_eq!(WowClass::from_localized("Demon Hunter"), Some(WowClass::DemonHunter));
+        assert_eq!(WowClass::from_localized("DEATHKNIGHT"), Some(WowClass::DeathKnight));
+        assert_eq!(WowClass::from_localized("Barde"), None);
+
+        // A localized name must never belong to two classes
+        let mut seen = std::collections::HashMap::new();
+        for (class, names) in LOCALIZED_CLASS_NAMES {
+            for name in *names {
+                assert_eq!(*seen.entry(name.to_lowercase()).or_insert(*class), *class, "{}", name);
+            }
+        }
+    }
+
+    #[test]
+    fn test_spec_lookup() {
+        let spec = WowClass::Rogue.spec("Combat").unwrap();
+        assert_eq!(spec, WowSpec { class: WowClass::Rogue, slug: "outlaw", index: 2 });
+        assert_eq!(spec.display_name(), "Outlaw");
+
+        let classic = WowClass::Rogue.spec_for(GameFlavor::ClassicEra, "combat").unwrap();
+        assert_eq!((classic.slug, classic.display_name()), ("combat", "combat"));
+
+        let error = WowClass::Warrior.spec("furry").unwrap_err().to_string();
+        assert_eq!(error, "Invalid spec 'furry' for Warrior, did you mean 'fury'? (valid: arms, fury, protection)");
+    }
+
+    #[test]
+    fn test_spec_index_for_flavor() {
+        for class in WowClass::all() {
+            for spec in class.spec_names_for(GameFlavor::Retail) {
+                assert_eq!(class.spec_index_for(GameFlavor::Retail, spec), class.spec_index(spec));
+            }
+        }
+
+        assert_eq!(WowClass::Druid.spec_index_for(GameFlavor::Cataclysm, "restoration"), Some(3));
+        assert_eq!(WowClass::Druid.spec_index_for(GameFlavor::ClassicEra, "guardian"), None);
+        assert_eq!(WowClass::DeathKnight.spec_index_for(GameFlavor::Cataclysm, "unholy"), Some(3));
+        assert_eq!(WowClass::DeathKnight.spec_index_for(GameFlavor::ClassicEra, "unholy"), None);
+        assert_eq!(WowClass::Monk.spec_index_for(GameFlavor::Cataclysm, "brewmaster"), None);
+        assert_eq!(WowClass::Rogue.spec_index_for(GameFlavor::ClassicEra, "combat"), Some(2));
+        assert!(WowClass::Evoker.spec_names_for(GameFlavor::Cataclysm).is_empty());
+
+        assert_eq!(serde_json::from_str::<GameFlavor>("\"classicEra\"").unwrap(), GameFlavor::ClassicEra);
+    }
+
+    #[test]
+    fn test_spec_name_for_index() {
+        for class in WowClass::all() {
+            for index in 1..=4 {
+                match class.spec_name_for_index(index) {
+                    Some(name) => assert_eq!(class.spec_index(name), Some(index)),
+                    None => assert!(class.spec_id_for_index(index).is_none(), "{:?} {}", class, index),
+                }
+            }
+            assert_eq!(class.spec_name_for_index(0), None);
+        }
+        assert_eq!(WowClass::Warrior.spec_name_for_index(2), Some("fury"));
+    }
+
+    #[test]
+    fn test_class_ids() {
+        for (class, id) in WowClass::all().iter().zip(1u8..) {
+            assert_eq!(class.class_id(), id);
+            assert_eq!(WowClass::from_class_id(id), Some(*class));
+        }
+        assert_eq!(WowClass::from_class_id(0), None);
+        assert_eq!(WowClass::from_class_id(14), None);
+    }
+
+    #[test]
+    fn test_spec_ids_by_slug() {
+        let mut ids = std::collections::HashSet::new();
+        for class in WowClass::all() {
+            for spec in class.specs() {
+                let id = class.spec_id(spec.slug).unwrap();
+                assert!(ids.insert(id), "duplicate spec ID {}", id);
+                assert_eq!(WowClass::from_spec_id(id), Some((*class, spec.slug)));
+            }
+        }
+        assert_eq!(ids.len(), 39);
+
+        assert_eq!(WowClass::Warrior.spec_id("arms"), Some(71));
+        assert_eq!(WowClass::Monk.spec_id("windwalker"), Some(269));
+        assert_eq!(WowClass::Evoker.spec_id("augmentation"), Some(1473));
+        assert_eq!(WowClass::Warrior.spec_id("frost"), None);
+        assert_eq!(WowClass::from_spec_id(1), None);
     }
 
     #[test]
@@ -226,6 +1429,13 @@ mod tests {
         assert_eq!(WowClass::Warrior.spec_index("protection"), Some(3));
         assert_eq!(WowClass::Warrior.spec_index("invalid"), None);
 
+        // Outlaw is still accepted under its pre-Legion name
+        assert_eq!(WowClass::Rogue.spec_index("outlaw"), Some(2));
+        assert_eq!(WowClass::Rogue.spec_index("combat"), Some(2));
+        assert_eq!(WowClass::Rogue.deprecated_spec_name("combat"), Some("outlaw"));
+        assert_eq!(WowClass::Rogue.deprecated_spec_name("outlaw"), None);
+        assert_eq!(WowClass::Rogue.spec_name_for_index(2), Some("outlaw"));
+
         assert_eq!(WowClass::DeathKnight.spec_index("blood"), Some(1));
         assert_eq!(WowClass::DeathKnight.spec_index("frost"), Some(2));
         assert_eq!(WowClass::DeathKnight.spec_index("unholy"), Some(3));
@@ -241,6 +1451,129 @@ mod tests {
         assert_eq!(WowClass::Druid.spec_index("restoration"), Some(4));
     }
 
+    #[test]
+    fn test_hero_talents() {
+        let mut used = std::collections::HashSet::new();
+        for class in WowClass::all() {
+            for spec_index in 1..=4 {
+                let heroes = class.hero_talents(spec_index);
+                assert_eq!(heroes.len(), if class.spec_id_for_index(spec_index).is_some() { 2 } else { 0 });
+                used.extend(heroes.iter().copied());
+            }
+        }
+        assert_eq!(used.len(), HeroTalent::all().len());
+
+        assert_eq!(WowClass::Warrior.hero_talents(1), &[HeroTalent::Colossus, HeroTalent::Slayer]);
+        assert_eq!(WowClass::Mage.hero_talents(3), &[HeroTalent::Frostfire, HeroTalent::Spellslinger]);
+        assert_eq!(HeroTalent::SanLayn.display_name(), "San'layn");
+        assert_eq!(HeroTalent::MountainThane.slug(), "mountain-thane");
+    }
+
+    #[test]
+    fn test_parse_hero_talent() {
+        for hero in HeroTalent::all() {
+            assert_eq!(HeroTalent::from_slug(hero.slug()), Some(*hero));
+            assert_eq!(HeroTalent::from_slug(hero.display_name()), Some(*hero));
+        }
+        assert_eq!(HeroTalent::from_slug("Elune's chosen"), Some(HeroTalent::ElunesChosen));
+
+        assert_eq!(WowClass::Mage.parse_hero_talent(3, "frostfire").unwrap(), HeroTalent::Frostfire);
+        let error = WowClass::Mage.parse_hero_talent(3, "sunfury").unwrap_err().to_string();
+        assert!(error.contains("frostfire, spellslinger"), "{}", error);
+        assert!(WowClass::Mage.parse_hero_talent(3, "nonsense").is_err());
+    }
+
+    #[test]
+    fn test_every_spec_has_a_display_name() {
+        let mut specs = 0;
+        for class in WowClass::all() {
+            for slug in class.valid_specs() {
+                assert!(class.spec_display_name(slug).is_some(), "{:?} {}", class, slug);
+                let index = class.spec_index(slug).unwrap();
+                assert_eq!(class.specs()[usize::from(index) - 1].slug, slug);
+                specs += 1;
+            }
+        }
+        assert_eq!(specs, 39);
+
+        assert_eq!(WowClass::Hunter.spec_display_name("beast-mastery"), Some("Beast Mastery"));
+        assert_eq!(WowClass::Rogue.spec_display_name("outlaw"), Some("Outlaw"));
+        assert_eq!(WowClass::Warrior.spec_display_name("holy"), None);
+    }
+
+    #[test]
+    fn test_class_metadata() {
+        let metadata = class_metadata();
+        assert_eq!(metadata.len(), 13);
+        assert_eq!(metadata.iter().map(|class| class.specs.len()).sum::<usize>(), 39);
+
+        for (class, meta) in WowClass::all().iter().zip(&metadata) {
+            assert_eq!(meta.class.parse::<WowClass>().unwrap(), *class);
+            assert!(meta.color.starts_with('#') && meta.color.len() == 7);
+            for spec in &meta.specs {
+                assert_eq!(class.spec_role_for_index(spec.index), Some(spec.role));
+                assert_eq!(class.spec_role(spec.slug), Some(spec.role));
+            }
+        }
+
+        let death_knight = &metadata[5];
+        assert_eq!(death_knight.display_name, "Death Knight");
+        assert_eq!(death_knight.icon_name, "classicon_deathknight");
+        assert_eq!(death_knight.specs[0].role, Role::Tank);
+        assert_eq!(WowClass::Druid.spec_role_for_index(4), Some(Role::Healer));
+        assert_eq!(WowClass::Druid.spec_role_for_index(5), None);
+        assert_eq!(WowClass::Druid.spec_role("resto"), Some(Role::Healer));
+        assert_eq!(WowClass::Warrior.spec_role("frost"), None);
+        assert_eq!(serde_json::from_str::<Role>("\"dps\"").unwrap(), Role::Damage);
+    }
+
+    #[test]
+    fn test_every_spec_has_an_icon() {
+        let mut specs = 0;
+        for class in WowClass::all() {
+            for spec_index in 1..=4 {
+                if class.spec_id_for_index(spec_index).is_some() {
+                    assert_ne!(class.spec_icon(spec_index), FALLBACK_SPEC_ICON, "{:?} {}", class, spec_index);
+                    specs += 1;
+                }
+            }
+        }
+        assert_eq!(specs, 39);
+
+        assert_eq!(WowClass::Warrior.spec_icon(1), 132355);
+        assert_eq!(WowClass::Warrior.spec_icon(4), FALLBACK_SPEC_ICON);
+        assert_eq!(WowClass::Mage.spec_icon(0), FALLBACK_SPEC_ICON);
+    }
+
+    #[test]
+    fn test_from_lua_format() {
+        assert_eq!(WowClass::from_lua_format("DEATHKNIGHT"), Some(WowClass::DeathKnight));
+        assert_eq!(WowClass::from_lua_format("EVOKER"), Some(WowClass::Evoker));
+        assert_eq!(WowClass::from_lua_format("Warrior"), None);
+    }
+
+    #[test]
+    fn test_spec_ids() {
+        assert_eq!(WowClass::Warrior.spec_id_for_index(1), Some(71));
+        assert_eq!(WowClass::Monk.spec_id_for_index(2), Some(270));
+        assert_eq!(WowClass::Druid.spec_id_for_index(4), Some(105));
+        assert_eq!(WowClass::DemonHunter.spec_id_for_index(3), None);
+        assert_eq!(WowClass::Warrior.spec_id_for_index(0), None);
+
+        assert_eq!(WowClass::spec_index_from_id(64), Some((WowClass::Mage, 3)));
+        assert_eq!(WowClass::spec_index_from_id(581), Some((WowClass::DemonHunter, 2)));
+        assert_eq!(WowClass::spec_index_from_id(1), None);
+
+        // Every spec of every class has an ID that maps back to it
+        for class in WowClass::all() {
+            for spec in class.valid_specs() {
+                let index = class.spec_index(spec).unwrap();
+                let id = class.spec_id_for_index(index).unwrap();
+                assert_eq!(WowClass::spec_index_from_id(id), Some((*class, index)));
+            }
+        }
+    }
+
     #[test]
     fn test_valid_specs() {
         let warrior_specs = WowClass::Warrior.valid_specs();
@@ -251,5 +1584,16 @@ mod tests {
 
         let druid_specs = WowClass::Druid.valid_specs();
         assert_eq!(druid_specs.len(), 4);
+
+        // Always in spec index order, so UI listings don't reshuffle between calls
+        assert_eq!(warrior_specs, ["arms", "fury", "protection"]);
+        assert_eq!(druid_specs, ["balance", "feral", "guardian", "restoration"]);
+        for class in WowClass::all() {
+            let specs = class.valid_specs();
+            assert_eq!(class.valid_specs(), specs);
+            for (position, slug) in specs.iter().enumerate() {
+                assert_eq!(class.spec_index(slug), Some(position as u8 + 1));
+            }
+        }
     }
 }
@@ -1,231 +1,1924 @@
+use crate::battlenet;
+use crate::lua_talent::{lua_unescape_string, TalentFileFormat};
+use crate::wow::{GameFlavor, WowClass};
 use anyhow::Result;
+use chrono::{DateTime, Duration, SecondsFormat, Utc};
 use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet};
 use std::fs;
 use std::path::{Path, PathBuf};
-use std::time::SystemTime;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::time::{Instant, SystemTime};
+use sysinfo::{ProcessRefreshKind, RefreshKind, System};
 
 /// Represents a discovered WoW character
 #[derive(Debug, Clone, Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
 pub struct DiscoveredCharacter {
     pub name: String,
+    /// Realm folder name
     pub realm: String,
+    /// Realm name for display; folder name variants of one realm ("MalGanis", "Mal'Ganis")
+    /// share the spelling the client uses
+    pub realm_display_name: String,
+    /// Class name as used in the config (e.g., "DeathKnight"), empty if it couldn't be detected
     pub class: String,
+    /// Whether `class` was detected; if not, the UI has to ask for it
+    pub class_detected: bool,
     pub account_id: String,
-    /// Unix timestamp of last login (from config-cache.wtf mtime), 0 if unknown
-    pub last_played: u64,
+    /// Game flavor of the installation the character was found in
+    pub flavor: GameFlavor,
+    /// Suggested output path: TalentLoadoutsEx.lua of the character's account in that installation
+    pub talent_file_path: PathBuf,
+    /// Whether the talent file exists yet (the addon creates it on logout)
+    pub talent_file_exists: bool,
+    /// Whether the client last logged in with this account (from Config.wtf)
+    pub is_last_played_account: bool,
+    /// Last time the character was played as an ISO-8601 (RFC 3339, UTC) timestamp: the newest
+    /// modification time of its config-cache.wtf and SavedVariables files, None if unknown
+    pub last_played: Option<String>,
+    /// Other accounts the same character was found under (merged into this entry)
+    pub also_found_in: Vec<String>,
+    /// Spec slugs the character has played (e.g., "arms"), in spec order, from spec IDs in its
+    /// SavedVariables; empty if none were found
+    #[serde(default)]
+    pub specializations: Vec<String>,
+    /// The character's folder on disk; `name` and `realm` are lossy if its path isn't valid Unicode
+    #[serde(default)]
+    pub path: PathBuf,
+}
+
+impl DiscoveredCharacter {
+    /// Whether the character was played within the last `days` days; characters without a
+    /// known last played time are kept
+    pub fn played_within(&self, days: u32, now: DateTime<Utc>) -> bool {
+        let Some(last_played) = self.last_played.as_deref() else {
+            return true;
+        };
+        DateTime::parse_from_rfc3339(last_played)
+            .map_or(true, |last_played| now.signed_duration_since(last_played) <= Duration::days(i64::from(days)))
+    }
+}
+
+/// Progress of a character scan, counted so far
+#[derive(Debug, Clone, Default, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ScanProgress {
+    pub accounts_found: usize,
+    pub realms_scanned: usize,
+    pub characters_found: usize,
+}
+
+/// Result of a character scan
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ScanResult {
+    /// Characters sorted by most recently played
+    pub characters: Vec<DiscoveredCharacter>,
+    /// Folders that couldn't be read; characters in them are missing from the result
+    pub warnings: Vec<ScanWarning>,
+    pub elapsed_ms: u64,
+}
+
+/// Characters found in one account folder, kept to skip unchanged accounts on the next scan
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AccountScan {
+    pub account_id: String,
+    pub path: PathBuf,
+    /// Modification times of the account folder and its realm folders; adding or removing a
+    /// realm or character folder changes them
+    pub folder_times: Vec<(String, SystemTime)>,
+    pub characters: Vec<DiscoveredCharacter>,
+    pub warnings: Vec<ScanWarning>,
+}
+
+/// Modification times of an account folder (as "") and its subfolders, sorted by name
+fn folder_times(account_path: &Path) -> Vec<(String, SystemTime)> {
+    let modified = |path: &Path| fs::metadata(path).and_then(|m| m.modified()).ok();
+    let mut times: Vec<(String, SystemTime)> = fs::read_dir(account_path)
+        .into_iter()
+        .flatten()
+        .flatten()
+        .filter(|entry| entry.path().is_dir())
+        .filter_map(|entry| Some((entry.file_name().to_string_lossy().into_owned(), modified(&entry.path())?)))
+        .chain(modified(account_path).map(|time| (String::new(), time)))
+        .collect();
+    times.sort();
+    times
+}
+
+/// A folder the scan had to skip
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ScanWarning {
+    pub path: PathBuf,
+    /// The IO error reading it
+    pub error: String,
+}
+
+impl ScanWarning {
+    fn new(path: &Path, error: std::io::Error) -> Self {
+        Self {
+            path: path.to_path_buf(),
+            error: error.to_string(),
+        }
+    }
+}
+
+/// State of a walk over the WTF folders: the canonical folders visited so far, to skip
+/// folders reached twice through links (and link cycles), and the folders that were skipped
+#[derive(Default)]
+struct FolderWalk {
+    visited: HashSet<PathBuf>,
+    warnings: Vec<ScanWarning>,
+}
+
+impl FolderWalk {
+    /// Subfolders of `dir` as (folder name, canonical path), following links; folders and
+    /// entries that can't be read or were already visited are recorded as warnings and skipped
+    fn subdirectories(&mut self, dir: &Path) -> Vec<(String, PathBuf)> {
+        if let Ok(canonical) = dunce::canonicalize(dir) {
+            self.visited.insert(canonical);
+        }
+
+        let entries = match fs::read_dir(dir) {
+            Ok(entries) => entries,
+            Err(e) => {
+                self.warnings.push(ScanWarning::new(dir, e));
+                return Vec::new();
+            }
+        };
+
+        // Sorted, so which of several links to one folder is kept doesn't depend on the file system
+        let mut entries: Vec<fs::DirEntry> = entries
+            .filter_map(|entry| match entry {
+                Ok(entry) => Some(entry),
+                Err(e) => {
+                    self.warnings.push(ScanWarning::new(dir, e));
+                    None
+                }
+            })
+            .collect();
+        entries.sort_by_key(|entry| entry.file_name());
+
+        let mut dirs = Vec::new();
+        for entry in entries {
+            let path = entry.path();
+
+            // Broken links and link loops fail here instead of looking like plain files
+            match fs::metadata(&path) {
+                Ok(metadata) if metadata.is_dir() => {}
+                Ok(_) => continue,
+                Err(e) => {
+                    self.warnings.push(ScanWarning::new(&path, e));
+                    continue;
+                }
+            }
+
+            let canonical = match dunce::canonicalize(&path) {
+                Ok(canonical) => canonical,
+                Err(e) => {
+                    self.warnings.push(ScanWarning::new(&path, e));
+                    continue;
+                }
+            };
+            if !self.visited.insert(canonical.clone()) {
+                let error = std::io::Error::other(format!("already scanned as {}", canonical.display()));
+                self.warnings.push(ScanWarning::new(&path, error));
+                continue;
+            }
+
+            // The name of the link, not of its target, is the account, realm or character
+            let name = entry.file_name().to_string_lossy().into_owned();
+            if entry.file_name().to_str().is_none() {
+                let error = std::io::Error::other(format!("folder name isn't valid Unicode, shown as \"{}\"", name));
+                self.warnings.push(ScanWarning::new(&path, error));
+            }
+            dirs.push((name, canonical));
+        }
+        dirs
+    }
+}
+
+/// Record a warning if the folder at `dir` exists but can't be listed
+fn check_readable(dir: &Path, warnings: &mut Vec<ScanWarning>) {
+    if fs::symlink_metadata(dir).is_ok() {
+        if let Err(e) = fs::read_dir(dir) {
+            warnings.push(ScanWarning::new(dir, e));
+        }
+    }
+}
+
+
+/// Product folders under the WoW root and the game flavor they run
+/// Test realms run the talent trees of their flavor, so they map to it as well.
+const PRODUCT_FOLDERS: &[(&str, GameFlavor)] = &[
+    ("_retail_", GameFlavor::Retail),
+    ("_ptr_", GameFlavor::Retail),
+    ("_xptr_", GameFlavor::Retail),
+    ("_beta_", GameFlavor::Retail),
+    ("_classic_", GameFlavor::Cataclysm),
+    ("_classic_ptr_", GameFlavor::Cataclysm),
+    ("_classic_era_", GameFlavor::ClassicEra),
+    ("_classic_era_ptr_", GameFlavor::ClassicEra),
+];
+
+/// An installed game product, e.g. the PTR
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct WowInstallation {
+    pub flavor: GameFlavor,
+    /// Product folder name (e.g., "_ptr_")
+    pub product: String,
+    /// Path of the product folder
+    pub path: PathBuf,
+    /// Account folders below WTF/Account; only filled in by `WowScanner::inspect`
+    #[serde(default)]
+    pub accounts: Vec<AccountInfo>,
+    /// State of the TalentLoadoutsEx addon; only filled in by `WowScanner::inspect`
+    #[serde(default)]
+    pub addon_status: Option<AddonStatus>,
+}
+
+impl WowInstallation {
+    /// Installation for a product folder path; unknown folder names are treated as retail
+    pub fn from_path(path: impl Into<PathBuf>) -> Self {
+        let path = path.into();
+        let product = path.file_name().and_then(|n| n.to_str()).unwrap_or("").to_string();
+        let flavor = PRODUCT_FOLDERS
+            .iter()
+            .find(|(folder, _)| *folder == product)
+            .map_or(GameFlavor::Retail, |(_, flavor)| *flavor);
+
+        Self {
+            flavor,
+            product,
+            path,
+            accounts: Vec::new(),
+            addon_status: None,
+        }
+    }
+}
+
+/// Game executables (Windows) and app bundles (macOS) found in product folders
+const WOW_EXECUTABLES: &[&str] = &[
+    "Wow.exe",
+    "WowT.exe",
+    "WowB.exe",
+    "WowClassic.exe",
+    "WowClassicT.exe",
+    "World of Warcraft.app",
+    "World of Warcraft Test.app",
+    "World of Warcraft Beta.app",
+    "World of Warcraft Classic.app",
+];
+
+/// Process names of the game client (Windows and Wine, then macOS), compared ignoring case
+const WOW_PROCESS_NAMES: &[&str] = &[
+    "Wow.exe",
+    "WowT.exe",
+    "WowB.exe",
+    "WowClassic.exe",
+    "WowClassicT.exe",
+    "WowClassicB.exe",
+    "World of Warcraft",
+    "World of Warcraft Test",
+    "World of Warcraft Beta",
+    "World of Warcraft Classic",
+];
+
+/// Whether a process name is one of the game client's
+fn is_wow_process_name(name: &str) -> bool {
+    WOW_PROCESS_NAMES.iter().any(|wow| wow.eq_ignore_ascii_case(name))
+}
+
+/// Which parts of a WoW product folder a user-supplied path has, for feedback in the folder picker
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PathDiagnosis {
+    pub exists: bool,
+    /// Product folders directly below the path, i.e. the path is the WoW root
+    pub product_folders: Vec<String>,
+    pub has_executable: bool,
+    pub has_wtf_folder: bool,
+    pub has_account_folder: bool,
+    /// Whether characters can be scanned with the path as it is
+    pub valid: bool,
+    /// Product folder to use instead, e.g. the path with `_retail_` appended
+    pub suggested_path: Option<PathBuf>,
+    /// What's wrong with the path, None if it is valid
+    pub problem: Option<String>,
+}
+
+/// Folder of the TalentLoadoutsEx addon below Interface/AddOns
+const ADDON_FOLDER: &str = "TalentLoadoutsEx";
+
+/// SavedVariables file of the TalentLoadoutsEx addon
+const TALENT_FILE_NAME: &str = "TalentLoadoutsEx.lua";
+
+/// Whether the TalentLoadoutsEx addon is installed, and which accounts already have its data
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AddonStatus {
+    pub installed: bool,
+    /// `## Version` of the addon's .toc file, if it has one
+    pub version: Option<String>,
+    pub accounts: Vec<AccountInfo>,
+}
+
+/// An account folder below WTF/Account and its talent file
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AccountInfo {
+    /// Account folder name (e.g., "12345678#1" or "WOW1")
+    pub account_id: String,
+    pub path: PathBuf,
+    /// SavedVariables/TalentLoadoutsEx.lua of the account
+    pub talent_file_path: PathBuf,
+    /// Whether the talent file exists, i.e. the addon ran on this account
+    pub talent_file_exists: bool,
+    /// Size of the talent file in bytes, 0 if it doesn't exist
+    pub talent_file_size: u64,
+    /// Last modification of the talent file as an RFC 3339 (UTC) timestamp
+    pub talent_file_modified: Option<String>,
+}
+
+/// Loadout addons and the talent file format written for them; None for addons
+/// talent-heron can't write
+const LOADOUT_ADDONS: &[(&str, Option<TalentFileFormat>)] = &[
+    ("TalentLoadoutsEx", Some(TalentFileFormat::TalentLoadoutsEx)),
+    ("TalentLoadouts", Some(TalentFileFormat::TalentLoadouts)),
+    ("BtWLoadouts", Some(TalentFileFormat::BtwLoadouts)),
+    ("TalentLoadoutManager", None),
+];
+
+/// An addon folder in Interface/AddOns
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct InstalledAddon {
+    pub folder: String,
+    /// `## Title` of the .toc file without color codes
+    pub title: Option<String>,
+    pub version: Option<String>,
+    /// Whether this is a known talent loadout addon
+    pub is_loadout_addon: bool,
+    /// Format to write for this addon, if talent-heron supports it
+    pub output_format: Option<TalentFileFormat>,
+}
+
+/// Output format to propose for the installed addons: TalentLoadoutsEx if present,
+/// otherwise the first other supported loadout addon
+pub fn suggested_output_format(addons: &[InstalledAddon]) -> Option<TalentFileFormat> {
+    LOADOUT_ADDONS.iter().find_map(|(folder, format)| {
+        addons.iter().any(|addon| addon.folder == *folder).then_some(*format).flatten()
+    })
+}
+
+/// Contents of an addon's .toc file: `<folder>.toc`, or a flavor-specific one like
+/// `<folder>_Mainline.toc`
+fn read_toc(addon_dir: &Path, folder: &str) -> Option<String> {
+    if let Ok(toc) = fs::read_to_string(addon_dir.join(format!("{}.toc", folder))) {
+        return Some(toc);
+    }
+
+    let mut tocs: Vec<PathBuf> = fs::read_dir(addon_dir)
+        .ok()?
+        .flatten()
+        .map(|entry| entry.path())
+        .filter(|path| {
+            path.extension().is_some_and(|ext| ext.eq_ignore_ascii_case("toc"))
+                && path.file_name().and_then(|n| n.to_str()).is_some_and(|n| n.starts_with(folder))
+        })
+        .collect();
+    tocs.sort();
+    tocs.iter().find_map(|path| fs::read_to_string(path).ok())
+}
+
+/// Remove WoW's `|cAARRGGBB` and `|r` color escapes from a .toc title
+fn strip_color_codes(text: &str) -> String {
+    let mut result = String::with_capacity(text.len());
+    let mut rest = text;
+    while let Some(pos) = rest.find('|') {
+        result.push_str(&rest[..pos]);
+        let escape = &rest[pos + 1..];
+        rest = if escape.starts_with(['c', 'C']) && escape.len() >= 9 && escape.is_char_boundary(9) {
+            &escape[9..]
+        } else if escape.starts_with(['r', 'R']) {
+            &escape[1..]
+        } else {
+            result.push('|');
+            escape
+        };
+    }
+    result.push_str(rest);
+    result.trim().to_string()
+}
+
+/// Value of a `## Key: value` line in a .toc file (keys are case-insensitive)
+fn toc_field(contents: &str, key: &str) -> Option<String> {
+    contents.lines().find_map(|line| {
+        let (name, value) = line.trim_start_matches('\u{feff}').strip_prefix("##")?.split_once(':')?;
+        let value = value.trim();
+        (name.trim().eq_ignore_ascii_case(key) && !value.is_empty()).then(|| value.to_string())
+    })
+}
+
+/// WoW installation path (product folder) of a talent file at the usual
+/// `WTF/Account/<id>/SavedVariables/TalentLoadoutsEx.lua` location
+pub fn wow_path_for_talent_file(path: &Path) -> Option<PathBuf> {
+    let saved_variables = path.parent()?;
+    let account_dir = saved_variables.parent()?;
+    let accounts = account_dir.parent()?;
+    let wtf = accounts.parent()?;
+    if saved_variables.file_name()? != "SavedVariables" || accounts.file_name()? != "Account" || wtf.file_name()? != "WTF" {
+        return None;
+    }
+    wtf.parent().map(Path::to_path_buf)
+}
+
+/// WoW root folder of a path that may point at a product folder like `_retail_`
+fn wow_root(path: PathBuf) -> PathBuf {
+    let is_product_folder = path
+        .file_name()
+        .and_then(|n| n.to_str())
+        .is_some_and(|name| PRODUCT_FOLDERS.iter().any(|(folder, _)| *folder == name));
+    match path.parent() {
+        Some(root) if is_product_folder => root.to_path_buf(),
+        _ => path,
+    }
+}
+
+/// WoW folders on mounted volumes (external drives): `<volume>/World of Warcraft` and
+/// `<volume>/Applications/World of Warcraft`
+#[cfg(any(target_os = "macos", test))]
+fn volume_wow_paths(volumes: &Path) -> Vec<PathBuf> {
+    let mut volume_paths: Vec<PathBuf> = fs::read_dir(volumes)
+        .into_iter()
+        .flatten()
+        .flatten()
+        .map(|entry| entry.path())
+        .collect();
+    volume_paths.sort();
+
+    volume_paths
+        .iter()
+        .flat_map(|volume| [volume.join("World of Warcraft"), volume.join("Applications").join("World of Warcraft")])
+        .filter(|path| path.is_dir())
+        .collect()
+}
+
+/// Registry keys (below HKLM) and values holding a WoW install path
+#[cfg(target_os = "windows")]
+const REGISTRY_INSTALL_KEYS: &[(&str, &str)] = &[
+    ("SOFTWARE\\Blizzard Entertainment\\World of Warcraft", "InstallPath"),
+    ("SOFTWARE\\Microsoft\\Windows\\CurrentVersion\\Uninstall\\World of Warcraft", "InstallLocation"),
+    ("SOFTWARE\\Microsoft\\Windows\\CurrentVersion\\Uninstall\\World of Warcraft Public Test", "InstallLocation"),
+];
+
+/// WoW install paths from the registry, in the 32-bit view (WOW6432Node, where the launcher
+/// writes them) and the 64-bit view; missing keys and values are skipped
+#[cfg(target_os = "windows")]
+fn registry_wow_paths() -> Vec<PathBuf> {
+    use winreg::enums::{HKEY_LOCAL_MACHINE, KEY_READ, KEY_WOW64_32KEY, KEY_WOW64_64KEY};
+    use winreg::RegKey;
+
+    let hklm = RegKey::predef(HKEY_LOCAL_MACHINE);
+    let mut paths: Vec<PathBuf> = Vec::new();
+    for view in [KEY_WOW64_32KEY, KEY_WOW64_64KEY] {
+        for (subkey, value) in REGISTRY_INSTALL_KEYS {
+            let Ok(key) = hklm.open_subkey_with_flags(subkey, KEY_READ | view) else {
+                continue;
+            };
+            if let Ok(path) = key.get_value::<String, _>(value) {
+                let path = PathBuf::from(path.trim_end_matches(['\\', '/']));
+                if !path.as_os_str().is_empty() && !paths.contains(&path) {
+                    paths.push(path);
+                }
+            }
+        }
+    }
+    paths
+}
+
+/// Settings from the client's WTF/Config.wtf (`SET key "value"` lines)
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct ClientConfig {
+    values: HashMap<String, String>,
+}
+
+impl ClientConfig {
+    /// Parse the contents of Config.wtf; lines that aren't `SET key "value"` are ignored
+    pub fn parse(contents: &str) -> Self {
+        let values = contents
+            .lines()
+            .filter_map(|line| {
+                let (key, value) = line.trim().strip_prefix("SET ")?.split_once(' ')?;
+                let value = value.trim().strip_prefix('"')?.strip_suffix('"')?;
+                Some((key.to_string(), value.to_string()))
+            })
+            .collect();
+
+        Self { values }
+    }
+
+    /// Value of a setting
+    pub fn get(&self, key: &str) -> Option<&str> {
+        self.values.get(key).map(String::as_str)
+    }
+
+    /// Account folder the client last logged in with, marked with "!" in accountList
+    /// ("WOW1|!WOW2|"); the only account if there is just one
+    pub fn last_account(&self) -> Option<&str> {
+        let accounts: Vec<_> = self.get("accountList")?.split('|').filter(|a| !a.is_empty()).collect();
+        accounts
+            .iter()
+            .find_map(|account| account.strip_prefix('!'))
+            .or_else(|| (accounts.len() == 1).then(|| accounts[0]))
+    }
+
+    /// Realm the client last logged in to
+    pub fn last_realm(&self) -> Option<&str> {
+        self.get("realmName").filter(|realm| !realm.is_empty())
+    }
+}
+
+/// Region tags some realm folder names carry, e.g. "Silvermoon (EU)"
+const REGION_TAGS: &[&str] = &["us", "eu", "kr", "tw", "cn"];
+
+/// Realm name without a trailing region tag ("Silvermoon (EU)", "Silvermoon-EU" -> "Silvermoon")
+fn strip_region_tag(realm: &str) -> &str {
+    let trimmed = realm.trim_end();
+    let inner = trimmed.strip_suffix([')', ']']).unwrap_or(trimmed);
+    match inner.rfind(['(', '[', '-', '_', ' ']) {
+        Some(pos) if pos > 0 && REGION_TAGS.iter().any(|tag| inner[pos + 1..].eq_ignore_ascii_case(tag)) => {
+            realm[..pos].trim_end()
+        }
+        _ => realm,
+    }
+}
+
+/// Realm name without case, spaces, punctuation and region tag, to match folder name variants
+fn realm_key(realm: &str) -> String {
+    strip_region_tag(realm)
+        .chars()
+        .filter(|c| c.is_alphanumeric())
+        .flat_map(char::to_lowercase)
+        .collect()
+}
+
+/// Merge characters found more than once, on realm folder variants or under several
+/// accounts, into the most recently played entry; expects characters sorted by last played
+fn merge_duplicates(characters: Vec<DiscoveredCharacter>) -> Vec<DiscoveredCharacter> {
+    let mut merged: Vec<DiscoveredCharacter> = Vec::new();
+    let mut seen: HashMap<(String, String), usize> = HashMap::new();
+
+    for character in characters {
+        let key = (realm_key(&character.realm), character.name.to_lowercase());
+        match seen.get(&key) {
+            Some(&index) => {
+                let kept = &mut merged[index];
+                if kept.account_id != character.account_id && !kept.also_found_in.contains(&character.account_id) {
+                    kept.also_found_in.push(character.account_id);
+                }
+            }
+            None => {
+                seen.insert(key, merged.len());
+                merged.push(character);
+            }
+        }
+    }
+    merged
+}
+
+/// How reliable a detected class is, from least to most
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum DetectionConfidence {
+    /// The Adventure Guide loot filter (EJLootClass), which the user can switch to another class
+    Low,
+    /// Found in account-wide addon data keyed by character and realm
+    Medium,
+    /// Found in the character's own entry of its addon data
+    High,
+}
+
+/// A detected class and how it was found
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ClassGuess {
+    pub class: WowClass,
+    pub confidence: DetectionConfidence,
 }
 
 /// Scanner for finding WoW installation and characters
 pub struct WowScanner {
+    installation: WowInstallation,
     wow_path: PathBuf,
 }
 
 impl WowScanner {
-    /// Create a new scanner with the given WoW installation path
+    /// Create a new scanner with the given WoW installation path (a product folder like `_retail_`)
     pub fn new(wow_path: impl Into<PathBuf>) -> Self {
+        Self::for_installation(WowInstallation::from_path(wow_path))
+    }
+
+    /// Create a scanner for an installation found by `find_installations`
+    /// Links in the installation path are resolved, so talent files are written where they
+    /// actually live.
+    pub fn for_installation(installation: WowInstallation) -> Self {
         Self {
-            wow_path: wow_path.into(),
+            wow_path: dunce::canonicalize(&installation.path).unwrap_or_else(|_| installation.path.clone()),
+            installation,
         }
     }
 
-    /// Find the default WoW installation path based on the platform
+    /// The installation path being scanned, with links resolved
+    pub fn wow_path(&self) -> &Path {
+        &self.wow_path
+    }
+
+    /// Check a user-supplied WoW path piece by piece, suggesting the product folder to use
+    /// when the path is the WoW root or a folder inside a product folder
+    pub fn diagnose_path(path: &Path) -> PathDiagnosis {
+        let account_folder = |path: &Path| path.join("WTF").join("Account").is_dir();
+        let product_folders: Vec<String> = PRODUCT_FOLDERS
+            .iter()
+            .filter(|(folder, _)| path.join(folder).is_dir())
+            .map(|(folder, _)| folder.to_string())
+            .collect();
+        let has_account_folder = account_folder(path);
+
+        let suggested_path = if has_account_folder {
+            None
+        } else if !product_folders.is_empty() {
+            // The first folder the game has run from (retail first), otherwise the first one
+            let folders: Vec<PathBuf> = product_folders.iter().map(|folder| path.join(folder)).collect();
+            folders.iter().find(|folder| account_folder(folder)).or(folders.first()).cloned()
+        } else {
+            // A folder inside a product folder, e.g. `_retail_/WTF/Account`
+            path.ancestors()
+                .skip(1)
+                .find(|ancestor| {
+                    let name = ancestor.file_name().and_then(|n| n.to_str());
+                    name.is_some_and(|name| PRODUCT_FOLDERS.iter().any(|(folder, _)| *folder == name))
+                })
+                .map(Path::to_path_buf)
+        };
+
+        let mut diagnosis = PathDiagnosis {
+            exists: path.is_dir(),
+            product_folders,
+            has_executable: WOW_EXECUTABLES.iter().any(|name| path.join(name).exists()),
+            has_wtf_folder: path.join("WTF").is_dir(),
+            has_account_folder,
+            valid: has_account_folder,
+            suggested_path,
+            problem: None,
+        };
+
+        diagnosis.problem = if diagnosis.valid {
+            None
+        } else if !diagnosis.exists {
+            Some("The folder doesn't exist".to_string())
+        } else if let Some(suggested) = &diagnosis.suggested_path {
+            Some(format!("Choose the game folder {} instead", suggested.display()))
+        } else if diagnosis.has_wtf_folder || diagnosis.has_executable {
+            Some("WTF/Account not found; log in to the game once so it is created".to_string())
+        } else {
+            Some("This doesn't look like a World of Warcraft folder".to_string())
+        };
+        diagnosis
+    }
+
+    /// Find the default WoW installation path based on the platform, preferring retail
     pub fn find_default_wow_path() -> Option<PathBuf> {
+        Self::find_installation_candidates().into_iter().next()
+    }
+
+    /// Paths of all installations found on this machine, retail ones first, so the UI can
+    /// offer a choice when there are several
+    pub fn find_installation_candidates() -> Vec<PathBuf> {
+        let (retail, others): (Vec<_>, Vec<_>) = Self::find_installations()
+            .into_iter()
+            .partition(|installation| installation.product == "_retail_");
+        retail.into_iter().chain(others).map(|installation| installation.path).collect()
+    }
+
+    /// All installed game products (retail, PTR, beta, Classic) in the platform's default
+    /// WoW folders
+    pub fn find_installations() -> Vec<WowInstallation> {
+        Self::default_wow_roots()
+            .iter()
+            .flat_map(|root| Self::find_installations_in(root))
+            .collect()
+    }
+
+    /// Installed game products in a WoW root folder (the folder containing `_retail_`)
+    pub fn find_installations_in(root: &Path) -> Vec<WowInstallation> {
+        PRODUCT_FOLDERS
+            .iter()
+            .map(|(folder, _)| root.join(folder))
+            .filter(|path| path.is_dir())
+            .map(WowInstallation::from_path)
+            .collect()
+    }
+
+    /// Existing WoW root folders: install locations registered with the Battle.net launcher,
+    /// then the platform's usual install locations
+    fn default_wow_roots() -> Vec<PathBuf> {
+        #[allow(unused_mut)]
+        let mut registered = battlenet::wow_install_paths();
+
+        #[cfg(target_os = "windows")]
+        registered.extend(registry_wow_paths());
+
+        // Registered paths may name a product folder itself (e.g. ".../_ptr_") instead of the root
+        let mut candidates: Vec<PathBuf> = registered.into_iter().map(wow_root).collect();
+
         #[cfg(target_os = "macos")]
         {
-            let path = PathBuf::from("/Applications/World of Warcraft/_retail_");
-            if path.exists() {
-                return Some(path);
+            candidates.push(PathBuf::from("/Applications/World of Warcraft"));
+            if let Ok(home) = std::env::var("HOME") {
+                candidates.push(PathBuf::from(home).join("Applications/World of Warcraft"));
             }
+            candidates.extend(volume_wow_paths(Path::new("/Volumes")));
         }
 
         #[cfg(target_os = "windows")]
+        candidates.extend([
+            PathBuf::from("C:\\Program Files (x86)\\World of Warcraft"),
+            PathBuf::from("C:\\Program Files\\World of Warcraft"),
+        ]);
+
+        #[cfg(target_os = "linux")]
         {
-            let paths = vec![
-                PathBuf::from("C:\\Program Files (x86)\\World of Warcraft\\_retail_"),
-                PathBuf::from("C:\\Program Files\\World of Warcraft\\_retail_"),
-            ];
-            for path in paths {
-                if path.exists() {
-                    return Some(path);
-                }
+            if let Ok(home) = std::env::var("HOME") {
+                candidates.extend([
+                    PathBuf::from(&home).join("Games/World of Warcraft"),
+                    PathBuf::from(&home).join(".wine/drive_c/Program Files (x86)/World of Warcraft"),
+                    PathBuf::from(&home).join("Games/battlenet/World of Warcraft"),
+                ]);
             }
+            candidates.push(PathBuf::from("/opt/games/World of Warcraft"));
         }
 
-        #[cfg(target_os = "linux")]
+        let mut roots: Vec<PathBuf> = Vec::new();
+        for path in candidates {
+            if path.is_dir() && !roots.contains(&path) {
+                roots.push(path);
+            }
+        }
+        roots
+    }
+
+    /// Check whether the TalentLoadoutsEx addon is installed and which accounts have its
+    /// SavedVariables file
+    pub fn addon_status(&self) -> AddonStatus {
+        let toc = read_toc(&self.addons_path().join(ADDON_FOLDER), ADDON_FOLDER);
+
+        AddonStatus {
+            installed: toc.is_some(),
+            version: toc.as_deref().and_then(|toc| toc_field(toc, "Version")),
+            accounts: self.list_accounts(),
+        }
+    }
+
+    /// Whether a WoW client is running: any game process on this machine, or (on Windows) this
+    /// installation's Config.wtf held open by the client
+    /// The client writes SavedVariables on logout, overwriting talent files written meanwhile.
+    pub fn is_wow_running(&self) -> bool {
+        let system = System::new_with_specifics(RefreshKind::nothing().with_processes(ProcessRefreshKind::nothing()));
+        let process_running = system
+            .processes()
+            .values()
+            .any(|process| is_wow_process_name(&process.name().to_string_lossy()));
+
+        process_running || self.config_in_use()
+    }
+
+    /// Whether another process holds Config.wtf open, which fails opening it without sharing
+    #[cfg(target_os = "windows")]
+    fn config_in_use(&self) -> bool {
+        use std::os::windows::fs::OpenOptionsExt;
+        const ERROR_SHARING_VIOLATION: i32 = 32;
+
+        let config = self.wow_path.join("WTF").join("Config.wtf");
+        match fs::OpenOptions::new().read(true).share_mode(0).open(config) {
+            Ok(_) => false,
+            Err(e) => e.raw_os_error() == Some(ERROR_SHARING_VIOLATION),
+        }
+    }
+
+    /// Files aren't locked against other processes outside Windows
+    #[cfg(not(target_os = "windows"))]
+    fn config_in_use(&self) -> bool {
+        false
+    }
+
+    /// The installation with its accounts and addon state, everything the UI shows about it
+    pub fn inspect(&self) -> WowInstallation {
+        let addon_status = self.addon_status();
+        WowInstallation {
+            path: self.wow_path.clone(),
+            accounts: addon_status.accounts.clone(),
+            addon_status: Some(addon_status),
+            ..self.installation.clone()
+        }
+    }
+
+    /// Account folders below WTF/Account with the state of their talent file, sorted by
+    /// folder name
+    pub fn list_accounts(&self) -> Vec<AccountInfo> {
+        self.accounts(&mut FolderWalk::default())
+    }
+
+    /// Account folders (canonical paths)
+    fn accounts(&self, walk: &mut FolderWalk) -> Vec<AccountInfo> {
+        let wtf_path = self.wow_path.join("WTF").join("Account");
+        if !wtf_path.exists() {
+            return Vec::new();
+        }
+
+        let mut accounts: Vec<AccountInfo> = walk
+            .subdirectories(&wtf_path)
+            .into_iter()
+            .filter(|(account_id, _)| account_id != "SavedVariables")
+            .map(|(account_id, path)| {
+                let talent_file_path = path.join("SavedVariables").join(TALENT_FILE_NAME);
+                let metadata = fs::metadata(&talent_file_path).ok().filter(|m| m.is_file());
+                AccountInfo {
+                    talent_file_exists: metadata.is_some(),
+                    talent_file_size: metadata.as_ref().map_or(0, |m| m.len()),
+                    talent_file_modified: metadata
+                        .and_then(|m| m.modified().ok())
+                        .map(|modified| DateTime::<Utc>::from(modified).to_rfc3339_opts(SecondsFormat::Secs, true)),
+                    account_id,
+                    path,
+                    talent_file_path,
+                }
+            })
+            .collect();
+        accounts.sort_by(|a, b| a.account_id.cmp(&b.account_id));
+        accounts
+    }
+
+    /// Installed addons in Interface/AddOns, sorted by folder name
+    /// Folders without a .toc file aren't loaded by the client and are left out.
+    pub fn scan_addons(&self) -> Vec<InstalledAddon> {
+        let mut addons: Vec<InstalledAddon> = FolderWalk::default()
+            .subdirectories(&self.addons_path())
+            .into_iter()
+            .filter_map(|(folder, dir)| {
+                let toc = read_toc(&dir, &folder)?;
+                let loadout_addon = LOADOUT_ADDONS.iter().find(|(name, _)| *name == folder);
+                Some(InstalledAddon {
+                    title: toc_field(&toc, "Title").map(|title| strip_color_codes(&title)),
+                    version: toc_field(&toc, "Version"),
+                    is_loadout_addon: loadout_addon.is_some(),
+                    output_format: loadout_addon.and_then(|(_, format)| *format),
+                    folder,
+                })
+            })
+            .collect();
+        addons.sort_by(|a, b| a.folder.cmp(&b.folder));
+        addons
+    }
+
+    fn addons_path(&self) -> PathBuf {
+        self.wow_path.join("Interface").join("AddOns")
+    }
+
+    /// Read the client settings from WTF/Config.wtf
+    pub fn read_client_config(&self) -> Result<ClientConfig> {
+        let contents = fs::read_to_string(self.wow_path.join("WTF").join("Config.wtf"))?;
+        Ok(ClientConfig::parse(&contents))
+    }
+
+    /// Talent file of the account to use when none is configured: the only account, the one the
+    /// client last logged in with, or the only one whose talent file exists
+    /// Fails with the candidates when the choice is ambiguous.
+    pub fn default_talent_file(&self) -> Result<PathBuf> {
+        let accounts = self.list_accounts();
+        if let [account] = accounts.as_slice() {
+            return Ok(account.talent_file_path.clone());
+        }
+
+        let last_account = self.read_client_config().ok();
+        let last_account = last_account.as_ref().and_then(|config| config.last_account());
+        if let Some(account) = accounts
+            .iter()
+            .find(|account| last_account.is_some_and(|last| last.eq_ignore_ascii_case(&account.account_id)))
         {
-            if let Ok(home) = std::env::var("HOME") {
-                let candidates = [
-                    PathBuf::from(&home).join("Games/World of Warcraft/_retail_"),
-                    PathBuf::from(&home).join(".wine/drive_c/Program Files (x86)/World of Warcraft/_retail_"),
-                    PathBuf::from(&home).join("Games/battlenet/World of Warcraft/_retail_"),
-                    PathBuf::from("/opt/games/World of Warcraft/_retail_"),
-                ];
-                for path in &candidates {
-                    if path.exists() {
-                        return Some(path.clone());
-                    }
+            return Ok(account.talent_file_path.clone());
+        }
+
+        let with_talent_file: Vec<_> = accounts.iter().filter(|account| account.talent_file_exists).collect();
+        if let [account] = with_talent_file.as_slice() {
+            return Ok(account.talent_file_path.clone());
+        }
+
+        if accounts.is_empty() {
+            anyhow::bail!("No accounts found in {}; log in to the game once", self.wow_path.display());
+        }
+        let candidates: Vec<_> = accounts.iter().map(|account| account.account_id.as_str()).collect();
+        anyhow::bail!(
+            "Several accounts found in {} ({}); set outputPath to the talent file of one of them",
+            self.wow_path.display(),
+            candidates.join(", ")
+        )
+    }
+
+    /// Talent file for a config without `outputPath`, from the installations on this machine
+    /// Retail installations come first; the first installation of `flavor` with accounts is used.
+    pub fn discover_output_path(flavor: GameFlavor) -> Result<PathBuf> {
+        let (retail, others): (Vec<_>, Vec<_>) = Self::find_installations()
+            .into_iter()
+            .filter(|installation| installation.flavor == flavor)
+            .partition(|installation| installation.product == "_retail_");
+        let installations: Vec<_> = retail.into_iter().chain(others).collect();
+        if installations.is_empty() {
+            anyhow::bail!("No {:?} installation of World of Warcraft found; set outputPath", flavor);
+        }
+
+        let mut first_error = None;
+        for installation in installations {
+            match Self::for_installation(installation).default_talent_file() {
+                Ok(path) => return Ok(path),
+                Err(e) => {
+                    first_error.get_or_insert(e);
                 }
             }
         }
-
-        None
+        Err(first_error.expect("at least one installation was tried"))
     }
 
     /// Get the path to TalentLoadoutsEx.lua for a specific account
-    #[allow(dead_code)]
     pub fn get_talent_loadouts_path(&self, account_id: &str) -> PathBuf {
         self.wow_path
             .join("WTF")
             .join("Account")
             .join(account_id)
             .join("SavedVariables")
-            .join("TalentLoadoutsEx.lua")
+            .join(TALENT_FILE_NAME)
+    }
+
+    /// Scan for all characters in the WoW installation, reporting progress after each account
+    /// and realm. Setting `cancel` stops the scan with an error. Folders that can't be read
+    /// are skipped and reported in the warnings.
+    pub fn scan_characters(
+        &self,
+        cancel: &AtomicBool,
+        on_progress: impl FnMut(&ScanProgress),
+    ) -> Result<ScanResult> {
+        Ok(self.scan_characters_incremental(&[], cancel, on_progress)?.0)
     }
 
-    /// Scan for all characters in the WoW installation, sorted by most recently played
-    pub fn scan_characters(&self) -> Result<Vec<DiscoveredCharacter>> {
+    /// Scan for all characters like `scan_characters`, reusing the characters of accounts from
+    /// `previous` whose account and realm folders haven't been modified since. Also returns the
+    /// per-account results to pass in next time.
+    pub fn scan_characters_incremental(
+        &self,
+        previous: &[AccountScan],
+        cancel: &AtomicBool,
+        mut on_progress: impl FnMut(&ScanProgress),
+    ) -> Result<(ScanResult, Vec<AccountScan>)> {
+        let started = Instant::now();
         let wtf_path = self.wow_path.join("WTF").join("Account");
 
         if !wtf_path.exists() {
             anyhow::bail!("WTF/Account directory not found at {:?}", wtf_path);
         }
 
-        let mut characters = Vec::new();
+        let client_config = self.read_client_config().unwrap_or_default();
+        let mut walk = FolderWalk::default();
+        let mut progress = ScanProgress::default();
+        let mut account_scans = Vec::new();
 
-        for account_entry in fs::read_dir(&wtf_path)? {
-            let account_entry = account_entry?;
-            let account_path = account_entry.path();
+        for account in self.accounts(&mut walk) {
+            progress.accounts_found += 1;
+            on_progress(&progress);
 
-            if !account_path.is_dir() {
-                continue;
-            }
+            let folder_times = folder_times(&account.path);
+            let unchanged = previous.iter().find(|scan| {
+                scan.account_id == account.account_id && scan.path == account.path && scan.folder_times == folder_times
+            });
+            let account_scan = match unchanged {
+                Some(scan) => {
+                    progress.characters_found += scan.characters.len();
+                    on_progress(&progress);
+                    AccountScan {
+                        // The talent file isn't part of the folder times
+                        characters: scan
+                            .characters
+                            .iter()
+                            .map(|character| DiscoveredCharacter {
+                                talent_file_exists: account.talent_file_exists,
+                                ..character.clone()
+                            })
+                            .collect(),
+                        ..scan.clone()
+                    }
+                }
+                None => {
+                    let scan = self.scan_account(account, folder_times, cancel, &mut walk, &mut progress, &mut on_progress)?;
+                    progress.characters_found += scan.characters.len();
+                    scan
+                }
+            };
+            account_scans.push(account_scan);
+        }
 
-            let account_id = account_path
-                .file_name()
-                .and_then(|n| n.to_str())
-                .unwrap_or("")
-                .to_string();
+        let mut characters: Vec<DiscoveredCharacter> =
+            account_scans.iter().flat_map(|scan| scan.characters.iter().cloned()).collect();
+        let mut warnings = walk.warnings;
+        warnings.extend(account_scans.iter().flat_map(|scan| scan.warnings.iter().cloned()));
 
-            if account_id == "SavedVariables" {
-                continue;
+        let last_account = client_config.last_account();
+        for character in &mut characters {
+            character.is_last_played_account = last_account.is_some_and(|last| last.eq_ignore_ascii_case(&character.account_id));
+        }
+
+        // One display name per realm: the client's spelling if it knows the realm, otherwise
+        // the longest folder name variant, which keeps spaces and apostrophes
+        let mut display_names: HashMap<String, String> = HashMap::new();
+        for character in &characters {
+            let display = display_names.entry(realm_key(&character.realm)).or_default();
+            let realm = strip_region_tag(&character.realm);
+            if realm.len() > display.len() {
+                *display = realm.to_string();
             }
+        }
+        if let Some(realm) = client_config.last_realm() {
+            if let Some(display) = display_names.get_mut(&realm_key(realm)) {
+                *display = realm.to_string();
+            }
+        }
+        for character in &mut characters {
+            character.realm_display_name = display_names[&realm_key(&character.realm)].clone();
+        }
 
-            if let Ok(realm_entries) = fs::read_dir(&account_path) {
-                for realm_entry in realm_entries.flatten() {
-                    let realm_path = realm_entry.path();
+        // Sort by most recently played first
+        characters.sort_by(|a, b| b.last_played.cmp(&a.last_played));
+        let characters = merge_duplicates(characters);
 
-                    if !realm_path.is_dir() {
-                        continue;
-                    }
+        let result = ScanResult {
+            characters,
+            warnings,
+            elapsed_ms: u64::try_from(started.elapsed().as_millis()).unwrap_or(u64::MAX),
+        };
+        Ok((result, account_scans))
+    }
 
-                    let realm_name = realm_path
-                        .file_name()
-                        .and_then(|n| n.to_str())
-                        .unwrap_or("")
-                        .to_string();
+    /// Scan the characters of one account; `progress.characters_found` is left to the caller to update
+    fn scan_account(
+        &self,
+        account: AccountInfo,
+        folder_times: Vec<(String, SystemTime)>,
+        cancel: &AtomicBool,
+        walk: &mut FolderWalk,
+        progress: &mut ScanProgress,
+        on_progress: &mut impl FnMut(&ScanProgress),
+    ) -> Result<AccountScan> {
+        let AccountInfo {
+            account_id,
+            path: account_path,
+            talent_file_path,
+            talent_file_exists,
+            ..
+        } = account;
 
-                    if realm_name == "SavedVariables" {
-                        continue;
-                    }
+        // Warnings of this account are kept with it, so they're reported again when it's reused
+        let warnings_before = walk.warnings.len();
+        let mut characters = Vec::new();
 
-                    if let Ok(char_entries) = fs::read_dir(&realm_path) {
-                        for char_entry in char_entries.flatten() {
-                            let char_path = char_entry.path();
-
-                            if !char_path.is_dir() {
-                                continue;
-                            }
-
-                            let char_name = char_path
-                                .file_name()
-                                .and_then(|n| n.to_str())
-                                .unwrap_or("")
-                                .to_string();
-
-                            if let Ok((class, last_played)) = self.detect_character_info(&char_path) {
-                                characters.push(DiscoveredCharacter {
-                                    name: char_name,
-                                    realm: realm_name.clone(),
-                                    class,
-                                    account_id: account_id.clone(),
-                                    last_played,
-                                });
-                            }
-                        }
-                    }
-                }
+        let account_saved_variables = account_path.join("SavedVariables");
+        check_readable(&account_saved_variables, &mut walk.warnings);
+        let account_data = Self::read_saved_variables(&account_saved_variables);
+
+        for (realm_name, realm_path) in walk.subdirectories(&account_path) {
+            if cancel.load(Ordering::Relaxed) {
+                anyhow::bail!("Character scan cancelled");
             }
-        }
 
-        // Sort by most recently played first
-        characters.sort_by(|a, b| b.last_played.cmp(&a.last_played));
+            if realm_name == "SavedVariables" {
+                continue;
+            }
 
-        Ok(characters)
-    }
-
-    /// Detect class and last-played time from config-cache.wtf.
-    /// Returns an error if the class cannot be determined (skips the character).
-    fn detect_character_info(&self, char_path: &Path) -> Result<(String, u64)> {
-        let config_path = char_path.join("config-cache.wtf");
-        let contents = fs::read_to_string(&config_path)?;
-
-        // Get last-modified time as a proxy for last played
-        let last_played = fs::metadata(&config_path)
-            .ok()
-            .and_then(|m| m.modified().ok())
-            .and_then(|t| t.duration_since(SystemTime::UNIX_EPOCH).ok())
-            .map(|d| d.as_secs())
-            .unwrap_or(0);
-
-        for line in contents.lines() {
-            let line = line.trim();
-            if let Some(rest) = line.strip_prefix("SET EJLootClass \"") {
-                let id_str = rest.trim_end_matches('"').trim();
-                if let Ok(id) = id_str.parse::<u8>() {
-                    // ID 0 = character slot exists but no class (deleted/placeholder)
-                    if id == 0 {
-                        anyhow::bail!("character has no class (id=0)");
-                    }
-                    let class = Self::class_id_to_name(id);
-                    if class == "Unknown" {
-                        anyhow::bail!("unrecognized class id: {}", id);
-                    }
-                    return Ok((class.to_string(), last_played));
+            for (char_name, char_path) in walk.subdirectories(&realm_path) {
+                // EJLootClass 0: the slot exists but has no character (deleted/placeholder)
+                if Self::ej_loot_class(&char_path) == Some(0) {
+                    continue;
+                }
+
+                if let Ok(last_played) = Self::last_played(&char_path) {
+                    check_readable(&char_path.join("SavedVariables"), &mut walk.warnings);
+                    let guess = Self::detect_character_class(&char_path, &account_data, &char_name, &realm_name);
+                    let specializations = guess
+                        .map(|g| Self::detect_specializations(&char_path, g.class))
+                        .unwrap_or_default();
+                    characters.push(DiscoveredCharacter {
+                        name: char_name,
+                        realm: realm_name.clone(),
+                        realm_display_name: realm_name.clone(),
+                        class: guess.map(|g| g.class.as_str().to_string()).unwrap_or_default(),
+                        class_detected: guess.is_some(),
+                        is_last_played_account: false,
+                        account_id: account_id.clone(),
+                        flavor: self.installation.flavor,
+                        talent_file_path: talent_file_path.clone(),
+                        talent_file_exists,
+                        last_played,
+                        also_found_in: Vec::new(),
+                        specializations,
+                        path: char_path,
+                    });
                 }
             }
+
+            progress.realms_scanned += 1;
+            on_progress(&ScanProgress {
+                characters_found: progress.characters_found + characters.len(),
+                ..progress.clone()
+            });
         }
 
-        anyhow::bail!("EJLootClass not found in config-cache.wtf")
+        Ok(AccountScan {
+            account_id,
+            path: account_path,
+            folder_times,
+            characters,
+            warnings: walk.warnings.split_off(warnings_before),
+        })
     }
 
-    fn class_id_to_name(id: u8) -> &'static str {
-        match id {
-            1 => "Warrior",
-            2 => "Paladin",
-            3 => "Hunter",
-            4 => "Rogue",
-            5 => "Priest",
-            6 => "DeathKnight",
-            7 => "Shaman",
-            8 => "Mage",
-            9 => "Warlock",
-            10 => "Monk",
-            11 => "Druid",
-            12 => "DemonHunter",
-            13 => "Evoker",
-            _ => "Unknown",
+    /// Newest modification time of a character's config-cache.wtf and SavedVariables files
+    /// Fails if the folder has no config-cache.wtf, i.e. isn't a character.
+    fn last_played(char_path: &Path) -> Result<Option<String>> {
+        let config_cache = fs::metadata(char_path.join("config-cache.wtf"))?;
+
+        let saved_variables = fs::read_dir(char_path.join("SavedVariables"))
+            .into_iter()
+            .flatten()
+            .flatten()
+            .filter_map(|entry| entry.metadata().ok())
+            .filter(|metadata| metadata.is_file());
+
+        Ok(std::iter::once(config_cache)
+            .chain(saved_variables)
+            .filter_map(|metadata| metadata.modified().ok())
+            .max()
+            .map(|modified| DateTime::<Utc>::from(modified).to_rfc3339_opts(SecondsFormat::Secs, true)))
+    }
+
+    /// Best guess of a character's class: class entries (possibly localized) under the character's
+    /// name in its SavedVariables, then account-wide addon data keyed by "Name - Realm", then the
+    /// Adventure Guide loot filter. Returns None if no source knows the class.
+    /// Entries of other players (addons record group members) are ignored.
+    pub fn detect_character_class(
+        char_path: &Path,
+        account_data: &[String],
+        name: &str,
+        realm: &str,
+    ) -> Option<ClassGuess> {
+        let guess = |class, confidence| ClassGuess { class, confidence };
+        let own_keys = [name.to_string(), format!("{} - {}", name, realm), format!("{}-{}", name, realm)];
+
+        Self::read_saved_variables(&char_path.join("SavedVariables"))
+            .iter()
+            .find_map(|contents| class_in_section(contents, &own_keys))
+            .map(|class| guess(class, DetectionConfidence::High))
+            .or_else(|| {
+                account_data
+                    .iter()
+                    .find_map(|contents| class_in_section(contents, &own_keys[1..]))
+                    .map(|class| guess(class, DetectionConfidence::Medium))
+            })
+            .or_else(|| {
+                Self::ej_loot_class(char_path)
+                    .and_then(WowClass::from_class_id)
+                    .map(|class| guess(class, DetectionConfidence::Low))
+            })
+    }
+
+    /// Specs of `class` the character has played, from spec IDs in its SavedVariables
+    /// (Details! and other addons store them as `["spec"] = 71` or `["specID"] = 71`)
+    /// IDs of other classes are ignored; addons also record the specs of group members.
+    pub fn detect_specializations(char_path: &Path, class: WowClass) -> Vec<String> {
+        let mut spec_indices: Vec<u8> = Self::read_saved_variables(&char_path.join("SavedVariables"))
+            .iter()
+            .flat_map(|contents| contents.lines().filter_map(spec_id_entry).collect::<Vec<_>>())
+            .filter_map(WowClass::spec_index_from_id)
+            .filter(|(spec_class, _)| *spec_class == class)
+            .map(|(_, spec_index)| spec_index)
+            .collect();
+        spec_indices.sort_unstable();
+        spec_indices.dedup();
+
+        spec_indices
+            .into_iter()
+            .filter_map(|spec_index| class.spec_name_for_index(spec_index))
+            .map(str::to_string)
+            .collect()
+    }
+
+    /// Class ID stored as EJLootClass in config-cache.wtf
+    fn ej_loot_class(char_path: &Path) -> Option<u8> {
+        let contents = fs::read_to_string(char_path.join("config-cache.wtf")).ok()?;
+
+        contents.lines().find_map(|line| {
+            let id = line.trim().strip_prefix("SET EJLootClass \"")?.trim_end_matches('"').trim();
+            id.parse().ok()
+        })
+    }
+
+    /// Contents of all .lua files in a SavedVariables folder (empty if there is none)
+    fn read_saved_variables(dir: &Path) -> Vec<String> {
+        let Ok(entries) = fs::read_dir(dir) else {
+            return Vec::new();
+        };
+
+        entries
+            .flatten()
+            .map(|entry| entry.path())
+            .filter(|path| path.extension().is_some_and(|ext| ext == "lua"))
+            .filter_map(|path| fs::read_to_string(path).ok())
+            .collect()
+    }
+}
+
+/// Class of a `["class"] = "..."` or `["className"] = "..."` line, as written by many addons
+/// in the client's language (e.g., "Todesritter") or as a class token (e.g., "DEATHKNIGHT")
+fn class_entry(line: &str) -> Option<WowClass> {
+    let line = line.trim();
+    let value = line
+        .strip_prefix("[\"class\"] = \"")
+        .or_else(|| line.strip_prefix("[\"className\"] = \""))?;
+    WowClass::from_localized(&lua_unescape_string(value.split('"').next()?))
+}
+
+/// Spec ID of a `["spec"] = 71,` line, or the same with one of the other keys addons use
+fn spec_id_entry(line: &str) -> Option<u16> {
+    const KEYS: &[&str] = &[
+        "[\"spec\"] = ",
+        "[\"specID\"] = ",
+        "[\"specId\"] = ",
+        "[\"spec_id\"] = ",
+        "[\"specializationID\"] = ",
+    ];
+    let line = line.trim();
+    let value = KEYS.iter().find_map(|key| line.strip_prefix(key))?;
+    value.trim_end_matches(',').trim().parse().ok()
+}
+
+/// Class entry directly inside a table keyed by one of `keys` (e.g., `["Name - Realm"] = {`)
+/// Nested tables are skipped, as they may describe other players.
+fn class_in_section(contents: &str, keys: &[String]) -> Option<WowClass> {
+    let keys: Vec<_> = keys.iter().map(|key| format!("[\"{}\"] = {{", key)).collect();
+    let mut lines = contents.lines();
+
+    while let Some(line) = lines.next() {
+        if !keys.iter().any(|key| line.trim_start().starts_with(key.as_str())) {
+            continue;
+        }
+
+        // Look inside the character's table only
+        let mut depth = 0i32;
+        for line in std::iter::once(line).chain(lines.by_ref()) {
+            if depth == 1 {
+                if let Some(class) = class_entry(line) {
+                    return Some(class);
+                }
+            }
+            depth += line.matches('{').count() as i32 - line.matches('}').count() as i32;
+            if depth <= 0 {
+                break;
+            }
         }
     }
+
+    None
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::test_util::temp_dir;
+
+    /// Scan all characters of an installation
+    fn scan(scanner: WowScanner) -> Vec<DiscoveredCharacter> {
+        scanner.scan_characters(&AtomicBool::new(false), |_| {}).unwrap().characters
+    }
 
     #[test]
     fn test_find_default_wow_path() {
         let path = WowScanner::find_default_wow_path();
         println!("Default WoW path: {:?}", path);
     }
+
+    #[test]
+    fn test_scan_detects_localized_class_names() {
+        let wow_path = temp_dir("scan");
+        let realm = wow_path.join("WTF/Account/12345/Blackrock");
+
+        let by_id = realm.join("Schurkin");
+        fs::create_dir_all(&by_id).unwrap();
+        fs::write(by_id.join("config-cache.wtf"), "SET EJLootClass \"4\"\n").unwrap();
+
+        let localized = realm.join("Kälte");
+        fs::create_dir_all(localized.join("SavedVariables")).unwrap();
+        fs::write(localized.join("config-cache.wtf"), "SET autoLootDefault \"1\"\n").unwrap();
+        fs::write(
+            localized.join("SavedVariables/SomeAddon.lua"),
+            "SomeAddonDB = {\n\t[\"Kälte\"] = {\n\t\t[\"class\"] = \"Todesritterin\",\n\t},\n}\n",
+        )
+        .unwrap();
+
+        let unknown = realm.join("Leer");
+        fs::create_dir_all(&unknown).unwrap();
+        fs::write(unknown.join("config-cache.wtf"), "").unwrap();
+
+        let empty_slot = realm.join("Geloescht");
+        fs::create_dir_all(&empty_slot).unwrap();
+        fs::write(empty_slot.join("config-cache.wtf"), "SET EJLootClass \"0\"\n").unwrap();
+
+        let mut characters = scan(WowScanner::new(&wow_path));
+        characters.sort_by(|a, b| a.name.cmp(&b.name));
+        let found: Vec<_> = characters
+            .iter()
+            .map(|c| (c.name.as_str(), c.class.as_str(), c.class_detected))
+            .collect();
+        assert_eq!(
+            found,
+            [("Kälte", "DeathKnight", true), ("Leer", "", false), ("Schurkin", "Rogue", true)]
+        );
+    }
+
+    #[test]
+    fn test_find_installations_in_root() {
+        let root = temp_dir("products");
+        for folder in ["_retail_", "_ptr_", "_classic_era_", "_classic_", "Interface", "Data"] {
+            fs::create_dir_all(root.join(folder)).unwrap();
+        }
+
+        let installations = WowScanner::find_installations_in(&root);
+        let found: Vec<_> = installations.iter().map(|i| (i.product.as_str(), i.flavor)).collect();
+        assert_eq!(
+            found,
+            [
+                ("_retail_", GameFlavor::Retail),
+                ("_ptr_", GameFlavor::Retail),
+                ("_classic_", GameFlavor::Cataclysm),
+                ("_classic_era_", GameFlavor::ClassicEra),
+            ]
+        );
+
+        // Characters carry the flavor and the talent file of their installation
+        let char_path = root.join("_classic_era_/WTF/Account/WOW1/Firemaw/Alt");
+        fs::create_dir_all(&char_path).unwrap();
+        fs::write(char_path.join("config-cache.wtf"), "SET EJLootClass \"8\"\n").unwrap();
+        let characters = scan(WowScanner::for_installation(installations[3].clone()));
+        assert_eq!(characters[0].flavor, GameFlavor::ClassicEra);
+        assert!(!characters[0].talent_file_exists);
+        fs::create_dir_all(characters[0].talent_file_path.parent().unwrap()).unwrap();
+        fs::write(&characters[0].talent_file_path, "").unwrap();
+        let characters = scan(WowScanner::for_installation(installations[3].clone()));
+        assert!(characters[0].talent_file_exists);
+        assert_eq!(
+            characters[0].talent_file_path,
+            root.join("_classic_era_/WTF/Account/WOW1/SavedVariables/TalentLoadoutsEx.lua")
+        );
+
+        assert_eq!(WowInstallation::from_path(root.join("_xptr_")).flavor, GameFlavor::Retail);
+        assert_eq!(WowInstallation::from_path("/somewhere/else").flavor, GameFlavor::Retail);
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_scan_resolves_symlinks_and_skips_cycles() {
+        use std::os::unix::fs::symlink;
+
+        let root = temp_dir("symlinks");
+        let wow_path = root.join("_retail_");
+        let accounts = wow_path.join("WTF/Account");
+        fs::create_dir_all(&accounts).unwrap();
+
+        // Account folder moved elsewhere (e.g. out of a synced folder) and linked back
+        let moved = root.join("moved/WOW1");
+        fs::create_dir_all(moved.join("Blackrock/Linked")).unwrap();
+        fs::write(moved.join("Blackrock/Linked/config-cache.wtf"), "SET EJLootClass \"1\"\n").unwrap();
+        symlink(&moved, accounts.join("WOW1")).unwrap();
+        // A second link to the same account and a link back up to the account folder
+        symlink(&moved, accounts.join("WOW1-copy")).unwrap();
+        symlink(&moved, moved.join("Blackrock/Loop")).unwrap();
+
+        // The installation itself reached through a link
+        symlink(&wow_path, root.join("retail-link")).unwrap();
+        let scanner = WowScanner::new(root.join("retail-link"));
+        let scan = scanner.scan_characters(&AtomicBool::new(false), |_| {}).unwrap();
+
+        let found: Vec<_> = scan.characters.iter().map(|c| (c.name.as_str(), c.account_id.as_str())).collect();
+        assert_eq!(found, [("Linked", "WOW1")]);
+        assert_eq!(scan.characters[0].talent_file_path, moved.join("SavedVariables/TalentLoadoutsEx.lua"));
+
+        let mut skipped: Vec<_> = scan.warnings.iter().map(|w| w.path.clone()).collect();
+        skipped.sort();
+        assert_eq!(skipped, [accounts.join("WOW1-copy"), moved.join("Blackrock/Loop")]);
+        assert!(scan.warnings[0].error.contains("already scanned"));
+
+        let listed: Vec<_> = scanner.list_accounts().into_iter().map(|a| (a.account_id, a.path)).collect();
+        assert_eq!(listed, [("WOW1".to_string(), moved.clone())]);
+    }
+
+    #[cfg(windows)]
+    #[test]
+    fn test_scan_resolves_junctions() {
+        let root = temp_dir("junctions");
+        let accounts = root.join("_retail_/WTF/Account");
+        fs::create_dir_all(&accounts).unwrap();
+        let moved = root.join("moved/WOW1");
+        fs::create_dir_all(moved.join("Blackrock/Linked")).unwrap();
+        fs::write(moved.join("Blackrock/Linked/config-cache.wtf"), "").unwrap();
+
+        let junction = |link: PathBuf, target: &Path| {
+            let status = std::process::Command::new("cmd")
+                .arg("/C")
+                .arg("mklink")
+                .arg("/J")
+                .arg(&link)
+                .arg(target)
+                .output()
+                .unwrap()
+                .status;
+            assert!(status.success(), "mklink /J {:?} failed", link);
+        };
+        junction(accounts.join("WOW1"), &moved);
+        junction(moved.join("Blackrock/Loop"), &moved);
+
+        let scan = WowScanner::new(root.join("_retail_"))
+            .scan_characters(&AtomicBool::new(false), |_| {})
+            .unwrap();
+        let found: Vec<_> = scan.characters.iter().map(|c| (c.name.as_str(), c.account_id.as_str())).collect();
+        assert_eq!(found, [("Linked", "WOW1")]);
+        assert_eq!(scan.characters[0].talent_file_path, moved.join("SavedVariables\\TalentLoadoutsEx.lua"));
+        let skipped: Vec<_> = scan.warnings.iter().map(|w| w.path.clone()).collect();
+        assert_eq!(skipped, [moved.join("Blackrock\\Loop")]);
+    }
+
+    #[test]
+    fn test_list_accounts() {
+        let wow_path = temp_dir("accounts");
+        let scanner = WowScanner::new(&wow_path);
+        assert!(scanner.list_accounts().is_empty());
+
+        fs::create_dir_all(wow_path.join("WTF/Account/SavedVariables")).unwrap();
+        fs::create_dir_all(wow_path.join("WTF/Account/98765#2")).unwrap();
+        fs::create_dir_all(wow_path.join("WTF/Account/12345#1/SavedVariables")).unwrap();
+        fs::write(scanner.get_talent_loadouts_path("12345#1"), "TalentLoadoutEx = {}\n").unwrap();
+
+        let accounts = scanner.list_accounts();
+        let found: Vec<_> = accounts
+            .iter()
+            .map(|a| (a.account_id.as_str(), a.talent_file_exists, a.talent_file_size, a.talent_file_modified.is_some()))
+            .collect();
+        assert_eq!(found, [("12345#1", true, 21, true), ("98765#2", false, 0, false)]);
+        assert_eq!(accounts[1].path, wow_path.join("WTF/Account/98765#2"));
+    }
+
+    #[test]
+    fn test_default_talent_file() {
+        let wow_path = temp_dir("default-talent-file");
+        let scanner = WowScanner::new(&wow_path);
+        assert!(scanner.default_talent_file().is_err());
+
+        fs::create_dir_all(wow_path.join("WTF/Account/WOW1")).unwrap();
+        assert_eq!(scanner.default_talent_file().unwrap(), scanner.get_talent_loadouts_path("WOW1"));
+
+        // Two accounts without a hint which one is played
+        fs::create_dir_all(wow_path.join("WTF/Account/WOW2")).unwrap();
+        let error = scanner.default_talent_file().unwrap_err().to_string();
+        assert!(error.contains("(WOW1, WOW2)"), "{}", error);
+
+        // The addon only ran on one of them
+        fs::create_dir_all(wow_path.join("WTF/Account/WOW2/SavedVariables")).unwrap();
+        fs::write(scanner.get_talent_loadouts_path("WOW2"), "").unwrap();
+        assert_eq!(scanner.default_talent_file().unwrap(), scanner.get_talent_loadouts_path("WOW2"));
+
+        // The last played account wins
+        fs::write(wow_path.join("WTF/Config.wtf"), "SET accountList \"!WOW1|WOW2|\"\n").unwrap();
+        assert_eq!(scanner.default_talent_file().unwrap(), scanner.get_talent_loadouts_path("WOW1"));
+    }
+
+    #[test]
+    fn test_addon_status() {
+        let wow_path = temp_dir("addon");
+        fs::create_dir_all(wow_path.join("WTF/Account/WOW2")).unwrap();
+        fs::create_dir_all(wow_path.join("WTF/Account/WOW1/SavedVariables")).unwrap();
+        fs::write(wow_path.join("WTF/Account/WOW1/SavedVariables/TalentLoadoutsEx.lua"), "").unwrap();
+
+        let scanner = WowScanner::new(&wow_path);
+        let status = scanner.addon_status();
+        assert!(!status.installed);
+        assert_eq!(status.version, None);
+        let accounts: Vec<_> = status.accounts.iter().map(|a| (a.account_id.as_str(), a.talent_file_exists)).collect();
+        assert_eq!(accounts, [("WOW1", true), ("WOW2", false)]);
+
+        let addon = wow_path.join("Interface/AddOns/TalentLoadoutsEx");
+        fs::create_dir_all(&addon).unwrap();
+        fs::write(
+            addon.join("TalentLoadoutsEx.toc"),
+            "\u{feff}## Interface: 110002\r\n## Title: TalentLoadoutsEx\r\n## version: 2.3.1 \r\nmain.lua\r\n",
+        )
+        .unwrap();
+        let status = scanner.addon_status();
+        assert!(status.installed);
+        assert_eq!(status.version.as_deref(), Some("2.3.1"));
+
+        // Everything about the installation in one go
+        let installation = WowScanner::new(wow_path.join("WTF/..")).inspect();
+        assert_eq!(installation.path, dunce::canonicalize(&wow_path).unwrap());
+        assert_eq!(installation.flavor, GameFlavor::Retail);
+        assert_eq!(installation.accounts, status.accounts);
+        assert_eq!(installation.addon_status, Some(status));
+        assert!(WowInstallation::from_path(&wow_path).addon_status.is_none());
+    }
+
+    #[test]
+    fn test_scan_addons() {
+        let wow_path = temp_dir("addons");
+        let addons_path = wow_path.join("Interface/AddOns");
+        for (folder, toc_name, toc) in [
+            ("BtWLoadouts", "BtWLoadouts_Mainline.toc", "## Title: |cff00ff00BtW|r Loadouts\n## Version: v110002-1\n"),
+            ("Details", "Details.toc", "## Title: Details! Damage Meter\n"),
+            ("TalentLoadoutManager", "TalentLoadoutManager.toc", "## Title: Talent Loadout Manager\n## Version: 1.9\n"),
+        ] {
+            fs::create_dir_all(addons_path.join(folder)).unwrap();
+            fs::write(addons_path.join(folder).join(toc_name), toc).unwrap();
+        }
+        // Leftover folder without a .toc, not loaded by the client
+        fs::create_dir_all(addons_path.join("Blizzard_Deleted")).unwrap();
+
+        let addons = WowScanner::new(&wow_path).scan_addons();
+        let found: Vec<_> = addons
+            .iter()
+            .map(|a| (a.folder.as_str(), a.title.as_deref(), a.version.as_deref(), a.is_loadout_addon, a.output_format))
+            .collect();
+        assert_eq!(
+            found,
+            [
+                ("BtWLoadouts", Some("BtW Loadouts"), Some("v110002-1"), true, Some(TalentFileFormat::BtwLoadouts)),
+                ("Details", Some("Details! Damage Meter"), None, false, None),
+                ("TalentLoadoutManager", Some("Talent Loadout Manager"), Some("1.9"), true, None),
+            ]
+        );
+        assert_eq!(suggested_output_format(&addons), Some(TalentFileFormat::BtwLoadouts));
+
+        fs::create_dir_all(addons_path.join("TalentLoadoutsEx")).unwrap();
+        fs::write(addons_path.join("TalentLoadoutsEx/TalentLoadoutsEx.toc"), "## Title: TalentLoadoutsEx\n").unwrap();
+        let addons = WowScanner::new(&wow_path).scan_addons();
+        assert_eq!(suggested_output_format(&addons), Some(TalentFileFormat::TalentLoadoutsEx));
+        assert_eq!(suggested_output_format(&addons[1..2]), None);
+    }
+
+    #[test]
+    fn test_volume_wow_paths() {
+        let volumes = temp_dir("volumes");
+        fs::create_dir_all(volumes.join("Games/World of Warcraft/_retail_")).unwrap();
+        fs::create_dir_all(volumes.join("External/Applications/World of Warcraft")).unwrap();
+        fs::create_dir_all(volumes.join("Backup/Photos")).unwrap();
+
+        assert_eq!(
+            volume_wow_paths(&volumes),
+            [
+                volumes.join("External/Applications/World of Warcraft"),
+                volumes.join("Games/World of Warcraft"),
+            ]
+        );
+        assert!(volume_wow_paths(&volumes.join("missing")).is_empty());
+    }
+
+    #[test]
+    fn test_wow_root_of_registered_paths() {
+        let root = PathBuf::from("/games/World of Warcraft");
+        assert_eq!(wow_root(root.join("_retail_")), root);
+        assert_eq!(wow_root(root.join("_classic_era_")), root);
+        assert_eq!(wow_root(root.clone()), root);
+    }
+
+    #[test]
+    fn test_wow_path_for_talent_file() {
+        let wow_path = Path::new("/games/World of Warcraft/_retail_");
+        assert_eq!(
+            wow_path_for_talent_file(&wow_path.join("WTF/Account/WOW1/SavedVariables/TalentLoadoutsEx.lua")),
+            Some(wow_path.to_path_buf())
+        );
+        assert_eq!(wow_path_for_talent_file(Path::new("/tmp/TalentLoadoutsEx.lua")), None);
+        assert_eq!(wow_path_for_talent_file(Path::new("/backups/WOW1/SavedVariables/TalentLoadoutsEx.lua")), None);
+    }
+
+    #[test]
+    fn test_parse_client_config() {
+        let config = ClientConfig::parse(include_str!("../tests/fixtures/Config.wtf"));
+        assert_eq!(config.get("locale"), Some("deDE"));
+        assert_eq!(config.get("accountList"), Some("WOW1|!WOW2|"));
+        assert_eq!(config.last_account(), Some("WOW2"));
+        assert_eq!(config.last_realm(), Some("Mal'Ganis"));
+        assert_eq!(config.get("missing"), None);
+
+        assert_eq!(ClientConfig::parse("SET accountList \"WOW1|\"").last_account(), Some("WOW1"));
+        assert_eq!(ClientConfig::parse("SET accountList \"WOW1|WOW2|\"").last_account(), None);
+        assert_eq!(ClientConfig::default().last_realm(), None);
+    }
+
+    #[test]
+    fn test_scan_marks_last_account_and_normalizes_realms() {
+        let wow_path = temp_dir("realms");
+        fs::create_dir_all(wow_path.join("WTF")).unwrap();
+        fs::write(wow_path.join("WTF/Config.wtf"), include_str!("../tests/fixtures/Config.wtf")).unwrap();
+
+        for (account, realm, name) in [
+            ("WOW1", "MalGanis", "Alt"),
+            ("WOW2", "Mal'Ganis", "Main"),
+            ("WOW2", "Argent Dawn", "Bank"),
+            ("WOW2", "ArgentDawn", "Twink"),
+        ] {
+            let char_path = wow_path.join("WTF/Account").join(account).join(realm).join(name);
+            fs::create_dir_all(&char_path).unwrap();
+            fs::write(char_path.join("config-cache.wtf"), "SET EJLootClass \"1\"\n").unwrap();
+        }
+
+        let mut characters = scan(WowScanner::new(&wow_path));
+        characters.sort_by(|a, b| a.name.cmp(&b.name));
+        let found: Vec<_> = characters
+            .iter()
+            .map(|c| (c.name.as_str(), c.realm_display_name.as_str(), c.is_last_played_account))
+            .collect();
+        assert_eq!(
+            found,
+            [
+                ("Alt", "Mal'Ganis", false),
+                ("Bank", "Argent Dawn", true),
+                ("Main", "Mal'Ganis", true),
+                ("Twink", "Argent Dawn", true),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_scan_sorts_by_last_played() {
+        let wow_path = temp_dir("recency");
+        let realm = wow_path.join("WTF/Account/WOW1/Blackrock");
+        let days_ago = |days: u64| SystemTime::now() - std::time::Duration::from_secs(days * 24 * 60 * 60);
+        let touch = |path: PathBuf, modified: SystemTime| {
+            fs::create_dir_all(path.parent().unwrap()).unwrap();
+            fs::write(&path, "").unwrap();
+            fs::File::options().write(true).open(&path).unwrap().set_modified(modified).unwrap();
+        };
+
+        // The addon data of "Recent" was saved after its config-cache.wtf
+        touch(realm.join("Recent/config-cache.wtf"), days_ago(30));
+        touch(realm.join("Recent/SavedVariables/SomeAddon.lua"), days_ago(1));
+        touch(realm.join("Weekly/config-cache.wtf"), days_ago(10));
+        touch(realm.join("Retired/config-cache.wtf"), days_ago(400));
+
+        let characters = scan(WowScanner::new(&wow_path));
+        let names: Vec<_> = characters.iter().map(|c| c.name.as_str()).collect();
+        assert_eq!(names, ["Recent", "Weekly", "Retired"]);
+
+        // Progress after the account and its realm, then cancellation before the next realm
+        let scanner = WowScanner::new(&wow_path);
+        let mut progress = Vec::new();
+        let scan = scanner.scan_characters(&AtomicBool::new(false), |p| progress.push(p.clone())).unwrap();
+        assert!(scan.warnings.is_empty());
+        let counts: Vec<_> = progress.iter().map(|p| (p.accounts_found, p.realms_scanned, p.characters_found)).collect();
+        assert_eq!(counts, [(1, 0, 0), (1, 1, 3)]);
+        let cancelled = scanner.scan_characters(&AtomicBool::new(true), |_| {});
+        assert!(cancelled.unwrap_err().to_string().contains("cancelled"));
+
+        // A file where the addon data folder should be is reported; the other characters are still found
+        fs::remove_dir_all(realm.join("Recent/SavedVariables")).unwrap();
+        fs::write(realm.join("Recent/SavedVariables"), "").unwrap();
+        let scan = scanner.scan_characters(&AtomicBool::new(false), |_| {}).unwrap();
+        assert_eq!(scan.characters.len(), 3);
+        let paths: Vec<_> = scan.warnings.iter().map(|w| w.path.clone()).collect();
+        assert_eq!(paths, [realm.join("Recent/SavedVariables")]);
+        assert!(!scan.warnings[0].error.is_empty());
+        assert!(characters[0].last_played.as_deref().unwrap().ends_with('Z'));
+
+        let now = Utc::now();
+        let active: Vec<_> = characters.iter().filter(|c| c.played_within(180, now)).map(|c| c.name.as_str()).collect();
+        assert_eq!(active, ["Recent", "Weekly"]);
+
+        let unknown = DiscoveredCharacter { last_played: None, ..characters[2].clone() };
+        assert!(unknown.played_within(180, now));
+    }
+
+    #[test]
+    fn test_realm_key_normalization() {
+        for variant in ["Mal'Ganis", "MalGanis", "Mal Ganis", "malganis", "Mal'Ganis (US)", "MalGanis-US"] {
+            assert_eq!(realm_key(variant), "malganis", "{}", variant);
+        }
+        for variant in ["Silvermoon (EU)", "Silvermoon [EU]", "Silvermoon_EU", "Silvermoon EU", "Silvermoon"] {
+            assert_eq!(strip_region_tag(variant), "Silvermoon", "{}", variant);
+        }
+        // Hyphenated realm names and words that only end like a tag are kept
+        assert_eq!(strip_region_tag("Azjol-Nerub"), "Azjol-Nerub");
+        assert_eq!(strip_region_tag("Argent Dawn"), "Argent Dawn");
+        assert_eq!(realm_key("Quel'Thalas"), "quelthalas");
+        assert_eq!(strip_region_tag("EU"), "EU");
+    }
+
+    #[test]
+    fn test_scan_merges_duplicate_characters() {
+        let wow_path = temp_dir("duplicates");
+        let days_ago = |days: u64| SystemTime::now() - std::time::Duration::from_secs(days * 24 * 60 * 60);
+        for (account, realm, name, days) in [
+            ("WOW1", "Silvermoon", "Mychar", 20),
+            ("WOW1", "Silvermoon (EU)", "Mychar", 5),
+            ("WOW2", "Silvermoon", "Mychar", 40),
+            ("WOW2", "Silvermoon", "Other", 1),
+        ] {
+            let char_path = wow_path.join("WTF/Account").join(account).join(realm).join(name);
+            fs::create_dir_all(&char_path).unwrap();
+            let config_cache = char_path.join("config-cache.wtf");
+            fs::write(&config_cache, "").unwrap();
+            fs::File::options().write(true).open(&config_cache).unwrap().set_modified(days_ago(days)).unwrap();
+        }
+
+        let characters = scan(WowScanner::new(&wow_path));
+        let found: Vec<_> = characters
+            .iter()
+            .map(|c| (c.name.as_str(), c.realm.as_str(), c.realm_display_name.as_str(), c.account_id.as_str()))
+            .collect();
+        assert_eq!(
+            found,
+            [("Other", "Silvermoon", "Silvermoon", "WOW2"), ("Mychar", "Silvermoon (EU)", "Silvermoon", "WOW1")]
+        );
+        assert!(characters[0].also_found_in.is_empty());
+        assert_eq!(characters[1].also_found_in, ["WOW2"]);
+    }
+
+    #[test]
+    fn test_detect_class_by_confidence() {
+        let char_path = temp_dir("detect");
+        fs::create_dir_all(char_path.join("SavedVariables")).unwrap();
+        fs::write(char_path.join("config-cache.wtf"), "SET EJLootClass \"11\"\n").unwrap();
+
+        let account_data = vec![
+            "DataDB = {\n\t[\"Other - Blackrock\"] = {\n\t\t[\"class\"] = \"MAGE\",\n\t},\n\
+             \t[\"Kälte - Blackrock\"] = {\n\t\t[\"level\"] = 80,\n\t\t[\"class\"] = \"Chaman\",\n\t},\n}\n"
+                .to_string(),
+        ];
+        let detect = |name| WowScanner::detect_character_class(&char_path, &account_data, name, "Blackrock").unwrap();
+
+        // The loot filter is only a fallback, the user may have switched it to another class
+        let guess = detect("Nobody");
+        assert_eq!((guess.class, guess.confidence), (WowClass::Druid, DetectionConfidence::Low));
+        let guess = detect("Kälte");
+        assert_eq!((guess.class, guess.confidence), (WowClass::Shaman, DetectionConfidence::Medium));
+
+        // Group members in the character's own data don't count
+        fs::write(
+            char_path.join("SavedVariables/Details.lua"),
+            "DetailsDB = {\n\t[\"class\"] = \"PRIEST\",\n\t[\"Healbot - Blackrock\"] = {\n\
+             \t\t[\"class\"] = \"PRIEST\",\n\t},\n}\n",
+        )
+        .unwrap();
+        let guess = detect("Kälte");
+        assert_eq!((guess.class, guess.confidence), (WowClass::Shaman, DetectionConfidence::Medium));
+
+        // ...only the class under the character's own name, outside its nested tables
+        fs::write(
+            char_path.join("SavedVariables/Details.lua"),
+            "DetailsDB = {\n\t[\"Healbot - Blackrock\"] = {\n\t\t[\"class\"] = \"PRIEST\",\n\t},\n\
+             \t[\"Kälte\"] = {\n\t\t[\"group\"] = {\n\t\t\t[\"class\"] = \"MAGE\",\n\t\t},\n\
+             \t\t[\"class\"] = \"WARLOCK\",\n\t},\n}\n",
+        )
+        .unwrap();
+        let guess = detect("Kälte");
+        assert_eq!((guess.class, guess.confidence), (WowClass::Warlock, DetectionConfidence::High));
+        assert!(DetectionConfidence::High > DetectionConfidence::Low);
+
+        fs::write(char_path.join("config-cache.wtf"), "").unwrap();
+        assert_eq!(WowScanner::detect_character_class(&char_path, &account_data, "Nobody", "Blackrock"), None);
+    }
+
+    #[test]
+    fn test_scan_keeps_non_ascii_folder_names() {
+        let wow_path = temp_dir("unicode");
+        let account = wow_path.join("WTF/Account/WOW1");
+        for char_path in [account.join("Cho'gall/Ångela"), account.join("Zul'jin/Ñandú")] {
+            fs::create_dir_all(&char_path).unwrap();
+            fs::write(char_path.join("config-cache.wtf"), "SET EJLootClass \"5\"\n").unwrap();
+        }
+
+        let scan = WowScanner::new(&wow_path).scan_characters(&AtomicBool::new(false), |_| {}).unwrap();
+        assert!(scan.warnings.is_empty());
+        let mut found: Vec<_> = scan
+            .characters
+            .iter()
+            .map(|c| (c.name.as_str(), c.realm.as_str(), c.realm_display_name.as_str()))
+            .collect();
+        found.sort();
+        assert_eq!(found, [("Ångela", "Cho'gall", "Cho'gall"), ("Ñandú", "Zul'jin", "Zul'jin")]);
+        for character in &scan.characters {
+            assert_eq!(character.path, account.join(&character.realm).join(&character.name));
+            assert!(character.path.is_dir());
+            assert_eq!(character.talent_file_path, account.join("SavedVariables").join(TALENT_FILE_NAME));
+        }
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_scan_warns_about_folder_names_that_are_not_unicode() {
+        use std::ffi::OsStr;
+        use std::os::unix::ffi::OsStrExt;
+
+        let wow_path = temp_dir("not-unicode");
+        let realm = wow_path.join("WTF/Account/WOW1/Blackrock");
+        // "Ångela" in Latin-1
+        let char_path = realm.join(OsStr::from_bytes(b"\xc5ngela"));
+        if fs::create_dir_all(&char_path).is_err() {
+            // Some file systems (e.g. APFS) only allow UTF-8 names
+            return;
+        }
+        fs::write(char_path.join("config-cache.wtf"), "SET EJLootClass \"5\"\n").unwrap();
+
+        let scan = WowScanner::new(&wow_path).scan_characters(&AtomicBool::new(false), |_| {}).unwrap();
+        assert_eq!(scan.characters.len(), 1);
+        assert_eq!(scan.characters[0].name, "\u{FFFD}ngela");
+        assert_eq!(scan.characters[0].path, char_path);
+        assert_eq!(scan.warnings.len(), 1);
+        assert_eq!(scan.warnings[0].path, char_path);
+        assert!(scan.warnings[0].error.contains("valid Unicode"));
+    }
+
+    #[test]
+    fn test_wow_process_names() {
+        assert!(is_wow_process_name("Wow.exe"));
+        assert!(is_wow_process_name("wowclassic.exe"));
+        assert!(is_wow_process_name("World of Warcraft"));
+        assert!(!is_wow_process_name("Battle.net.exe"));
+        assert!(!is_wow_process_name("Wow"));
+        // The test process isn't a game client
+        assert!(!WowScanner::new(&temp_dir("running")).is_wow_running());
+    }
+
+    #[test]
+    fn test_diagnose_path() {
+        let root = temp_dir("diagnose");
+        let retail = root.join("_retail_");
+        fs::create_dir_all(root.join("_classic_era_")).unwrap();
+        fs::create_dir_all(&retail).unwrap();
+        fs::write(retail.join("Wow.exe"), "").unwrap();
+
+        // Never logged in: the executable is there, the WTF folder isn't
+        let diagnosis = WowScanner::diagnose_path(&retail);
+        assert!(diagnosis.exists && diagnosis.has_executable && !diagnosis.has_wtf_folder && !diagnosis.valid);
+        assert_eq!(diagnosis.suggested_path, None);
+        assert!(diagnosis.problem.unwrap().contains("log in"));
+
+        // The WoW root points to the product folders, retail first
+        let diagnosis = WowScanner::diagnose_path(&root);
+        assert_eq!(diagnosis.product_folders, ["_retail_", "_classic_era_"]);
+        assert_eq!(diagnosis.suggested_path, Some(retail.clone()));
+
+        // ...preferring one the game has run from
+        fs::create_dir_all(root.join("_classic_era_/WTF/Account")).unwrap();
+        assert_eq!(WowScanner::diagnose_path(&root).suggested_path, Some(root.join("_classic_era_")));
+
+        fs::create_dir_all(retail.join("WTF/Account")).unwrap();
+        let diagnosis = WowScanner::diagnose_path(&retail);
+        assert!(diagnosis.valid && diagnosis.has_wtf_folder && diagnosis.has_account_folder);
+        assert_eq!((diagnosis.suggested_path, diagnosis.problem), (None, None));
+
+        // A folder inside the product folder
+        let diagnosis = WowScanner::diagnose_path(&retail.join("WTF/Account"));
+        assert_eq!(diagnosis.suggested_path, Some(retail.clone()));
+
+        let diagnosis = WowScanner::diagnose_path(&root.join("missing"));
+        assert!(!diagnosis.exists && !diagnosis.valid);
+        assert_eq!(diagnosis.problem.as_deref(), Some("The folder doesn't exist"));
+    }
+
+    #[test]
+    fn test_detect_specializations_from_saved_variables() {
+        let wow_path = temp_dir("specs");
+        let char_path = wow_path.join("WTF/Account/WOW1/Blackrock/Tank");
+        fs::create_dir_all(char_path.join("SavedVariables")).unwrap();
+        fs::write(char_path.join("config-cache.wtf"), "SET EJLootClass \"1\"\n").unwrap();
+        // Protection and Arms for this Warrior; 65 (Holy Paladin) belongs to a group member
+        fs::write(
+            char_path.join("SavedVariables/Details.lua"),
+            "_detalhes_database = {\n\t[\"spec\"] = 73,\n\t[\"spec\"] = 65,\n\t[\"spec\"] = 73,\n}\n",
+        )
+        .unwrap();
+        fs::write(char_path.join("SavedVariables/Other.lua"), "OtherDB = {\n\t[\"specID\"] = 71,\n}\n").unwrap();
+
+        assert_eq!(WowScanner::detect_specializations(&char_path, WowClass::Warrior), ["arms", "protection"]);
+        assert_eq!(scan(WowScanner::new(&wow_path))[0].specializations, ["arms", "protection"]);
+        assert!(WowScanner::detect_specializations(&char_path, WowClass::Mage).is_empty());
+
+        // Nothing to go on
+        fs::remove_dir_all(char_path.join("SavedVariables")).unwrap();
+        assert!(scan(WowScanner::new(&wow_path))[0].specializations.is_empty());
+    }
 }
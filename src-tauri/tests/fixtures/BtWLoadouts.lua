@@ -0,0 +1,18 @@
+BtWLoadoutsSets = {
+  ["talents"] = {
+    [1] = { ["setID"] = 1, ["specID"] = 71, ["name"] = "My Arms Build", ["exportString"] = "warrior/arms/ABC123", ["useCount"] = 3 },
+    [7] = { ["setID"] = 7, ["specID"] = 71, ["name"] = "R-heroic-sikran_ARCT", ["exportString"] = "warrior/arms/XYZ789" },
+    [9] = { ["setID"] = 9, ["specID"] = 71, ["name"] = "M+-ara-kara_ARCT", ["exportString"] = "warrior/arms/DEF456" },
+  },
+  ["profiles"] = {
+    [1] = { ["setID"] = 1, ["specID"] = 71, ["name"] = "Arms", ["sets"] = { ["talents"] = { 1 } } },
+    [2] = { ["setID"] = 2, ["specID"] = 71, ["name"] = "Arms raid", ["sets"] = { ["talents"] = { 7 } } },
+    [3] = { ["setID"] = 3, ["specID"] = 71, ["name"] = "R-heroic-sikran_ARCT", ["sets"] = { ["talents"] = { 7 } } },
+  },
+  ["conditions"] = {
+    [1] = { ["setID"] = 1, ["name"] = "My Raid Condition", ["profileSet"] = 1, ["type"] = "raid", ["instanceID"] = 2657, ["difficultyID"] = 15 },
+    [4] = { ["setID"] = 4, ["name"] = "R-heroic-sikran_ARCT", ["profileSet"] = 3, ["type"] = "raid", ["instanceID"] = 2657, ["difficultyID"] = 15, ["bossID"] = 2599 },
+  },
+  ["equipment"] = {
+  },
+}
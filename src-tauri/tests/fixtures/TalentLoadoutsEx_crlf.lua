@@ -0,0 +1,17 @@
+TalentLoadoutEx = {
+  ["WARRIOR"] = {
+    [1] = {
+      { ["icon"] = 132355, ["name"] = "My Arms Build", ["text"] = "warrior/arms/ABC123" },
+      { ["icon"] = 0, ["name"] = "R-heroic-sikran_ARCT", ["text"] = "warrior/arms/XYZ789" },
+    },
+    [2] = {
+      { ["icon"] = 132347, ["name"] = "My Fury Build", ["text"] = "warrior/fury/DEF456" },
+    },
+  },
+  ["MAGE"] = {
+    [3] = {
+      { ["icon"] = 135846, ["name"] = "M+-ara-kara_ARCT", ["text"] = "mage/frost/GHI789" },
+    },
+  },
+  ["OPTION"] = { ["IsEnabledPvp"] = false },
+}
@@ -0,0 +1,15 @@
+TLEPlusVersion = 3
+TLEPlusGUI = {
+  ["WARRIOR"] = {
+    [1] = { "R-heroic-sikran_ARCT", "My Arms Build" },
+  },
+}
+TLEPlus = {
+  ["WARRIOR"] = {
+    [1] = {
+      { ["icon"] = 132355, ["name"] = "My Arms Build", ["text"] = "warrior/arms/ABC123" },
+      { ["icon"] = 0, ["name"] = "R-heroic-sikran_ARCT", ["text"] = "warrior/arms/XYZ789" },
+    },
+  },
+  ["OPTION"] = { ["IsEnabledPvp"] = true },
+}
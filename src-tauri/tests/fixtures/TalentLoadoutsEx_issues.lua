@@ -0,0 +1,14 @@
+TalentLoadoutEx = {
+  ["WARRIOR"] = {
+    [1] = {
+      { ["icon"] = 132355, ["name"] = "My Arms Build", ["text"] = "warrior/arms/ABC123" },
+      "warrior/arms/DANGLING",
+      { ["icon"] = 0, ["name"] = "No String" },
+      { ["icon"] = 0, ["name"] = "Tagged", ["text"] = "warrior/arms/XYZ789", ["note"] = "keep me" },
+      ["extra"] = { ["icon"] = 0, ["name"] = "Keyed", ["text"] = "warrior/arms/KEY000" },
+    },
+  },
+  ["MAGE"] = "broken",
+  [42] = {},
+  ["OPTION"] = { ["IsEnabledPvp"] = false },
+}
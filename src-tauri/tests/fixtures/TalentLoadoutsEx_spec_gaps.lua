@@ -0,0 +1,23 @@
+TalentLoadoutEx = {
+  ["WARRIOR"] = {
+    [1] = {
+      { ["icon"] = 132355, ["name"] = "My Arms Build", ["text"] = "warrior/arms/ABC123" },
+    },
+    [4] = {
+      { ["icon"] = 0, ["name"] = "Future Spec Build", ["text"] = "warrior/unknown/JKL012" },
+    },
+    [7] = {
+      { ["icon"] = 0, ["name"] = "Gap Build", ["text"] = "warrior/unknown/MNO345" },
+    },
+    [300] = {
+      { ["icon"] = 0, ["name"] = "Out Of Range", ["text"] = "warrior/unknown/PQR678" },
+    },
+    ["legacy"] = {
+      { ["icon"] = 0, ["name"] = "Legacy Build", ["text"] = "warrior/legacy/STU901" },
+    },
+  },
+  ["EVOKER"] = {
+    [5] = false,
+  },
+  ["OPTION"] = { ["IsEnabledPvp"] = false },
+}
@@ -0,0 +1,16 @@
+TalentLoadoutEx = {
+  ["MAGE"] = {
+    [3] = {
+      { ["icon"] = 135846, ["name"] = "Überbuild Frost – Feuer", ["text"] = "mage/frost/ÄÖÜ" },
+      { ["icon"] = 0, ["name"] = "冰霜 法师 大秘境", ["text"] = "mage/frost/CJK" },
+      { ["icon"] = 0, ["name"] = "M\195\164chtig \"AoE\"", ["text"] = "mage/frost/\229\144\141" },
+      { ["icon"] = 0, ["name"] = "Gemischt ä\195\182ü 名\229\173\151", ["text"] = "mage/frost/MIX" },
+    },
+  },
+  ["OPTION"] = { ["IsEnabledPvp"] = false },
+}
+TalentLoadoutExGUI = {
+  ["MAGE"] = {
+    [3] = { "冰霜 法师 大秘境", "Überbuild Frost – Feuer" },
+  },
+}
@@ -0,0 +1,65 @@
+
+TalentLoadoutsDB = {
+	["profiles"] = {
+		["Thrall - Draenor"] = {
+			["class"] = "WARRIOR",
+			["categories"] = {
+				["Custom"] = {
+					{
+						{
+							["text"] = "warrior/arms/ABC123",
+							["name"] = "My Arms Build",
+							["icon"] = 132355,
+						}, -- [1]
+					}, -- [1]
+					{
+						{
+							["text"] = "warrior/fury/DEF456",
+							["name"] = "My Fury Build",
+							["icon"] = 132347,
+						}, -- [1]
+					}, -- [2]
+				},
+				["Raid"] = {
+					{
+						{
+							["text"] = "warrior/arms/XYZ789",
+							["name"] = "R-heroic-sikran_ARCT",
+							["icon"] = 0,
+						}, -- [1]
+					}, -- [1]
+				},
+			},
+			["minimized"] = false,
+		},
+		["Garrosh - Draenor"] = {
+			["class"] = "WARRIOR",
+			["categories"] = {
+				["Custom"] = {
+					{
+						{
+							["text"] = "warrior/arms/GAR001",
+							["name"] = "Garrosh Arms",
+							["icon"] = 132355,
+						}, -- [1]
+					}, -- [1]
+				},
+			},
+		},
+		["Jaina - Twisting Nether"] = {
+			["class"] = "MAGE",
+			["categories"] = {
+				["Mythic+"] = {
+					[3] = {
+						{
+							["text"] = "mage/frost/GHI789",
+							["name"] = "M+-ara-kara_ARCT",
+							["icon"] = 135846,
+						}, -- [1]
+					},
+				},
+			},
+		},
+	},
+	["version"] = 2,
+}